@@ -1,443 +1,4468 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use anyhow::{Context, Result, bail};
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use comfy_table::{
-    Cell, ContentArrangement, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL,
+    Cell, ContentArrangement, Row, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL,
 };
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
 use serde::Serialize;
-use typopotamus_core::download;
-use typopotamus_core::extractor::{extract_fonts_from_url, normalize_target_url};
+use typopotamus_core::audit::{
+    AuditOptions, AuditReport, AuditRule, DEFAULT_MAX_FILE_BYTES, audit_fonts, summarize_site,
+};
+use typopotamus_core::binary::{
+    FontContainer, FontMetadata, probe_content_hashes, probe_font_metadata,
+};
+use typopotamus_core::cancel::CancellationToken;
+use typopotamus_core::check::{CheckOptions, FontCheck, check_fonts};
+use typopotamus_core::compare::{DeliveredFont, UserAgentProfile, compare_scans};
+use typopotamus_core::compress::{CompressReport, compress_saved_files};
+use typopotamus_core::config::Config;
+use typopotamus_core::doctor::{self, CheckStatus, DEFAULT_DOCTOR_URL, DoctorReport};
+use typopotamus_core::download::{
+    self, CommandSink, ConflictPolicy, DEFAULT_DOWNLOAD_TIMEOUT, DirectorySink, DiscardSink,
+    DownloadProgress, DownloadReport, Downloader, OutputSink, RefererPolicy, SavedFile,
+};
+use typopotamus_core::enrich::{EnrichOptions, enrich_fonts, filter_indices_by_size};
+use typopotamus_core::error::{DownloadFailure, ExtractError, error_chain};
+use typopotamus_core::extractor::{
+    DEFAULT_MAX_IMPORT_DEPTH, DEFAULT_MAX_STYLESHEET_BYTES, DEFAULT_MAX_STYLESHEETS,
+    DEFAULT_TIMEOUT, DedupeStrategy, ExtractEvent, ExtractOptions,
+    extract_fonts_from_urls_with_fetcher, normalize_target_url,
+};
+use typopotamus_core::fetch::{Fetcher, HttpOptions, RateLimit, ReqwestFetcher};
+use typopotamus_core::fontface::{LocalFont, font_face_css, relative_href};
+use typopotamus_core::fontsource::{bundled_package, fetch_package};
 use typopotamus_core::inspect::{
-    InferredFamilyGroup, infer_family_groups, select_indices_by_inferred_family_names,
+    GroupBy, Inference, InferenceOptions, InferredFamilyGroup, infer_family_groups,
+    infer_family_groups_by, match_inferred_family_names, merge_candidate_indices,
+    select_indices_by_inferred_family_names,
 };
-use typopotamus_core::model::FontInfo;
-use typopotamus_core::selection::{FontSelection, select_font_indices};
+use typopotamus_core::layout::{DEFAULT_DIRECTORY_TEMPLATE, DownloadLayout, PathTemplate};
+use typopotamus_core::local::{DirectoryFetcher, DirectoryScan, scan_directory};
+use typopotamus_core::lock::{FontLock, LOCK_FILE_NAME, LockMismatch};
+use typopotamus_core::mirror::mirror_stylesheets;
+use typopotamus_core::model::{FontInfo, FontUsageStatus};
+use typopotamus_core::output::{
+    self, FamilyOutput, FontsourceOutput, InspectFonts, InspectOutput, InspectView,
+    MissingFontOutput,
+};
+use typopotamus_core::perf::{
+    DEFAULT_MAX_VARIANTS, DEFAULT_PERF_BUDGET_BYTES, PerfOptions, PerfReport, PerfStatus,
+    check_font_performance,
+};
+use typopotamus_core::provider::{CommercialFoundry, FontProvider};
+use typopotamus_core::selection::{
+    FontSelection, MatchMode, SelectorKind, SelectorMatch, filter_indices_by_format,
+    filter_indices_by_provider, filter_indices_by_weight, match_selectors, one_file_per_face,
+    prefer_source_formats,
+};
+use typopotamus_core::session::Session;
+use typopotamus_core::snippet::{Framework, framework_snippet};
+use typopotamus_core::specimen::render_specimens;
+use typopotamus_core::stats::{FontStats, font_stats};
+use typopotamus_core::subset::{SubsetReport, page_characters, subset_saved_files};
+use typopotamus_core::sync::{SyncPlan, SyncStatus, plan_sync, remove_saved_font};
+use typopotamus_core::theme::{css_custom_properties, tailwind_config, theme_families};
+use typopotamus_core::trace::{NetworkEvent, NetworkTrace, TracingFetcher};
+
+const HEAVIEST_FAMILIES: usize = 5;
 
 #[derive(Debug, Parser)]
 #[command(
     name = "typopotamus-cli",
     version,
-    about = "Inspect and download web fonts from a website"
+    about = "Inspect and download web fonts from a website",
+    after_help = EXIT_CODES_HELP
 )]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Config file with inference rules (defaults to ~/.config/typopotamus/config.toml)"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "TOML file forcing fonts whose family, file name, or URL matches a pattern into a display family, e.g. \"brandfont-v7-*\" = \"Acme Sans\""
+    )]
+    aliases: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print tables as tab-separated rows without borders, for grep and CI logs"
+    )]
+    plain: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "COLUMNS",
+        help = "Wrap tables to at most this many columns"
+    )]
+    max_width: Option<u16>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  unexpected error
+  2  invalid command-line usage
+  3  the website could not be reached while scanning, or a failed doctor check
+  4  no fonts found on the website (see --no-fail-on-empty)
+  5  no fonts matched the provided selectors or filters
+  6  one or more downloads failed
+  7  --block-commercial refused fonts from a commercial foundry
+  8  an inspect --perf check failed
+  9  check found font files that could not be fetched
+ 10  download --locked found fonts that differ from the lockfile
+ 11  some of several --url pages could not be scanned; output covers the rest";
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     Inspect(InspectArgs),
-    Download(DownloadArgs),
+    Download(Box<DownloadArgs>),
+    /// Download fonts into a project and write @font-face CSS for them
+    Vendor(Box<VendorArgs>),
+    /// Keep a directory in sync with a website's fonts: download new and
+    /// changed files, optionally delete ones the site dropped
+    Sync(Box<SyncArgs>),
+    /// Scan a lockfile's website again, show which fonts changed, and
+    /// rewrite the lockfile
+    Update(UpdateArgs),
+    /// Score a website's fonts against common web font anti-patterns
+    Audit(AuditArgs),
+    /// Request every font file without saving it and report broken links
+    Check(CheckArgs),
+    /// Check DNS, TLS, proxy settings and blocking for a website, with hints
+    Doctor(DoctorArgs),
+    /// Print the JSON Schema of the inspect or download JSON output
+    Schema(SchemaArgs),
+}
+
+impl Commands {
+    fn scan(&self) -> Option<&ScanArgs> {
+        match self {
+            Self::Inspect(args) => Some(&args.scan),
+            Self::Download(args) => Some(&args.scan),
+            Self::Vendor(args) => Some(&args.scan),
+            Self::Sync(args) => Some(&args.scan),
+            Self::Update(args) => Some(&args.scan),
+            Self::Audit(args) => Some(&args.scan),
+            Self::Check(args) => Some(&args.scan),
+            Self::Doctor(_) | Self::Schema(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Args)]
-struct InspectArgs {
-    #[arg(short, long, help = "Website URL to inspect")]
-    url: String,
+struct ScanArgs {
+    #[arg(
+        long,
+        help = "Also search same-origin <script src> bundles for font URLs"
+    )]
+    scan_scripts: bool,
 
     #[arg(
         long,
-        value_name = "FAMILY",
-        help = "Limit output to one or more family names (matches inferred and source family names)",
-        num_args = 1..
+        value_name = "DEPTH",
+        default_value_t = DEFAULT_MAX_IMPORT_DEPTH,
+        help = "Levels of @import followed below each linked stylesheet"
     )]
-    family: Vec<String>,
+    max_import_depth: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = DEFAULT_MAX_STYLESHEETS,
+        help = "Stop fetching stylesheets after this many"
+    )]
+    max_stylesheets: usize,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop fetching stylesheets and scripts once this many bytes have been read"
+    )]
+    max_bytes: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_STYLESHEET_BYTES,
+        help = "Read at most this many bytes of any one stylesheet"
+    )]
+    max_stylesheet_bytes: u64,
+
+    #[arg(
+        long,
+        value_name = "PAGES",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        help = "Also scan up to PAGES pages (default 10) sampled from the site's sitemap.xml"
+    )]
+    sitemap: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Do not ask servers for gzip, brotli, or deflate compressed responses"
+    )]
+    no_compression: bool,
 
     #[arg(
         long,
-        default_value_t = InspectView::Family,
         value_enum,
-        help = "Inspect grouped families or individual font files"
+        value_name = "STRATEGY",
+        default_value_t = Dedupe::Url,
+        help = "Treat fonts as the same file when their full URL matches, their URL without ?query and #fragment matches, or their content hash matches (fetches every file)"
     )]
-    view: InspectView,
+    dedupe: Dedupe,
+
+    #[arg(
+        long,
+        help = "Load stylesheets and fonts over https even when the page references them with http://"
+    )]
+    force_https: bool,
 
     #[arg(
         long,
-        default_value_t = OutputFormat::Pretty,
         value_enum,
-        help = "Output format for inspect results"
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "pretty",
+        help = "Print every HTTP request made (status, time, bytes, CDN cache hits) to stderr when the command ends"
     )]
-    format: OutputFormat,
+    trace_network: Option<OutputFormat>,
+
+    /// Requests recorded by the fetchers built from these arguments.
+    #[arg(skip)]
+    trace: NetworkTrace,
+
+    /// One client, cookie jar and response cache for every phase.
+    #[arg(skip)]
+    session: OnceLock<Session<ReqwestFetcher>>,
 }
 
-#[derive(Debug, Args)]
-struct DownloadArgs {
-    #[arg(short, long, help = "Website URL to inspect and download from")]
-    url: String,
+impl ScanArgs {
+    fn extract_options(&self) -> ExtractOptions {
+        ExtractOptions {
+            scan_scripts: self.scan_scripts,
+            max_import_depth: self.max_import_depth,
+            max_stylesheets: self.max_stylesheets,
+            max_bytes: self.max_bytes,
+            max_stylesheet_bytes: Some(self.max_stylesheet_bytes),
+            sitemap_pages: self.sitemap,
+            http: self.http_options(),
+            dedupe: self.dedupe.into(),
+            force_https: self.force_https,
+            ..ExtractOptions::default()
+        }
+    }
+
+    fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            compression: !self.no_compression,
+        }
+    }
+
+    /// The shared session, built on first use.
+    fn session(&self) -> Result<&Session<ReqwestFetcher>> {
+        if let Some(session) = self.session.get() {
+            return Ok(session);
+        }
+        let client = ReqwestFetcher::with_options(DEFAULT_DOWNLOAD_TIMEOUT, &self.http_options())?;
+        Ok(self.session.get_or_init(|| Session::new(client)))
+    }
+
+    /// The shared session with its own per-request timeout.
+    fn fetcher(&self, timeout: Duration) -> Result<TracingFetcher<Session<ReqwestFetcher>>> {
+        let session = self.session()?;
+        Ok(self.traced(session.share(session.inner().clone().with_timeout(timeout))))
+    }
+
+    fn traced<F: Fetcher>(&self, fetcher: F) -> TracingFetcher<F> {
+        TracingFetcher::new(fetcher, self.trace.clone())
+    }
+}
 
+#[derive(Debug, Args)]
+struct InspectArgs {
     #[arg(
         short,
         long,
-        default_value = "downloads",
-        help = "Directory where selected fonts are saved"
+        required_unless_present = "dir",
+        help = "Website URL to inspect; repeat to scan several pages concurrently and merge them"
     )]
-    output: PathBuf,
+    url: Vec<String>,
 
-    #[arg(long, help = "Download all discovered fonts")]
-    all: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "url",
+        help = "Scan the HTML and CSS files of a build output directory instead of a website, and report referenced font files missing from it"
+    )]
+    dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
 
     #[arg(
         long,
         value_name = "FAMILY",
-        help = "Select all fonts in a family (matches inferred and source family names)",
+        help = "Limit output to one or more family names (matches inferred and source family names)",
         num_args = 1..
     )]
     family: Vec<String>,
 
     #[arg(
-        long = "font-name",
-        value_name = "NAME",
-        help = "Select a specific font by file name (repeatable)",
+        long,
+        value_enum,
+        value_name = "PROVIDER",
+        help = "Only include fonts served by this provider (repeatable)",
         num_args = 1..
     )]
-    font_name: Vec<String>,
+    provider: Vec<Provider>,
+
+    #[command(flatten)]
+    weight: WeightRangeArgs,
 
     #[arg(
-        long = "font-url",
-        value_name = "URL",
-        help = "Select a specific font by URL (repeatable)",
-        num_args = 1..
+        long,
+        default_value_t = View::Family,
+        value_enum,
+        help = "Inspect grouped families, faces (one row per weight/style across formats), or individual font files"
     )]
-    font_url: Vec<String>,
+    view: View,
 
     #[arg(
         long,
-        value_name = "INDEX",
-        help = "Select a font by index from inspect output (repeatable)",
-        num_args = 1..
+        default_value_t = GroupAxis::Family,
+        value_enum,
+        help = "Group the family and face views by inferred family, declared CSS family, format, or provider, or list every file on its own"
     )]
-    index: Vec<usize>,
+    group_by: GroupAxis,
 
-    #[arg(long, help = "Show selected fonts without downloading")]
-    dry_run: bool,
-}
+    #[arg(
+        long,
+        help = "Print totals per format and provider, variable vs static counts, and the heaviest families instead of the --view listing"
+    )]
+    stats: bool,
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum, Serialize)]
-#[serde(rename_all = "lowercase")]
-enum OutputFormat {
-    Pretty,
-    Json,
-}
+    #[arg(
+        long,
+        conflicts_with = "stats",
+        help = "Check preload hints, font-display, duplicate downloads, CORS headers, faces per family, and total font bytes against --perf-budget instead of the --view listing (implies --sizes)"
+    )]
+    perf: bool,
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum, Serialize)]
-#[serde(rename_all = "lowercase")]
-enum InspectView {
-    Family,
-    Font,
-}
+    #[arg(
+        long,
+        value_name = "KIB",
+        default_value_t = DEFAULT_PERF_BUDGET_BYTES / 1024,
+        help = "Total KiB of font files a page may load before the --perf budget check fails"
+    )]
+    perf_budget: u64,
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = DEFAULT_MAX_VARIANTS,
+        help = "Faces per family above which --perf warns"
+    )]
+    max_variants: usize,
 
-    match cli.command {
-        Commands::Inspect(args) => run_inspect(args),
-        Commands::Download(args) => run_download(args),
-    }
-}
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PROFILES",
+        value_delimiter = ',',
+        conflicts_with = "dir",
+        help = "Scan once per user agent, e.g. mobile,desktop, and list the font files only some of them were served"
+    )]
+    compare_ua: Vec<UaProfile>,
 
-fn run_inspect(args: InspectArgs) -> Result<()> {
-    let normalized_url = normalize_target_url(&args.url);
-    let fonts = extract_fonts_from_url(&normalized_url)
-        .with_context(|| format!("failed to extract fonts from {normalized_url}"))?;
+    #[arg(
+        long,
+        help = "Send a HEAD request per font to report its size, Last-Modified date, and Content-Type"
+    )]
+    sizes: bool,
 
-    if fonts.is_empty() {
-        return render_empty_inspect(&normalized_url, args.view, args.format);
-    }
+    #[arg(
+        long,
+        value_name = "KIB",
+        help = "Only include fonts of at least this many KiB (implies --sizes; fonts of unknown size are kept)"
+    )]
+    min_size: Option<u64>,
 
-    let filtered_indices = if args.family.is_empty() {
-        (0..fonts.len()).collect::<Vec<_>>()
-    } else {
-        select_indices_by_inferred_family_names(&fonts, &args.family)
-    };
+    #[arg(
+        long,
+        value_name = "KIB",
+        help = "Only include fonts of at most this many KiB (implies --sizes; fonts of unknown size are kept)"
+    )]
+    max_size: Option<u64>,
 
-    if filtered_indices.is_empty() {
-        bail!("no fonts matched requested family filter");
-    }
+    #[arg(
+        long,
+        help = "Suggest the @fontsource npm package and available weights for each family, from a bundled index of popular fonts"
+    )]
+    fontsource: bool,
 
-    let groups = infer_family_groups(&fonts, &filtered_indices);
-    let grouped_output = build_grouped_output(&normalized_url, &fonts, args.view, groups);
+    #[arg(
+        long,
+        help = "Like --fontsource, but ask the Fontsource API about families missing from the bundled index"
+    )]
+    fontsource_api: bool,
 
-    match args.format {
-        OutputFormat::Pretty => print_inspect_pretty(&grouped_output),
-        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&grouped_output)?),
-    }
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for inspect results"
+    )]
+    format: OutputFormat,
 
-    Ok(())
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+
+    #[arg(
+        long,
+        default_value_t = InferFrom::Name,
+        value_enum,
+        help = "Group families from file names or from each font's name/OS2 tables; content also downloads fonts to confirm merges across hosts"
+    )]
+    infer_from: InferFrom,
 }
 
-fn run_download(args: DownloadArgs) -> Result<()> {
-    let normalized_url = normalize_target_url(&args.url);
-    let fonts = extract_fonts_from_url(&normalized_url)
-        .with_context(|| format!("failed to extract fonts from {normalized_url}"))?;
+#[derive(Debug, Args)]
+struct DownloadArgs {
+    #[arg(
+        short,
+        long,
+        required_unless_present = "from_json",
+        help = "Website URL to inspect and download from"
+    )]
+    url: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "url",
+        help = "Select from a saved `inspect --view font --format json` document instead of scanning (- reads standard input); --index and --id match the document"
+    )]
+    from_json: Option<PathBuf>,
 
-    if fonts.is_empty() {
-        bail!("no fonts were found on {normalized_url}");
-    }
+    #[command(flatten)]
+    scan: ScanArgs,
 
-    if !has_download_selectors(&args) {
-        bail!("no selection provided. Use --all or one of --family/--font-name/--font-url/--index");
-    }
+    #[arg(
+        short,
+        long,
+        default_value = "downloads",
+        help = "Directory where selected fonts are saved, or s3://bucket/prefix to upload them with the AWS CLI"
+    )]
+    output: PathBuf,
 
-    let selected_indices = resolve_download_indices(&fonts, &args);
-    if selected_indices.is_empty() {
-        bail!("no fonts matched the provided selectors");
-    }
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        conflicts_with = "output",
+        help = "Pipe each saved file into this shell command instead of writing it to --output; $TYPOPOTAMUS_PATH holds its relative path"
+    )]
+    output_cmd: Option<String>,
 
-    print_download_selection_pretty(&normalized_url, &fonts, &selected_indices);
+    #[command(flatten)]
+    selection: SelectionArgs,
 
-    if args.dry_run {
-        println!("\nDry run enabled; no files were downloaded.");
-        return Ok(());
-    }
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = DEFAULT_DIRECTORY_TEMPLATE,
+        value_parser = PathTemplate::parse,
+        help = "Directory layout below --output; placeholders: {family}, {inferred_family}, {weight}, {style}, {format}, {provider}, {host}"
+    )]
+    layout: PathTemplate,
 
-    let selected_fonts = select_fonts(&fonts, &selected_indices);
-    let total = selected_fonts.len();
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = PathTemplate::parse,
+        help = "File name template, e.g. \"{family}-{weight}{style_suffix}.{ext}\"; also accepts {name} (source file name)"
+    )]
+    name_template: Option<PathTemplate>,
 
-    eprintln!(
-        "\nDownloading {total} fonts into {} ...",
-        args.output.display()
-    );
+    #[arg(
+        long,
+        conflicts_with = "name_template",
+        help = "Name saved files after the family and subfamily in their OpenType name table, e.g. Inter-SemiBoldItalic.woff2"
+    )]
+    rename_from_metadata: bool,
 
-    let report = download::download_fonts(&selected_fonts, &args.output, |current, total, font| {
-        eprintln!("[{current}/{total}] {}", font.name);
-    });
+    #[arg(
+        long,
+        default_value_t = OnConflict::Rename,
+        value_enum,
+        help = "What to do when a target file already exists"
+    )]
+    on_conflict: OnConflict,
 
-    println!(
-        "\nDownloaded {}/{} fonts into {}",
-        report.success_count(),
-        report.attempted,
-        args.output.display()
-    );
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write SHA-256 checksums of saved files in sha256sum format (paths relative to --output)"
+    )]
+    checksum_file: Option<PathBuf>,
 
-    if !report.failures.is_empty() {
-        eprintln!("{} download(s) failed:", report.failures.len());
-        for failure in &report.failures {
-            eprintln!("- {failure}");
-        }
-        bail!("some downloads failed");
-    }
+    #[arg(
+        long,
+        help = "Write a <file>.json sidecar next to each saved font with its source URL, referer, response headers, declared descriptors, and download time"
+    )]
+    sidecar: bool,
 
-    Ok(())
-}
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = LOCK_FILE_NAME,
+        help = "Record each downloaded font's URL, descriptors, and SHA-256 in a lockfile (default typopotamus.lock)"
+    )]
+    lockfile: Option<PathBuf>,
 
-fn has_download_selectors(args: &DownloadArgs) -> bool {
-    args.all
-        || !args.family.is_empty()
-        || !args.font_name.is_empty()
-        || !args.font_url.is_empty()
-        || !args.index.is_empty()
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "fail",
+        help = "Check the selected fonts and downloaded bytes against --lockfile instead of writing it; fail (default) or warn on differences"
+    )]
+    locked: Option<LockMode>,
+
+    #[arg(
+        long,
+        help = "Render an SVG specimen sheet per downloaded family into <output>/specimens (PNG is not supported)"
+    )]
+    specimens: bool,
+
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for the download report; json prints it on stdout and moves progress to stderr"
+    )]
+    format: OutputFormat,
+
+    #[arg(
+        long,
+        value_name = "RATE",
+        help = "Cap total download throughput in bytes per second, e.g. 500K or 2M"
+    )]
+    limit_rate: Option<RateLimit>,
+
+    #[command(flatten)]
+    file_time: FileTimeArgs,
+
+    #[command(flatten)]
+    request: FontRequestArgs,
+
+    #[arg(
+        long,
+        help = "Also save the declaring stylesheets with url(...) references rewritten to the downloaded files, plus an index fonts.css, for an offline mirror"
+    )]
+    mirror: bool,
+
+    #[arg(
+        long,
+        help = "Subset saved TrueType fonts to the characters in the scanned pages' visible text and report the bytes saved"
+    )]
+    subset_to_page: bool,
+
+    #[arg(
+        long,
+        help = "Convert saved TTF/OTF files to WOFF2 locally, replacing the originals"
+    )]
+    compress_woff2: bool,
+
+    #[arg(
+        long,
+        requires = "compress_woff2",
+        help = "Keep the original TTF/OTF next to each file converted by --compress-woff2"
+    )]
+    keep_original: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FRAMEWORK",
+        help = "Print a ready-to-paste snippet loading the downloaded fonts: a next/font/local module, an Astro component, or preload links plus @font-face rules"
+    )]
+    emit: Option<EmitFramework>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "emit",
+        help = "Write the --emit snippet to this file instead of stdout; its paths are relative to the file"
+    )]
+    emit_to: Option<PathBuf>,
+
+    #[command(flatten)]
+    theme: ThemeArgs,
+
+    #[arg(long, help = "Show selected fonts without downloading")]
+    dry_run: bool,
+
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+
+    #[arg(
+        long,
+        default_value_t = InferFrom::Name,
+        value_enum,
+        help = "Group families from file names or from each font's name/OS2 tables; content also downloads fonts to confirm merges across hosts"
+    )]
+    infer_from: InferFrom,
+}
+
+impl DownloadArgs {
+    /// The sink for `--output-cmd` or an `s3://` `--output`; `None` when
+    /// fonts are written below a local directory.
+    fn remote_sink(&self) -> Result<Option<CommandSink>> {
+        if let Some(command) = &self.output_cmd {
+            return Ok(Some(CommandSink::new(command)));
+        }
+        match self.output.to_str() {
+            Some(url) if url.starts_with("s3://") => Ok(Some(CommandSink::s3(url)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn lockfile(&self) -> PathBuf {
+        self.lockfile
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(LOCK_FILE_NAME))
+    }
+
+    fn source(&self) -> ScanSource<'_> {
+        match (&self.url, &self.from_json) {
+            (Some(url), _) => ScanSource::Url(url),
+            (None, Some(path)) => ScanSource::Saved(path),
+            (None, None) => ScanSource::Url(""),
+        }
+    }
+
+    fn destination(&self) -> String {
+        match &self.output_cmd {
+            Some(command) => format!("`{command}`"),
+            None => self.output.display().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct SelectionArgs {
+    #[arg(long, help = "Download all discovered fonts")]
+    all: bool,
+
+    #[arg(
+        long,
+        value_name = "FAMILY",
+        help = "Select all fonts in a family (matches inferred and source family names)",
+        num_args = 1..
+    )]
+    family: Vec<String>,
+
+    #[arg(
+        long = "font-name",
+        value_name = "NAME",
+        help = "Select a specific font by file name (repeatable)",
+        num_args = 1..
+    )]
+    font_name: Vec<String>,
+
+    #[arg(
+        long = "font-url",
+        value_name = "URL",
+        help = "Select a specific font by URL (repeatable)",
+        num_args = 1..
+    )]
+    font_url: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "INDEX",
+        help = "Select a font by index from inspect output (repeatable)",
+        num_args = 1..
+    )]
+    index: Vec<usize>,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Select a font by the stable ID shown in inspect output, which survives site changes (repeatable)",
+        num_args = 1..
+    )]
+    id: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Add the selectors in a file, one per line: \"family: NAME\", \"font-name: NAME\", \"font-url: URL\", \"index: N\", \"id: ID\", \"min-weight: N\", \"max-weight: N\", or \"all\"; # starts a comment"
+    )]
+    select_file: Option<PathBuf>,
+
+    #[arg(
+        long = "match",
+        value_enum,
+        default_value_t = NameMatch::Exact,
+        help = "How --family and --font-name compare: whole names, substrings ignoring case and punctuation (inter matches \"Inter var\" and inter-v12), or substrings allowing one typo per five letters"
+    )]
+    matching: NameMatch,
+
+    #[arg(
+        long = "only-format",
+        value_enum,
+        value_name = "FORMAT",
+        help = "Only download fonts in this format, e.g. woff2 (repeatable)",
+        num_args = 1..
+    )]
+    only_format: Vec<FormatArg>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMATS",
+        value_delimiter = ',',
+        help = "Download one file per face in the first available format of this chain, e.g. woff2,woff,ttf"
+    )]
+    prefer_format: Vec<FormatArg>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PROVIDER",
+        help = "Only include fonts served by this provider (repeatable)",
+        num_args = 1..
+    )]
+    provider: Vec<Provider>,
+
+    #[command(flatten)]
+    weight: WeightRangeArgs,
+
+    #[arg(
+        long,
+        help = "Refuse to continue when the selection includes fonts from a commercial foundry (MyFonts/Monotype, Hoefler&Co., Fontspring)"
+    )]
+    block_commercial: bool,
+}
+
+impl SelectionArgs {
+    /// Adds the selectors of `--select-file`; weight bounds given as flags
+    /// win over the file's.
+    fn load_select_file(&mut self) -> Result<()> {
+        let Some(path) = &self.select_file else {
+            return Ok(());
+        };
+        let file = FontSelection::read(path)
+            .with_context(|| format!("could not load selection file {}", path.display()))?;
+        self.all |= file.all;
+        self.family.extend(file.families);
+        self.font_name.extend(file.names);
+        self.font_url.extend(file.urls);
+        self.index.extend(file.indices);
+        self.id.extend(file.ids);
+        self.weight.min_weight = self.weight.min_weight.or(file.min_weight);
+        self.weight.max_weight = self.weight.max_weight.or(file.max_weight);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+struct WeightRangeArgs {
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        value_parser = clap::value_parser!(u16).range(1..=1000),
+        help = "Only include fonts at least this heavy, e.g. 400 (variable ranges match when they overlap)"
+    )]
+    min_weight: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "WEIGHT",
+        value_parser = clap::value_parser!(u16).range(1..=1000),
+        help = "Only include fonts at most this heavy, e.g. 700"
+    )]
+    max_weight: Option<u16>,
+}
+
+#[derive(Debug, Args)]
+struct AuditArgs {
+    #[arg(
+        short,
+        long,
+        required_unless_present = "url_file",
+        help = "Website URL to audit; repeat to scan several pages and merge them"
+    )]
+    url: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "url",
+        help = "Audit each site listed in this file separately, one URL per line; blank lines and lines starting with # are ignored"
+    )]
+    url_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[arg(
+        long,
+        value_name = "KIB",
+        default_value_t = DEFAULT_MAX_FILE_BYTES / 1024,
+        help = "Flag font files larger than this many KiB"
+    )]
+    max_file_size: u64,
+
+    #[arg(
+        long,
+        default_value_t = AuditFormat::Pretty,
+        value_enum,
+        help = "Output format for the audit report; csv prints one row per site"
+    )]
+    format: AuditFormat,
+
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+}
+
+#[derive(Debug, Args)]
+struct CheckArgs {
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "Website URL whose fonts to check; repeat to scan several pages and merge them"
+    )]
+    url: Vec<String>,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for the check results"
+    )]
+    format: OutputFormat,
+
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+}
+
+#[derive(Debug, Args)]
+struct UpdateArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = LOCK_FILE_NAME,
+        help = "Lockfile to update; its recorded URL is scanned again"
+    )]
+    lockfile: PathBuf,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[command(flatten)]
+    request: FontRequestArgs,
+
+    #[arg(short, long, help = "Rewrite the lockfile without asking")]
+    yes: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "yes",
+        help = "Show the changes without rewriting the lockfile"
+    )]
+    dry_run: bool,
+
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for the changes"
+    )]
+    format: OutputFormat,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+}
+
+#[derive(Debug, Args)]
+struct DoctorArgs {
+    #[arg(
+        short,
+        long,
+        help = "Website to check; defaults to the Google Fonts API"
+    )]
+    url: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for the checks"
+    )]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+struct SchemaArgs {
+    #[arg(value_enum, help = "Command whose --format json output to describe")]
+    document: SchemaDocument,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum SchemaDocument {
+    Inspect,
+    Download,
+}
+
+#[derive(Debug, Args)]
+struct FontRequestArgs {
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "page",
+        help = "Referer sent with font requests: page (where the font was found), none, or custom:<url>"
+    )]
+    referer: RefererPolicy,
+
+    #[arg(
+        long,
+        help = "Send no Origin header with font requests; by default it is derived from the referer"
+    )]
+    no_origin: bool,
+}
+
+#[derive(Debug, Args)]
+struct FileTimeArgs {
+    #[arg(
+        long,
+        help = "Keep the download time as each saved file's modification time instead of the server's Last-Modified"
+    )]
+    no_server_mtime: bool,
+}
+
+#[derive(Debug, Args)]
+struct ThemeArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write a Tailwind fontFamily module, e.g. tailwind.fonts.cjs, mapping the downloaded families to fallback stacks"
+    )]
+    tailwind_config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write a stylesheet of --font-<family> custom properties with fallback stacks"
+    )]
+    css_variables: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct VendorArgs {
+    #[arg(short, long, help = "Website URL whose fonts to self-host")]
+    url: String,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Project directory to save the fonts into, e.g. assets/fonts"
+    )]
+    into: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Stylesheet to write the @font-face rules to, e.g. src/styles/fonts.css"
+    )]
+    css: PathBuf,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = DEFAULT_DIRECTORY_TEMPLATE,
+        value_parser = PathTemplate::parse,
+        help = "Directory layout below --into; same placeholders as download --layout"
+    )]
+    layout: PathTemplate,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = PathTemplate::parse,
+        help = "File name template; same placeholders as download --name-template"
+    )]
+    name_template: Option<PathTemplate>,
+
+    #[arg(
+        long,
+        default_value_t = OnConflict::Overwrite,
+        value_enum,
+        help = "What to do when a target file already exists"
+    )]
+    on_conflict: OnConflict,
+
+    #[command(flatten)]
+    file_time: FileTimeArgs,
+
+    #[command(flatten)]
+    request: FontRequestArgs,
+
+    #[command(flatten)]
+    theme: ThemeArgs,
+
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(short, long, help = "Do not print scan progress to stderr")]
+    quiet: bool,
+
+    #[arg(
+        long,
+        default_value_t = InferFrom::Name,
+        value_enum,
+        help = "Group families from file names or from each font's name/OS2 tables; content also downloads fonts to confirm merges across hosts"
+    )]
+    infer_from: InferFrom,
+}
+
+#[derive(Debug, Args)]
+struct SyncArgs {
+    #[arg(short, long, help = "Website URL whose fonts to keep in sync")]
+    url: String,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[arg(
+        short,
+        long,
+        value_name = "DIR",
+        help = "Directory to keep in sync; each saved font gets a <file>.json sidecar recording its URL"
+    )]
+    output: PathBuf,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = DEFAULT_DIRECTORY_TEMPLATE,
+        value_parser = PathTemplate::parse,
+        help = "Directory layout below --output; same placeholders as download --layout"
+    )]
+    layout: PathTemplate,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = PathTemplate::parse,
+        help = "File name template; same placeholders as download --name-template"
+    )]
+    name_template: Option<PathTemplate>,
+
+    #[arg(
+        long,
+        help = "Delete saved fonts, and their sidecars, whose URL the site no longer declares; refused when the scan was incomplete"
+    )]
+    prune: bool,
+
+    #[command(flatten)]
+    file_time: FileTimeArgs,
+
+    #[command(flatten)]
+    request: FontRequestArgs,
+
+    #[arg(
+        long,
+        default_value_t = OutputFormat::Pretty,
+        value_enum,
+        help = "Output format for the change summary"
+    )]
+    format: OutputFormat,
+
+    #[arg(long, help = "Show what would change without downloading or deleting")]
+    dry_run: bool,
+
+    #[arg(long, help = "Exit successfully when the website has no fonts")]
+    no_fail_on_empty: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Do not print scan and download progress to stderr"
+    )]
+    quiet: bool,
+
+    #[arg(
+        long,
+        default_value_t = InferFrom::Name,
+        value_enum,
+        help = "Group families from file names or from each font's name/OS2 tables; content also downloads fonts to confirm merges across hosts"
+    )]
+    infer_from: InferFrom,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum AuditFormat {
+    Pretty,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum View {
+    Family,
+    Face,
+    Font,
+}
+
+impl From<View> for InspectView {
+    fn from(value: View) -> Self {
+        match value {
+            View::Family => Self::Family,
+            View::Face => Self::Face,
+            View::Font => Self::Font,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum GroupAxis {
+    Family,
+    CssFamily,
+    Format,
+    Provider,
+    None,
+}
+
+impl From<GroupAxis> for GroupBy {
+    fn from(value: GroupAxis) -> Self {
+        match value {
+            GroupAxis::Family => Self::InferredFamily,
+            GroupAxis::CssFamily => Self::CssFamily,
+            GroupAxis::Format => Self::Format,
+            GroupAxis::Provider => Self::Provider,
+            GroupAxis::None => Self::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum Dedupe {
+    Url,
+    IgnoreQuery,
+    ContentHash,
+}
+
+impl From<Dedupe> for DedupeStrategy {
+    fn from(value: Dedupe) -> Self {
+        match value {
+            Dedupe::Url => Self::Url,
+            Dedupe::IgnoreQuery => Self::IgnoreQuery,
+            Dedupe::ContentHash => Self::ContentHash,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum UaProfile {
+    Desktop,
+    Mobile,
+}
+
+impl From<UaProfile> for UserAgentProfile {
+    fn from(value: UaProfile) -> Self {
+        match value {
+            UaProfile::Desktop => Self::Desktop,
+            UaProfile::Mobile => Self::Mobile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum FormatArg {
+    Woff2,
+    Woff,
+    #[value(alias = "ttf")]
+    Truetype,
+    #[value(alias = "otf")]
+    Opentype,
+    #[value(alias = "embedded-opentype")]
+    Eot,
+    Svg,
+}
+
+impl From<FormatArg> for FontContainer {
+    fn from(value: FormatArg) -> Self {
+        match value {
+            FormatArg::Woff2 => Self::Woff2,
+            FormatArg::Woff => Self::Woff,
+            FormatArg::Truetype => Self::TrueType,
+            FormatArg::Opentype => Self::OpenType,
+            FormatArg::Eot => Self::Eot,
+            FormatArg::Svg => Self::Svg,
+        }
+    }
+}
+
+/// The format labels of `formats`, as the selection filters take them.
+fn format_labels(formats: &[FormatArg]) -> Vec<String> {
+    formats
+        .iter()
+        .map(|format| FontContainer::from(*format).format().to_owned())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum OnConflict {
+    Rename,
+    Skip,
+    Overwrite,
+    Error,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(value: OnConflict) -> Self {
+        match value {
+            OnConflict::Rename => Self::Rename,
+            OnConflict::Skip => Self::Skip,
+            OnConflict::Overwrite => Self::Overwrite,
+            OnConflict::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum LockMode {
+    Fail,
+    Warn,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum EmitFramework {
+    Next,
+    Astro,
+    Html,
+}
+
+impl From<EmitFramework> for Framework {
+    fn from(value: EmitFramework) -> Self {
+        match value {
+            EmitFramework::Next => Self::Next,
+            EmitFramework::Astro => Self::Astro,
+            EmitFramework::Html => Self::Html,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum Provider {
+    Google,
+    Adobe,
+    Bunny,
+    Fontshare,
+    Fontawesome,
+    Cdn,
+    SelfHosted,
+    Unknown,
+}
+
+impl From<Provider> for FontProvider {
+    fn from(value: Provider) -> Self {
+        match value {
+            Provider::Google => Self::GoogleFonts,
+            Provider::Adobe => Self::AdobeFonts,
+            Provider::Bunny => Self::BunnyFonts,
+            Provider::Fontshare => Self::Fontshare,
+            Provider::Fontawesome => Self::FontAwesome,
+            Provider::Cdn => Self::PublicCdn,
+            Provider::SelfHosted => Self::SelfHosted,
+            Provider::Unknown => Self::Unknown,
+        }
+    }
+}
+
+fn font_providers(values: &[Provider]) -> Vec<FontProvider> {
+    values.iter().copied().map(FontProvider::from).collect()
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum NameMatch {
+    Exact,
+    Substring,
+    Fuzzy,
+}
+
+impl From<NameMatch> for MatchMode {
+    fn from(value: NameMatch) -> Self {
+        match value {
+            NameMatch::Exact => Self::Exact,
+            NameMatch::Substring => Self::Substring,
+            NameMatch::Fuzzy => Self::Fuzzy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum InferFrom {
+    Name,
+    Binary,
+    /// Binary metadata, plus the hashes of fonts that look like one family
+    /// served from several hosts.
+    Content,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ExitStatus {
+    Network = 3,
+    NoFonts = 4,
+    NoMatch = 5,
+    PartialDownload = 6,
+    CommercialFonts = 7,
+    PerfFailed = 8,
+    Unavailable = 9,
+    LockMismatch = 10,
+    PartialScan = 11,
+}
+
+#[derive(Debug)]
+struct CliFailure {
+    status: ExitStatus,
+    message: String,
+}
+
+impl CliFailure {
+    fn new(status: ExitStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CliFailure {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliFailure {}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    TABLE_STYLE.get_or_init(|| TableStyle {
+        plain: cli.plain,
+        max_width: cli.max_width,
+        no_color: env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+    });
+    let trace = cli.command.scan().and_then(|scan| {
+        scan.trace_network
+            .map(|format| (format, scan.trace.clone()))
+    });
+
+    let result = Config::load_or_default(cli.config.as_deref())
+        .and_then(|config| match &cli.aliases {
+            Some(path) => config.with_family_aliases(path),
+            None => Ok(config),
+        })
+        .map_err(anyhow::Error::new)
+        .and_then(|config| match cli.command {
+            Commands::Inspect(args) => run_inspect(args, &config.inference),
+            Commands::Download(args) => run_download(*args, &config.inference),
+            Commands::Vendor(args) => run_vendor(*args, &config.inference),
+            Commands::Sync(args) => run_sync(*args, &config.inference),
+            Commands::Update(args) => run_update(args),
+            Commands::Audit(args) => run_audit(args),
+            Commands::Check(args) => run_check(args),
+            Commands::Doctor(args) => run_doctor(args),
+            Commands::Schema(args) => run_schema(args),
+        });
+
+    // Printed even when the command failed, since that is when it helps most.
+    if let Some((format, trace)) = trace
+        && let Err(error) = print_network_trace(format, &trace.events())
+    {
+        eprintln!("Error: failed to print the network trace: {error:?}");
+    }
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error:?}");
+            exit_code_for(&error)
+        }
+    }
+}
+
+/// How tables are printed, from `--plain`, `--max-width`, and `NO_COLOR`.
+#[derive(Clone, Copy, Debug, Default)]
+struct TableStyle {
+    plain: bool,
+    max_width: Option<u16>,
+    no_color: bool,
+}
+
+static TABLE_STYLE: OnceLock<TableStyle> = OnceLock::new();
+
+/// A table that prints in the chosen [`TableStyle`]: bordered and wrapped to
+/// the terminal, or one tab-separated line per row.
+struct CliTable {
+    table: Table,
+    plain: bool,
+}
+
+fn cli_table(header: impl Into<Row>) -> CliTable {
+    let style = TABLE_STYLE.get().copied().unwrap_or_default();
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+    if let Some(width) = style.max_width {
+        table.set_width(width);
+    }
+    if style.no_color {
+        table.force_no_tty();
+    }
+    CliTable {
+        table,
+        plain: style.plain,
+    }
+}
+
+impl Deref for CliTable {
+    type Target = Table;
+
+    fn deref(&self) -> &Table {
+        &self.table
+    }
+}
+
+impl DerefMut for CliTable {
+    fn deref_mut(&mut self) -> &mut Table {
+        &mut self.table
+    }
+}
+
+impl fmt::Display for CliTable {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.plain {
+            return self.table.fmt(formatter);
+        }
+
+        let rows = self.table.header().into_iter().chain(self.table.row_iter());
+        for (index, row) in rows.enumerate() {
+            if index > 0 {
+                formatter.write_str("\n")?;
+            }
+            let line = row
+                .cell_iter()
+                .map(|cell| cell.content().replace(['\t', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t");
+            formatter.write_str(&line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scans the pages once per `--compare-ua` profile and reports which font
+/// files each one was served.
+fn run_compare_ua(args: &InspectArgs) -> Result<()> {
+    let mut profiles = Vec::<UserAgentProfile>::new();
+    for profile in &args.compare_ua {
+        let profile = UserAgentProfile::from(*profile);
+        if !profiles.contains(&profile) {
+            profiles.push(profile);
+        }
+    }
+    if profiles.len() < 2 {
+        bail!("--compare-ua needs at least two different profiles, e.g. mobile,desktop");
+    }
+
+    let normalized_urls = args
+        .url
+        .iter()
+        .map(|url| normalize_target_url(url))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scans = profiles
+        .iter()
+        .map(|profile| {
+            if !args.quiet {
+                eprintln!("Scanning as {} ...", profile.as_str());
+            }
+            let options = ExtractOptions {
+                user_agent: profile.user_agent().to_owned(),
+                ..args.scan.extract_options()
+            };
+            extract_fonts_with_options(&normalized_urls, &args.scan, &options, args.quiet)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut failed_seeds = Vec::new();
+    let scans = scans
+        .into_iter()
+        .map(|(fonts, failed)| {
+            failed_seeds.extend(failed);
+            fonts
+        })
+        .collect::<Vec<_>>();
+    failed_seeds.sort_unstable();
+    failed_seeds.dedup();
+    let compared = compare_scans(&scans);
+
+    if compared.is_empty() && !args.no_fail_on_empty {
+        return Err(CliFailure::new(
+            ExitStatus::NoFonts,
+            format!("no fonts were found on {}", normalized_urls.join(", ")),
+        )
+        .into());
+    }
+
+    let output = UaComparisonOutput {
+        source: normalized_urls.join(", "),
+        profiles: profiles.iter().map(|profile| profile.as_str()).collect(),
+        total_found: compared.len(),
+        differing: compared.iter().filter(|font| font.differs()).count(),
+        fonts: compared
+            .iter()
+            .map(|font| UaFontOutput {
+                family: font.family.clone(),
+                weight: font.weight.clone(),
+                style: font.style.clone(),
+                format: font.format.clone(),
+                url: font.url.clone(),
+                delivered_to: profiles
+                    .iter()
+                    .zip(&font.delivered)
+                    .filter(|(_, delivered)| **delivered)
+                    .map(|(profile, _)| profile.as_str())
+                    .collect(),
+                differs: font.differs(),
+            })
+            .collect(),
+    };
+
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+        OutputFormat::Pretty => print_ua_comparison_pretty(&output, &compared),
+    }
+    partial_scan_result(&failed_seeds)
+}
+
+/// Only the differing files, marked `-` when the first profile got them and
+/// `+` when it did not, like a diff against the first profile.
+fn print_ua_comparison_pretty(output: &UaComparisonOutput, compared: &[DeliveredFont]) {
+    println!(
+        "Compared {} font file(s) on {} as {}",
+        output.total_found,
+        output.source,
+        output.profiles.join(", ")
+    );
+    if output.differing == 0 {
+        println!("Every user agent was served the same fonts.");
+        return;
+    }
+
+    let mut header = vec!["", "Family", "Weight", "Style", "Format"];
+    header.extend(&output.profiles);
+    header.push("URL");
+    let mut table = cli_table(header);
+    for font in compared.iter().filter(|font| font.differs()) {
+        let marker = match font.delivered.first() {
+            Some(true) => "-",
+            _ => "+",
+        };
+        let mut row = vec![
+            Cell::new(marker),
+            Cell::new(&font.family),
+            Cell::new(&font.weight),
+            Cell::new(&font.style),
+            Cell::new(&font.format),
+        ];
+        row.extend(
+            font.delivered
+                .iter()
+                .map(|delivered| Cell::new(if *delivered { "yes" } else { "-" })),
+        );
+        row.push(Cell::new(display_url(&font.url)));
+        table.add_row(row);
+    }
+
+    println!(
+        "{} file(s) differ between user agents ({} served to all):\n{table}",
+        output.differing,
+        output.total_found - output.differing
+    );
+}
+
+fn print_network_trace(format: OutputFormat, events: &[NetworkEvent]) -> Result<()> {
+    if format == OutputFormat::Json {
+        let output = events
+            .iter()
+            .map(NetworkEventOutput::new)
+            .collect::<Vec<_>>();
+        eprintln!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let mut table = cli_table(["Method", "Status", "Time", "Bytes", "Cache", "URL"]);
+    for event in events {
+        table.add_row([
+            Cell::new(event.method.as_str()),
+            Cell::new(match (event.status, &event.error) {
+                (Some(status), _) => status.to_string(),
+                (None, Some(error)) => truncate_for_cli(error, 40),
+                (None, None) => "-".to_owned(),
+            }),
+            Cell::new(format!("{} ms", event.duration.as_millis())),
+            Cell::new(event.bytes),
+            Cell::new(match event.cache_hit {
+                Some(true) => "hit",
+                Some(false) => "miss",
+                None => "-",
+            }),
+            Cell::new(display_url(&event.url)),
+        ]);
+    }
+
+    let total_bytes = events.iter().map(|event| event.bytes).sum::<u64>();
+    eprintln!(
+        "\nNetwork trace: {} request(s), {total_bytes} bytes\n{table}",
+        events.len()
+    );
+    Ok(())
+}
+
+fn exit_code_for(error: &anyhow::Error) -> ExitCode {
+    error
+        .downcast_ref::<CliFailure>()
+        .map_or(ExitCode::FAILURE, |failure| {
+            ExitCode::from(failure.status as u8)
+        })
+}
+
+fn extract_fonts(normalized_url: &str, scan: &ScanArgs, quiet: bool) -> Result<Vec<FontInfo>> {
+    extract_fonts_from_seeds(&[normalized_url.to_owned()], scan, quiet).map(|(fonts, _)| fonts)
+}
+
+/// Also returns the seeds that failed while others were scanned.
+fn extract_fonts_from_seeds(
+    normalized_urls: &[String],
+    scan: &ScanArgs,
+    quiet: bool,
+) -> Result<(Vec<FontInfo>, Vec<String>)> {
+    extract_fonts_with_options(normalized_urls, scan, &scan.extract_options(), quiet)
+}
+
+fn extract_fonts_with_options(
+    normalized_urls: &[String],
+    scan: &ScanArgs,
+    options: &ExtractOptions,
+    quiet: bool,
+) -> Result<(Vec<FontInfo>, Vec<String>)> {
+    extract_fonts_with_gaps(normalized_urls, scan, options, quiet)
+        .map(|(fonts, gaps)| (fonts, failed_seeds(normalized_urls, &gaps)))
+}
+
+/// The seeds among `normalized_urls` that `gaps` reports as failed.
+fn failed_seeds(normalized_urls: &[String], gaps: &[ExtractEvent]) -> Vec<String> {
+    gaps.iter()
+        .filter_map(|event| match event {
+            ExtractEvent::PageFailed { url } if normalized_urls.contains(url) => Some(url.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Fails with [`ExitStatus::PartialScan`] when some seeds could not be
+/// scanned, after the command has reported on the others.
+fn partial_scan_result(failed_seeds: &[String]) -> Result<()> {
+    if failed_seeds.is_empty() {
+        return Ok(());
+    }
+    Err(CliFailure::new(
+        ExitStatus::PartialScan,
+        format!(
+            "could not scan {}; results cover the other pages",
+            failed_seeds.join(", ")
+        ),
+    )
+    .into())
+}
+
+/// Also returns the events that left part of the site unscanned.
+fn extract_fonts_with_gaps(
+    normalized_urls: &[String],
+    scan: &ScanArgs,
+    options: &ExtractOptions,
+    quiet: bool,
+) -> Result<(Vec<FontInfo>, Vec<ExtractEvent>)> {
+    let fetcher = scan
+        .fetcher(DEFAULT_TIMEOUT)
+        .context("failed to create HTTP client")?;
+    let mut gaps = Vec::new();
+    extract_fonts_from_urls_with_fetcher(
+        normalized_urls,
+        &fetcher,
+        options,
+        &CancellationToken::new(),
+        |event| {
+            if event.leaves_gap() {
+                gaps.push(event.clone());
+            }
+            if !quiet {
+                print_extract_event(event);
+            }
+        },
+    )
+    .map(|fonts| (fonts, gaps))
+    .map_err(|error| {
+        let message = format!(
+            "failed to extract fonts from {}",
+            normalized_urls.join(", ")
+        );
+        // Only transport failures are network errors; HTTP error statuses,
+        // cancellation, and bad URLs are not.
+        match error {
+            ExtractError::Request { .. } => {
+                anyhow::Error::new(error).context(CliFailure::new(ExitStatus::Network, message))
+            }
+            _ => anyhow::Error::new(error).context(message),
+        }
+    })
+}
+
+fn run_inspect(args: InspectArgs, options: &InferenceOptions) -> Result<()> {
+    if !args.compare_ua.is_empty() {
+        return run_compare_ua(&args);
+    }
+    let (normalized_urls, local) = match &args.dir {
+        Some(dir) => (
+            vec![dir.display().to_string()],
+            Some(scan_local_directory(dir, &args.scan, args.quiet)?),
+        ),
+        None => (
+            args.url
+                .iter()
+                .map(|url| normalize_target_url(url))
+                .collect::<Result<Vec<_>, _>>()?,
+            None,
+        ),
+    };
+    let normalized_url = normalized_urls.join(", ");
+    let (mut fonts, failed_seeds, pages, missing) = match local {
+        Some(scan) => (
+            scan.fonts,
+            Vec::new(),
+            Some(scan.pages),
+            scan.missing.iter().map(MissingFontOutput::new).collect(),
+        ),
+        None => {
+            let (fonts, failed_seeds) =
+                extract_fonts_from_seeds(&normalized_urls, &args.scan, args.quiet)?;
+            (fonts, failed_seeds, None, Vec::new())
+        }
+    };
+    if fonts.is_empty() {
+        render_empty_inspect(
+            &normalized_urls,
+            args.view.into(),
+            args.group_by.into(),
+            args.format,
+        )?;
+        if args.no_fail_on_empty {
+            return partial_scan_result(&failed_seeds);
+        }
+        return Err(CliFailure::new(
+            ExitStatus::NoFonts,
+            format!("no fonts were found on {normalized_url}"),
+        )
+        .into());
+    }
+
+    if args.sizes || args.perf || args.min_size.is_some() || args.max_size.is_some() {
+        enrich_inspected_fonts(&mut fonts, args.dir.as_deref(), &args.scan, args.quiet)?;
+    }
+
+    let all_indices = (0..fonts.len()).collect::<Vec<_>>();
+    let filtered_indices =
+        filter_indices_by_provider(&fonts, &all_indices, &font_providers(&args.provider));
+    let filtered_indices = filter_indices_by_weight(
+        &fonts,
+        &filtered_indices,
+        args.weight.min_weight,
+        args.weight.max_weight,
+    );
+    let filtered_indices = filter_indices_by_size(
+        &fonts,
+        &filtered_indices,
+        args.min_size.map(|kib| kib * 1024),
+        args.max_size.map(|kib| kib * 1024),
+    );
+    // Families are matched after probing, so only the filters above narrow
+    // what is probed.
+    let inference = &resolve_inference(
+        options,
+        &fonts,
+        &filtered_indices,
+        args.infer_from,
+        &args.scan,
+        args.quiet,
+    )?;
+    let filtered_indices = if args.family.is_empty() {
+        filtered_indices
+    } else {
+        select_indices_by_inferred_family_names(&fonts, &args.family, inference)
+            .into_iter()
+            .filter(|index| filtered_indices.contains(index))
+            .collect()
+    };
+
+    if filtered_indices.is_empty() {
+        return Err(CliFailure::new(
+            ExitStatus::NoMatch,
+            "no fonts matched requested family, provider, weight, or size filter",
+        )
+        .into());
+    }
+
+    if args.perf {
+        run_perf_checks(&args, &normalized_url, &fonts, &filtered_indices)?;
+        return partial_scan_result(&failed_seeds);
+    }
+
+    let families = infer_family_groups(&fonts, &filtered_indices, inference);
+    if args.stats {
+        let stats = font_stats(
+            &fonts,
+            &families,
+            &inference.binary_metadata,
+            HEAVIEST_FAMILIES,
+        );
+        match args.format {
+            OutputFormat::Pretty => print_stats_pretty(&normalized_url, &stats, args.sizes),
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&StatsOutput::new(&normalized_url, &stats))?
+            ),
+        }
+        return partial_scan_result(&failed_seeds);
+    }
+
+    let fontsource = if args.fontsource || args.fontsource_api {
+        fontsource_suggestions(&families, args.fontsource_api, &args.scan, args.quiet)?
+    } else {
+        Vec::new()
+    };
+    let groups = match args.group_by {
+        GroupAxis::Family => families,
+        group_by => infer_family_groups_by(&fonts, &filtered_indices, inference, group_by.into()),
+    };
+    let mut grouped_output = InspectOutput::new(
+        &normalized_urls,
+        &fonts,
+        args.view.into(),
+        args.group_by.into(),
+        groups,
+    );
+    grouped_output.fontsource = fontsource;
+    if let Some(pages) = pages {
+        grouped_output.pages = pages;
+    }
+    grouped_output.missing = missing;
+
+    match args.format {
+        OutputFormat::Pretty => {
+            print_inspect_pretty(&grouped_output);
+            print_missing_fonts(&grouped_output.missing);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&grouped_output)?),
+    }
+
+    partial_scan_result(&failed_seeds)
+}
+
+fn run_perf_checks(
+    args: &InspectArgs,
+    source: &str,
+    fonts: &[FontInfo],
+    indices: &[usize],
+) -> Result<()> {
+    let selected = indices
+        .iter()
+        .map(|index| fonts[*index].clone())
+        .collect::<Vec<_>>();
+    let options = PerfOptions {
+        budget_bytes: args.perf_budget.saturating_mul(1024),
+        max_variants: args.max_variants,
+    };
+    let report = check_font_performance(&selected, &options);
+    let output = PerfOutput::new(source, &report, &options);
+    match args.format {
+        OutputFormat::Pretty => print_perf_pretty(&output),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+    }
+
+    if report.status() == PerfStatus::Fail {
+        let failed = report
+            .findings
+            .iter()
+            .filter(|finding| finding.status == PerfStatus::Fail)
+            .map(|finding| finding.check.id())
+            .collect::<Vec<_>>();
+        return Err(CliFailure::new(
+            ExitStatus::PerfFailed,
+            format!(
+                "font performance checks failed for {source}: {}",
+                failed.join(", ")
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn scan_local_directory(dir: &Path, scan: &ScanArgs, quiet: bool) -> Result<DirectoryScan> {
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+
+    scan_directory(
+        dir,
+        &scan.extract_options(),
+        &CancellationToken::new(),
+        |event| {
+            if !quiet {
+                print_extract_event(event);
+            }
+        },
+    )
+    .with_context(|| format!("failed to scan {}", dir.display()))
+}
+
+/// Fills in sizes, modification dates, and content types, reading files from
+/// `dir` for local scans.
+fn enrich_inspected_fonts(
+    fonts: &mut [FontInfo],
+    dir: Option<&Path>,
+    scan: &ScanArgs,
+    quiet: bool,
+) -> Result<()> {
+    if !quiet {
+        eprintln!("Requesting headers for {} font(s) ...", fonts.len());
+    }
+
+    let options = EnrichOptions::default();
+    let cancel = CancellationToken::new();
+    match dir {
+        Some(dir) => enrich_fonts(fonts, &DirectoryFetcher::new(dir), &options, &cancel),
+        None => {
+            let fetcher = scan
+                .fetcher(Duration::from_secs(30))
+                .context("failed to create HTTP client for font headers")?;
+            enrich_fonts(fonts, &fetcher, &options, &cancel);
+        }
+    }
+    Ok(())
+}
+
+/// Where human-readable summaries go. `download --format json` keeps stdout
+/// for the report, so they move to stderr.
+#[derive(Clone, Copy, Debug, Default)]
+struct Console {
+    stderr: bool,
+}
+
+impl Console {
+    fn line(self, message: impl fmt::Display) {
+        if self.stderr {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+}
+
+/// Download progress on stderr: an overall bar and one for the file in
+/// flight when stderr is a terminal, a `[current/total] name` line per file
+/// otherwise.
+struct DownloadBars {
+    bars: Option<(MultiProgress, ProgressBar)>,
+    file: Option<ProgressBar>,
+}
+
+impl DownloadBars {
+    fn new(total: usize) -> Self {
+        let bars = io::stderr().is_terminal().then(|| {
+            let multi = MultiProgress::new();
+            let overall = multi.add(
+                ProgressBar::new(total as u64)
+                    .with_style(progress_style("[{bar:30}] {pos}/{len} files, ETA {eta}")),
+            );
+            (multi, overall)
+        });
+        Self { bars, file: None }
+    }
+
+    fn update(&mut self, progress: DownloadProgress<'_>) {
+        let Some((multi, overall)) = &self.bars else {
+            if progress.is_start() {
+                eprintln!(
+                    "[{}/{}] {}",
+                    progress.current, progress.total, progress.font.name
+                );
+            }
+            return;
+        };
+
+        if progress.is_start() {
+            if let Some(file) = self.file.take() {
+                file.finish_and_clear();
+            }
+            overall.set_position(progress.current as u64 - 1);
+            let _ = multi.println(format!(
+                "[{}/{}] {}",
+                progress.current, progress.total, progress.font.name
+            ));
+            let file = multi.insert_before(
+                overall,
+                ProgressBar::no_length()
+                    .with_style(progress_style(
+                        "{msg:32!} {spinner} {binary_bytes} {binary_bytes_per_sec}",
+                    ))
+                    .with_message(progress.font.name.clone()),
+            );
+            self.file = Some(file);
+        }
+
+        let Some(file) = &self.file else {
+            return;
+        };
+        // Lengths may only be known, or corrected, once the response arrives.
+        if let Some(length) = progress.content_length
+            && file.length() != Some(length)
+        {
+            if file.length().is_none() {
+                file.set_style(progress_style(
+                    "{msg:32!} [{bar:30}] {binary_bytes}/{binary_total_bytes} {binary_bytes_per_sec} ETA {eta}",
+                ));
+            }
+            file.set_length(length);
+        }
+        file.set_position(progress.received);
+    }
+
+    /// Clears the bars before the summary is printed.
+    fn finish(self) {
+        if let Some(file) = self.file {
+            file.finish_and_clear();
+        }
+        if let Some((_, overall)) = self.bars {
+            overall.finish_and_clear();
+        }
+    }
+}
+
+fn progress_style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("progress templates are valid")
+        .progress_chars("=> ")
+}
+
+/// Fonts chosen by `download` or `vendor` from a scanned page.
+struct ScanSelection {
+    /// The normalized page URL.
+    url: String,
+    fonts: Vec<FontInfo>,
+    /// The index each font is shown and selected by: its position, or its
+    /// index in a saved inspect document.
+    labels: Vec<usize>,
+    indices: Vec<usize>,
+    inference: Inference,
+    /// Events that left part of the site unscanned.
+    gaps: Vec<ExtractEvent>,
+}
+
+/// Where [`scan_and_select`] finds the fonts.
+#[derive(Clone, Copy, Debug)]
+enum ScanSource<'a> {
+    Url(&'a str),
+    /// A saved font-view inspect document; `-` is standard input.
+    Saved(&'a Path),
+}
+
+/// Scans `url` and applies the selectors. The selection is empty only when
+/// the page has no fonts and that is allowed.
+fn scan_and_select(
+    source: ScanSource<'_>,
+    scan: &ScanArgs,
+    selection: &SelectionArgs,
+    infer_from: InferFrom,
+    quiet: bool,
+    no_fail_on_empty: bool,
+    options: &InferenceOptions,
+) -> Result<ScanSelection> {
+    let (normalized_url, mut fonts, labels, gaps) = match source {
+        ScanSource::Url(url) => {
+            let normalized_url = normalize_target_url(url)?;
+            let (fonts, gaps) = extract_fonts_with_gaps(
+                std::slice::from_ref(&normalized_url),
+                scan,
+                &scan.extract_options(),
+                quiet,
+            )?;
+            let labels = (0..fonts.len()).collect();
+            (normalized_url, fonts, labels, gaps)
+        }
+        ScanSource::Saved(path) => {
+            let saved = InspectFonts::read(path)
+                .with_context(|| format!("could not load {}", path.display()))?;
+            (
+                saved.pages.join(", "),
+                saved.fonts,
+                saved.indices,
+                Vec::new(),
+            )
+        }
+    };
+    prefer_source_formats(&mut fonts, &format_labels(&selection.only_format));
+
+    if fonts.is_empty() {
+        if no_fail_on_empty {
+            return Ok(ScanSelection {
+                url: normalized_url,
+                fonts,
+                labels,
+                indices: Vec::new(),
+                inference: Inference::from(options.clone()),
+                gaps,
+            });
+        }
+        return Err(CliFailure::new(
+            ExitStatus::NoFonts,
+            format!("no fonts were found on {normalized_url}"),
+        )
+        .into());
+    }
+
+    if !has_download_selectors(selection) {
+        bail!(
+            "no selection provided. Use --all, --select-file, or one of --family/--font-name/--font-url/--index/--id"
+        );
+    }
+
+    // Only family selectors depend on what probing reads, so without them
+    // just the selected fonts are probed.
+    let candidates = if selection.family.is_empty() {
+        let by_name = Inference::from(options.clone());
+        resolve_download_indices(&fonts, &labels, selection, &by_name).0
+    } else {
+        filter_selected_indices(&fonts, &(0..fonts.len()).collect::<Vec<_>>(), selection)
+    };
+    let inference = resolve_inference(options, &fonts, &candidates, infer_from, scan, quiet)?;
+    let (indices, report) = resolve_download_indices(&fonts, &labels, selection, &inference);
+    if !quiet {
+        print_selector_matches(&fonts, &report);
+    }
+    let indices = one_file_per_face(
+        &mut fonts,
+        &indices,
+        &format_labels(&selection.prefer_format),
+    );
+    if indices.is_empty() {
+        return Err(CliFailure::new(
+            ExitStatus::NoMatch,
+            "no fonts matched the provided selectors",
+        )
+        .into());
+    }
+    check_commercial_fonts(&fonts, &indices, selection.block_commercial)?;
+
+    Ok(ScanSelection {
+        url: normalized_url,
+        fonts,
+        labels,
+        indices,
+        inference,
+        gaps,
+    })
+}
+
+/// Warns about selected fonts from commercial foundries, whose licenses
+/// cover only the website that bought them, or refuses them when `block`.
+fn check_commercial_fonts(fonts: &[FontInfo], indices: &[usize], block: bool) -> Result<()> {
+    let commercial = indices
+        .iter()
+        .filter_map(|index| fonts.get(*index))
+        .filter_map(|font| CommercialFoundry::for_font(font).map(|foundry| (font, foundry)))
+        .collect::<Vec<_>>();
+    if commercial.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!(
+        "\nWarning: {} selected font(s) come from commercial foundries. Their licenses \
+         usually cover only the website that bought them; do not reuse them without \
+         your own license:",
+        commercial.len()
+    );
+    for (font, foundry) in &commercial {
+        eprintln!("- {} ({})", font.name, foundry.name);
+    }
+
+    if block {
+        return Err(CliFailure::new(
+            ExitStatus::CommercialFonts,
+            format!(
+                "refusing to continue with {} font(s) from commercial foundries (--block-commercial)",
+                commercial.len()
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn run_download(mut args: DownloadArgs, options: &InferenceOptions) -> Result<()> {
+    args.selection.load_select_file()?;
+    let json = args.format == OutputFormat::Json;
+    let console = Console { stderr: json };
+    let remote_sink = args.remote_sink()?;
+    if remote_sink.is_some()
+        && (args.mirror || args.specimens || args.subset_to_page || args.compress_woff2)
+    {
+        bail!(
+            "--mirror, --specimens, --subset-to-page, and --compress-woff2 write next to the fonts, so they need a local --output"
+        );
+    }
+    if remote_sink.is_some() && args.emit.is_some() {
+        bail!("--emit refers to the saved files by path, so it needs a local --output");
+    }
+    let ScanSelection {
+        url,
+        fonts,
+        labels,
+        indices: selected_indices,
+        inference,
+        ..
+    } = scan_and_select(
+        args.source(),
+        &args.scan,
+        &args.selection,
+        args.infer_from,
+        args.quiet,
+        args.no_fail_on_empty,
+        options,
+    )?;
+    if fonts.is_empty() {
+        console.line(format_args!("No fonts found on {url}"));
+        if json {
+            print_download_json(
+                &args,
+                &DownloadReport::default(),
+                PostProcessing::default(),
+                &[],
+            )?;
+        }
+        return Ok(());
+    }
+    print_download_selection_pretty(
+        &url,
+        &fonts,
+        &labels,
+        &selected_indices,
+        &inference,
+        console,
+    );
+    let mut selected_fonts = select_fonts(&fonts, &selected_indices);
+    let lock = match args.locked {
+        Some(mode) => {
+            let lock = FontLock::load(&args.lockfile())?;
+            report_lock_mismatches(mode, &args.lockfile(), &lock.compare_fonts(&selected_fonts))?;
+            Some((mode, lock))
+        }
+        None => None,
+    };
+
+    if args.dry_run {
+        console.line("\nDry run enabled; no files were downloaded.");
+        if json {
+            print_download_json(
+                &args,
+                &DownloadReport::default(),
+                PostProcessing::default(),
+                &[],
+            )?;
+        }
+        return Ok(());
+    }
+
+    let total = selected_fonts.len();
+
+    eprintln!(
+        "\nDownloading {total} fonts into {} ...",
+        args.destination()
+    );
+
+    let layout = DownloadLayout {
+        directory: args.layout.clone(),
+        file_name: args.name_template.clone(),
+        on_conflict: args.on_conflict.into(),
+        inference: inference.clone(),
+        referer: args.request.referer.clone(),
+        omit_origin: args.request.no_origin,
+        omit_server_mtime: args.file_time.no_server_mtime,
+        sidecar: args.sidecar,
+        rename_from_metadata: args.rename_from_metadata,
+        http: args.scan.http_options(),
+    };
+    let collisions = download::colliding_targets(&selected_fonts, &layout);
+    if !collisions.is_empty() {
+        eprintln!(
+            "Warning: the layout maps several fonts to {} path(s); duplicates get -1, -2 suffixes:",
+            collisions.len()
+        );
+        for path in &collisions {
+            eprintln!("- {}", path.display());
+        }
+    }
+    let mut bars = DownloadBars::new(total);
+    let cancel = CancellationToken::new();
+    let session = args
+        .scan
+        .session()
+        .context("failed to create HTTP client for downloads")?;
+    let fetcher = match args.limit_rate {
+        // Throttled bodies take longer to arrive, so allow far more than the
+        // default per-request timeout.
+        Some(limit) => session
+            .inner()
+            .clone()
+            .with_timeout(Duration::from_secs(600))
+            .with_rate_limit(limit),
+        None => session
+            .inner()
+            .clone()
+            .with_timeout(DEFAULT_DOWNLOAD_TIMEOUT),
+    };
+    let local_sink = DirectorySink::new(&args.output);
+    let sink: &dyn OutputSink = match &remote_sink {
+        Some(sink) => sink,
+        None => &local_sink,
+    };
+    let mut report = Downloader::builder()
+        .layout(layout)
+        .fetcher(args.scan.traced(session.share(fetcher.clone())))
+        .build()?
+        .download_to_sink(&selected_fonts, sink, &cancel, |progress| {
+            bars.update(progress)
+        });
+    bars.finish();
+
+    console.line(format_args!(
+        "\nDownloaded {}/{} fonts into {}",
+        report.success_count(),
+        report.attempted,
+        args.destination()
+    ));
+    print_download_warnings(&report.warnings);
+
+    // Checked before post-processing rewrites the files.
+    if let Some((mode, lock)) = &lock {
+        report_lock_mismatches(
+            *mode,
+            &args.lockfile(),
+            &lock.compare_saved(&report.saved_files),
+        )?;
+    } else if let Some(path) = &args.lockfile {
+        if report.failures.is_empty() && !report.cancelled {
+            FontLock::new(&url, &selected_fonts, &report.saved_files).write(path)?;
+            console.line(format_args!("Wrote {}", path.display()));
+        } else {
+            eprintln!(
+                "Warning: not writing {} since not every font was downloaded",
+                path.display()
+            );
+        }
+    }
+
+    let mut subset = None;
+    if args.subset_to_page && !report.cancelled {
+        let pages = std::iter::once(&url)
+            .chain(selected_fonts.iter().flat_map(|font| &font.found_on))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        subset = Some(subset_to_pages(
+            &args.scan,
+            &pages.into_iter().collect::<Vec<_>>(),
+            &mut report.saved_files,
+            console,
+        )?);
+    }
+
+    let mut compressed = None;
+    if args.compress_woff2 && !report.cancelled {
+        let compress = compress_saved_files(&mut report.saved_files, args.keep_original);
+        print_compress_report(&compress, console);
+        // Generated CSS should declare the files as they are now.
+        for font in &mut selected_fonts {
+            if compress.compressed.iter().any(|file| file.url == font.url) {
+                font.format = FontContainer::Woff2.format().to_owned();
+            }
+        }
+        compressed = Some(compress);
+    }
+
+    if let Some(path) = &args.checksum_file {
+        write_checksum_file(path, &args.output, &report.saved_files)?;
+        console.line(format_args!("Wrote checksums to {}", path.display()));
+    }
+
+    print_identical_fonts(&selected_fonts, &report.saved_files, &inference, console);
+    write_theme_files(&args.theme, &selected_fonts, &report.saved_files, console)?;
+    if args.specimens {
+        write_specimens(&selected_fonts, &report.saved_files, &args.output, console)?;
+    }
+    let snippet = match args.emit {
+        Some(framework) => Some(emit_snippet(
+            framework.into(),
+            args.emit_to.as_deref(),
+            &selected_fonts,
+            &report.saved_files,
+            console,
+        )?),
+        None => None,
+    };
+
+    let mut mirror_failures = Vec::new();
+    if args.mirror && !report.cancelled {
+        // Stylesheets share the fonts' session, rate limit, and HTTP options.
+        let fetcher = args.scan.traced(session.share(fetcher));
+        let mirror = mirror_stylesheets(&selected_fonts, &report, &args.output, &fetcher, &cancel);
+        console.line(format_args!(
+            "Mirrored {} stylesheet(s) ({} reference(s) rewritten to local files)",
+            mirror.stylesheets.len(),
+            mirror
+                .stylesheets
+                .iter()
+                .map(|stylesheet| stylesheet.rewritten)
+                .sum::<usize>()
+        ));
+        if mirror.generated_fonts > 0 {
+            console.line(format_args!(
+                "Generated @font-face rules for {} font(s) declared outside a fetchable stylesheet",
+                mirror.generated_fonts
+            ));
+        }
+        if let Some(path) = &mirror.index_path {
+            console.line(format_args!("Wrote {}", path.display()));
+        }
+        mirror_failures = mirror.failures;
+    }
+
+    if !report.skipped_files.is_empty() {
+        console.line(format_args!(
+            "Skipped {} font(s) whose target file already exists",
+            report.skipped_files.len()
+        ));
+    }
+
+    if json {
+        let post = PostProcessing {
+            subset: subset.as_ref(),
+            compressed: compressed.as_ref(),
+            snippet: snippet.as_deref(),
+        };
+        print_download_json(&args, &report, post, &mirror_failures)?;
+    }
+
+    let mut failures = report.failures;
+    failures.extend(mirror_failures);
+    fail_on_download_errors(&failures)
+}
+
+/// Lists the differences from the lockfile at `path`; they are an error
+/// unless `mode` only warns.
+fn report_lock_mismatches(mode: LockMode, path: &Path, mismatches: &[LockMismatch]) -> Result<()> {
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    eprintln!(
+        "{} difference(s) from {}:",
+        mismatches.len(),
+        path.display()
+    );
+    for mismatch in mismatches {
+        eprintln!("- {mismatch}");
+    }
+
+    match mode {
+        LockMode::Warn => Ok(()),
+        LockMode::Fail => Err(CliFailure::new(
+            ExitStatus::LockMismatch,
+            format!("the site's fonts no longer match {}", path.display()),
+        )
+        .into()),
+    }
+}
+
+/// Fetches the pages again, normally from the session cache, and subsets
+/// the saved fonts to their visible text.
+fn subset_to_pages(
+    scan: &ScanArgs,
+    pages: &[String],
+    saved_files: &mut [SavedFile],
+    console: Console,
+) -> Result<SubsetReport> {
+    let fetcher = scan
+        .fetcher(Duration::from_secs(30))
+        .context("failed to create HTTP client for page text")?;
+    let characters = page_characters(pages, &fetcher, &scan.extract_options().user_agent)
+        .context("failed to collect page text for --subset-to-page")?;
+    let report = subset_saved_files(saved_files, &characters);
+
+    console.line(format_args!(
+        "\nSubset {} font(s) to {} character(s) from {} page(s):",
+        report.subset.len(),
+        characters.len(),
+        pages.len()
+    ));
+    for file in &report.subset {
+        console.line(format_args!(
+            "- {}: {} -> {}",
+            file.path.display(),
+            format_bytes(file.original_size),
+            format_bytes(file.subset_size)
+        ));
+    }
+    for (path, error) in &report.skipped {
+        console.line(format_args!(
+            "- {}: kept as downloaded ({})",
+            path.display(),
+            error_chain(error)
+        ));
+    }
+    if !report.subset.is_empty() {
+        let (original, subset) = (report.original_size(), report.subset_size());
+        console.line(format_args!(
+            "Saved {} of {} ({:.0}%)",
+            format_bytes(original - subset),
+            format_bytes(original),
+            (original - subset) as f64 * 100.0 / original.max(1) as f64
+        ));
+    }
+    Ok(report)
+}
+
+/// Builds the `--emit` snippet with paths relative to `emit_to`, or to the
+/// current directory, and writes it there or prints it. Returns the code.
+fn emit_snippet(
+    framework: Framework,
+    emit_to: Option<&Path>,
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+    console: Console,
+) -> Result<String> {
+    let base = emit_to
+        .and_then(Path::parent)
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let base = std::path::absolute(base).context("failed to resolve the --emit-to directory")?;
+    let hrefs = saved_files
+        .iter()
+        .map(|saved| {
+            let path = std::path::absolute(&saved.path)?;
+            Ok((saved.url.as_str(), relative_href(&base, &path)))
+        })
+        .collect::<std::io::Result<Vec<_>>>()
+        .context("failed to resolve saved font paths")?;
+    let local_fonts = fonts
+        .iter()
+        .filter_map(|font| {
+            let (_, href) = hrefs.iter().find(|(url, _)| *url == font.url)?;
+            Some(LocalFont { font, href })
+        })
+        .collect::<Vec<_>>();
+    let code = framework_snippet(framework, &local_fonts);
+
+    match emit_to {
+        Some(path) => {
+            fs::create_dir_all(&base)
+                .with_context(|| format!("failed to create {}", base.display()))?;
+            fs::write(path, &code)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            console.line(format_args!(
+                "Wrote the {} snippet to {}",
+                framework.as_str(),
+                path.display()
+            ));
+        }
+        // JSON output carries the snippet instead.
+        None if console.stderr => {}
+        None => println!(
+            "\n{} snippet (paths relative to the current directory):\n\n{code}",
+            framework.as_str()
+        ),
+    }
+    Ok(code)
+}
+
+fn print_compress_report(report: &CompressReport, console: Console) {
+    console.line(format_args!(
+        "\nConverted {} font(s) to WOFF2:",
+        report.compressed.len()
+    ));
+    for file in &report.compressed {
+        console.line(format_args!(
+            "- {} -> {}: {} -> {}",
+            file.original.display(),
+            file.path.display(),
+            format_bytes(file.original_size),
+            format_bytes(file.compressed_size)
+        ));
+    }
+    for (path, error) in &report.skipped {
+        console.line(format_args!(
+            "- {}: kept as downloaded ({})",
+            path.display(),
+            error_chain(error)
+        ));
+    }
+}
+
+/// Results of the steps run on the saved files after the download.
+#[derive(Clone, Copy, Default)]
+struct PostProcessing<'a> {
+    subset: Option<&'a SubsetReport>,
+    compressed: Option<&'a CompressReport>,
+    snippet: Option<&'a str>,
+}
+
+/// Prints the download report on stdout for `--format json`.
+fn print_download_json(
+    args: &DownloadArgs,
+    report: &DownloadReport,
+    post: PostProcessing<'_>,
+    mirror_failures: &[DownloadFailure],
+) -> Result<()> {
+    let output = DownloadOutput {
+        schema_version: output::SCHEMA_VERSION,
+        source: match args.source() {
+            ScanSource::Url(url) => url.to_owned(),
+            ScanSource::Saved(path) => path.display().to_string(),
+        },
+        output: args.output.clone(),
+        output_cmd: args.output_cmd.clone(),
+        dry_run: args.dry_run,
+        attempted: report.attempted,
+        saved: report
+            .saved_files
+            .iter()
+            .map(|saved| SavedFileOutput {
+                path: saved.path.clone(),
+                url: saved.url.clone(),
+                size: saved.size,
+                sha256: saved.sha256.clone(),
+            })
+            .collect(),
+        skipped: report
+            .skipped_files
+            .iter()
+            .map(|skipped| skipped.path.clone())
+            .collect(),
+        failures: report
+            .failures
+            .iter()
+            .chain(mirror_failures)
+            .map(|failure| DownloadFailureOutput {
+                name: failure.name.clone(),
+                url: failure.url.clone(),
+                kind: failure.error.kind(),
+                error: error_chain(&failure.error),
+            })
+            .collect(),
+        warnings: report
+            .warnings
+            .iter()
+            .map(|warning| DownloadFailureOutput {
+                name: warning.name.clone(),
+                url: warning.url.clone(),
+                kind: warning.error.kind(),
+                error: error_chain(&warning.error),
+            })
+            .collect(),
+        cancelled: report.cancelled,
+        subset: post.subset.map(|subset| SubsetOutput {
+            original_size: subset.original_size(),
+            subset_size: subset.subset_size(),
+            files: subset
+                .subset
+                .iter()
+                .map(|file| SubsetFileOutput {
+                    path: file.path.clone(),
+                    original_size: file.original_size,
+                    subset_size: file.subset_size,
+                })
+                .collect(),
+            skipped: subset
+                .skipped
+                .iter()
+                .map(|(path, error)| SkippedFileOutput {
+                    path: path.clone(),
+                    error: error_chain(error),
+                })
+                .collect(),
+        }),
+        snippet: post.snippet.map(|code| SnippetOutput {
+            framework: args.emit,
+            path: args.emit_to.clone(),
+            code: code.to_owned(),
+        }),
+        compressed: post.compressed.map(|compressed| CompressOutput {
+            original_size: compressed.original_size(),
+            compressed_size: compressed.compressed_size(),
+            files: compressed
+                .compressed
+                .iter()
+                .map(|file| CompressedFileOutput {
+                    original: file.original.clone(),
+                    path: file.path.clone(),
+                    original_size: file.original_size,
+                    compressed_size: file.compressed_size,
+                    kept_original: args.keep_original,
+                })
+                .collect(),
+            skipped: compressed
+                .skipped
+                .iter()
+                .map(|(path, error)| SkippedFileOutput {
+                    path: path.clone(),
+                    error: error_chain(error),
+                })
+                .collect(),
+        }),
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn run_audit(args: AuditArgs) -> Result<()> {
+    if let Some(path) = &args.url_file {
+        return run_audit_sites(&args, path);
+    }
+    let normalized_urls = args
+        .url
+        .iter()
+        .map(|url| normalize_target_url(url))
+        .collect::<Result<Vec<_>, _>>()?;
+    let source = normalized_urls.join(", ");
+    let (fonts, failed_seeds) = extract_fonts_from_seeds(&normalized_urls, &args.scan, args.quiet)?;
+    if fonts.is_empty() {
+        if args.no_fail_on_empty {
+            println!("No fonts found on {source}");
+            return partial_scan_result(&failed_seeds);
+        }
+        return Err(CliFailure::new(
+            ExitStatus::NoFonts,
+            format!("no fonts were found on {source}"),
+        )
+        .into());
+    }
+
+    let fetcher = args
+        .scan
+        .fetcher(Duration::from_secs(30))
+        .context("failed to create HTTP client for font probing")?;
+    let metadata = probe_font_metadata(&fonts, &fetcher, &CancellationToken::new());
+    if !args.quiet {
+        eprintln!(
+            "Measured {} of {} font files",
+            metadata
+                .values()
+                .filter(|entry| entry.size.is_some())
+                .count(),
+            fonts.len()
+        );
+    }
+
+    let report = audit_fonts(&fonts, &metadata, &audit_options(&args));
+    match args.format {
+        AuditFormat::Pretty => print_audit_pretty(&AuditOutput::new(&source, &fonts, &report)),
+        AuditFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&AuditOutput::new(&source, &fonts, &report))?
+        ),
+        AuditFormat::Csv => {
+            print_audit_csv(&[AuditSiteOutput::new(&source, &fonts, &metadata, &report)])
+        }
+    }
+
+    partial_scan_result(&failed_seeds)
+}
+
+fn audit_options(args: &AuditArgs) -> AuditOptions {
+    AuditOptions {
+        max_file_bytes: args.max_file_size.saturating_mul(1024),
+    }
+}
+
+/// Audits each site listed in `path` on its own. A site that cannot be
+/// scanned gets a row with its error; only a file of failures is an error.
+fn run_audit_sites(args: &AuditArgs, path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read URL file {}", path.display()))?;
+    let urls = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>();
+    if urls.is_empty() {
+        bail!("no URLs listed in {}", path.display());
+    }
+
+    let fetcher = args
+        .scan
+        .fetcher(Duration::from_secs(30))
+        .context("failed to create HTTP client for font probing")?;
+    let options = audit_options(args);
+    let mut sites = Vec::with_capacity(urls.len());
+    for (position, url) in urls.iter().enumerate() {
+        if !args.quiet {
+            eprintln!("Auditing {url} ({}/{})", position + 1, urls.len());
+        }
+        let site = audit_site(url, args, &fetcher, &options).unwrap_or_else(|error| {
+            if !args.quiet {
+                eprintln!("Warning: {error:#}");
+            }
+            AuditSiteOutput::failed(url, format!("{error:#}"))
+        });
+        sites.push(site);
+    }
+
+    match args.format {
+        AuditFormat::Pretty => print_audit_sites_pretty(&sites),
+        AuditFormat::Json => println!("{}", serde_json::to_string_pretty(&sites)?),
+        AuditFormat::Csv => print_audit_csv(&sites),
+    }
+
+    if sites.iter().all(|site| site.error.is_some()) {
+        return Err(CliFailure::new(
+            ExitStatus::Network,
+            format!("none of the sites in {} could be audited", path.display()),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn audit_site(
+    url: &str,
+    args: &AuditArgs,
+    fetcher: &dyn Fetcher,
+    options: &AuditOptions,
+) -> Result<AuditSiteOutput> {
+    let normalized = normalize_target_url(url)?;
+    let fonts = extract_fonts(&normalized, &args.scan, args.quiet)?;
+    let metadata = probe_font_metadata(&fonts, fetcher, &CancellationToken::new());
+    let report = audit_fonts(&fonts, &metadata, options);
+    Ok(AuditSiteOutput::new(
+        &normalized,
+        &fonts,
+        &metadata,
+        &report,
+    ))
+}
+
+fn run_check(args: CheckArgs) -> Result<()> {
+    let normalized_urls = args
+        .url
+        .iter()
+        .map(|url| normalize_target_url(url))
+        .collect::<Result<Vec<_>, _>>()?;
+    let source = normalized_urls.join(", ");
+    let (fonts, failed_seeds) = extract_fonts_from_seeds(&normalized_urls, &args.scan, args.quiet)?;
+    if fonts.is_empty() {
+        if args.no_fail_on_empty {
+            println!("No fonts found on {source}");
+            return partial_scan_result(&failed_seeds);
+        }
+        return Err(CliFailure::new(
+            ExitStatus::NoFonts,
+            format!("no fonts were found on {source}"),
+        )
+        .into());
+    }
+
+    let fetcher = args
+        .scan
+        .fetcher(Duration::from_secs(30))
+        .context("failed to create HTTP client for font checks")?;
+    if !args.quiet {
+        eprintln!("Checking {} font file(s) ...", fonts.len());
+    }
+    let checks = check_fonts(
+        &fonts,
+        &fetcher,
+        &CheckOptions::default(),
+        &CancellationToken::new(),
+    );
+
+    let output = CheckOutput::new(&source, &fonts, &checks);
+    match args.format {
+        OutputFormat::Pretty => print_check_pretty(&output),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+    }
+
+    let broken = checks.iter().filter(|check| !check.is_available()).count();
+    if broken > 0 {
+        return Err(CliFailure::new(
+            ExitStatus::Unavailable,
+            format!(
+                "{broken} of {} font file(s) on {source} could not be fetched",
+                checks.len()
+            ),
+        )
+        .into());
+    }
+    partial_scan_result(&failed_seeds)
+}
+
+fn run_update(args: UpdateArgs) -> Result<()> {
+    let lock = FontLock::load(&args.lockfile)?;
+    let fonts = extract_fonts(&lock.source, &args.scan, args.quiet)?;
+    let selected_fonts = select_fonts(&fonts, &lock.live_selection(&fonts));
+
+    if !args.quiet {
+        eprintln!("Hashing {} font file(s) ...", selected_fonts.len());
+    }
+    let layout = DownloadLayout {
+        referer: args.request.referer.clone(),
+        omit_origin: args.request.no_origin,
+        http: args.scan.http_options(),
+        ..DownloadLayout::default()
+    };
+    let fetcher = args
+        .scan
+        .fetcher(DEFAULT_DOWNLOAD_TIMEOUT)
+        .context("failed to create HTTP client for downloads")?;
+    let report = Downloader::builder()
+        .layout(layout)
+        .fetcher(fetcher)
+        .build()?
+        .download_to_sink(
+            &selected_fonts,
+            &DiscardSink,
+            &CancellationToken::new(),
+            |_| {},
+        );
+    // A font that could not be hashed would otherwise look removed.
+    fail_on_download_errors(&report.failures)?;
+
+    let updated = FontLock::new(&lock.source, &selected_fonts, &report.saved_files);
+    let changes = lock.compare_lock(&updated);
+    let mut output = UpdateOutput::new(&args.lockfile, &lock, &updated, &changes);
+    let json = args.format == OutputFormat::Json;
+    if !json {
+        print_update_pretty(&output);
+    }
+    if changes.is_empty() {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        return Ok(());
+    }
+
+    output.updated = if args.dry_run {
+        false
+    } else if args.yes {
+        true
+    } else {
+        // JSON output is read by scripts, which pass --yes instead.
+        !json
+            && confirm(&format!(
+                "Rewrite {} with these changes?",
+                args.lockfile.display()
+            ))?
+    };
+    if output.updated {
+        updated.write(&args.lockfile)?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if output.updated {
+        println!(
+            "\nUpdated {} ({} change(s))",
+            args.lockfile.display(),
+            changes.len()
+        );
+    } else if args.dry_run {
+        println!("\nDry run: {} was not changed", args.lockfile.display());
+    } else {
+        println!(
+            "\n{} was not changed; pass --yes to rewrite it",
+            args.lockfile.display()
+        );
+    }
+    Ok(())
+}
+
+/// Asks a yes/no question on stderr. Anything but `y` or `yes` is a no, as
+/// is a stdin that is not a terminal.
+fn confirm(question: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("failed to read the answer")?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+fn run_doctor(args: DoctorArgs) -> Result<()> {
+    let url = match &args.url {
+        Some(url) => normalize_target_url(url)?,
+        None => DEFAULT_DOCTOR_URL.to_owned(),
+    };
+    // A plain client: a session's cache would answer the comparison requests.
+    let fetcher = ReqwestFetcher::new(DEFAULT_TIMEOUT).context("failed to create HTTP client")?;
+    let report = doctor::run_doctor(&url, &fetcher, Duration::from_secs(10));
+
+    let output = DoctorOutput::new(&report);
+    match args.format {
+        OutputFormat::Pretty => print_doctor_pretty(&output),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+    }
+
+    let failed = output
+        .checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Failed.as_str())
+        .count();
+    if failed > 0 {
+        return Err(CliFailure::new(
+            ExitStatus::Network,
+            format!("{failed} check(s) failed for {url}"),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn run_schema(args: SchemaArgs) -> Result<()> {
+    // Fields skipped when empty are optional in what the CLI writes.
+    let generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    let schema = match args.document {
+        SchemaDocument::Inspect => generator.into_root_schema_for::<InspectOutput>(),
+        SchemaDocument::Download => generator.into_root_schema_for::<DownloadOutput>(),
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+fn run_vendor(mut args: VendorArgs, options: &InferenceOptions) -> Result<()> {
+    args.selection.load_select_file()?;
+    let selection = scan_and_select(
+        ScanSource::Url(&args.url),
+        &args.scan,
+        &args.selection,
+        args.infer_from,
+        args.quiet,
+        args.no_fail_on_empty,
+        options,
+    )?;
+    if selection.fonts.is_empty() {
+        println!("No fonts found on {}", selection.url);
+        return Ok(());
+    }
+    print_download_selection_pretty(
+        &selection.url,
+        &selection.fonts,
+        &selection.labels,
+        &selection.indices,
+        &selection.inference,
+        Console::default(),
+    );
+
+    let selected_fonts = select_fonts(&selection.fonts, &selection.indices);
+    eprintln!(
+        "\nVendoring {} fonts into {} ...",
+        selected_fonts.len(),
+        args.into.display()
+    );
+
+    let layout = DownloadLayout {
+        directory: args.layout.clone(),
+        file_name: args.name_template.clone(),
+        on_conflict: args.on_conflict.into(),
+        inference: selection.inference.clone(),
+        referer: args.request.referer.clone(),
+        omit_origin: args.request.no_origin,
+        omit_server_mtime: args.file_time.no_server_mtime,
+        http: args.scan.http_options(),
+        ..DownloadLayout::default()
+    };
+    let fetcher = args
+        .scan
+        .fetcher(DEFAULT_DOWNLOAD_TIMEOUT)
+        .context("failed to create HTTP client for downloads")?;
+    let mut bars = DownloadBars::new(selected_fonts.len());
+    let report = Downloader::builder()
+        .layout(layout)
+        .fetcher(fetcher)
+        .build()?
+        .download(
+            &selected_fonts,
+            &args.into,
+            &CancellationToken::new(),
+            |progress| bars.update(progress),
+        );
+    bars.finish();
+    print_download_warnings(&report.warnings);
+
+    let css_dir = std::path::absolute(args.css.parent().unwrap_or(Path::new("")))
+        .context("failed to resolve the --css directory")?;
+    // Fonts kept from an earlier run belong in the stylesheet too.
+    let hrefs = report
+        .local_files()
+        .map(|(url, path)| {
+            let path = std::path::absolute(path)?;
+            Ok((url, relative_href(&css_dir, &path)))
+        })
+        .collect::<std::io::Result<Vec<_>>>()
+        .context("failed to resolve saved font paths")?;
+    let local_fonts = selected_fonts
+        .iter()
+        .filter_map(|font| {
+            let (_, href) = hrefs.iter().find(|(url, _)| *url == font.url)?;
+            Some(LocalFont { font, href })
+        })
+        .collect::<Vec<_>>();
+
+    fs::create_dir_all(&css_dir)
+        .with_context(|| format!("failed to create {}", css_dir.display()))?;
+    let css = format!(
+        "/* Self-hosted fonts from {}, generated by typopotamus-cli vendor. */\n\n{}",
+        args.url,
+        font_face_css(&local_fonts)
+    );
+    fs::write(&args.css, css).with_context(|| format!("failed to write {}", args.css.display()))?;
+
+    println!(
+        "\nSaved {}/{} fonts into {} and wrote {} @font-face source(s) to {}",
+        report.success_count(),
+        report.attempted,
+        args.into.display(),
+        local_fonts.len(),
+        args.css.display()
+    );
+    println!(
+        "\nAdd this to the HTML <head>, adjusting the href to where the stylesheet is served:\n"
+    );
+    let href = if args.css.is_relative() {
+        format!("/{}", relative_href(Path::new(""), &args.css))
+    } else {
+        args.css
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    };
+    println!("  <link rel=\"stylesheet\" href=\"{href}\">");
+    print_identical_fonts(
+        &selected_fonts,
+        &report.saved_files,
+        &selection.inference,
+        Console::default(),
+    );
+    write_theme_files(
+        &args.theme,
+        &selected_fonts,
+        &report.saved_files,
+        Console::default(),
+    )?;
+
+    fail_on_download_errors(&report.failures)
+}
+
+fn run_sync(mut args: SyncArgs, options: &InferenceOptions) -> Result<()> {
+    args.selection.load_select_file()?;
+    // Without selectors the whole site is kept in sync.
+    if !has_download_selectors(&args.selection) {
+        args.selection.all = true;
+    }
+    let selection = scan_and_select(
+        ScanSource::Url(&args.url),
+        &args.scan,
+        &args.selection,
+        args.infer_from,
+        args.quiet,
+        args.no_fail_on_empty,
+        options,
+    )?;
+    if selection.fonts.is_empty() {
+        println!("No fonts found on {}; nothing was changed", selection.url);
+        return Ok(());
+    }
+
+    // Files of fonts the scan missed would look stale.
+    if args.prune && !selection.gaps.is_empty() {
+        bail!(
+            "not pruning: the scan of {} was incomplete ({} failed, truncated, or skipped resource(s)); rerun without --prune, or raise the scan limits",
+            selection.url,
+            selection.gaps.len()
+        );
+    }
+
+    let fonts = select_fonts(&selection.fonts, &selection.indices);
+    let layout = DownloadLayout {
+        directory: args.layout.clone(),
+        file_name: args.name_template.clone(),
+        sidecar: true,
+        inference: selection.inference.clone(),
+        referer: args.request.referer.clone(),
+        omit_origin: args.request.no_origin,
+        omit_server_mtime: args.file_time.no_server_mtime,
+        http: args.scan.http_options(),
+        ..DownloadLayout::default()
+    };
+    let fetcher = args
+        .scan
+        .fetcher(DEFAULT_DOWNLOAD_TIMEOUT)
+        .context("failed to create HTTP client for downloads")?;
+    let cancel = CancellationToken::new();
+    if !args.quiet {
+        eprintln!(
+            "Comparing {} font(s) with {} ...",
+            fonts.len(),
+            args.output.display()
+        );
+    }
+    let plan = plan_sync(
+        &fonts,
+        &selection.fonts,
+        &layout,
+        &args.output,
+        &fetcher,
+        &cancel,
+    );
+
+    let mut saved_files = Vec::new();
+    let mut failures = Vec::new();
+    if !args.dry_run {
+        // New files never replace a file another font was saved to; changed
+        // files replace their previous copy.
+        for (status, on_conflict) in [
+            (SyncStatus::New, ConflictPolicy::Rename),
+            (SyncStatus::Changed, ConflictPolicy::Overwrite),
+        ] {
+            let pending = select_fonts(&fonts, &plan.indices(status));
+            if pending.is_empty() {
+                continue;
+            }
+            let mut bars = (!args.quiet).then(|| DownloadBars::new(pending.len()));
+            let report = Downloader::builder()
+                .layout(DownloadLayout {
+                    on_conflict,
+                    ..layout.clone()
+                })
+                .fetcher(fetcher.clone())
+                .build()?
+                .download(&pending, &args.output, &cancel, |progress| {
+                    if let Some(bars) = &mut bars {
+                        bars.update(progress);
+                    }
+                });
+            if let Some(bars) = bars {
+                bars.finish();
+            }
+            print_download_warnings(&report.warnings);
+            saved_files.extend(report.saved_files);
+            failures.extend(report.failures);
+        }
+
+        // A changed font saved under an older layout now has a copy at its
+        // new path.
+        for entry in &plan.entries {
+            if let Some(previous) = &entry.path
+                && saved_files.iter().any(|saved| {
+                    saved.url == fonts[entry.font_index].url && saved.path != *previous
+                })
+            {
+                remove_saved_font(previous)
+                    .with_context(|| format!("failed to remove {}", previous.display()))?;
+            }
+        }
+        if args.prune {
+            for path in &plan.stale {
+                remove_saved_font(path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+            }
+        }
+    }
+
+    let output = SyncOutput::new(
+        &selection.url,
+        &args,
+        &fonts,
+        &plan,
+        &saved_files,
+        &failures,
+    );
+    match args.format {
+        OutputFormat::Pretty => print_sync_pretty(&output),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+    }
+
+    fail_on_download_errors(&failures)
+}
+
+/// Writes the requested theme files for the families of the saved fonts.
+fn write_theme_files(
+    theme: &ThemeArgs,
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+    console: Console,
+) -> Result<()> {
+    if theme.tailwind_config.is_none() && theme.css_variables.is_none() {
+        return Ok(());
+    }
+
+    let families = theme_families(
+        fonts
+            .iter()
+            .filter(|font| saved_files.iter().any(|saved| saved.url == font.url))
+            .map(|font| font.family.as_str()),
+    );
+    let outputs = [
+        (&theme.tailwind_config, tailwind_config(&families)),
+        (&theme.css_variables, css_custom_properties(&families)),
+    ];
+    for (path, contents) in outputs {
+        let Some(path) = path else {
+            continue;
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+        console.line(format_args!(
+            "Wrote {} font family token(s) to {}",
+            families.len(),
+            path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Writes one SVG specimen sheet per family of the saved fonts.
+fn write_specimens(
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+    output: &Path,
+    console: Console,
+) -> Result<()> {
+    let specimens =
+        render_specimens(fonts, saved_files).context("failed to render specimen sheets")?;
+    if specimens.is_empty() {
+        console.line("No saved fonts could be embedded in a specimen sheet");
+        return Ok(());
+    }
+
+    let directory = output.join("specimens");
+    fs::create_dir_all(&directory)
+        .with_context(|| format!("failed to create {}", directory.display()))?;
+    for specimen in &specimens {
+        let path = directory.join(&specimen.file_name);
+        fs::write(&path, &specimen.svg)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    console.line(format_args!(
+        "Wrote {} specimen sheet(s) to {}",
+        specimens.len(),
+        directory.display()
+    ));
+
+    Ok(())
+}
+
+fn fail_on_download_errors(failures: &[DownloadFailure]) -> Result<()> {
+    if !failures.is_empty() {
+        eprintln!("{} download(s) failed:", failures.len());
+        for failure in failures {
+            eprintln!("- {failure}");
+        }
+        return Err(CliFailure::new(ExitStatus::PartialDownload, "some downloads failed").into());
+    }
+
+    Ok(())
+}
+
+/// Lists fonts that were saved despite a problem.
+fn print_download_warnings(warnings: &[DownloadFailure]) {
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+/// Warns about files saved more than once under different family names, so
+/// only one canonical copy needs to be kept.
+fn print_identical_fonts(
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+    inference: &Inference,
+    console: Console,
+) {
+    let identical = download::identical_fonts_across_families(fonts, saved_files, inference);
+    if identical.is_empty() {
+        return;
+    }
+
+    console.line("\nByte-identical fonts saved under different families:");
+    for group in identical {
+        let copies = group
+            .copies
+            .iter()
+            .map(|copy| format!("{} ({})", copy.family, copy.path.display()))
+            .collect::<Vec<_>>();
+        console.line(format_args!(
+            "- sha256 {}: {}",
+            &group.sha256[..12],
+            copies.join(", ")
+        ));
+    }
+}
+
+fn write_checksum_file(path: &Path, output_root: &Path, saved_files: &[SavedFile]) -> Result<()> {
+    let mut contents = String::new();
+    for saved in saved_files {
+        let relative = saved.path.strip_prefix(output_root).unwrap_or(&saved.path);
+        contents.push_str(&format!("{}  {}\n", saved.sha256, relative.display()));
+    }
+
+    fs::write(path, contents)
+        .with_context(|| format!("failed to write checksum file {}", path.display()))
+}
+
+/// Reads the binaries of the fonts at `indices` as `infer_from` asks.
+fn resolve_inference(
+    options: &InferenceOptions,
+    fonts: &[FontInfo],
+    indices: &[usize],
+    infer_from: InferFrom,
+    scan: &ScanArgs,
+    quiet: bool,
+) -> Result<Inference> {
+    let inference = Inference::from(options.clone());
+    if infer_from == InferFrom::Name || indices.is_empty() {
+        return Ok(inference);
+    }
+    let fonts = select_fonts(fonts, indices);
+    let fonts = fonts.as_slice();
+
+    let fetcher = scan
+        .fetcher(Duration::from_secs(30))
+        .context("failed to create HTTP client for font probing")?;
+    let metadata = probe_font_metadata(fonts, &fetcher, &CancellationToken::new());
+    if !quiet {
+        eprintln!(
+            "Read name/OS2 tables for {} of {} fonts",
+            metadata.len(),
+            fonts.len()
+        );
+    }
+
+    let inference = inference.with_binary_metadata(metadata);
+    if infer_from != InferFrom::Content {
+        return Ok(inference);
+    }
+
+    let candidates = merge_candidate_indices(fonts, &inference)
+        .into_iter()
+        .map(|index| fonts[index].clone())
+        .collect::<Vec<_>>();
+    let hashes = probe_content_hashes(
+        &candidates,
+        &fetcher,
+        &scan.extract_options().user_agent,
+        &CancellationToken::new(),
+    );
+    if !quiet && !candidates.is_empty() {
+        eprintln!(
+            "Hashed {} of {} fonts to confirm merges across hosts",
+            hashes.len(),
+            candidates.len()
+        );
+    }
+
+    Ok(inference.with_content_hashes(hashes))
+}
+
+fn print_extract_event(event: &ExtractEvent) {
+    match event {
+        ExtractEvent::PageFetched { url } => eprintln!("Fetched page {url}"),
+        ExtractEvent::StylesheetDiscovered { .. } => {}
+        ExtractEvent::StylesheetFetched { url } => eprintln!("Fetched stylesheet {url}"),
+        ExtractEvent::StylesheetFailed { url } => eprintln!("Could not fetch stylesheet {url}"),
+        ExtractEvent::StylesheetTruncated { url, limit } => {
+            eprintln!(
+                "Warning: stylesheet {url} exceeds {limit} bytes; only the start was scanned"
+            );
+        }
+        ExtractEvent::ScriptFetched { url } => eprintln!("Fetched script {url}"),
+        ExtractEvent::ScriptFailed { url } => eprintln!("Could not fetch script {url}"),
+        ExtractEvent::PageFailed { url } => eprintln!("Could not fetch page {url}"),
+        ExtractEvent::SitemapFetched { url, pages } => {
+            eprintln!("Fetched sitemap {url} ({pages} page(s))");
+        }
+        ExtractEvent::SitemapFailed { url } => eprintln!("Could not fetch sitemap {url}"),
+        ExtractEvent::SitemapTruncated { url, limit } => {
+            eprintln!("Warning: sitemap {url} exceeds {limit} bytes; only the start was read");
+        }
+        ExtractEvent::FontsFound { source, count } => {
+            eprintln!("Found {count} font(s) in {source}");
+        }
+        ExtractEvent::LimitReached { limit } => {
+            eprintln!("Warning: scan limit of {limit} reached; remaining resources were skipped");
+        }
+        ExtractEvent::LossyDecoding { url, encoding } => {
+            eprintln!("Warning: {url} is not valid {encoding}; undecodable bytes were replaced");
+        }
+    }
+}
+
+fn fontsource_suggestions(
+    groups: &[InferredFamilyGroup],
+    use_api: bool,
+    scan: &ScanArgs,
+    quiet: bool,
+) -> Result<Vec<FontsourceOutput>> {
+    let fetcher = if use_api {
+        Some(
+            scan.fetcher(Duration::from_secs(30))
+                .context("failed to create HTTP client for the Fontsource API")?,
+        )
+    } else {
+        None
+    };
+
+    let lookup = |family: &str| {
+        bundled_package(family).or_else(|| {
+            let fetcher = fetcher.as_ref()?;
+            fetch_package(fetcher, family).unwrap_or_else(|error| {
+                if !quiet {
+                    eprintln!("Warning: {}", error_chain(&error));
+                }
+                None
+            })
+        })
+    };
+
+    Ok(groups
+        .iter()
+        .map(|group| {
+            let package = std::iter::once(&group.name)
+                .chain(&group.aliases)
+                .find_map(|name| lookup(name));
+            FontsourceOutput::new(group, package)
+        })
+        .collect())
+}
+
+fn print_fontsource_suggestions(suggestions: &[FontsourceOutput]) {
+    let mut table = cli_table([
+        "Family",
+        "Fontsource package",
+        "Weights",
+        "Styles",
+        "Not on Fontsource",
+    ]);
+
+    for suggestion in suggestions {
+        let Some(package) = &suggestion.package else {
+            continue;
+        };
+        let package = match &suggestion.variable_package {
+            Some(variable) => format!("{package}\n{variable}"),
+            None => package.clone(),
+        };
+        table.add_row([
+            Cell::new(truncate_for_cli(&suggestion.family, 28)),
+            Cell::new(package),
+            Cell::new(weight_span(&suggestion.weights)),
+            Cell::new(compact_join(&suggestion.styles, 14)),
+            Cell::new(compact_join(&suggestion.missing_weights, 20)),
+        ]);
+    }
+
+    let unmatched = suggestions
+        .iter()
+        .filter(|suggestion| suggestion.package.is_none())
+        .map(|suggestion| suggestion.family.as_str())
+        .collect::<Vec<_>>();
+    if unmatched.len() < suggestions.len() {
+        println!("\n{table}");
+    }
+    if !unmatched.is_empty() {
+        println!(
+            "\nNo Fontsource package found for: {}",
+            unmatched.join(", ")
+        );
+    }
+}
+
+/// Lists weights, collapsing a full run of hundreds such as `100-900`.
+fn weight_span(weights: &[u16]) -> String {
+    match weights {
+        [] => "-".to_owned(),
+        [first, .., last]
+            if weights.len() > 2 && weights.windows(2).all(|pair| pair[1] == pair[0] + 100) =>
+        {
+            format!("{first}-{last}")
+        }
+        _ => weights
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn print_family_merges(families: &[FamilyOutput]) {
+    let merges = families
+        .iter()
+        .flat_map(|family| family.merged.iter().map(move |merge| (family, merge)))
+        .collect::<Vec<_>>();
+    if merges.is_empty() {
+        return;
+    }
+
+    println!("\nMerged across hosts:");
+    for (family, merge) in merges {
+        println!(
+            "- {} from {} into {} ({})",
+            merge.name,
+            merge.hosts.join(", "),
+            family.name,
+            merge.reason
+        );
+    }
+}
+
+fn print_unused_families(families: &[FamilyOutput]) {
+    let names_with = |status: FontUsageStatus| {
+        families
+            .iter()
+            .filter(|family| family.usage == Some(status.as_str()))
+            .map(|family| family.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let unused = names_with(FontUsageStatus::Unused);
+    if !unused.is_empty() {
+        println!(
+            "\nDeclared but not used by any CSS rule: {}",
+            unused.join(", ")
+        );
+    }
+    let unmatched = names_with(FontUsageStatus::UnmatchedSelectors);
+    if !unmatched.is_empty() {
+        println!(
+            "Only used by selectors with no matching classes or ids on the page: {}",
+            unmatched.join(", ")
+        );
+    }
+}
+fn print_google_fonts_equivalents(families: &[FamilyOutput]) {
+    let matches = families
+        .iter()
+        .filter_map(|family| Some((&family.name, family.google_fonts.as_ref()?)))
+        .collect::<Vec<_>>();
+    if matches.is_empty() {
+        return;
+    }
+
+    println!("\nSelf-hosted families also published as open source on Google Fonts:");
+    for (name, google_fonts) in matches {
+        println!(
+            "- {name}: {} ({})",
+            google_fonts.family, google_fonts.specimen_url
+        );
+    }
+}
+
+fn print_missing_fonts(missing: &[MissingFontOutput]) {
+    if missing.is_empty() {
+        return;
+    }
+
+    println!("\nReferenced font files missing from the directory:");
+    for font in missing {
+        println!(
+            "- {} (referenced from {})",
+            font.path,
+            font.referenced_from.join(", ")
+        );
+    }
+}
+
+fn has_download_selectors(args: &SelectionArgs) -> bool {
+    args.all
+        || !args.family.is_empty()
+        || !args.font_name.is_empty()
+        || !args.font_url.is_empty()
+        || !args.index.is_empty()
+        || !args.id.is_empty()
+}
+
+/// `labels` gives the index `--index` selects each font by.
+fn resolve_download_indices(
+    fonts: &[FontInfo],
+    labels: &[usize],
+    args: &SelectionArgs,
+    inference: &Inference,
+) -> (Vec<usize>, Vec<SelectorMatch>) {
+    let mut report =
+        match_inferred_family_names(fonts, &args.family, inference, args.matching.into());
+    let direct_selection = FontSelection {
+        names: args.font_name.clone(),
+        urls: args.font_url.clone(),
+        // Indices missing from `labels` match nothing.
+        indices: args
+            .index
+            .iter()
+            .map(|index| {
+                labels
+                    .iter()
+                    .position(|label| label == index)
+                    .unwrap_or(usize::MAX)
+            })
+            .collect(),
+        ids: args.id.clone(),
+        matching: args.matching.into(),
+        ..FontSelection::default()
+    };
+    report.extend(match_selectors(fonts, &direct_selection));
+    let index_matches = report
+        .iter_mut()
+        .filter(|matched| matched.kind == SelectorKind::Index);
+    for (matched, index) in index_matches.zip(&args.index) {
+        matched.selector = index.to_string();
+    }
+
+    let mut selected = report
+        .iter()
+        .flat_map(|matched| matched.indices.iter().copied())
+        .collect::<HashSet<_>>();
+    if args.all {
+        selected.extend(0..fonts.len());
+    }
+
+    let mut selected_indices = selected.into_iter().collect::<Vec<_>>();
+    selected_indices.sort_unstable();
+    (
+        filter_selected_indices(fonts, &selected_indices, args),
+        report,
+    )
+}
+
+/// The weight, format, and provider filters of `args` applied to `indices`.
+fn filter_selected_indices(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    args: &SelectionArgs,
+) -> Vec<usize> {
+    let indices = filter_indices_by_weight(
+        fonts,
+        indices,
+        args.weight.min_weight,
+        args.weight.max_weight,
+    );
+    let indices = filter_indices_by_format(fonts, &indices, &format_labels(&args.only_format));
+    filter_indices_by_provider(fonts, &indices, &font_providers(&args.provider))
+}
+
+/// Lists what each selector matched on stderr, before the weight, format
+/// and provider filters.
+fn print_selector_matches(fonts: &[FontInfo], report: &[SelectorMatch]) {
+    if report.is_empty() {
+        return;
+    }
+
+    eprintln!("Selectors:");
+    for matched in report {
+        let names = matched
+            .indices
+            .iter()
+            .map(|index| fonts[*index].name.as_str())
+            .collect::<BTreeSet<_>>();
+        let shown = names.iter().take(3).copied().collect::<Vec<_>>().join(", ");
+        let found = match matched.indices.len() {
+            0 => "no fonts".to_owned(),
+            1 => format!("1 font ({shown})"),
+            count if names.len() > 3 => format!("{count} fonts ({shown}, ...)"),
+            count => format!("{count} fonts ({shown})"),
+        };
+        eprintln!(
+            "  --{} {}: {found}",
+            matched.kind.as_str(),
+            matched.selector
+        );
+    }
+}
+
+fn render_empty_inspect(
+    source_urls: &[String],
+    view: InspectView,
+    group_by: GroupBy,
+    format: OutputFormat,
+) -> Result<()> {
+    match format {
+        OutputFormat::Pretty => {
+            println!("No fonts found on {}", source_urls.join(", "));
+        }
+        OutputFormat::Json => {
+            let output = InspectOutput::empty(source_urls, view, group_by);
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Heading of the grouping column in pretty output.
+fn group_label(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::InferredFamily => "Family",
+        GroupBy::CssFamily => "CSS family",
+        GroupBy::Format => "Format",
+        GroupBy::Provider => "Provider",
+        GroupBy::None => "File",
+    }
+}
+
+fn print_inspect_pretty(output: &InspectOutput) {
+    println!("Source: {}", output.source);
+    println!(
+        "Selected fonts: {} of {}",
+        output.selected_count, output.total_found
+    );
+
+    match output.view {
+        InspectView::Family => {
+            match output.group_by {
+                GroupBy::InferredFamily => {
+                    println!("Grouped families: {}", output.family_count)
+                }
+                group_by => println!(
+                    "Groups by {}: {}",
+                    group_label(group_by).to_ascii_lowercase(),
+                    output.family_count
+                ),
+            }
+            let mut table = cli_table([
+                group_label(output.group_by),
+                "Provider",
+                "Faces",
+                "Files",
+                "Weights",
+                "Styles",
+                "Formats",
+                "Indexes",
+                "Used by",
+            ]);
+
+            for family in &output.families {
+                table.add_row([
+                    Cell::new(&family.name),
+                    Cell::new(family.providers.join(", ")),
+                    Cell::new(family.variants),
+                    Cell::new(family.files),
+                    Cell::new(compact_join(&family.weights, 20)),
+                    Cell::new(compact_join(&family.styles, 18)),
+                    Cell::new(compact_join(&family.formats, 14)),
+                    Cell::new(compact_join(&family.index_ranges, 24)),
+                    Cell::new(if family.used_by.is_empty() {
+                        "-".to_owned()
+                    } else {
+                        compact_join(&family.used_by, 28)
+                    }),
+                ]);
+            }
+
+            println!("\n{table}");
+            if output.pages.len() > 1 {
+                println!("\nFound on:");
+                for family in &output.families {
+                    println!("- {}: {}", family.name, family.found_on.join(", "));
+                }
+            }
+            if matches!(
+                output.group_by,
+                GroupBy::InferredFamily | GroupBy::CssFamily
+            ) {
+                print_family_merges(&output.families);
+                print_unused_families(&output.families);
+                print_google_fonts_equivalents(&output.families);
+            }
+        }
+        InspectView::Face => {
+            let mut table = cli_table([
+                group_label(output.group_by),
+                "Weight",
+                "Style",
+                "Formats",
+                "Indexes",
+                "Preferred URL",
+            ]);
+
+            for face in &output.faces {
+                let preferred_url = face
+                    .sources
+                    .first()
+                    .map_or("-", |source| source.url.as_str());
+                let indices = face
+                    .indices
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                table.add_row([
+                    Cell::new(truncate_for_cli(&face.family, 28)),
+                    Cell::new(&face.weight),
+                    Cell::new(&face.style),
+                    Cell::new(compact_join(&face.formats, 22)),
+                    Cell::new(compact_join(&indices, 16)),
+                    Cell::new(display_url(preferred_url)),
+                ]);
+            }
+
+            println!("\n{table}");
+        }
+        InspectView::Font => {
+            let sized = output
+                .fonts
+                .iter()
+                .any(|font| font.content_length.is_some());
+            let mut header = vec![
+                "Index",
+                "ID",
+                "Family",
+                "Name",
+                "Weight",
+                "Style",
+                "Format",
+                "Provider",
+                "Loads when",
+            ];
+            if sized {
+                header.extend(["Size", "Last modified"]);
+            }
+            header.push("URL");
+
+            let mut table = cli_table(header);
+
+            for font in &output.fonts {
+                let mut row = vec![
+                    Cell::new(font.index),
+                    Cell::new(&font.id),
+                    Cell::new(truncate_for_cli(&font.family, 28)),
+                    Cell::new(truncate_for_cli(&font.name, 32)),
+                    Cell::new(&font.weight),
+                    Cell::new(&font.style),
+                    Cell::new(if font.color {
+                        format!("{} (color)", font.format)
+                    } else {
+                        font.format.clone()
+                    }),
+                    Cell::new(&font.provider),
+                    Cell::new(if font.conditions.is_empty() {
+                        "always".to_owned()
+                    } else {
+                        font.conditions.join("; ")
+                    }),
+                ];
+                if sized {
+                    row.push(Cell::new(
+                        font.content_length
+                            .map_or_else(|| "?".to_owned(), format_bytes),
+                    ));
+                    row.push(Cell::new(font.last_modified.as_deref().unwrap_or("?")));
+                }
+                row.push(Cell::new(display_url(&font.url)));
+                table.add_row(row);
+            }
+
+            println!("\n{table}");
+        }
+    }
+
+    if !output.fontsource.is_empty() {
+        print_fontsource_suggestions(&output.fontsource);
+    }
+}
+
+fn print_audit_pretty(output: &AuditOutput) {
+    println!("Source: {}", output.source);
+    println!(
+        "Score: {}/100 ({} font(s) checked, {} finding(s))",
+        output.score,
+        output.fonts_checked,
+        output.findings.len()
+    );
+    if output.findings.is_empty() {
+        println!("\nNo issues found.");
+        return;
+    }
+
+    let mut table = cli_table(["Index", "Font", "Severity", "Rule", "Issue"]);
+    for finding in &output.findings {
+        table.add_row([
+            Cell::new(finding.index),
+            Cell::new(format!(
+                "{} {} {}",
+                truncate_for_cli(&finding.family, 24),
+                finding.weight,
+                finding.style
+            )),
+            Cell::new(finding.severity),
+            Cell::new(finding.rule),
+            Cell::new(&finding.message),
+        ]);
+    }
+    println!("\n{table}");
+}
+
+fn print_audit_sites_pretty(sites: &[AuditSiteOutput]) {
+    let mut table = cli_table([
+        "Site",
+        "Score",
+        "Fonts",
+        "Families",
+        "Providers",
+        "Size",
+        "Findings",
+    ]);
+    for site in sites {
+        let findings = match &site.error {
+            Some(error) => format!("error: {}", truncate_for_cli(error, 60)),
+            None => site
+                .findings
+                .iter()
+                .filter(|(_, count)| **count > 0)
+                .map(|(rule, count)| format!("{rule} ({count})"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        table.add_row([
+            Cell::new(&site.url),
+            Cell::new(
+                site.score
+                    .map(|score| score.to_string())
+                    .unwrap_or_default(),
+            ),
+            Cell::new(site.fonts),
+            Cell::new(truncate_for_cli(&site.families.join(", "), 40)),
+            Cell::new(site.providers.join(", ")),
+            Cell::new(format_bytes(site.total_bytes)),
+            Cell::new(findings),
+        ]);
+    }
+    println!("{table}");
+}
+
+/// One row per site; lists are joined with `; ` and each rule gets a
+/// column counting its findings.
+fn print_audit_csv(sites: &[AuditSiteOutput]) {
+    let mut header = [
+        "url",
+        "score",
+        "fonts",
+        "families",
+        "providers",
+        "formats",
+        "total_bytes",
+        "unmeasured_files",
+    ]
+    .map(str::to_owned)
+    .to_vec();
+    header.extend(AuditRule::ALL.iter().map(|rule| rule.id().to_owned()));
+    header.push("error".to_owned());
+    println!("{}", csv_record(&header));
+
+    for site in sites {
+        let mut record = vec![
+            site.url.clone(),
+            site.score
+                .map(|score| score.to_string())
+                .unwrap_or_default(),
+            site.fonts.to_string(),
+            site.families.join("; "),
+            site.providers.join("; "),
+            site.formats.join("; "),
+            site.total_bytes.to_string(),
+            site.unmeasured_files.to_string(),
+        ];
+        record.extend(AuditRule::ALL.iter().map(|rule| {
+            site.findings
+                .get(rule.id())
+                .copied()
+                .unwrap_or(0)
+                .to_string()
+        }));
+        record.push(site.error.clone().unwrap_or_default());
+        println!("{}", csv_record(&record));
+    }
+}
+
+/// Quotes fields per RFC 4180 where needed.
+fn csv_record(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn print_perf_pretty(output: &PerfOutput) {
+    println!("Source: {}", output.source);
+    let mut table = cli_table(["Check", "Status", "Result"]);
+    for check in &output.checks {
+        table.add_row([
+            Cell::new(check.id),
+            Cell::new(check.status),
+            Cell::new(&check.summary),
+        ]);
+    }
+    println!("\n{table}");
+
+    let details = output
+        .checks
+        .iter()
+        .flat_map(|check| check.details.iter().map(move |detail| (check.id, detail)))
+        .collect::<Vec<_>>();
+    if !details.is_empty() {
+        println!("\nDetails:");
+        for (id, detail) in details {
+            println!("- {id}: {detail}");
+        }
+    }
+}
+
+fn print_check_pretty(output: &CheckOutput) {
+    println!("Source: {}", output.source);
+    let mut table = cli_table(["Status", "Size", "Font", "URL"]);
+    for file in &output.files {
+        let status = file
+            .status
+            .map_or_else(|| "error".to_owned(), |status| status.to_string());
+        let mut url = truncate_for_cli(&file.url, 72);
+        if let Some(final_url) = &file.final_url {
+            url.push_str(&format!("\n-> {}", truncate_for_cli(final_url, 72)));
+        }
+        if let Some(error) = &file.error {
+            url.push_str(&format!("\n{}", truncate_for_cli(error, 72)));
+        }
+        table.add_row([
+            Cell::new(status),
+            Cell::new(
+                file.size
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "?".to_owned()),
+            ),
+            Cell::new(truncate_for_cli(&file.families.join(", "), 24)),
+            Cell::new(url),
+        ]);
+    }
+    println!("\n{table}");
+    println!(
+        "\n{} of {} file(s) available, {} redirected",
+        output.available,
+        output.files.len(),
+        output.redirected
+    );
+}
+
+fn print_sync_pretty(output: &SyncOutput) {
+    println!("Source: {}", output.url);
+    let changes = output
+        .files
+        .iter()
+        .filter(|file| file.status != SyncStatus::Unchanged.as_str())
+        .map(|file| (file.status, file.path.as_deref(), Some(file.url.as_str())))
+        .chain(
+            output
+                .stale
+                .iter()
+                .map(|path| (output.stale_status(), Some(path.as_path()), None)),
+        )
+        .collect::<Vec<_>>();
+
+    if !changes.is_empty() {
+        let mut table = cli_table(["Change", "File", "URL"]);
+        for (status, path, url) in changes {
+            table.add_row([
+                Cell::new(status),
+                Cell::new(path.map_or_else(String::new, |path| path.display().to_string())),
+                Cell::new(url.map_or_else(String::new, |url| truncate_for_cli(url, 72))),
+            ]);
+        }
+        println!("\n{table}");
+    }
+
+    let mut summary = format!(
+        "\n{} new, {} changed, {} unchanged",
+        output.new, output.changed, output.unchanged
+    );
+    if output.unchecked > 0 {
+        summary.push_str(&format!(", {} unchecked", output.unchecked));
+    }
+    if output.pruned {
+        summary.push_str(&format!(", {} removed", output.stale.len()));
+    }
+    println!("{summary} in {}", output.output.display());
+    if !output.stale.is_empty() && !output.pruned {
+        println!(
+            "{} saved font(s) are no longer declared by the site; pass --prune to delete them",
+            output.stale.len()
+        );
+    }
+    if output.dry_run {
+        println!("Dry run: nothing was downloaded or deleted");
+    }
 }
 
-fn resolve_download_indices(fonts: &[FontInfo], args: &DownloadArgs) -> Vec<usize> {
-    let mut selected = HashSet::new();
-
-    if args.all {
-        selected.extend(0..fonts.len());
+fn print_update_pretty(output: &UpdateOutput) {
+    println!("Source: {}", output.source);
+    if output.changes.is_empty() {
+        println!("\n{} is up to date", output.lockfile.display());
+        return;
     }
 
-    if !args.family.is_empty() {
-        let family_indices = select_indices_by_inferred_family_names(fonts, &args.family);
-        selected.extend(family_indices);
+    let mut table = cli_table(["Change", "Font", "Detail", "URL"]);
+    for change in &output.changes {
+        table.add_row([
+            Cell::new(change.change),
+            Cell::new(truncate_for_cli(&change.font, 32)),
+            Cell::new(change.detail.as_deref().unwrap_or_default()),
+            Cell::new(truncate_for_cli(&change.url, 72)),
+        ]);
     }
+    println!("\n{table}");
+}
 
-    let direct_selection = FontSelection {
-        all: false,
-        families: Vec::new(),
-        names: args.font_name.clone(),
-        urls: args.font_url.clone(),
-        indices: args.index.clone(),
-    };
-    selected.extend(select_font_indices(fonts, &direct_selection));
+fn print_doctor_pretty(output: &DoctorOutput) {
+    println!("Checking {}", output.url);
 
-    let mut selected_indices = selected.into_iter().collect::<Vec<_>>();
-    selected_indices.sort_unstable();
-    selected_indices
-}
+    let mut table = cli_table(["Check", "Status", "Result"]);
+    for check in &output.checks {
+        table.add_row([
+            Cell::new(check.id),
+            Cell::new(check.status),
+            Cell::new(&check.summary),
+        ]);
+    }
+    println!("\n{table}");
 
-fn render_empty_inspect(source: &str, view: InspectView, format: OutputFormat) -> Result<()> {
-    match format {
-        OutputFormat::Pretty => {
-            println!("No fonts found on {source}");
-        }
-        OutputFormat::Json => {
-            let output = InspectOutput {
-                source: source.to_owned(),
-                total_found: 0,
-                selected_count: 0,
-                view,
-                family_count: 0,
-                families: Vec::new(),
-                fonts: Vec::new(),
-            };
-            println!("{}", serde_json::to_string_pretty(&output)?);
+    let hints = output
+        .checks
+        .iter()
+        .filter(|check| check.status != CheckStatus::Ok.as_str() || check.id == "proxy")
+        .filter_map(|check| check.hint.as_ref().map(|hint| (check.id, hint)))
+        .collect::<Vec<_>>();
+    if !hints.is_empty() {
+        println!("\nHints:");
+        for (id, hint) in hints {
+            println!("- {id}: {hint}");
         }
     }
-
-    Ok(())
 }
 
-fn print_inspect_pretty(output: &InspectOutput) {
-    println!("Source: {}", output.source);
+fn print_stats_pretty(source: &str, stats: &FontStats, sizes_requested: bool) {
+    println!("Source: {source}");
+    println!("Families: {}", stats.families);
     println!(
-        "Selected fonts: {} of {}",
-        output.selected_count, output.total_found
+        "Files: {} ({} variable, {} static)",
+        stats.files, stats.variable_files, stats.static_files
     );
+    println!(
+        "Known size: {} across {} of {} files",
+        format_bytes(stats.bytes),
+        stats.sized_files,
+        stats.files
+    );
+    if stats.sized_files < stats.files && !sizes_requested {
+        println!("Pass --sizes to fetch the size of every file");
+    }
 
-    match output.view {
-        InspectView::Family => {
-            println!("Grouped families: {}", output.family_count);
-            let mut table = Table::new();
-            table
-                .load_preset(UTF8_FULL)
-                .apply_modifier(UTF8_ROUND_CORNERS)
-                .set_content_arrangement(ContentArrangement::Dynamic)
-                .set_header([
-                    "Family", "Files", "Variants", "Weights", "Styles", "Formats", "Indexes",
-                ]);
+    let sized = |bytes: u64, sized_files: usize| {
+        if sized_files == 0 {
+            "-".to_owned()
+        } else {
+            format_bytes(bytes)
+        }
+    };
 
-            for family in &output.families {
-                table.add_row([
-                    Cell::new(&family.name),
-                    Cell::new(family.files),
-                    Cell::new(family.variants),
-                    Cell::new(compact_join(&family.weights, 20)),
-                    Cell::new(compact_join(&family.styles, 18)),
-                    Cell::new(compact_join(&family.formats, 14)),
-                    Cell::new(compact_join(&family.index_ranges, 24)),
-                ]);
-            }
+    let mut formats = cli_table(["Format", "Files", "Bytes"]);
+    for format in &stats.formats {
+        formats.add_row([
+            Cell::new(&format.format),
+            Cell::new(format.files),
+            Cell::new(sized(format.bytes, format.sized_files)),
+        ]);
+    }
+    println!("\n{formats}");
 
-            println!("\n{table}");
-        }
-        InspectView::Font => {
-            let mut table = Table::new();
-            table
-                .load_preset(UTF8_FULL)
-                .apply_modifier(UTF8_ROUND_CORNERS)
-                .set_content_arrangement(ContentArrangement::Dynamic)
-                .set_header([
-                    "Index", "Family", "Name", "Weight", "Style", "Format", "URL",
-                ]);
+    let mut providers = cli_table(["Provider", "Files", "Families"]);
+    for provider in &stats.providers {
+        providers.add_row([
+            Cell::new(provider.provider),
+            Cell::new(provider.files),
+            Cell::new(provider.families),
+        ]);
+    }
+    println!("\n{providers}");
 
-            for font in &output.fonts {
-                table.add_row([
-                    Cell::new(font.index),
-                    Cell::new(truncate_for_cli(&font.family, 28)),
-                    Cell::new(truncate_for_cli(&font.name, 32)),
-                    Cell::new(&font.weight),
-                    Cell::new(&font.style),
-                    Cell::new(&font.format),
-                    Cell::new(truncate_for_cli(&font.url, 76)),
-                ]);
-            }
+    let mut families = cli_table(["Heaviest family", "Files", "Bytes"]);
+    for family in &stats.heaviest_families {
+        families.add_row([
+            Cell::new(truncate_for_cli(&family.name, 28)),
+            Cell::new(family.files),
+            Cell::new(sized(family.bytes, family.sized_files)),
+        ]);
+    }
+    println!("\n{families}");
+}
 
-            println!("\n{table}");
-        }
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
     }
+    format!("{value:.1} {}", UNITS[unit])
 }
 
 fn print_download_selection_pretty(
     source_url: &str,
     fonts: &[FontInfo],
+    labels: &[usize],
     selected_indices: &[usize],
+    inference: &Inference,
+    console: Console,
 ) {
-    let groups = infer_family_groups(fonts, selected_indices);
+    let groups = infer_family_groups(fonts, selected_indices, inference);
 
-    println!("Source: {source_url}");
-    println!(
+    console.line(format_args!("Source: {source_url}"));
+    console.line(format_args!(
         "Selected fonts: {} of {}",
         selected_indices.len(),
         fonts.len()
-    );
+    ));
 
-    let mut table = Table::new();
-    table
-        .load_preset(UTF8_FULL)
-        .apply_modifier(UTF8_ROUND_CORNERS)
-        .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header([
-            "Index", "Family", "Name", "Weight", "Style", "Format", "URL",
-        ]);
+    let mut table = cli_table([
+        "Index", "ID", "Family", "Name", "Weight", "Style", "Format", "URL",
+    ]);
 
     for group in groups {
         for font in group.fonts {
             table.add_row([
-                Cell::new(font.index),
+                Cell::new(labels[font.index]),
+                Cell::new(fonts[font.index].stable_id()),
                 Cell::new(truncate_for_cli(&group.name, 28)),
                 Cell::new(truncate_for_cli(&font.name, 32)),
                 Cell::new(font.weight),
                 Cell::new(font.style),
                 Cell::new(font.format),
-                Cell::new(truncate_for_cli(&font.url, 76)),
+                Cell::new(display_url(&font.url)),
             ]);
         }
     }
 
-    println!("\n{table}");
-}
-
-fn build_grouped_output(
-    source_url: &str,
-    all_fonts: &[FontInfo],
-    view: InspectView,
-    groups: Vec<InferredFamilyGroup>,
-) -> InspectOutput {
-    let selected_count = groups.iter().map(|group| group.files).sum();
-
-    let families = groups
-        .iter()
-        .map(|group| FamilyOutput {
-            key: group.key.clone(),
-            name: group.name.clone(),
-            aliases: group.aliases.clone(),
-            files: group.files,
-            variants: group.variants,
-            weights: group.weights.clone(),
-            styles: group.styles.clone(),
-            formats: group.formats.clone(),
-            indices: group.font_indices.clone(),
-            index_ranges: group.index_ranges.clone(),
-        })
-        .collect::<Vec<_>>();
-
-    let fonts = groups
-        .into_iter()
-        .flat_map(|group| {
-            group.fonts.into_iter().map(move |font| FontOutput {
-                index: font.index,
-                family: group.name.clone(),
-                source_family: font.source_family,
-                name: font.name,
-                weight: font.weight,
-                style: font.style,
-                format: font.format,
-                url: font.url,
-                referer: font.referer,
-            })
-        })
-        .collect::<Vec<_>>();
-
-    InspectOutput {
-        source: source_url.to_owned(),
-        total_found: all_fonts.len(),
-        selected_count,
-        view,
-        family_count: families.len(),
-        families: if view == InspectView::Family {
-            families
-        } else {
-            Vec::new()
-        },
-        fonts: if view == InspectView::Font {
-            fonts
-        } else {
-            Vec::new()
-        },
-    }
+    console.line(format_args!("\n{table}"));
 }
-
 fn select_fonts(fonts: &[FontInfo], indices: &[usize]) -> Vec<FontInfo> {
     indices
         .iter()
@@ -445,6 +4470,14 @@ fn select_fonts(fonts: &[FontInfo], indices: &[usize]) -> Vec<FontInfo> {
         .collect()
 }
 
+fn display_url(url: &str) -> String {
+    match download::embedded_font_size(url) {
+        Some(size) => format!("embedded data: URL ({size} bytes)"),
+        None if url.starts_with("data:") => "embedded data: URL (undecodable)".to_owned(),
+        None => truncate_for_cli(url, 76),
+    }
+}
+
 fn compact_join(values: &[String], max_chars: usize) -> String {
     if values.is_empty() {
         return "-".to_owned();
@@ -474,41 +4507,599 @@ fn truncate_for_cli(input: &str, max_width: usize) -> String {
     output.push_str("...");
     output
 }
-
 #[derive(Debug, Serialize)]
-struct InspectOutput {
+struct StatsOutput {
     source: String,
-    total_found: usize,
-    selected_count: usize,
-    view: InspectView,
-    family_count: usize,
-    families: Vec<FamilyOutput>,
-    fonts: Vec<FontOutput>,
+    files: usize,
+    families: usize,
+    variable_files: usize,
+    static_files: usize,
+    sized_files: usize,
+    bytes: u64,
+    formats: Vec<FormatStatsOutput>,
+    providers: Vec<ProviderStatsOutput>,
+    heaviest_families: Vec<FamilyStatsOutput>,
+}
+
+impl StatsOutput {
+    fn new(source: &str, stats: &FontStats) -> Self {
+        Self {
+            source: source.to_owned(),
+            files: stats.files,
+            families: stats.families,
+            variable_files: stats.variable_files,
+            static_files: stats.static_files,
+            sized_files: stats.sized_files,
+            bytes: stats.bytes,
+            formats: stats
+                .formats
+                .iter()
+                .map(|format| FormatStatsOutput {
+                    format: format.format.clone(),
+                    files: format.files,
+                    sized_files: format.sized_files,
+                    bytes: format.bytes,
+                })
+                .collect(),
+            providers: stats
+                .providers
+                .iter()
+                .map(|provider| ProviderStatsOutput {
+                    provider: provider.provider.as_str(),
+                    files: provider.files,
+                    families: provider.families,
+                })
+                .collect(),
+            heaviest_families: stats
+                .heaviest_families
+                .iter()
+                .map(|family| FamilyStatsOutput {
+                    name: family.name.clone(),
+                    files: family.files,
+                    sized_files: family.sized_files,
+                    bytes: family.bytes,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
-struct FamilyOutput {
-    key: String,
+struct NetworkEventOutput {
+    method: &'static str,
+    url: String,
+    status: Option<u16>,
+    duration_ms: u128,
+    bytes: u64,
+    cache_hit: Option<bool>,
+    error: Option<String>,
+}
+
+impl NetworkEventOutput {
+    fn new(event: &NetworkEvent) -> Self {
+        Self {
+            method: event.method.as_str(),
+            url: event.url.clone(),
+            status: event.status,
+            duration_ms: event.duration.as_millis(),
+            bytes: event.bytes,
+            cache_hit: event.cache_hit,
+            error: event.error.clone(),
+        }
+    }
+}
+
+/// The `download --format json` document.
+#[derive(Debug, Serialize, JsonSchema)]
+struct DownloadOutput {
+    /// Bumped whenever a field is renamed, removed, or changes meaning.
+    schema_version: u32,
+    source: String,
+    output: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_cmd: Option<String>,
+    dry_run: bool,
+    attempted: usize,
+    saved: Vec<SavedFileOutput>,
+    skipped: Vec<PathBuf>,
+    failures: Vec<DownloadFailureOutput>,
+    /// Fonts saved despite a problem, such as a modification time that
+    /// could not be set.
+    warnings: Vec<DownloadFailureOutput>,
+    cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subset: Option<SubsetOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compressed: Option<CompressOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<SnippetOutput>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct SnippetOutput {
+    framework: Option<EmitFramework>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    code: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct SubsetOutput {
+    original_size: u64,
+    subset_size: u64,
+    files: Vec<SubsetFileOutput>,
+    skipped: Vec<SkippedFileOutput>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct SubsetFileOutput {
+    path: PathBuf,
+    original_size: u64,
+    subset_size: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct CompressOutput {
+    original_size: u64,
+    compressed_size: u64,
+    files: Vec<CompressedFileOutput>,
+    skipped: Vec<SkippedFileOutput>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct CompressedFileOutput {
+    original: PathBuf,
+    path: PathBuf,
+    original_size: u64,
+    compressed_size: u64,
+    kept_original: bool,
+}
+
+/// A saved file a post-processing step left as downloaded.
+#[derive(Debug, Serialize, JsonSchema)]
+struct SkippedFileOutput {
+    path: PathBuf,
+    error: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct SavedFileOutput {
+    path: PathBuf,
+    url: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct DownloadFailureOutput {
     name: String,
-    aliases: Vec<String>,
-    files: usize,
-    variants: usize,
-    weights: Vec<String>,
-    styles: Vec<String>,
-    formats: Vec<String>,
-    indices: Vec<usize>,
-    index_ranges: Vec<String>,
+    url: String,
+    kind: &'static str,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditOutput {
+    source: String,
+    score: u8,
+    fonts_checked: usize,
+    findings: Vec<AuditFindingOutput>,
 }
 
 #[derive(Debug, Serialize)]
-struct FontOutput {
+struct AuditFindingOutput {
     index: usize,
     family: String,
-    source_family: String,
+    weight: String,
+    style: String,
+    url: String,
+    rule: &'static str,
+    severity: &'static str,
+    message: String,
+}
+
+impl AuditOutput {
+    fn new(source: &str, fonts: &[FontInfo], report: &AuditReport) -> Self {
+        Self {
+            source: source.to_owned(),
+            score: report.score,
+            fonts_checked: report.fonts_checked,
+            findings: report
+                .findings
+                .iter()
+                .map(|finding| {
+                    let font = &fonts[finding.font_index];
+                    AuditFindingOutput {
+                        index: finding.font_index,
+                        family: font.family.clone(),
+                        weight: font.weight.clone(),
+                        style: font.style.clone(),
+                        url: font.url.clone(),
+                        rule: finding.rule.id(),
+                        severity: finding.rule.severity().as_str(),
+                        message: finding.message.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AuditSiteOutput {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<u8>,
+    fonts: usize,
+    families: Vec<String>,
+    providers: Vec<&'static str>,
+    formats: Vec<&'static str>,
+    total_bytes: u64,
+    unmeasured_files: usize,
+    findings: BTreeMap<&'static str, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl AuditSiteOutput {
+    fn new(
+        url: &str,
+        fonts: &[FontInfo],
+        metadata: &HashMap<String, FontMetadata>,
+        report: &AuditReport,
+    ) -> Self {
+        let summary = summarize_site(fonts, metadata, report);
+        Self {
+            url: url.to_owned(),
+            score: Some(report.score),
+            fonts: report.fonts_checked,
+            families: summary.families,
+            providers: summary
+                .providers
+                .iter()
+                .map(|provider| provider.as_str())
+                .collect(),
+            formats: summary.formats,
+            total_bytes: summary.total_bytes,
+            unmeasured_files: summary.unmeasured,
+            findings: AuditRule::ALL
+                .iter()
+                .zip(summary.rule_counts)
+                .map(|(rule, count)| (rule.id(), count))
+                .collect(),
+            error: None,
+        }
+    }
+
+    fn failed(url: &str, error: String) -> Self {
+        Self {
+            url: url.to_owned(),
+            score: None,
+            fonts: 0,
+            families: Vec::new(),
+            providers: Vec::new(),
+            formats: Vec::new(),
+            total_bytes: 0,
+            unmeasured_files: 0,
+            findings: BTreeMap::new(),
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PerfOutput {
+    source: String,
+    status: &'static str,
+    total_bytes: u64,
+    unmeasured_files: usize,
+    budget_bytes: u64,
+    checks: Vec<PerfCheckOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct PerfCheckOutput {
+    id: &'static str,
+    status: &'static str,
+    summary: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    details: Vec<String>,
+}
+
+impl PerfOutput {
+    fn new(source: &str, report: &PerfReport, options: &PerfOptions) -> Self {
+        Self {
+            source: source.to_owned(),
+            status: report.status().as_str(),
+            total_bytes: report.total_bytes,
+            unmeasured_files: report.unmeasured,
+            budget_bytes: options.budget_bytes,
+            checks: report
+                .findings
+                .iter()
+                .map(|finding| PerfCheckOutput {
+                    id: finding.check.id(),
+                    status: finding.status.as_str(),
+                    summary: finding.summary.clone(),
+                    details: finding.details.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CheckOutput {
+    source: String,
+    available: usize,
+    redirected: usize,
+    files: Vec<CheckFileOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckFileOutput {
+    url: String,
+    indices: Vec<usize>,
+    families: Vec<String>,
+    method: &'static str,
+    available: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    final_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl CheckOutput {
+    fn new(source: &str, fonts: &[FontInfo], checks: &[FontCheck]) -> Self {
+        let files = checks
+            .iter()
+            .map(|check| {
+                let mut families = check
+                    .font_indices
+                    .iter()
+                    .map(|index| fonts[*index].family.clone())
+                    .collect::<Vec<_>>();
+                families.dedup();
+                CheckFileOutput {
+                    url: check.url.clone(),
+                    indices: check.font_indices.clone(),
+                    families,
+                    method: check.method.as_str(),
+                    available: check.is_available(),
+                    status: check.status,
+                    final_url: check.final_url.clone(),
+                    size: check.size,
+                    content_type: check.content_type.clone(),
+                    error: check.error.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        Self {
+            source: source.to_owned(),
+            available: files.iter().filter(|file| file.available).count(),
+            redirected: files.iter().filter(|file| file.final_url.is_some()).count(),
+            files,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SyncOutput {
+    url: String,
+    output: PathBuf,
+    dry_run: bool,
+    new: usize,
+    changed: usize,
+    unchanged: usize,
+    unchecked: usize,
+    files: Vec<SyncFileOutput>,
+    /// Saved fonts the site no longer declares.
+    stale: Vec<PathBuf>,
+    /// Whether `stale` files were deleted.
+    pruned: bool,
+    failures: Vec<DownloadFailureOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct SyncFileOutput {
+    status: &'static str,
+    url: String,
+    /// Where the font is saved, or `None` when it is not (yet).
+    path: Option<PathBuf>,
+}
+
+impl SyncOutput {
+    fn new(
+        url: &str,
+        args: &SyncArgs,
+        fonts: &[FontInfo],
+        plan: &SyncPlan,
+        saved_files: &[SavedFile],
+        failures: &[DownloadFailure],
+    ) -> Self {
+        let files = plan
+            .entries
+            .iter()
+            .map(|entry| {
+                let font = &fonts[entry.font_index];
+                let saved = saved_files.iter().find(|saved| saved.url == font.url);
+                SyncFileOutput {
+                    status: entry.status.as_str(),
+                    url: font.url.clone(),
+                    path: saved
+                        .map_or_else(|| entry.path.clone(), |saved| Some(saved.path.clone())),
+                }
+            })
+            .collect();
+        Self {
+            url: url.to_owned(),
+            output: args.output.clone(),
+            dry_run: args.dry_run,
+            new: plan.count(SyncStatus::New),
+            changed: plan.count(SyncStatus::Changed),
+            unchanged: plan.count(SyncStatus::Unchanged),
+            unchecked: plan.count(SyncStatus::Unchecked),
+            files,
+            stale: plan.stale.clone(),
+            pruned: args.prune && !args.dry_run,
+            failures: failures
+                .iter()
+                .map(|failure| DownloadFailureOutput {
+                    name: failure.name.clone(),
+                    url: failure.url.clone(),
+                    kind: failure.error.kind(),
+                    error: error_chain(&failure.error),
+                })
+                .collect(),
+        }
+    }
+
+    fn stale_status(&self) -> &'static str {
+        if self.pruned { "removed" } else { "stale" }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateOutput {
+    lockfile: PathBuf,
+    source: String,
+    changes: Vec<UpdateChangeOutput>,
+    /// Whether the lockfile was rewritten.
+    updated: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateChangeOutput {
+    change: &'static str,
+    url: String,
+    /// Family, weight, style, and format, as locked or as now declared.
+    font: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl UpdateOutput {
+    fn new(lockfile: &Path, lock: &FontLock, updated: &FontLock, changes: &[LockMismatch]) -> Self {
+        let describe = |url: &str| {
+            updated
+                .fonts
+                .iter()
+                .chain(&lock.fonts)
+                .find(|font| font.url == url)
+                .map(|font| {
+                    format!(
+                        "{} {} {} {}",
+                        font.family, font.weight, font.style, font.format
+                    )
+                })
+                .unwrap_or_default()
+        };
+        let short_hash = |sha256: &str| sha256.chars().take(12).collect::<String>();
+
+        Self {
+            lockfile: lockfile.to_path_buf(),
+            source: lock.source.clone(),
+            changes: changes
+                .iter()
+                .map(|change| UpdateChangeOutput {
+                    change: change.kind(),
+                    url: change.url().to_owned(),
+                    font: describe(change.url()),
+                    detail: match change {
+                        LockMismatch::Descriptors { locked, .. } => Some(format!("was {locked}")),
+                        LockMismatch::Content { locked, live, .. } => Some(format!(
+                            "sha256 {} -> {}",
+                            short_hash(locked),
+                            short_hash(live)
+                        )),
+                        LockMismatch::Removed { .. } | LockMismatch::Added { .. } => None,
+                    },
+                })
+                .collect(),
+            updated: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorOutput {
+    url: String,
+    healthy: bool,
+    checks: Vec<DoctorCheckOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorCheckOutput {
+    id: &'static str,
+    status: &'static str,
+    summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl DoctorOutput {
+    fn new(report: &DoctorReport) -> Self {
+        Self {
+            url: report.url.clone(),
+            healthy: !report.has_failures(),
+            checks: report
+                .checks
+                .iter()
+                .map(|check| DoctorCheckOutput {
+                    id: check.id,
+                    status: check.status.as_str(),
+                    summary: check.summary.clone(),
+                    hint: check.hint.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct FormatStatsOutput {
+    format: String,
+    files: usize,
+    sized_files: usize,
+    bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderStatsOutput {
+    provider: &'static str,
+    files: usize,
+    families: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct FamilyStatsOutput {
     name: String,
+    files: usize,
+    sized_files: usize,
+    bytes: u64,
+}
+#[derive(Debug, Serialize)]
+struct UaComparisonOutput {
+    source: String,
+    profiles: Vec<&'static str>,
+    total_found: usize,
+    differing: usize,
+    fonts: Vec<UaFontOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct UaFontOutput {
+    family: String,
     weight: String,
     style: String,
     format: String,
     url: String,
-    referer: String,
+    delivered_to: Vec<&'static str>,
+    differs: bool,
 }
@@ -0,0 +1,421 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::binary::FontMetadata;
+use crate::download::embedded_font_size;
+use crate::model::FontInfo;
+use crate::provider::FontProvider;
+use crate::selection::canonical_format;
+
+/// Files above this many bytes are flagged as oversized by default.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 100 * 1024;
+
+/// Selectors whose text is almost always on screen when a page first
+/// renders, so the fonts they use are worth preloading.
+const ABOVE_THE_FOLD_SELECTORS: &[&str] = &["html", "body", ":root", "h1"];
+
+/// How much a finding costs the score.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    fn penalty(self) -> u32 {
+        match self {
+            Self::Info => 2,
+            Self::Warning => 5,
+            Self::Error => 10,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuditRule {
+    /// Only EOT or SVG sources, which current browsers cannot use.
+    LegacyFormatsOnly,
+    /// No WOFF2 source, so every browser downloads a larger file.
+    NoWoff2,
+    /// No `font-display`, so text may stay invisible while the font loads.
+    MissingFontDisplay,
+    /// The regular face of a family used by `body`, `html`, `:root` or `h1`
+    /// is not preloaded.
+    MissingPreload,
+    /// The file is larger than [`AuditOptions::max_file_bytes`].
+    Oversized,
+}
+
+impl AuditRule {
+    pub const ALL: [Self; 5] = [
+        Self::LegacyFormatsOnly,
+        Self::NoWoff2,
+        Self::MissingFontDisplay,
+        Self::MissingPreload,
+        Self::Oversized,
+    ];
+
+    /// Stable kebab-case id, for JSON output and filtering.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::LegacyFormatsOnly => "legacy-formats-only",
+            Self::NoWoff2 => "no-woff2",
+            Self::MissingFontDisplay => "missing-font-display",
+            Self::MissingPreload => "missing-preload",
+            Self::Oversized => "oversized",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            Self::LegacyFormatsOnly => Severity::Error,
+            Self::NoWoff2 | Self::MissingFontDisplay | Self::Oversized => Severity::Warning,
+            Self::MissingPreload => Severity::Info,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditFinding {
+    pub rule: AuditRule,
+    /// Index into the audited fonts.
+    pub font_index: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditReport {
+    /// 100 minus each finding's penalty, never below 0.
+    pub score: u8,
+    pub fonts_checked: usize,
+    /// Ordered by font, then by severity, most severe first.
+    pub findings: Vec<AuditFinding>,
+}
+
+/// What one site loads, for comparing many sites side by side.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SiteSummary {
+    /// Sorted and deduplicated, as are `providers` and `formats`.
+    pub families: Vec<String>,
+    pub providers: Vec<FontProvider>,
+    pub formats: Vec<&'static str>,
+    /// Sum of the measured files, each URL counted once.
+    pub total_bytes: u64,
+    /// Distinct files whose size is unknown.
+    pub unmeasured: usize,
+    /// Findings per rule, in [`AuditRule::ALL`] order.
+    pub rule_counts: [usize; AuditRule::ALL.len()],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditOptions {
+    pub max_file_bytes: u64,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+        }
+    }
+}
+
+/// Checks each font for common web font anti-patterns. File sizes come from
+/// `metadata` (see [`probe_font_metadata`](crate::binary::probe_font_metadata))
+/// or from embedded `data:` URLs; fonts of unknown size are not flagged.
+pub fn audit_fonts(
+    fonts: &[FontInfo],
+    metadata: &HashMap<String, FontMetadata>,
+    options: &AuditOptions,
+) -> AuditReport {
+    let mut findings = Vec::new();
+
+    for (font_index, font) in fonts.iter().enumerate() {
+        let mut flag = |rule: AuditRule, message: String| {
+            findings.push(AuditFinding {
+                rule,
+                font_index,
+                message,
+            });
+        };
+        // Preload hints and script URLs have no @font-face to check.
+        let declared = font.usage.is_some();
+
+        if declared {
+            let formats = if font.sources.is_empty() {
+                vec![canonical_format(&font.format)]
+            } else {
+                font.sources
+                    .iter()
+                    .map(|source| canonical_format(&source.format))
+                    .collect()
+            };
+            if formats
+                .iter()
+                .all(|format| matches!(*format, "EOT" | "SVG"))
+            {
+                flag(
+                    AuditRule::LegacyFormatsOnly,
+                    format!("only legacy {} sources; add WOFF2", formats.join("/")),
+                );
+            } else if !formats.contains(&"WOFF2") {
+                flag(
+                    AuditRule::NoWoff2,
+                    format!("no WOFF2 source (has {})", formats.join(", ")),
+                );
+            }
+
+            if font.font_display.is_none() {
+                flag(
+                    AuditRule::MissingFontDisplay,
+                    "no font-display; text may be invisible while the font loads".to_owned(),
+                );
+            }
+
+            if let Some(selector) = above_the_fold_selector(font)
+                && is_regular_face(font)
+                && !font.preloaded
+            {
+                flag(
+                    AuditRule::MissingPreload,
+                    format!("used by {selector} but not preloaded"),
+                );
+            }
+        }
+
+        if let Some(size) = font_size(font, metadata)
+            && size > options.max_file_bytes
+        {
+            flag(
+                AuditRule::Oversized,
+                format!(
+                    "{} KiB, over the {} KiB budget; subset or compress it",
+                    size.div_ceil(1024),
+                    options.max_file_bytes / 1024
+                ),
+            );
+        }
+    }
+
+    findings.sort_by(|left, right| {
+        left.font_index
+            .cmp(&right.font_index)
+            .then(right.rule.severity().cmp(&left.rule.severity()))
+    });
+    let penalty = findings
+        .iter()
+        .map(|finding| finding.rule.severity().penalty())
+        .sum::<u32>();
+
+    AuditReport {
+        score: 100_u32.saturating_sub(penalty) as u8,
+        fonts_checked: fonts.len(),
+        findings,
+    }
+}
+
+/// Summarizes a site from its fonts, their `metadata` and its audit report.
+pub fn summarize_site(
+    fonts: &[FontInfo],
+    metadata: &HashMap<String, FontMetadata>,
+    report: &AuditReport,
+) -> SiteSummary {
+    let mut summary = SiteSummary::default();
+    let mut families = BTreeSet::new();
+    let mut providers = BTreeSet::new();
+    let mut formats = BTreeSet::new();
+    let mut urls = BTreeSet::new();
+    for font in fonts {
+        families.insert(font.family.clone());
+        providers.insert(FontProvider::for_font(font));
+        formats.insert(canonical_format(&font.format));
+        if !urls.insert(font.url.as_str()) {
+            continue;
+        }
+        match font_size(font, metadata) {
+            Some(size) => summary.total_bytes += size,
+            None => summary.unmeasured += 1,
+        }
+    }
+    summary.families = families.into_iter().collect();
+    summary.providers = providers.into_iter().collect();
+    summary.formats = formats.into_iter().collect();
+
+    for finding in &report.findings {
+        if let Some(position) = AuditRule::ALL.iter().position(|rule| *rule == finding.rule) {
+            summary.rule_counts[position] += 1;
+        }
+    }
+    summary
+}
+
+fn font_size(font: &FontInfo, metadata: &HashMap<String, FontMetadata>) -> Option<u64> {
+    metadata
+        .get(&font.url)
+        .and_then(|metadata| metadata.size)
+        .or_else(|| embedded_font_size(&font.url).map(|size| size as u64))
+}
+
+pub(crate) fn above_the_fold_selector(font: &FontInfo) -> Option<&str> {
+    font.used_by.iter().map(String::as_str).find(|selector| {
+        selector
+            .split(',')
+            .any(|part| ABOVE_THE_FOLD_SELECTORS.contains(&part.trim()))
+    })
+}
+
+/// Upright, with a regular weight or a range that covers it.
+pub(crate) fn is_regular_face(font: &FontInfo) -> bool {
+    let style = font.style.trim().to_ascii_lowercase();
+    if style != "normal" {
+        return false;
+    }
+
+    let mut bounds = font
+        .weight
+        .split_whitespace()
+        .filter_map(|part| match part {
+            "normal" => Some(400),
+            other => other.parse::<u16>().ok(),
+        });
+    match (bounds.next(), bounds.next()) {
+        (Some(weight), None) => weight == 400,
+        (Some(low), Some(high)) => (low..=high).contains(&400),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{AuditOptions, AuditRule, audit_fonts, summarize_site};
+    use crate::binary::FontMetadata;
+    use crate::model::{FontInfo, FontSource, FontUsageStatus};
+    use crate::provider::FontProvider;
+
+    fn font(url: &str, formats: &[&str]) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: formats[0].to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            sources: formats
+                .iter()
+                .map(|format| FontSource {
+                    url: url.to_owned(),
+                    format: (*format).to_owned(),
+                    tech: Vec::new(),
+                })
+                .collect(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            used_by: vec!["body".to_owned()],
+            usage: Some(FontUsageStatus::Used),
+            font_display: Some("swap".to_owned()),
+            preloaded: true,
+            ..FontInfo::default()
+        }
+    }
+
+    fn rules(report: &super::AuditReport, font_index: usize) -> Vec<AuditRule> {
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.font_index == font_index)
+            .map(|finding| finding.rule)
+            .collect()
+    }
+
+    #[test]
+    fn flags_formats_display_preloads_and_sizes() {
+        let clean = font("https://www.example.com/brand.woff2", &["WOFF2", "WOFF"]);
+        let legacy = font("https://www.example.com/brand.eot", &["EOT", "SVG"]);
+        let mut woff_only = font("https://www.example.com/brand.woff", &["WOFF"]);
+        woff_only.font_display = None;
+        woff_only.preloaded = false;
+        let mut bold = font("https://www.example.com/brand-bold.woff2", &["WOFF2"]);
+        bold.weight = "700".to_owned();
+        bold.preloaded = false;
+        let mut preload_hint = font("https://www.example.com/icons.woff2", &["WOFF2"]);
+        preload_hint.usage = None;
+        preload_hint.font_display = None;
+
+        let fonts = vec![clean, legacy, woff_only, bold, preload_hint];
+        let metadata = HashMap::from([(
+            fonts[0].url.clone(),
+            FontMetadata {
+                size: Some(180 * 1024),
+                ..FontMetadata::default()
+            },
+        )]);
+
+        let report = audit_fonts(&fonts, &metadata, &AuditOptions::default());
+
+        assert_eq!(rules(&report, 0), vec![AuditRule::Oversized]);
+        assert_eq!(rules(&report, 1), vec![AuditRule::LegacyFormatsOnly]);
+        assert_eq!(
+            rules(&report, 2),
+            vec![
+                AuditRule::NoWoff2,
+                AuditRule::MissingFontDisplay,
+                AuditRule::MissingPreload
+            ]
+        );
+        assert!(rules(&report, 3).is_empty());
+        assert!(rules(&report, 4).is_empty());
+        assert_eq!(report.score, 100 - 5 - 10 - 5 - 5 - 2);
+        assert!(report.findings[0].message.contains("180 KiB"));
+    }
+
+    #[test]
+    fn summarizes_families_providers_bytes_and_findings() {
+        let mut fonts = vec![
+            font("https://www.example.com/brand.woff2", &["WOFF2"]),
+            font("https://www.example.com/brand.woff2", &["WOFF2"]),
+            font("https://fonts.gstatic.com/s/inter.woff", &["WOFF"]),
+            font("https://www.example.com/unmeasured.ttf", &["TRUETYPE"]),
+        ];
+        fonts[2].family = "Inter".to_owned();
+        fonts[2].font_display = None;
+        let metadata = HashMap::from([
+            (
+                fonts[0].url.clone(),
+                FontMetadata {
+                    size: Some(20_000),
+                    ..FontMetadata::default()
+                },
+            ),
+            (
+                fonts[2].url.clone(),
+                FontMetadata {
+                    size: Some(30_000),
+                    ..FontMetadata::default()
+                },
+            ),
+        ]);
+        let report = audit_fonts(&fonts, &metadata, &AuditOptions::default());
+
+        let summary = summarize_site(&fonts, &metadata, &report);
+
+        assert_eq!(summary.families, vec!["Brand", "Inter"]);
+        assert_eq!(
+            summary.providers,
+            vec![FontProvider::GoogleFonts, FontProvider::SelfHosted]
+        );
+        assert_eq!(summary.formats, vec!["TRUETYPE", "WOFF", "WOFF2"]);
+        assert_eq!(summary.total_bytes, 50_000);
+        assert_eq!(summary.unmeasured, 1);
+        assert_eq!(summary.rule_counts, [0, 2, 1, 0, 0]);
+    }
+}
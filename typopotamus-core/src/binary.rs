@@ -0,0 +1,713 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use ttf_parser::{cmap, name, name_id, os2};
+
+use crate::cancel::CancellationToken;
+use crate::download::sha256_hex;
+use crate::extractor::DEFAULT_USER_AGENT;
+use crate::fetch::header::{ACCEPT, RANGE, REFERER, USER_AGENT};
+use crate::fetch::{FetchRequest, Fetcher};
+use crate::model::FontInfo;
+
+const PROBE_BYTES: usize = 64 * 1024;
+
+const NAME_TAG: [u8; 4] = *b"name";
+const OS2_TAG: [u8; 4] = *b"OS/2";
+const FVAR_TAG: [u8; 4] = *b"fvar";
+const CMAP_TAG: [u8; 4] = *b"cmap";
+/// Tables whose contents are kept when a font is parsed.
+const KEPT_TAGS: [[u8; 4]; 3] = [NAME_TAG, OS2_TAG, CMAP_TAG];
+
+pub(crate) const WOFF2_KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FontContainer {
+    Woff2,
+    Woff,
+    TrueType,
+    OpenType,
+    Collection,
+    Eot,
+    Svg,
+}
+
+impl FontContainer {
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        match bytes.get(..4)? {
+            b"wOF2" => return Some(Self::Woff2),
+            b"wOFF" => return Some(Self::Woff),
+            b"OTTO" => return Some(Self::OpenType),
+            [0x00, 0x01, 0x00, 0x00] | b"true" => return Some(Self::TrueType),
+            b"ttcf" => return Some(Self::Collection),
+            _ => {}
+        }
+
+        // EOT keeps its magic number 0x504C after a 34-byte header.
+        if bytes.get(34..36) == Some(&[0x4C, 0x50]) {
+            return Some(Self::Eot);
+        }
+
+        let head = text_head(bytes);
+        if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+            return Some(Self::Svg);
+        }
+
+        None
+    }
+
+    /// Format label in the same vocabulary as [`FontInfo::format`].
+    pub fn format(self) -> &'static str {
+        match self {
+            Self::Woff2 => "WOFF2",
+            Self::Woff => "WOFF",
+            Self::TrueType | Self::Collection => "TRUETYPE",
+            Self::OpenType => "OPENTYPE",
+            Self::Eot => "EOT",
+            Self::Svg => "SVG",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Woff2 => "woff2",
+            Self::Woff => "woff",
+            Self::TrueType => "ttf",
+            Self::OpenType => "otf",
+            Self::Collection => "ttc",
+            Self::Eot => "eot",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+/// Best guess at what a payload that is not a font actually contains, for
+/// error messages.
+pub fn describe_payload(bytes: &[u8]) -> &'static str {
+    if bytes.is_empty() {
+        return "an empty response";
+    }
+
+    let head = text_head(bytes);
+    if head.contains("challenge-platform")
+        || head.contains("cf-chl")
+        || head.contains("just a moment...")
+    {
+        "a Cloudflare challenge page"
+    } else if head.starts_with("<!doctype html")
+        || head.starts_with("<html")
+        || head.contains("<body")
+    {
+        "an HTML page"
+    } else if head.starts_with('{') || head.starts_with('[') {
+        "a JSON document"
+    } else if head.starts_with('<') {
+        "an XML document"
+    } else {
+        "unrecognized data"
+    }
+}
+
+fn text_head(bytes: &[u8]) -> String {
+    let head = &bytes[..bytes.len().min(2048)];
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    String::from_utf8_lossy(head)
+        .trim_start()
+        .to_ascii_lowercase()
+}
+
+/// Naming and classification data read from a font's `name` and `OS/2` tables,
+/// plus whether it is a variable font and, when probed, its size in bytes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FontMetadata {
+    pub family: Option<String>,
+    pub subfamily: Option<String>,
+    pub full_name: Option<String>,
+    pub postscript_name: Option<String>,
+    pub weight: Option<u16>,
+    pub style: Option<String>,
+    /// Has an `fvar` table, i.e. is a variable font.
+    pub variable: bool,
+    pub size: Option<u64>,
+}
+
+/// Parses naming metadata from an sfnt, WOFF, or WOFF2 payload.
+///
+/// Returns `None` when the container is unknown or the `name` table is
+/// missing or truncated, e.g. because only a prefix of the file was fetched.
+pub fn parse_font_metadata(bytes: &[u8]) -> Option<FontMetadata> {
+    let tables = match FontContainer::sniff(bytes)? {
+        FontContainer::TrueType | FontContainer::OpenType => sfnt_tables(bytes)?,
+        FontContainer::Woff => woff_tables(bytes)?,
+        FontContainer::Woff2 => woff2_tables(bytes)?,
+        FontContainer::Collection | FontContainer::Eot | FontContainer::Svg => return None,
+    };
+
+    let name_table = name::Table::parse(tables.get(&NAME_TAG)?)?;
+    let lookup = |ids: &[u16]| {
+        ids.iter().find_map(|id| {
+            name_table
+                .names
+                .into_iter()
+                .filter(|entry| entry.name_id == *id)
+                .find_map(|entry| entry.to_string())
+                .map(|value| value.trim().to_owned())
+                .filter(|value| !value.is_empty())
+        })
+    };
+
+    let mut metadata = FontMetadata {
+        family: lookup(&[name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY]),
+        subfamily: lookup(&[name_id::TYPOGRAPHIC_SUBFAMILY, name_id::SUBFAMILY]),
+        full_name: lookup(&[name_id::FULL_NAME]),
+        postscript_name: lookup(&[name_id::POST_SCRIPT_NAME]),
+        weight: None,
+        style: None,
+        variable: tables.contains_key(&FVAR_TAG),
+        size: None,
+    };
+
+    if let Some(table) = tables
+        .get(&OS2_TAG)
+        .and_then(|data| os2::Table::parse(data))
+    {
+        metadata.weight = Some(table.weight().to_number());
+        metadata.style = Some(
+            match table.style() {
+                os2::Style::Normal => "normal",
+                os2::Style::Italic => "italic",
+                os2::Style::Oblique => "oblique",
+            }
+            .to_owned(),
+        );
+    }
+
+    Some(metadata)
+}
+
+/// Every character an sfnt, WOFF, or WOFF2 payload maps to a glyph, in code
+/// point order. Empty when the container is unknown or has no `cmap`.
+pub fn font_characters(bytes: &[u8]) -> Vec<char> {
+    let tables = match FontContainer::sniff(bytes) {
+        Some(FontContainer::TrueType | FontContainer::OpenType) => sfnt_tables(bytes),
+        Some(FontContainer::Woff) => woff_tables(bytes),
+        Some(FontContainer::Woff2) => woff2_tables(bytes),
+        _ => None,
+    };
+    let Some(table) = tables
+        .as_ref()
+        .and_then(|tables| tables.get(&CMAP_TAG))
+        .and_then(|data| cmap::Table::parse(data))
+    else {
+        return Vec::new();
+    };
+
+    let mut characters = BTreeSet::new();
+    for subtable in table
+        .subtables
+        .into_iter()
+        .filter(|subtable| subtable.is_unicode())
+    {
+        subtable.codepoints(|codepoint| {
+            if let Some(character) = char::from_u32(codepoint)
+                && subtable
+                    .glyph_index(codepoint)
+                    .is_some_and(|glyph| glyph.0 != 0)
+            {
+                characters.insert(character);
+            }
+        });
+    }
+    characters.into_iter().collect()
+}
+
+/// Fetches the start of each font (the whole file when the prefix is not
+/// enough) and parses its metadata, keyed by font URL.
+pub fn probe_font_metadata(
+    fonts: &[FontInfo],
+    fetcher: &dyn Fetcher,
+    cancel: &CancellationToken,
+) -> HashMap<String, FontMetadata> {
+    let mut probed = HashMap::new();
+
+    for font in fonts {
+        if cancel.is_cancelled() {
+            break;
+        }
+
+        if font.url.starts_with("data:") || probed.contains_key(&font.url) {
+            continue;
+        }
+
+        if let Some(metadata) = probe_single_font(font, fetcher) {
+            probed.insert(font.url.clone(), metadata);
+        }
+    }
+
+    probed
+}
+
+/// Downloads each font and hashes it, keyed by font URL. Failed and
+/// truncated downloads are left out.
+pub fn probe_content_hashes(
+    fonts: &[FontInfo],
+    fetcher: &dyn Fetcher,
+    user_agent: &str,
+    cancel: &CancellationToken,
+) -> HashMap<String, String> {
+    let mut hashes = HashMap::new();
+
+    for font in fonts {
+        if cancel.is_cancelled() {
+            break;
+        }
+
+        if font.url.starts_with("data:") || hashes.contains_key(&font.url) {
+            continue;
+        }
+
+        let request = FetchRequest::get(&font.url)
+            .header(USER_AGENT, user_agent)
+            .header(ACCEPT, "*/*")
+            .header(REFERER, &font.referer);
+        if let Ok(response) = fetcher.fetch(&request)
+            && response.is_success()
+            && !response.truncated
+        {
+            hashes.insert(font.url.clone(), sha256_hex(&response.body));
+        }
+    }
+
+    hashes
+}
+
+fn probe_single_font(font: &FontInfo, fetcher: &dyn Fetcher) -> Option<FontMetadata> {
+    let request = FetchRequest::get(&font.url)
+        .header(USER_AGENT, DEFAULT_USER_AGENT)
+        .header(ACCEPT, "*/*")
+        .header(REFERER, &font.referer);
+
+    let prefix = fetcher
+        .fetch(
+            &request
+                .clone()
+                .header(RANGE, format!("bytes=0-{}", PROBE_BYTES - 1)),
+        )
+        .ok()
+        .filter(|response| response.is_success())?;
+
+    let size = match prefix.status {
+        206 => prefix
+            .header("content-range")
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| total.trim().parse().ok()),
+        _ => Some(prefix.body.len() as u64),
+    };
+
+    if let Some(metadata) = parse_font_metadata(&prefix.body) {
+        return Some(FontMetadata { size, ..metadata });
+    }
+
+    if prefix.status != 206 {
+        return None;
+    }
+
+    let full = fetcher
+        .fetch(&request)
+        .ok()
+        .filter(|response| response.is_success())?;
+    parse_font_metadata(&full.body).map(|metadata| FontMetadata {
+        size: Some(full.body.len() as u64),
+        ..metadata
+    })
+}
+
+type TableMap = HashMap<[u8; 4], Vec<u8>>;
+
+fn sfnt_tables(bytes: &[u8]) -> Option<TableMap> {
+    let num_tables = read_u16(bytes, 4)? as usize;
+    let mut tables = HashMap::new();
+
+    for index in 0..num_tables {
+        let record = 12 + index * 16;
+        let tag = read_tag(bytes, record)?;
+        if tag == FVAR_TAG {
+            // Only the presence of `fvar` matters.
+            tables.insert(tag, Vec::new());
+            continue;
+        }
+        if !KEPT_TAGS.contains(&tag) {
+            continue;
+        }
+
+        let offset = read_u32(bytes, record + 8)? as usize;
+        let length = read_u32(bytes, record + 12)? as usize;
+        if let Some(data) = bytes.get(offset..offset.checked_add(length)?) {
+            tables.insert(tag, data.to_vec());
+        }
+    }
+
+    Some(tables)
+}
+
+fn woff_tables(bytes: &[u8]) -> Option<TableMap> {
+    let num_tables = read_u16(bytes, 12)? as usize;
+    let mut tables = HashMap::new();
+
+    for index in 0..num_tables {
+        let record = 44 + index * 20;
+        let tag = read_tag(bytes, record)?;
+        if tag == FVAR_TAG {
+            // Only the presence of `fvar` matters.
+            tables.insert(tag, Vec::new());
+            continue;
+        }
+        if !KEPT_TAGS.contains(&tag) {
+            continue;
+        }
+
+        let offset = read_u32(bytes, record + 4)? as usize;
+        let compressed_length = read_u32(bytes, record + 8)? as usize;
+        let original_length = read_u32(bytes, record + 12)? as usize;
+        let Some(data) = bytes.get(offset..offset.checked_add(compressed_length)?) else {
+            continue;
+        };
+
+        if compressed_length < original_length {
+            // Inflated no further than the length the directory declares.
+            let mut decoded = Vec::new();
+            if ZlibDecoder::new(data)
+                .take(original_length as u64)
+                .read_to_end(&mut decoded)
+                .is_ok()
+            {
+                tables.insert(tag, decoded);
+            }
+        } else {
+            tables.insert(tag, data.to_vec());
+        }
+    }
+
+    Some(tables)
+}
+
+fn woff2_tables(bytes: &[u8]) -> Option<TableMap> {
+    if read_tag(bytes, 4)? == *b"ttcf" {
+        return None;
+    }
+
+    let num_tables = read_u16(bytes, 12)? as usize;
+    let compressed_length = read_u32(bytes, 20)? as usize;
+
+    let mut cursor = 48;
+    let mut entries = Vec::with_capacity(num_tables);
+    for _ in 0..num_tables {
+        let flags = *bytes.get(cursor)?;
+        cursor += 1;
+
+        let tag = match flags & 0x3f {
+            63 => {
+                let tag = read_tag(bytes, cursor)?;
+                cursor += 4;
+                tag
+            }
+            known => *WOFF2_KNOWN_TAGS[known as usize],
+        };
+
+        let original_length = read_base128(bytes, &mut cursor)?;
+        let transform_version = flags >> 6;
+        let transformed = if tag == *b"glyf" || tag == *b"loca" {
+            transform_version == 0
+        } else {
+            transform_version != 0
+        };
+        let stored_length = if transformed {
+            read_base128(bytes, &mut cursor)?
+        } else {
+            original_length
+        };
+
+        entries.push((tag, stored_length as usize));
+    }
+
+    let available = bytes.len().saturating_sub(cursor).min(compressed_length);
+    let compressed = bytes.get(cursor..cursor + available)?;
+
+    // Tables are stored back to back, so decoding can stop at the end of the
+    // last one kept, as the directory declares it.
+    let mut needed = 0_u64;
+    let mut end = 0_u64;
+    for (tag, length) in &entries {
+        end += *length as u64;
+        if KEPT_TAGS.contains(tag) {
+            needed = end;
+        }
+    }
+
+    let mut decompressed = Vec::new();
+    let decoder = brotli_decompressor::Decompressor::new(compressed, 4096);
+    // A truncated stream still yields every table decoded before the cut.
+    let _ = decoder.take(needed).read_to_end(&mut decompressed);
+
+    let mut tables = HashMap::new();
+    let mut offset = 0_usize;
+    for (tag, length) in entries {
+        let end = offset.checked_add(length)?;
+        if tag == FVAR_TAG {
+            tables.insert(tag, Vec::new());
+        } else if KEPT_TAGS.contains(&tag)
+            && let Some(data) = decompressed.get(offset..end)
+        {
+            tables.insert(tag, data.to_vec());
+        }
+        offset = end;
+    }
+
+    Some(tables)
+}
+
+pub(crate) fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([slice[0], slice[1]]))
+}
+
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+pub(crate) fn read_tag(bytes: &[u8], offset: usize) -> Option<[u8; 4]> {
+    bytes.get(offset..offset + 4)?.try_into().ok()
+}
+
+pub(crate) fn read_base128(bytes: &[u8], cursor: &mut usize) -> Option<u32> {
+    let mut value = 0_u32;
+    for index in 0..5 {
+        let byte = *bytes.get(*cursor)?;
+        *cursor += 1;
+
+        if index == 0 && byte == 0x80 {
+            return None;
+        }
+        if value & 0xfe00_0000 != 0 {
+            return None;
+        }
+
+        value = (value << 7) | u32::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::ZlibEncoder};
+
+    use super::{
+        FontContainer, describe_payload, font_characters, parse_font_metadata, woff_tables,
+    };
+
+    fn name_table(records: &[(u16, &str)]) -> Vec<u8> {
+        let mut storage = Vec::new();
+        let mut table = Vec::new();
+        table.extend_from_slice(&0_u16.to_be_bytes());
+        table.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        table.extend_from_slice(&(6 + records.len() as u16 * 12).to_be_bytes());
+
+        for (name_id, value) in records {
+            let encoded = value
+                .encode_utf16()
+                .flat_map(u16::to_be_bytes)
+                .collect::<Vec<_>>();
+            for field in [3_u16, 1, 0x0409, *name_id, encoded.len() as u16] {
+                table.extend_from_slice(&field.to_be_bytes());
+            }
+            table.extend_from_slice(&(storage.len() as u16).to_be_bytes());
+            storage.extend_from_slice(&encoded);
+        }
+
+        table.extend_from_slice(&storage);
+        table
+    }
+
+    fn os2_table(weight: u16, fs_selection: u16) -> Vec<u8> {
+        let mut table = vec![0_u8; 78];
+        table[4..6].copy_from_slice(&weight.to_be_bytes());
+        table[62..64].copy_from_slice(&fs_selection.to_be_bytes());
+        table
+    }
+
+    /// A Windows Unicode BMP `cmap` mapping `first..=last` to glyphs 1...
+    fn cmap_table(first: u16, last: u16) -> Vec<u8> {
+        let mut table = Vec::new();
+        for field in [0_u16, 1, 3, 1, 0, 12] {
+            table.extend_from_slice(&field.to_be_bytes());
+        }
+        let subtable = [
+            4,
+            32,
+            0,
+            4,
+            4,
+            1,
+            0,
+            last,
+            0xffff,
+            0,
+            first,
+            0xffff,
+            1_u16.wrapping_sub(first),
+            1,
+            0,
+            0,
+        ];
+        for field in subtable {
+            table.extend_from_slice(&field.to_be_bytes());
+        }
+        table
+    }
+
+    fn sfnt(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut font = vec![0x00, 0x01, 0x00, 0x00];
+        font.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+        font.extend_from_slice(&[0; 6]);
+
+        let mut offset = 12 + tables.len() * 16;
+        for (tag, data) in tables {
+            font.extend_from_slice(*tag);
+            font.extend_from_slice(&0_u32.to_be_bytes());
+            font.extend_from_slice(&(offset as u32).to_be_bytes());
+            font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            offset += data.len();
+        }
+        for (_, data) in tables {
+            font.extend_from_slice(data);
+        }
+        font
+    }
+
+    #[test]
+    fn lists_mapped_characters() {
+        let font = sfnt(&[(b"cmap", cmap_table(0x41, 0x43))]);
+        assert_eq!(font_characters(&font), vec!['A', 'B', 'C']);
+        assert!(font_characters(&sfnt(&[])).is_empty());
+        assert!(font_characters(b"not a font").is_empty());
+    }
+
+    #[test]
+    fn sniffs_font_containers_from_magic_numbers() {
+        assert_eq!(
+            FontContainer::sniff(b"wOF2...."),
+            Some(FontContainer::Woff2)
+        );
+        assert_eq!(FontContainer::sniff(b"wOFF...."), Some(FontContainer::Woff));
+        assert_eq!(
+            FontContainer::sniff(b"OTTO...."),
+            Some(FontContainer::OpenType)
+        );
+        assert_eq!(
+            FontContainer::sniff(&[0, 1, 0, 0, 0]),
+            Some(FontContainer::TrueType)
+        );
+        assert_eq!(FontContainer::sniff(b"<!DOCTYPE html>"), None);
+    }
+
+    #[test]
+    fn describes_html_and_challenge_pages_served_instead_of_fonts() {
+        assert_eq!(
+            describe_payload(b"\n<!DOCTYPE html><html><body>Not found</body></html>"),
+            "an HTML page"
+        );
+        assert_eq!(
+            describe_payload(
+                b"<!DOCTYPE html><title>Just a moment...</title><script src=\"/cdn-cgi/challenge-platform/h/b\"></script>"
+            ),
+            "a Cloudflare challenge page"
+        );
+        assert_eq!(describe_payload(b""), "an empty response");
+        assert_eq!(
+            FontContainer::sniff(b"<?xml version=\"1.0\"?><svg><defs><font id=\"a\">"),
+            Some(FontContainer::Svg)
+        );
+    }
+
+    #[test]
+    fn parses_typographic_names_weight_and_style_from_sfnt() {
+        let font = sfnt(&[
+            (b"OS/2", os2_table(600, 0x0001)),
+            (
+                b"name",
+                name_table(&[
+                    (1, "Inter SemiBold"),
+                    (2, "Italic"),
+                    (16, "Inter"),
+                    (17, "SemiBold Italic"),
+                ]),
+            ),
+        ]);
+
+        let metadata = parse_font_metadata(&font).expect("metadata should parse");
+        assert_eq!(metadata.family.as_deref(), Some("Inter"));
+        assert_eq!(metadata.subfamily.as_deref(), Some("SemiBold Italic"));
+        assert_eq!(metadata.weight, Some(600));
+        assert_eq!(metadata.style.as_deref(), Some("italic"));
+        assert!(!metadata.variable);
+    }
+
+    #[test]
+    fn fonts_with_an_fvar_table_are_variable() {
+        let font = sfnt(&[
+            (b"fvar", vec![0; 16]),
+            (b"name", name_table(&[(1, "Inter Variable")])),
+        ]);
+
+        let metadata = parse_font_metadata(&font).expect("metadata should parse");
+        assert!(metadata.variable);
+    }
+
+    #[test]
+    fn woff_tables_inflate_no_further_than_their_declared_length() {
+        let name = name_table(&[(1, "Inter")]);
+        let mut inflated = name.clone();
+        inflated.resize(1024 * 1024, 0);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&inflated).expect("deflate");
+        let compressed = encoder.finish().expect("deflate");
+
+        let mut font = b"wOFF".to_vec();
+        font.extend_from_slice(&[0; 8]);
+        font.extend_from_slice(&1_u16.to_be_bytes());
+        font.resize(44, 0);
+        font.extend_from_slice(b"name");
+        let declared = 4096;
+        for field in [64, compressed.len(), declared, 0] {
+            font.extend_from_slice(&(field as u32).to_be_bytes());
+        }
+        font.extend_from_slice(&compressed);
+
+        let tables = woff_tables(&font).expect("directory should parse");
+        assert_eq!(tables[b"name"], inflated[..declared]);
+        let metadata = parse_font_metadata(&font).expect("metadata should parse");
+        assert_eq!(metadata.family.as_deref(), Some("Inter"));
+    }
+
+    #[test]
+    fn truncated_payloads_yield_no_metadata() {
+        let font = sfnt(&[(b"name", name_table(&[(1, "Inter")]))]);
+        assert!(parse_font_metadata(&font[..font.len() - 4]).is_none());
+    }
+}
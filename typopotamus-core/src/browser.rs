@@ -0,0 +1,66 @@
+use js_sys::{ArrayBuffer, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{XmlHttpRequest, XmlHttpRequestResponseType};
+
+use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+
+/// A [`Fetcher`] for `wasm32-unknown-unknown` that goes through the browser's
+/// networking stack, so extraction and inference can run in a web UI or an
+/// extension.
+///
+/// Extraction is synchronous, so this issues synchronous `XMLHttpRequest`s and
+/// must run inside a Web Worker. The browser drops forbidden headers such as
+/// `User-Agent` and `Referer`, and cross-origin responses are only readable
+/// when the server allows them through CORS or the extension has host
+/// permissions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BrowserFetcher;
+
+impl Fetcher for BrowserFetcher {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+        let xhr = XmlHttpRequest::new().map_err(js_error)?;
+        xhr.open_with_async(request.method.as_str(), &request.url, false)
+            .map_err(js_error)?;
+        xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+        for (name, value) in &request.headers {
+            // Headers the browser controls itself are refused; send the rest.
+            let _ = xhr.set_request_header(name, value);
+        }
+        xhr.send().map_err(js_error)?;
+
+        let headers = xhr
+            .get_all_response_headers()
+            .map_err(js_error)?
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+            .collect();
+        let body = xhr
+            .response()
+            .map_err(js_error)?
+            .dyn_into::<ArrayBuffer>()
+            .map(|buffer| Uint8Array::new(&buffer).to_vec())
+            .unwrap_or_default();
+
+        let mut response = FetchResponse {
+            status: xhr.status().map_err(js_error)?,
+            headers,
+            body,
+            truncated: false,
+            from_cache: false,
+            final_url: Some(xhr.response_url())
+                .filter(|final_url| !final_url.is_empty() && *final_url != request.url),
+        };
+        response.truncate_body(request.max_body_bytes);
+        Ok(response)
+    }
+}
+
+fn js_error(value: JsValue) -> FetchError {
+    value
+        .dyn_ref::<js_sys::Error>()
+        .map(|error| String::from(error.message()))
+        .or_else(|| value.as_string())
+        .unwrap_or_else(|| "browser request failed".to_owned())
+        .into()
+}
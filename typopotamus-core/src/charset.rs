@@ -0,0 +1,125 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+
+/// How far into an HTML document `<meta charset>` is looked for, as in the
+/// HTML encoding sniffing prescan.
+const META_PRESCAN_BYTES: usize = 1024;
+
+static CSS_CHARSET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\A@charset "([^"]{1,64})";"#).expect("valid @charset regex"));
+static META_CHARSET_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<meta\s[^>]*?charset\s*=\s*["']?\s*([\w.:-]+)"#)
+        .expect("valid meta charset regex")
+});
+
+/// The kind of text resource being decoded, which decides where an in-band
+/// encoding declaration may appear.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TextKind {
+    Html,
+    Css,
+    Script,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecodedText {
+    pub text: String,
+    /// The WHATWG name of the encoding used, e.g. `UTF-8` or `GBK`.
+    pub encoding: &'static str,
+    /// Some bytes were invalid in that encoding and became U+FFFD.
+    pub lossy: bool,
+}
+
+/// Decodes a response body, honoring a byte order mark first, then the
+/// `Content-Type` charset, then `@charset` or `<meta charset>`, and finally
+/// falling back to UTF-8.
+pub fn decode_text(body: &[u8], content_type: Option<&str>, kind: TextKind) -> DecodedText {
+    let (encoding, body) = match Encoding::for_bom(body) {
+        Some((encoding, bom_length)) => (encoding, &body[bom_length..]),
+        None => (
+            content_type
+                .and_then(content_type_charset)
+                .or_else(|| declared_charset(body, kind))
+                .unwrap_or(UTF_8),
+            body,
+        ),
+    };
+
+    let (text, lossy) = encoding.decode_without_bom_handling(body);
+    DecodedText {
+        text: text.into_owned(),
+        encoding: encoding.name(),
+        lossy,
+    }
+}
+
+fn content_type_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+fn declared_charset(body: &[u8], kind: TextKind) -> Option<&'static Encoding> {
+    let label = match kind {
+        TextKind::Css => CSS_CHARSET_RE.captures(body)?.get(1)?,
+        TextKind::Html => {
+            let head = &body[..body.len().min(META_PRESCAN_BYTES)];
+            META_CHARSET_RE.captures(head)?.get(1)?
+        }
+        TextKind::Script => return None,
+    };
+
+    // An in-band UTF-16 declaration can only have been read if the bytes are
+    // ASCII-compatible, so both CSS and HTML treat it as UTF-8.
+    let encoding = Encoding::for_label(label.as_bytes())?;
+    Some(match encoding.name() {
+        "UTF-16LE" | "UTF-16BE" => UTF_8,
+        "x-user-defined" => WINDOWS_1252,
+        _ => encoding,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextKind, decode_text};
+
+    #[test]
+    fn honors_bom_then_header_then_in_band_declaration() {
+        // "Café" in ISO-8859-1 / windows-1252.
+        let latin1 = b"@font-face{font-family:\"Caf\xe9\"}";
+        let decoded = decode_text(latin1, Some("text/css; charset=ISO-8859-1"), TextKind::Css);
+        assert_eq!(decoded.text, "@font-face{font-family:\"Café\"}");
+        assert_eq!(decoded.encoding, "windows-1252");
+        assert!(!decoded.lossy);
+
+        // "宋体" in GBK, declared with @charset.
+        let gbk = b"@charset \"GBK\";@font-face{font-family:\"\xcb\xce\xcc\xe5\"}";
+        let decoded = decode_text(gbk, Some("text/css"), TextKind::Css);
+        assert_eq!(decoded.encoding, "GBK");
+        assert!(decoded.text.contains("\"宋体\""));
+
+        let html = b"<html><head><meta charset=\"shift_jis\"><title>\x83e</title>";
+        assert_eq!(
+            decode_text(html, None, TextKind::Html).encoding,
+            "Shift_JIS"
+        );
+
+        let bom = b"\xef\xbb\xbfbody{font-family:\"Caf\xc3\xa9\"}";
+        let decoded = decode_text(bom, Some("text/css; charset=GBK"), TextKind::Css);
+        assert_eq!(decoded.text, "body{font-family:\"Café\"}");
+        assert_eq!(decoded.encoding, "UTF-8");
+    }
+
+    #[test]
+    fn undeclared_invalid_utf8_decodes_lossily() {
+        let decoded = decode_text(b"a\xe9b", None, TextKind::Script);
+        assert_eq!(decoded.text, "a\u{fffd}b");
+        assert_eq!(decoded.encoding, "UTF-8");
+        assert!(decoded.lossy);
+    }
+}
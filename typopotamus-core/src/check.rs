@@ -0,0 +1,259 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::cancel::CancellationToken;
+use crate::enrich::{DEFAULT_ENRICH_CONCURRENCY, font_request, response_length};
+use crate::error::error_chain;
+use crate::fetch::header::{CONTENT_TYPE, ETAG, LAST_MODIFIED, RANGE};
+use crate::fetch::{FetchMethod, FetchRequest, FetchResponse, Fetcher};
+use crate::model::FontInfo;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CheckOptions {
+    /// Requests in flight at once; `0` is treated as `1`.
+    pub concurrency: usize,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_ENRICH_CONCURRENCY,
+        }
+    }
+}
+
+/// How one font file answered.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FontCheck {
+    pub url: String,
+    /// Indices of the fonts served from `url`.
+    pub font_indices: Vec<usize>,
+    /// `HEAD`, or `GET` when the server refused `HEAD` or sent no length.
+    pub method: FetchMethod,
+    /// `None` when the request failed; see `error`.
+    pub status: Option<u16>,
+    /// Where redirects led, when they did.
+    pub final_url: Option<String>,
+    pub size: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub error: Option<String>,
+}
+
+impl FontCheck {
+    /// Answered with a 2xx status, possibly after redirects.
+    pub fn is_available(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Requests every distinct font URL without downloading the files: a `HEAD`,
+/// or a one-byte ranged `GET` when that is refused. Embedded `data:` URLs
+/// are skipped, as are URLs not reached before `cancel`. Results follow the
+/// order the URLs first appear in.
+pub fn check_fonts(
+    fonts: &[FontInfo],
+    fetcher: &dyn Fetcher,
+    options: &CheckOptions,
+    cancel: &CancellationToken,
+) -> Vec<FontCheck> {
+    let mut pending = Vec::<(&str, &str, Vec<usize>)>::new();
+    for (index, font) in fonts.iter().enumerate() {
+        if font.url.starts_with("data:") {
+            continue;
+        }
+        match pending.iter_mut().find(|(url, _, _)| *url == font.url) {
+            Some((_, _, indices)) => indices.push(index),
+            None => pending.push((&font.url, &font.referer, vec![index])),
+        }
+    }
+
+    let queue = Mutex::new(pending.into_iter().enumerate());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..options.concurrency.max(1) {
+            scope.spawn(|| {
+                loop {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    let Some((position, (url, referer, font_indices))) =
+                        queue.lock().expect("check queue").next()
+                    else {
+                        break;
+                    };
+                    let check = check_url(url, referer, font_indices, fetcher);
+                    results
+                        .lock()
+                        .expect("check results")
+                        .push((position, check));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().expect("check results");
+    results.sort_by_key(|(position, _)| *position);
+    results.into_iter().map(|(_, check)| check).collect()
+}
+
+fn check_url(
+    url: &str,
+    referer: &str,
+    font_indices: Vec<usize>,
+    fetcher: &dyn Fetcher,
+) -> FontCheck {
+    let check = |method: FetchMethod, result: Result<FetchResponse, String>| match result {
+        Ok(response) => FontCheck {
+            url: url.to_owned(),
+            font_indices: font_indices.clone(),
+            method,
+            status: Some(response.status),
+            final_url: response.final_url.clone(),
+            size: response
+                .is_success()
+                .then(|| response_length(&response))
+                .flatten(),
+            content_type: response.header(CONTENT_TYPE).map(str::to_owned),
+            etag: response.header(ETAG).map(str::to_owned),
+            last_modified: response.header(LAST_MODIFIED).map(str::to_owned),
+            error: None,
+        },
+        Err(error) => FontCheck {
+            url: url.to_owned(),
+            font_indices: font_indices.clone(),
+            method,
+            status: None,
+            final_url: None,
+            size: None,
+            content_type: None,
+            etag: None,
+            last_modified: None,
+            error: Some(error),
+        },
+    };
+
+    let head = fetcher
+        .fetch(&font_request(FetchRequest::head(url), referer))
+        .map_err(|error| error_chain(error.as_ref()));
+    if let Ok(response) = &head
+        && !matches!(response.status, 405 | 501)
+        && (!response.is_success() || response_length(response).is_some())
+    {
+        return check(FetchMethod::Head, head);
+    }
+
+    // Some CDNs refuse `HEAD` or answer it without a length.
+    let probe = fetcher
+        .fetch(
+            &font_request(FetchRequest::get(url), referer)
+                .header(RANGE, "bytes=0-0")
+                .max_body_bytes(Some(1)),
+        )
+        .map_err(|error| error_chain(error.as_ref()));
+    check(FetchMethod::Get, probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CheckOptions, check_fonts};
+    use crate::cancel::CancellationToken;
+    use crate::fetch::{FetchError, FetchMethod, FetchRequest, FetchResponse, Fetcher};
+    use crate::model::FontInfo;
+
+    /// `ok.woff2` answers `HEAD`, `moved.woff2` redirects, `get.woff2` only
+    /// answers `GET`, `gone.woff2` is missing and `down.woff2` times out.
+    struct Server;
+
+    impl Fetcher for Server {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            let header = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+            let name = request.url.rsplit('/').next().unwrap_or_default();
+            Ok(match (request.method, name) {
+                (_, "down.woff2") => return Err("operation timed out".into()),
+                (FetchMethod::Head, "ok.woff2") => FetchResponse {
+                    status: 200,
+                    headers: vec![
+                        header("Content-Length", "1200"),
+                        header("Content-Type", "font/woff2"),
+                    ],
+                    ..FetchResponse::default()
+                },
+                (FetchMethod::Head, "moved.woff2") => FetchResponse {
+                    status: 200,
+                    headers: vec![header("Content-Length", "800")],
+                    final_url: Some("https://cdn.example/v2/moved.woff2".to_owned()),
+                    ..FetchResponse::default()
+                },
+                (FetchMethod::Get, "get.woff2") => FetchResponse {
+                    status: 206,
+                    headers: vec![header("Content-Range", "bytes 0-0/5000")],
+                    body: vec![0],
+                    ..FetchResponse::default()
+                },
+                (FetchMethod::Head, "get.woff2") => FetchResponse {
+                    status: 405,
+                    ..FetchResponse::default()
+                },
+                _ => FetchResponse {
+                    status: 404,
+                    ..FetchResponse::default()
+                },
+            })
+        }
+    }
+
+    fn font(url: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn reports_status_redirects_and_sizes_per_url() {
+        let fonts = [
+            font("https://cdn.example/ok.woff2"),
+            font("https://cdn.example/moved.woff2"),
+            font("https://cdn.example/get.woff2"),
+            font("https://cdn.example/gone.woff2"),
+            font("https://cdn.example/down.woff2"),
+            font("https://cdn.example/ok.woff2"),
+            font("data:font/woff2;base64,d09GMg=="),
+        ];
+
+        let checks = check_fonts(
+            &fonts,
+            &Server,
+            &CheckOptions { concurrency: 3 },
+            &CancellationToken::new(),
+        );
+
+        assert_eq!(checks.len(), 5);
+        assert_eq!(checks[0].font_indices, vec![0, 5]);
+        assert_eq!(checks[0].status, Some(200));
+        assert_eq!(checks[0].size, Some(1200));
+        assert_eq!(checks[0].content_type.as_deref(), Some("font/woff2"));
+        assert_eq!(
+            checks[1].final_url.as_deref(),
+            Some("https://cdn.example/v2/moved.woff2")
+        );
+        assert_eq!(checks[2].method, FetchMethod::Get);
+        assert_eq!(checks[2].size, Some(5000));
+        assert!(checks[2].is_available());
+        assert_eq!(checks[3].status, Some(404));
+        assert_eq!(checks[3].size, None);
+        assert!(!checks[3].is_available());
+        assert_eq!(checks[4].status, None);
+        assert_eq!(checks[4].error.as_deref(), Some("operation timed out"));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::extractor::DEFAULT_USER_AGENT;
+use crate::model::FontInfo;
+
+/// Safari on an iPhone, which providers that sniff the user agent treat as
+/// a mobile browser.
+pub const MOBILE_USER_AGENT: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+
+/// A browser a scan can present itself as.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum UserAgentProfile {
+    Desktop,
+    Mobile,
+}
+
+impl UserAgentProfile {
+    pub fn user_agent(self) -> &'static str {
+        match self {
+            Self::Desktop => DEFAULT_USER_AGENT,
+            Self::Mobile => MOBILE_USER_AGENT,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Desktop => "desktop",
+            Self::Mobile => "mobile",
+        }
+    }
+}
+
+/// A font file and which of the compared scans delivered it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeliveredFont {
+    pub family: String,
+    pub weight: String,
+    pub style: String,
+    pub format: String,
+    pub url: String,
+    /// One entry per scan, in the order the scans were given.
+    pub delivered: Vec<bool>,
+}
+
+impl DeliveredFont {
+    /// Whether some scan did not get this file.
+    pub fn differs(&self) -> bool {
+        !self.delivered.iter().all(|delivered| *delivered)
+    }
+}
+
+/// Lines up the fonts of several scans of the same pages by URL, so files
+/// served to only some user agents stand out. Fonts keep the order of the
+/// first scan that found them.
+pub fn compare_scans(scans: &[Vec<FontInfo>]) -> Vec<DeliveredFont> {
+    let mut compared = Vec::<DeliveredFont>::new();
+    for (scan_index, fonts) in scans.iter().enumerate() {
+        for font in fonts {
+            let position = match compared.iter().position(|known| known.url == font.url) {
+                Some(position) => position,
+                None => {
+                    compared.push(DeliveredFont {
+                        family: font.family.clone(),
+                        weight: font.weight.clone(),
+                        style: font.style.clone(),
+                        format: font.format.clone(),
+                        url: font.url.clone(),
+                        delivered: vec![false; scans.len()],
+                    });
+                    compared.len() - 1
+                }
+            };
+            compared[position].delivered[scan_index] = true;
+        }
+    }
+    compared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_scans;
+    use crate::model::FontInfo;
+
+    fn font(url: &str, format: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Inter".to_owned(),
+            format: format.to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://fonts.googleapis.com/css2?family=Inter".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn lines_up_fonts_delivered_to_each_user_agent() {
+        let shared = font("https://fonts.gstatic.com/s/inter/icons.woff2", "WOFF2");
+        let mobile = vec![
+            shared.clone(),
+            font("https://fonts.gstatic.com/s/inter/v1/a.ttf", "TTF"),
+        ];
+        let desktop = vec![
+            font("https://fonts.gstatic.com/s/inter/v1/a.woff2", "WOFF2"),
+            shared,
+        ];
+
+        let compared = compare_scans(&[mobile, desktop]);
+
+        let rows = compared
+            .iter()
+            .map(|font| (font.format.as_str(), font.delivered.clone(), font.differs()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                ("WOFF2", vec![true, true], false),
+                ("TTF", vec![true, false], true),
+                ("WOFF2", vec![false, true], true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::binary::FontContainer;
+use crate::download::{SavedFile, rewrite_font_file, sha256_hex};
+use crate::error::{CompressError, SfntError};
+use crate::sfnt::SfntFont;
+use crate::sidecar::{copy_sidecar, sidecar_path};
+
+/// A saved TrueType or OpenType font converted to WOFF2.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedFile {
+    pub url: String,
+    /// The downloaded file, removed unless the original was kept.
+    pub original: PathBuf,
+    pub path: PathBuf,
+    pub original_size: u64,
+    pub compressed_size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct CompressReport {
+    pub compressed: Vec<CompressedFile>,
+    /// Fonts left as downloaded, with the reason.
+    pub skipped: Vec<(PathBuf, CompressError)>,
+}
+
+impl CompressReport {
+    pub fn original_size(&self) -> u64 {
+        self.compressed.iter().map(|file| file.original_size).sum()
+    }
+
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed
+            .iter()
+            .map(|file| file.compressed_size)
+            .sum()
+    }
+}
+
+/// Converts a TrueType or OpenType file to WOFF2. Any other input is an
+/// error, including WOFF2 itself.
+pub fn compress_to_woff2(bytes: &[u8]) -> Result<Vec<u8>, CompressError> {
+    let decode = |source| CompressError::Decode { source };
+    match FontContainer::sniff(bytes) {
+        Some(FontContainer::TrueType | FontContainer::OpenType) => {
+            Ok(SfntFont::parse(bytes).map_err(decode)?.to_woff2())
+        }
+        other => Err(decode(SfntError::UnsupportedContainer {
+            format: other.map_or("unknown", FontContainer::format),
+        })),
+    }
+}
+
+/// Writes a `.woff2` next to each saved TrueType or OpenType font and points
+/// its [`SavedFile`] at it. The original and its sidecar are deleted unless
+/// `keep_original` is set; other formats are left alone.
+pub fn compress_saved_files(files: &mut [SavedFile], keep_original: bool) -> CompressReport {
+    let mut report = CompressReport::default();
+    for file in files {
+        match compress_saved_file(file, keep_original) {
+            Ok(Some(compressed)) => report.compressed.push(compressed),
+            Ok(None) => {}
+            Err(error) => report.skipped.push((file.path.clone(), error)),
+        }
+    }
+    report
+}
+
+fn compress_saved_file(
+    file: &mut SavedFile,
+    keep_original: bool,
+) -> Result<Option<CompressedFile>, CompressError> {
+    let bytes = fs::read(&file.path).map_err(|source| CompressError::Read {
+        path: file.path.clone(),
+        source,
+    })?;
+    if !matches!(
+        FontContainer::sniff(&bytes),
+        Some(FontContainer::TrueType | FontContainer::OpenType)
+    ) {
+        return Ok(None);
+    }
+    let woff2 = compress_to_woff2(&bytes)?;
+
+    let original = file.path.clone();
+    let compressed = SavedFile {
+        path: original.with_extension(FontContainer::Woff2.extension()),
+        url: file.url.clone(),
+        size: woff2.len() as u64,
+        sha256: sha256_hex(&woff2),
+    };
+    rewrite_font_file(&original, &compressed.path, &woff2).map_err(|source| {
+        CompressError::Write {
+            path: compressed.path.clone(),
+            source,
+        }
+    })?;
+    copy_sidecar(&original, &compressed).map_err(|source| CompressError::Write {
+        path: sidecar_path(&compressed.path),
+        source,
+    })?;
+
+    // A TrueType file saved as `.woff2` is simply replaced.
+    if !keep_original && compressed.path != original {
+        let remove = |path: &PathBuf| {
+            fs::remove_file(path).map_err(|source| CompressError::Remove {
+                path: path.clone(),
+                source,
+            })
+        };
+        remove(&original)?;
+        let sidecar = sidecar_path(&original);
+        if sidecar.exists() {
+            remove(&sidecar)?;
+        }
+    }
+
+    *file = compressed;
+    Ok(Some(CompressedFile {
+        url: file.url.clone(),
+        original,
+        path: file.path.clone(),
+        original_size: bytes.len() as u64,
+        compressed_size: file.size,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::compress_saved_files;
+    use crate::binary::FontContainer;
+    use crate::download::{SavedFile, sha256_hex};
+    use crate::sfnt::SfntFont;
+    use crate::sidecar::{FontSidecar, SidecarDescriptors, SidecarHeaders, sidecar_path};
+
+    fn saved(dir: &std::path::Path, name: &str, bytes: &[u8]) -> SavedFile {
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        SavedFile {
+            path,
+            url: format!("https://cdn.example/{name}"),
+            size: bytes.len() as u64,
+            sha256: sha256_hex(bytes),
+        }
+    }
+
+    fn sidecar(file: &SavedFile) -> FontSidecar {
+        FontSidecar {
+            url: Some(file.url.clone()),
+            referer: None,
+            page: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            descriptors: SidecarDescriptors {
+                font_family: "Brand".to_owned(),
+                font_weight: "400".to_owned(),
+                font_style: "normal".to_owned(),
+                format: "TRUETYPE".to_owned(),
+                conditions: Vec::new(),
+            },
+            license_notices: Vec::new(),
+            license_urls: Vec::new(),
+            headers: SidecarHeaders::default(),
+            size: file.size,
+            sha256: file.sha256.clone(),
+            downloaded_at: "2025-01-01T00:00:00Z".to_owned(),
+        }
+    }
+
+    #[test]
+    fn replaces_truetype_downloads_with_woff2() {
+        let dir =
+            std::env::temp_dir().join(format!("typopotamus-compress-tests-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let font = SfntFont {
+            flavor: 0x0001_0000,
+            tables: BTreeMap::from([
+                (*b"head", vec![0; 54]),
+                (*b"name", b"Brand Brand Brand Brand Brand Brand".repeat(8)),
+            ]),
+        };
+        let ttf = font.to_sfnt();
+        let woff2 = font.to_woff2();
+        let mut files = [
+            saved(&dir, "brand.ttf", &ttf),
+            saved(&dir, "kept.otf", &ttf),
+            saved(&dir, "already.woff2", &woff2),
+        ];
+        let json = serde_json::to_string(&sidecar(&files[0])).unwrap();
+        fs::write(sidecar_path(&files[0].path), json).unwrap();
+        let last_modified = UNIX_EPOCH + Duration::from_secs(1_735_689_600);
+        fs::File::options()
+            .write(true)
+            .open(&files[0].path)
+            .and_then(|file| file.set_modified(last_modified))
+            .unwrap();
+
+        let report = compress_saved_files(&mut files[..1], false);
+        let kept = compress_saved_files(&mut files[1..], true);
+
+        assert!(report.skipped.is_empty() && kept.skipped.is_empty());
+        assert_eq!(report.compressed.len(), 1);
+        assert_eq!(kept.compressed.len(), 1, "WOFF2 files are left alone");
+        assert!(report.compressed_size() < report.original_size());
+
+        let brand = dir.join("brand.woff2");
+        assert_eq!(files[0].path, brand);
+        let bytes = fs::read(&brand).unwrap();
+        assert_eq!(FontContainer::sniff(&bytes), Some(FontContainer::Woff2));
+        assert_eq!(
+            fs::metadata(&brand).unwrap().modified().unwrap(),
+            last_modified
+        );
+        assert_eq!(
+            SfntFont::parse(&bytes).unwrap(),
+            SfntFont::parse(&ttf).unwrap()
+        );
+        assert!(!dir.join("brand.ttf").exists());
+        assert!(!sidecar_path(&dir.join("brand.ttf")).exists());
+        let moved = fs::read_to_string(sidecar_path(&brand)).unwrap();
+        let moved = serde_json::from_str::<FontSidecar>(&moved).unwrap();
+        assert_eq!(moved.sha256, files[0].sha256);
+        assert_eq!(moved.size, bytes.len() as u64);
+
+        assert_eq!(files[1].path, dir.join("kept.woff2"));
+        assert!(dir.join("kept.otf").exists());
+        assert_eq!(files[2].path, dir.join("already.woff2"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
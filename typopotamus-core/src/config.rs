@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::ConfigError;
+use crate::inspect::InferenceOptions;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub inference: InferenceOptions,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        let mut config: Self = toml::from_str(contents)?;
+        config.inference = config.inference.normalized();
+        Ok(config)
+    }
+
+    /// Adds the family aliases in `path`, a TOML table of patterns and the
+    /// display family each forces, such as `"brandfont-v7-*" = "Acme Sans"`.
+    /// They replace config file aliases with the same pattern.
+    pub fn with_family_aliases(mut self, path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let aliases: BTreeMap<String, String> =
+            toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        self.inference.family_aliases.extend(aliases);
+        self.inference = self.inference.normalized();
+        Ok(self)
+    }
+
+    /// Loads `explicit_path` when given, else `$TYPOPOTAMUS_CONFIG`; either
+    /// must exist. Otherwise loads the default config file if it exists, or
+    /// falls back to the built-in defaults.
+    pub fn load_or_default(explicit_path: Option<&Path>) -> Result<Self, ConfigError> {
+        if let Some(path) = explicit_path
+            .map(Path::to_path_buf)
+            .or_else(env_config_path)
+        {
+            return Self::load(&path);
+        }
+
+        match default_config_path() {
+            Some(path) if path.is_file() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+/// `$TYPOPOTAMUS_CONFIG`, when set.
+fn env_config_path() -> Option<PathBuf> {
+    env::var_os("TYPOPOTAMUS_CONFIG")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$XDG_CONFIG_HOME/typopotamus/config.toml`, else
+/// `~/.config/typopotamus/config.toml`.
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join("typopotamus").join(CONFIG_FILE_NAME))
+}
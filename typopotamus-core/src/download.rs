@@ -1,149 +1,1191 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-use anyhow::{Context, Result};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD;
 use percent_encoding::percent_decode_str;
-use reqwest::blocking::Client;
-use reqwest::header::{ACCEPT, CONTENT_TYPE, ORIGIN, REFERER, USER_AGENT};
+use sha2::{Digest, Sha256};
 use url::Url;
 
+use crate::binary::{FontContainer, FontMetadata, describe_payload, parse_font_metadata};
+use crate::cancel::CancellationToken;
+use crate::error::{DownloadError, DownloadFailure, RefererPolicyError, S3UrlError};
+#[cfg(feature = "http")]
+use crate::fetch::ReqwestFetcher;
+use crate::fetch::header::{
+    ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, ETAG, LAST_MODIFIED, ORIGIN, REFERER, USER_AGENT,
+};
+use crate::fetch::{
+    BodyProgress, FetchError, FetchRequest, FetchResponse, Fetcher, HttpOptions, RateLimit,
+};
+use crate::inspect::{Inference, infer_family_fingerprint};
+use crate::layout::{DownloadLayout, PathTemplate, TemplateVars};
 use crate::model::FontInfo;
+use crate::sidecar::{FontSidecar, SidecarHeaders, parse_http_date, read_sidecar, sidecar_path};
+
+/// Per-request timeout of the built-in HTTP client.
+pub const DEFAULT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(45);
 
 const HTTP_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36";
 
+/// What to do when a font's target file already exists on disk.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictPolicy {
+    /// Save next to the existing file with a `-1`, `-2`, ... suffix.
+    #[default]
+    Rename,
+    /// Keep the existing file and record the font as skipped.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+    /// Fail the font with [`DownloadError::Exists`].
+    Error,
+}
+
+/// The `Referer` header font requests carry. Some CDNs only serve fonts to
+/// the sites they are licensed for; others reject any cross-site referer.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum RefererPolicy {
+    /// The page the font was found on.
+    #[default]
+    Page,
+    /// No `Referer` header.
+    None,
+    /// A fixed URL, parsed from `custom:<url>`.
+    Custom(String),
+}
+
+impl RefererPolicy {
+    fn referer<'a>(&'a self, font: &'a FontInfo) -> Option<&'a str> {
+        match self {
+            Self::Page => Some(font.referer.as_str()),
+            Self::None => None,
+            Self::Custom(url) => Some(url.as_str()),
+        }
+        .filter(|referer| !referer.is_empty())
+    }
+}
+
+impl FromStr for RefererPolicy {
+    type Err = RefererPolicyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim() {
+            "page" => Ok(Self::Page),
+            "none" => Ok(Self::None),
+            other => other
+                .strip_prefix("custom:")
+                .and_then(|url| Url::parse(url.trim()).ok())
+                .filter(|url| matches!(url.scheme(), "http" | "https"))
+                .map(|url| Self::Custom(url.to_string()))
+                .ok_or_else(|| RefererPolicyError {
+                    input: input.to_owned(),
+                }),
+        }
+    }
+}
+
+/// A font written to disk by a download run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedFile {
+    pub path: PathBuf,
+    pub url: String,
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+/// A font left alone because its target file already existed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkippedFile {
+    /// The existing file.
+    pub path: PathBuf,
+    pub url: String,
+}
+
+/// Where a download stands, passed to its progress callback: once as each
+/// font starts, then as its response body arrives.
+#[derive(Clone, Copy, Debug)]
+pub struct DownloadProgress<'a> {
+    /// 1-based position of `font` among the fonts being downloaded.
+    pub current: usize,
+    pub total: usize,
+    pub font: &'a FontInfo,
+    /// Body bytes of `font` received so far; `0` when it starts.
+    pub received: u64,
+    /// The response's `Content-Length`, or the size the scan recorded until
+    /// the response arrives.
+    pub content_length: Option<u64>,
+}
+
+impl DownloadProgress<'_> {
+    /// The first call for `font`, before any of it has arrived.
+    pub fn is_start(&self) -> bool {
+        self.received == 0
+    }
+
+    /// How much of `font` has arrived, from 0.0 to 1.0, when its size is
+    /// known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.content_length
+            .filter(|length| *length > 0)
+            .map(|length| (self.received as f64 / length as f64).min(1.0))
+    }
+}
+
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DownloadReport {
     pub attempted: usize,
-    pub saved_files: Vec<PathBuf>,
-    pub failures: Vec<String>,
+    pub saved_files: Vec<SavedFile>,
+    pub skipped_files: Vec<SkippedFile>,
+    pub failures: Vec<DownloadFailure>,
+    /// Fonts that were saved despite a problem, such as a modification time
+    /// the sink could not set.
+    pub warnings: Vec<DownloadFailure>,
+    pub cancelled: bool,
 }
 
 impl DownloadReport {
     pub fn success_count(&self) -> usize {
         self.saved_files.len()
     }
+
+    /// URL and path of every font now on disk, whether saved by this run or
+    /// kept from an earlier one.
+    pub fn local_files(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.saved_files
+            .iter()
+            .map(|saved| (saved.url.as_str(), saved.path.as_path()))
+            .chain(
+                self.skipped_files
+                    .iter()
+                    .map(|skipped| (skipped.url.as_str(), skipped.path.as_path())),
+            )
+    }
+
+    /// URLs of the failed fonts, which can be downloaded again.
+    pub fn retryable_urls(&self) -> Vec<&str> {
+        self.failures
+            .iter()
+            .map(|failure| failure.url.as_str())
+            .filter(|url| !url.is_empty())
+            .collect()
+    }
+
+    /// Folds in the report of retrying [`retryable_urls`](Self::retryable_urls):
+    /// its files are appended, and its failures replace the retried ones.
+    pub fn merge_retry(&mut self, retry: DownloadReport) {
+        self.saved_files.extend(retry.saved_files);
+        self.skipped_files.extend(retry.skipped_files);
+        self.failures.retain(|failure| failure.url.is_empty());
+        self.failures.extend(retry.failures);
+        self.warnings.extend(retry.warnings);
+        self.cancelled = retry.cancelled;
+    }
+}
+
+/// Where a download run stores fonts and sidecars. Paths passed in are
+/// relative to the output root, as laid out by [`DownloadLayout`].
+pub trait OutputSink: Send + Sync {
+    /// Called once before the first file is written.
+    fn prepare(&self) -> Result<(), DownloadError> {
+        Ok(())
+    }
+
+    /// Where `relative` is stored, as recorded in [`SavedFile::path`].
+    fn location(&self, relative: &Path) -> PathBuf;
+
+    /// Whether a file is already stored at `relative`. Sinks that cannot
+    /// tell answer `false`, so every [`ConflictPolicy`] overwrites.
+    fn exists(&self, relative: &Path) -> bool;
+
+    fn write(&self, relative: &Path, bytes: &[u8]) -> Result<(), DownloadError>;
+
+    /// Stamps a written file with the time the server last changed it.
+    /// Sinks that keep no file times ignore it.
+    fn set_modified(&self, _relative: &Path, _time: SystemTime) -> Result<(), DownloadError> {
+        Ok(())
+    }
+}
+
+/// Keeps nothing, for when only the hashes and sizes in [`SavedFile`]
+/// matter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DiscardSink;
+
+impl OutputSink for DiscardSink {
+    fn location(&self, relative: &Path) -> PathBuf {
+        relative.to_path_buf()
+    }
+
+    fn exists(&self, _relative: &Path) -> bool {
+        false
+    }
+
+    fn write(&self, _relative: &Path, _bytes: &[u8]) -> Result<(), DownloadError> {
+        Ok(())
+    }
+}
+
+/// Writes files below a local directory, creating it as needed. Each file
+/// is written to a `.part` file next to it and renamed into place, so an
+/// interrupted run never leaves a truncated font behind.
+#[derive(Clone, Debug)]
+pub struct DirectorySink {
+    root: PathBuf,
+}
+
+impl DirectorySink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn prepare(&self) -> Result<(), DownloadError> {
+        fs::create_dir_all(&self.root).map_err(|source| DownloadError::CreateDirectory {
+            path: self.root.clone(),
+            source,
+        })
+    }
+
+    fn location(&self, relative: &Path) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    fn exists(&self, relative: &Path) -> bool {
+        self.location(relative).exists()
+    }
+
+    fn write(&self, relative: &Path, bytes: &[u8]) -> Result<(), DownloadError> {
+        let path = self.location(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| DownloadError::CreateDirectory {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        write_font_file(&path, bytes).map_err(|source| DownloadError::Write { path, source })
+    }
+
+    fn set_modified(&self, relative: &Path, time: SystemTime) -> Result<(), DownloadError> {
+        let path = self.location(relative);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(time))
+            .map_err(|source| DownloadError::SetModified { path, source })
+    }
+}
+
+/// Writes `bytes` to a partial file next to `path` and renames it into
+/// place, so an interrupted write never leaves a truncated font behind.
+pub(crate) fn write_font_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let partial = partial_path(path);
+    fs::write(&partial, bytes)
+        .and_then(|()| fs::rename(&partial, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&partial);
+        })
+}
+
+/// [`write_font_file`] for a font rewritten from `source`, e.g. by subsetting,
+/// keeping the modification time `source` had, such as the server's
+/// `Last-Modified`.
+pub(crate) fn rewrite_font_file(source: &Path, path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let modified = fs::metadata(source).and_then(|metadata| metadata.modified());
+    write_font_file(path, bytes)?;
+    if let Ok(modified) = modified {
+        // The new bytes are in place either way.
+        let _ = fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified));
+    }
+    Ok(())
+}
+
+/// Where [`write_font_file`] writes `path` before renaming it into place.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+/// Pipes each file into a shell command, for object storage and other
+/// destinations without a local directory. The command runs once per file
+/// with the bytes on stdin and these variables set:
+///
+/// - `TYPOPOTAMUS_PATH`: the path relative to the output root, with `/`
+///   separators.
+/// - `TYPOPOTAMUS_DESTINATION`: the same path below the sink's base, such as
+///   `s3://bucket/prefix/inter/inter-400.woff2`.
+/// - `TYPOPOTAMUS_CONTENT_TYPE`: the MIME type for the file's extension.
+#[derive(Clone, Debug)]
+pub struct CommandSink {
+    command: String,
+    base: Option<String>,
+}
+
+impl CommandSink {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            base: None,
+        }
+    }
+
+    /// Uploads to `s3://bucket/prefix` with the AWS CLI, which must be
+    /// installed and configured.
+    pub fn s3(url: &str) -> Result<Self, S3UrlError> {
+        let bucket_and_prefix = url
+            .strip_prefix("s3://")
+            .map(|rest| rest.trim_end_matches('/'))
+            .filter(|rest| !rest.is_empty() && !rest.starts_with('/'))
+            .ok_or_else(|| S3UrlError {
+                input: url.to_owned(),
+            })?;
+        Ok(Self {
+            command: "aws s3 cp --only-show-errors --content-type \"$TYPOPOTAMUS_CONTENT_TYPE\" - \"$TYPOPOTAMUS_DESTINATION\"".to_owned(),
+            base: Some(format!("s3://{bucket_and_prefix}")),
+        })
+    }
+
+    fn shell(&self) -> Command {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c");
+            command
+        };
+        command.arg(&self.command);
+        command
+    }
+}
+
+impl OutputSink for CommandSink {
+    fn location(&self, relative: &Path) -> PathBuf {
+        let relative = slash_path(relative);
+        match &self.base {
+            Some(base) => PathBuf::from(format!("{base}/{relative}")),
+            None => PathBuf::from(relative),
+        }
+    }
+
+    fn exists(&self, _relative: &Path) -> bool {
+        false
+    }
+
+    fn write(&self, relative: &Path, bytes: &[u8]) -> Result<(), DownloadError> {
+        let path = self.location(relative);
+        let write_error = |source| DownloadError::Write {
+            path: path.clone(),
+            source,
+        };
+
+        let mut child = self
+            .shell()
+            .env("TYPOPOTAMUS_PATH", slash_path(relative))
+            .env("TYPOPOTAMUS_DESTINATION", &path)
+            .env("TYPOPOTAMUS_CONTENT_TYPE", content_type_for_path(relative))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(write_error)?;
+        let written = child
+            .stdin
+            .take()
+            .map_or(Ok(()), |mut stdin| stdin.write_all(bytes));
+        let output = child.wait_with_output().map_err(write_error)?;
+
+        if !output.status.success() {
+            return Err(DownloadError::Command {
+                path,
+                status: output.status.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            });
+        }
+        // A command that exits successfully without reading all of stdin
+        // closes the pipe early; only report that when it also failed.
+        match written {
+            Err(error) if error.kind() != io::ErrorKind::BrokenPipe => Err(write_error(error)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn content_type_for_path(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("woff2") => "font/woff2",
+        Some("woff") => "font/woff",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("eot") => "application/vnd.ms-fontobject",
+        Some("svg") => "image/svg+xml",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A reusable, configured downloader. New behaviour is added as builder
+/// methods, so callers only name the settings they change:
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use std::time::Duration;
+/// # use typopotamus_core::cancel::CancellationToken;
+/// # use typopotamus_core::download::{ConflictPolicy, Downloader};
+/// # let fonts = Vec::new();
+/// let downloader = Downloader::builder()
+///     .on_conflict(ConflictPolicy::Skip)
+///     .sidecar(true)
+///     .timeout(Duration::from_secs(20))
+///     .build()?;
+/// let report = downloader.download(&fonts, Path::new("fonts"), &CancellationToken::new(), |_| {});
+/// # Ok::<(), typopotamus_core::error::DownloadError>(())
+/// ```
+#[derive(Clone)]
+pub struct Downloader {
+    fetcher: Arc<dyn Fetcher>,
+    layout: DownloadLayout,
+}
+
+impl Downloader {
+    pub fn builder() -> DownloaderBuilder {
+        DownloaderBuilder::default()
+    }
+
+    pub fn layout(&self) -> &DownloadLayout {
+        &self.layout
+    }
+
+    /// Saves `fonts` below `output_root`, as [`download_fonts_with_fetcher`]
+    /// does.
+    pub fn download<F>(
+        &self,
+        fonts: &[FontInfo],
+        output_root: &Path,
+        cancel: &CancellationToken,
+        on_progress: F,
+    ) -> DownloadReport
+    where
+        F: FnMut(DownloadProgress<'_>),
+    {
+        self.download_to_sink(fonts, &DirectorySink::new(output_root), cancel, on_progress)
+    }
+
+    /// Stores `fonts` through `sink`, as [`download_fonts_to_sink`] does.
+    pub fn download_to_sink<F>(
+        &self,
+        fonts: &[FontInfo],
+        sink: &dyn OutputSink,
+        cancel: &CancellationToken,
+        on_progress: F,
+    ) -> DownloadReport
+    where
+        F: FnMut(DownloadProgress<'_>),
+    {
+        download_fonts_to_sink(
+            fonts,
+            sink,
+            &self.layout,
+            self.fetcher.as_ref(),
+            cancel,
+            on_progress,
+        )
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DownloaderBuilder {
+    layout: DownloadLayout,
+    timeout: Option<Duration>,
+    rate_limit: Option<RateLimit>,
+    fetcher: Option<Arc<dyn Fetcher>>,
+}
+
+impl DownloaderBuilder {
+    /// Replaces every layout setting at once, e.g. with values parsed from a
+    /// CLI.
+    pub fn layout(mut self, layout: DownloadLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn directory(mut self, template: PathTemplate) -> Self {
+        self.layout.directory = template;
+        self
+    }
+
+    pub fn file_name(mut self, template: PathTemplate) -> Self {
+        self.layout.file_name = Some(template);
+        self
+    }
+
+    pub fn on_conflict(mut self, policy: ConflictPolicy) -> Self {
+        self.layout.on_conflict = policy;
+        self
+    }
+
+    pub fn inference(mut self, inference: Inference) -> Self {
+        self.layout.inference = inference;
+        self
+    }
+
+    pub fn referer(mut self, referer: RefererPolicy) -> Self {
+        self.layout.referer = referer;
+        self
+    }
+
+    pub fn omit_origin(mut self, omit: bool) -> Self {
+        self.layout.omit_origin = omit;
+        self
+    }
+
+    pub fn sidecar(mut self, sidecar: bool) -> Self {
+        self.layout.sidecar = sidecar;
+        self
+    }
+
+    pub fn rename_from_metadata(mut self, rename: bool) -> Self {
+        self.layout.rename_from_metadata = rename;
+        self
+    }
+
+    pub fn omit_server_mtime(mut self, omit: bool) -> Self {
+        self.layout.omit_server_mtime = omit;
+        self
+    }
+
+    pub fn http(mut self, http: HttpOptions) -> Self {
+        self.layout.http = http;
+        self
+    }
+
+    /// Per-request timeout of the built-in HTTP client. Ignored when a
+    /// custom [`fetcher`](Self::fetcher) is supplied.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Caps the built-in HTTP client's throughput. Ignored when a custom
+    /// [`fetcher`](Self::fetcher) is supplied.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Sends requests through `fetcher` instead of the built-in HTTP client,
+    /// e.g. a shared [`Session`](crate::session::Session).
+    pub fn fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+        self.fetcher = Some(Arc::new(fetcher));
+        self
+    }
+
+    pub fn build(self) -> Result<Downloader, DownloadError> {
+        let fetcher = match self.fetcher {
+            Some(fetcher) => fetcher,
+            None => default_fetcher(
+                self.timeout.unwrap_or(DEFAULT_DOWNLOAD_TIMEOUT),
+                self.rate_limit,
+                &self.layout.http,
+            )?,
+        };
+        Ok(Downloader {
+            fetcher,
+            layout: self.layout,
+        })
+    }
+}
+
+#[cfg(feature = "http")]
+fn default_fetcher(
+    timeout: Duration,
+    rate_limit: Option<RateLimit>,
+    http: &HttpOptions,
+) -> Result<Arc<dyn Fetcher>, DownloadError> {
+    let fetcher =
+        ReqwestFetcher::with_options(timeout, http).map_err(|source| DownloadError::Client {
+            source: source.into(),
+        })?;
+    Ok(match rate_limit {
+        Some(limit) => Arc::new(fetcher.with_rate_limit(limit)),
+        None => Arc::new(fetcher),
+    })
 }
 
-pub fn download_fonts<F>(
+#[cfg(not(feature = "http"))]
+fn default_fetcher(
+    _timeout: Duration,
+    _rate_limit: Option<RateLimit>,
+    _http: &HttpOptions,
+) -> Result<Arc<dyn Fetcher>, DownloadError> {
+    Err(DownloadError::Client {
+        source: "no HTTP client is built in without the `http` feature; supply a fetcher".into(),
+    })
+}
+
+#[cfg(feature = "http")]
+pub fn download_fonts<F>(fonts: &[FontInfo], output_root: &Path, on_progress: F) -> DownloadReport
+where
+    F: FnMut(DownloadProgress<'_>),
+{
+    download_fonts_with_cancellation(
+        fonts,
+        output_root,
+        &DownloadLayout::default(),
+        &CancellationToken::new(),
+        on_progress,
+    )
+}
+
+#[cfg(feature = "http")]
+pub fn download_fonts_with_cancellation<F>(
     fonts: &[FontInfo],
     output_root: &Path,
+    layout: &DownloadLayout,
+    cancel: &CancellationToken,
+    on_progress: F,
+) -> DownloadReport
+where
+    F: FnMut(DownloadProgress<'_>),
+{
+    match build_http_fetcher(&layout.http) {
+        Ok(fetcher) => {
+            download_fonts_with_fetcher(fonts, output_root, layout, &fetcher, cancel, on_progress)
+        }
+        Err(error) => DownloadReport {
+            attempted: fonts.len(),
+            failures: vec![DownloadFailure {
+                name: String::new(),
+                url: String::new(),
+                error,
+            }],
+            ..DownloadReport::default()
+        },
+    }
+}
+
+pub fn download_fonts_with_fetcher<F>(
+    fonts: &[FontInfo],
+    output_root: &Path,
+    layout: &DownloadLayout,
+    fetcher: &dyn Fetcher,
+    cancel: &CancellationToken,
+    on_progress: F,
+) -> DownloadReport
+where
+    F: FnMut(DownloadProgress<'_>),
+{
+    download_fonts_to_sink(
+        fonts,
+        &DirectorySink::new(output_root),
+        layout,
+        fetcher,
+        cancel,
+        on_progress,
+    )
+}
+
+/// Like [`download_fonts_with_fetcher`], storing files through `sink`
+/// instead of below a local directory.
+pub fn download_fonts_to_sink<F>(
+    fonts: &[FontInfo],
+    sink: &dyn OutputSink,
+    layout: &DownloadLayout,
+    fetcher: &dyn Fetcher,
+    cancel: &CancellationToken,
     mut on_progress: F,
 ) -> DownloadReport
 where
-    F: FnMut(usize, usize, &FontInfo),
+    F: FnMut(DownloadProgress<'_>),
 {
     let mut report = DownloadReport {
         attempted: fonts.len(),
         ..DownloadReport::default()
     };
 
-    if let Err(error) = fs::create_dir_all(output_root) {
-        report.failures.push(format!(
-            "could not create output directory {}: {error}",
-            output_root.display()
-        ));
+    if let Err(error) = sink.prepare() {
+        report.failures.push(DownloadFailure {
+            name: String::new(),
+            url: String::new(),
+            error,
+        });
         return report;
     }
 
-    let client = match build_http_client() {
-        Ok(client) => client,
-        Err(error) => {
-            report
-                .failures
-                .push(format!("could not create HTTP client: {error}"));
-            return report;
-        }
-    };
-
     let mut used_paths = HashSet::new();
 
     for (index, font) in fonts.iter().enumerate() {
-        on_progress(index + 1, fonts.len(), font);
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+
+        let progress = |received, content_length| DownloadProgress {
+            current: index + 1,
+            total: fonts.len(),
+            font,
+            received,
+            content_length,
+        };
+        on_progress(progress(0, font.content_length));
 
-        match download_single_font(&client, font, output_root, &mut used_paths) {
-            Ok(saved_path) => report.saved_files.push(saved_path),
-            Err(error) => report
-                .failures
-                .push(format!("{} ({}) -> {error}", font.name, font.url)),
+        let mut on_body = |received, content_length: Option<u64>| {
+            on_progress(progress(received, content_length.or(font.content_length)));
+        };
+        match download_single_font(fetcher, font, sink, layout, &mut used_paths, &mut on_body) {
+            Ok(FontOutcome::Saved(saved, warning)) => {
+                report.saved_files.push(saved);
+                report.warnings.extend(warning.map(|error| DownloadFailure {
+                    name: font.name.clone(),
+                    url: font.url.clone(),
+                    error,
+                }));
+            }
+            Ok(FontOutcome::Skipped(path)) => report.skipped_files.push(SkippedFile {
+                path,
+                url: font.url.clone(),
+            }),
+            Err(error) => report.failures.push(DownloadFailure {
+                name: font.name.clone(),
+                url: font.url.clone(),
+                error,
+            }),
         }
     }
 
     report
 }
 
-fn build_http_client() -> Result<Client> {
-    Client::builder()
-        .timeout(Duration::from_secs(45))
-        .connect_timeout(Duration::from_secs(10))
-        .build()
-        .context("failed to create HTTP client")
+#[cfg(feature = "http")]
+fn build_http_fetcher(http: &HttpOptions) -> Result<ReqwestFetcher, DownloadError> {
+    ReqwestFetcher::with_options(DEFAULT_DOWNLOAD_TIMEOUT, http).map_err(|source| {
+        DownloadError::Client {
+            source: source.into(),
+        }
+    })
 }
 
 fn download_single_font(
-    client: &Client,
+    fetcher: &dyn Fetcher,
     font: &FontInfo,
-    output_root: &Path,
+    sink: &dyn OutputSink,
+    layout: &DownloadLayout,
     used_paths: &mut HashSet<PathBuf>,
-) -> Result<PathBuf> {
-    let (bytes, mime_type) = if font.url.starts_with("data:") {
-        decode_data_url(&font.url)?
+    on_body: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FontOutcome, DownloadError> {
+    // A taken target is skipped or refused without downloading the font,
+    // unless its name comes from the font's own metadata. The declared format
+    // predicts the extension; a file that sniffs as another container is
+    // settled once it arrives.
+    if matches!(
+        layout.on_conflict,
+        ConflictPolicy::Skip | ConflictPolicy::Error
+    ) && !layout.rename_from_metadata
+    {
+        let extension = extension_for_font(font, None);
+        let (relative_dir, stem) = target_for_font(font, layout, extension);
+        let candidate = target_file(&relative_dir, &stem, extension);
+        if !used_paths.contains(&candidate)
+            && let Some(existing) = existing_target(sink, &candidate, layout.on_conflict)?
+        {
+            return Ok(FontOutcome::Skipped(sink.location(&existing)));
+        }
+    }
+
+    let fetched = if font.url.starts_with("data:") {
+        let (bytes, mime_type) = decode_data_url(&font.url)?;
+        FetchedFont {
+            bytes,
+            referer: None,
+            headers: SidecarHeaders {
+                content_type: mime_type,
+                ..SidecarHeaders::default()
+            },
+        }
     } else {
-        fetch_remote_font(client, font)?
+        fetch_remote_font(fetcher, font, layout, on_body)?
     };
+    let bytes = &fetched.bytes;
 
-    let extension = extension_for_font(font, mime_type.as_deref());
-    let family_dir = output_root.join(sanitize_component(&font.family));
-    fs::create_dir_all(&family_dir)
-        .with_context(|| format!("failed to create family directory {}", family_dir.display()))?;
+    if FontContainer::sniff(bytes).is_none() {
+        return Err(DownloadError::NotAFont {
+            detected: describe_payload(bytes),
+        });
+    }
 
-    let stem = file_stem_for_font(font);
-    let file_path = unique_output_path(&family_dir, &stem, extension, used_paths);
+    let extension = FontContainer::sniff(bytes).map_or_else(
+        || extension_for_font(font, fetched.headers.content_type.as_deref()),
+        FontContainer::extension,
+    );
+    let (relative_dir, mut stem) = target_for_font(font, layout, extension);
+    if layout.rename_from_metadata
+        && let Some(named) =
+            parse_font_metadata(bytes).and_then(|metadata| metadata_file_stem(&metadata))
+    {
+        stem = named;
+    }
+    let relative_path = match resolve_output_path(
+        sink,
+        &relative_dir,
+        &stem,
+        extension,
+        layout.on_conflict,
+        used_paths,
+    )? {
+        OutputTarget::Write(relative_path) => relative_path,
+        OutputTarget::Skip(existing_path) => {
+            return Ok(FontOutcome::Skipped(sink.location(&existing_path)));
+        }
+    };
 
-    fs::write(&file_path, bytes)
-        .with_context(|| format!("failed writing file {}", file_path.display()))?;
+    sink.write(&relative_path, bytes)?;
+    // The font is in place either way, so a time that cannot be set is
+    // only a warning.
+    let warning = fetched
+        .headers
+        .last_modified
+        .as_deref()
+        .and_then(parse_http_date)
+        .filter(|_| !layout.omit_server_mtime)
+        .and_then(|modified| sink.set_modified(&relative_path, modified).err());
 
-    Ok(file_path)
+    let sha256 = sha256_hex(bytes);
+    if layout.sidecar {
+        let sidecar = FontSidecar::new(
+            font,
+            fetched.referer.as_deref(),
+            fetched.headers.clone(),
+            bytes,
+            sha256.clone(),
+        );
+        let json = serde_json::to_string_pretty(&sidecar).expect("sidecar serializes to JSON");
+        sink.write(&sidecar_path(&relative_path), (json + "\n").as_bytes())?;
+    }
+
+    Ok(FontOutcome::Saved(
+        SavedFile {
+            path: sink.location(&relative_path),
+            url: font.url.clone(),
+            size: bytes.len() as u64,
+            sha256,
+        },
+        warning,
+    ))
+}
+
+/// A downloaded font with what the sidecar records about its request.
+struct FetchedFont {
+    bytes: Vec<u8>,
+    referer: Option<String>,
+    headers: SidecarHeaders,
+}
+
+enum FontOutcome {
+    Saved(SavedFile, Option<DownloadError>),
+    Skipped(PathBuf),
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Saved files with the same bytes whose fonts were inferred to belong to
+/// different families, e.g. one font renamed per brand.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdenticalFonts {
+    pub sha256: String,
+    /// Every copy, in the order they were saved.
+    pub copies: Vec<IdenticalCopy>,
 }
 
-fn fetch_remote_font(client: &Client, font: &FontInfo) -> Result<(Vec<u8>, Option<String>)> {
-    let mut request = client
-        .get(&font.url)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdenticalCopy {
+    pub family: String,
+    pub path: PathBuf,
+    pub url: String,
+}
+
+/// Groups `saved_files` by content hash and keeps the groups spanning more
+/// than one inferred family. `fonts` are the fonts the files were saved from.
+pub fn identical_fonts_across_families(
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+    inference: &Inference,
+) -> Vec<IdenticalFonts> {
+    let mut groups = Vec::<(IdenticalFonts, HashSet<String>)>::new();
+
+    for saved in saved_files {
+        let Some(font) = fonts.iter().find(|font| font.url == saved.url) else {
+            continue;
+        };
+        let fingerprint = infer_family_fingerprint(font, inference);
+        let copy = IdenticalCopy {
+            family: fingerprint.display,
+            path: saved.path.clone(),
+            url: saved.url.clone(),
+        };
+
+        match groups
+            .iter_mut()
+            .find(|(group, _)| group.sha256 == saved.sha256)
+        {
+            Some((group, families)) => {
+                families.insert(fingerprint.key);
+                group.copies.push(copy);
+            }
+            None => groups.push((
+                IdenticalFonts {
+                    sha256: saved.sha256.clone(),
+                    copies: vec![copy],
+                },
+                HashSet::from([fingerprint.key]),
+            )),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, families)| families.len() > 1)
+        .map(|(group, _)| group)
+        .collect()
+}
+
+/// Relative paths that more than one of `fonts` would be saved to under
+/// `layout`, before the `-1`, `-2` suffixes are applied.
+pub fn colliding_targets(fonts: &[FontInfo], layout: &DownloadLayout) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut collisions = Vec::new();
+
+    for font in fonts {
+        let extension = extension_for_font(font, None);
+        let (directory, stem) = target_for_font(font, layout, extension);
+        let target = directory.join(format!("{stem}.{extension}"));
+        if !seen.insert(target.clone()) && !collisions.contains(&target) {
+            collisions.push(target);
+        }
+    }
+
+    collisions
+}
+
+/// Where each of `fonts` already sits under `output_root`: the path `layout`
+/// would save it to, or any file whose sidecar records its URL. `None` marks
+/// the fonts a download would add.
+pub fn existing_downloads(
+    fonts: &[FontInfo],
+    layout: &DownloadLayout,
+    output_root: &Path,
+) -> Vec<Option<PathBuf>> {
+    let recorded = recorded_downloads(output_root);
+
+    fonts
+        .iter()
+        .map(|font| {
+            let extension = extension_for_font(font, None);
+            let (directory, stem) = target_for_font(font, layout, extension);
+            let predicted = output_root
+                .join(directory)
+                .join(format!("{stem}.{extension}"));
+            // A sidecar at the predicted path naming another URL means a
+            // different font took the name.
+            let taken_by_other = read_sidecar(&sidecar_path(&predicted))
+                .and_then(|sidecar| sidecar.url)
+                .is_some_and(|url| url != font.url);
+            if predicted.is_file() && !taken_by_other {
+                return Some(predicted);
+            }
+            recorded.get(&font.url).cloned()
+        })
+        .collect()
+}
+
+/// Maps the URL of every saved font under `output_root` with a sidecar to
+/// its path.
+pub(crate) fn recorded_downloads(output_root: &Path) -> HashMap<String, PathBuf> {
+    let mut recorded = HashMap::new();
+    collect_sidecar_urls(output_root, &mut recorded);
+    recorded
+}
+
+fn collect_sidecar_urls(dir: &Path, recorded: &mut HashMap<String, PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            collect_sidecar_urls(&path, recorded);
+            continue;
+        }
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let font_path = path.with_extension("");
+        if let Some(url) = read_sidecar(&path).and_then(|sidecar| sidecar.url)
+            && font_path.is_file()
+        {
+            recorded.entry(url).or_insert(font_path);
+        }
+    }
+}
+
+fn target_for_font(font: &FontInfo, layout: &DownloadLayout, extension: &str) -> (PathBuf, String) {
+    let vars = TemplateVars::for_font(font, &layout.inference).with_extension(extension);
+    let mut directory = layout.directory.render(&vars);
+
+    let Some(template) = &layout.file_name else {
+        return (directory, file_stem_for_font(font));
+    };
+
+    let rendered = template.render(&vars);
+    let Some(file_name) = rendered.file_name().and_then(|name| name.to_str()) else {
+        return (directory, file_stem_for_font(font));
+    };
+    if let Some(parent) = rendered.parent() {
+        directory.push(parent);
+    }
+
+    let stem = file_name
+        .strip_suffix(&format!(".{extension}"))
+        .unwrap_or(file_name)
+        .to_owned();
+    (directory, stem)
+}
+
+/// Requests a font with the headers `layout` asks for. The `Origin` is taken
+/// from the referer sent, or from the page when no referer is sent.
+fn fetch_remote_font(
+    fetcher: &dyn Fetcher,
+    font: &FontInfo,
+    layout: &DownloadLayout,
+    on_body: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FetchedFont, DownloadError> {
+    let mut request = FetchRequest::get(&font.url)
         .header(USER_AGENT, HTTP_USER_AGENT)
         .header(ACCEPT, "*/*");
 
-    if !font.referer.is_empty() {
-        request = request.header(REFERER, &font.referer);
-        if let Ok(parsed_referer) = Url::parse(&font.referer) {
-            request = request.header(ORIGIN, parsed_referer.origin().ascii_serialization());
-        }
+    let referer = layout.referer.referer(font);
+    if let Some(referer) = referer {
+        request = request.header(REFERER, referer);
+    }
+    if !layout.omit_origin
+        && let Ok(parsed_referer) = Url::parse(referer.unwrap_or(&font.referer))
+    {
+        request = request.header(ORIGIN, parsed_referer.origin().ascii_serialization());
     }
 
-    let response = request.send().context("request failed")?;
-    if !response.status().is_success() {
-        anyhow::bail!("HTTP {}", response.status());
+    let response = fetch_reporting_body(fetcher, request, on_body).map_err(|source| {
+        DownloadError::Request {
+            url: font.url.clone(),
+            source,
+        }
+    })?;
+    if !response.is_success() {
+        return Err(DownloadError::Status {
+            url: font.url.clone(),
+            status: response.status,
+        });
     }
 
-    let content_type = response
-        .headers()
-        .get(CONTENT_TYPE)
-        .and_then(|value| value.to_str().ok())
-        .map(|value| value.to_owned());
+    let header = |name| response.header(name).map(str::to_owned);
+    let headers = SidecarHeaders {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        content_type: header(CONTENT_TYPE),
+    };
+    Ok(FetchedFont {
+        bytes: response.body,
+        referer: referer.map(str::to_owned),
+        headers,
+    })
+}
+
+enum Fetching {
+    Body(u64, Option<u64>),
+    Done(Result<FetchResponse, FetchError>),
+}
+
+/// Fetches `request` on a scoped thread, passing its body progress to
+/// `on_body` on this one so callers' callbacks need not be `Send`. Bodies
+/// the fetcher did not report as they arrived are reported once at the end.
+fn fetch_reporting_body(
+    fetcher: &dyn Fetcher,
+    request: FetchRequest,
+    on_body: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<FetchResponse, FetchError> {
+    let (sender, receiver) = mpsc::channel();
+    let body_sender = sender.clone();
+    let request = request.on_body(BodyProgress::new(move |received, content_length| {
+        let _ = body_sender.send(Fetching::Body(received, content_length));
+    }));
+
+    let mut reported = 0;
+    let result = thread::scope(|scope| {
+        // The thread owns the request, so a panic drops every sender.
+        scope.spawn(move || {
+            let _ = sender.send(Fetching::Done(fetcher.fetch(&request)));
+        });
+        loop {
+            match receiver.recv() {
+                Ok(Fetching::Body(received, content_length)) => {
+                    reported = received;
+                    on_body(received, content_length);
+                }
+                Ok(Fetching::Done(result)) => break result,
+                Err(_) => break Err("fetch thread stopped without a response".into()),
+            }
+        }
+    })?;
 
-    let bytes = response.bytes().context("failed to read response bytes")?;
-    Ok((bytes.to_vec(), content_type))
+    let received = result.body.len() as u64;
+    if result.is_success() && received > 0 && received != reported {
+        let content_length = result
+            .header(CONTENT_LENGTH)
+            .and_then(|length| length.parse().ok())
+            .or(Some(received));
+        on_body(received, content_length);
+    }
+    Ok(result)
 }
 
-fn decode_data_url(input: &str) -> Result<(Vec<u8>, Option<String>)> {
+pub fn decode_data_url(input: &str) -> Result<(Vec<u8>, Option<String>), DownloadError> {
     let payload = input
         .strip_prefix("data:")
-        .context("invalid data URL: missing data: prefix")?;
+        .ok_or(DownloadError::InvalidDataUrl {
+            reason: "missing data: prefix",
+        })?;
     let (meta, data) = payload
         .split_once(',')
-        .context("invalid data URL: missing comma separator")?;
+        .ok_or(DownloadError::InvalidDataUrl {
+            reason: "missing comma separator",
+        })?;
 
     let is_base64 = meta
         .split(';')
@@ -157,7 +1199,7 @@ fn decode_data_url(input: &str) -> Result<(Vec<u8>, Option<String>)> {
     let bytes = if is_base64 {
         STANDARD
             .decode(data.trim())
-            .context("failed to decode base64 font bytes")?
+            .map_err(|source| DownloadError::Base64 { source })?
     } else {
         percent_decode_str(data).collect::<Vec<u8>>()
     };
@@ -165,7 +1207,7 @@ fn decode_data_url(input: &str) -> Result<(Vec<u8>, Option<String>)> {
     Ok((bytes, mime_type))
 }
 
-fn extension_for_font(font: &FontInfo, content_type: Option<&str>) -> &'static str {
+pub(crate) fn extension_for_font(font: &FontInfo, content_type: Option<&str>) -> &'static str {
     let format = font.format.to_ascii_uppercase();
     match format.as_str() {
         "WOFF2" => "woff2",
@@ -194,8 +1236,42 @@ fn extension_for_font(font: &FontInfo, content_type: Option<&str>) -> &'static s
     }
 }
 
+/// Size in bytes of an embedded `data:` URL font once decoded.
+pub fn embedded_font_size(url: &str) -> Option<usize> {
+    if !url.starts_with("data:") {
+        return None;
+    }
+    decode_data_url(url).ok().map(|(bytes, _)| bytes.len())
+}
+
+/// `Inter` + `SemiBold Italic` -> `Inter-SemiBoldItalic`, the PostScript-style
+/// name most foundries ship their files under. Characters that are not ASCII
+/// alphanumerics, `-` or `_` are dropped.
+fn metadata_file_stem(metadata: &FontMetadata) -> Option<String> {
+    let compact = |value: &str| {
+        value
+            .chars()
+            .filter(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+            .collect::<String>()
+    };
+
+    let family = compact(metadata.family.as_deref()?);
+    if family.is_empty() {
+        return None;
+    }
+    match metadata.subfamily.as_deref().map(compact) {
+        Some(subfamily) if !subfamily.is_empty() => Some(format!("{family}-{subfamily}")),
+        _ => Some(family),
+    }
+}
+
 fn file_stem_for_font(font: &FontInfo) -> String {
     let base_name = strip_extension(&font.name);
+    // Embedded fonts are already named after their family, weight, and style.
+    if font.url.starts_with("data:") && !base_name.is_empty() {
+        return sanitize_component(&base_name);
+    }
+
     let normalized_base = sanitize_component(&base_name);
     let normalized_weight = sanitize_component(&font.weight);
     let normalized_style = sanitize_component(&font.style);
@@ -220,7 +1296,65 @@ fn file_stem_for_font(font: &FontInfo) -> String {
     stem
 }
 
+#[derive(Debug, Eq, PartialEq)]
+enum OutputTarget {
+    Write(PathBuf),
+    Skip(PathBuf),
+}
+
+/// Picks the path, relative to the sink's root, a font is written to.
+fn resolve_output_path(
+    sink: &dyn OutputSink,
+    directory: &Path,
+    stem: &str,
+    extension: &str,
+    policy: ConflictPolicy,
+    used_paths: &mut HashSet<PathBuf>,
+) -> Result<OutputTarget, DownloadError> {
+    let candidate = target_file(directory, stem, extension);
+
+    // Fonts from the same run that share a target are distinct files, so they
+    // always keep each other regardless of the policy.
+    if policy == ConflictPolicy::Rename || used_paths.contains(&candidate) {
+        return Ok(OutputTarget::Write(unique_output_path(
+            sink, directory, stem, extension, used_paths,
+        )));
+    }
+
+    if let Some(existing) = existing_target(sink, &candidate, policy)? {
+        return Ok(OutputTarget::Skip(existing));
+    }
+
+    used_paths.insert(candidate.clone());
+    Ok(OutputTarget::Write(candidate))
+}
+
+fn target_file(directory: &Path, stem: &str, extension: &str) -> PathBuf {
+    let normalized_stem = if stem.is_empty() { "font" } else { stem };
+    directory.join(format!("{normalized_stem}.{extension}"))
+}
+
+/// `candidate` when it is taken and `policy` skips it; an error when the
+/// policy refuses it.
+fn existing_target(
+    sink: &dyn OutputSink,
+    candidate: &Path,
+    policy: ConflictPolicy,
+) -> Result<Option<PathBuf>, DownloadError> {
+    if !sink.exists(candidate) {
+        return Ok(None);
+    }
+    match policy {
+        ConflictPolicy::Skip => Ok(Some(candidate.to_path_buf())),
+        ConflictPolicy::Error => Err(DownloadError::Exists {
+            path: sink.location(candidate),
+        }),
+        ConflictPolicy::Rename | ConflictPolicy::Overwrite => Ok(None),
+    }
+}
+
 fn unique_output_path(
+    sink: &dyn OutputSink,
     directory: &Path,
     stem: &str,
     extension: &str,
@@ -236,7 +1370,7 @@ fn unique_output_path(
         };
 
         let candidate = directory.join(file_name);
-        if !candidate.exists() && used_paths.insert(candidate.clone()) {
+        if !sink.exists(&candidate) && used_paths.insert(candidate.clone()) {
             return candidate;
         }
     }
@@ -252,7 +1386,7 @@ fn strip_extension(name: &str) -> String {
         .unwrap_or_else(|| name.to_owned())
 }
 
-fn sanitize_component(value: &str) -> String {
+pub(crate) fn sanitize_component(value: &str) -> String {
     let mut output = String::with_capacity(value.len());
     let mut previous_was_separator = false;
 
@@ -273,11 +1407,26 @@ fn sanitize_component(value: &str) -> String {
 mod tests {
     use std::collections::HashSet;
     use std::fs;
-    use std::path::PathBuf;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-    use super::{decode_data_url, file_stem_for_font, unique_output_path};
+    use super::{
+        CommandSink, ConflictPolicy, DirectorySink, DownloadReport, Downloader, OutputSink,
+        OutputTarget, RefererPolicy, SavedFile, colliding_targets, decode_data_url,
+        download_fonts_to_sink, download_fonts_with_fetcher, existing_downloads, fetch_remote_font,
+        file_stem_for_font, identical_fonts_across_families, metadata_file_stem, partial_path,
+        resolve_output_path, sha256_hex, unique_output_path,
+    };
+    use crate::binary::FontMetadata;
+    use crate::cancel::CancellationToken;
+    use crate::error::{DownloadError, DownloadFailure};
+    use crate::fetch::header::{ORIGIN, REFERER};
+    use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+    use crate::inspect::Inference;
+    use crate::layout::{DownloadLayout, PathTemplate};
     use crate::model::FontInfo;
+    use crate::sidecar::{FontSidecar, sidecar_path};
 
     fn make_font(name: &str) -> FontInfo {
         FontInfo {
@@ -288,6 +1437,8 @@ mod tests {
             weight: "400".to_owned(),
             style: "Italic".to_owned(),
             referer: "https://example.com".to_owned(),
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
         }
     }
 
@@ -317,24 +1468,730 @@ mod tests {
         assert_eq!(percent_mime.as_deref(), Some("application/octet-stream"));
     }
 
+    #[test]
+    fn decode_data_url_reports_typed_errors_for_malformed_input() {
+        assert!(matches!(
+            decode_data_url("data:font/woff2;base64"),
+            Err(DownloadError::InvalidDataUrl { .. })
+        ));
+        assert!(matches!(
+            decode_data_url("data:font/woff2;base64,@@@"),
+            Err(DownloadError::Base64 { .. })
+        ));
+    }
+
+    #[test]
+    fn metadata_names_join_family_and_subfamily() {
+        let metadata = |family: &str, subfamily: Option<&str>| FontMetadata {
+            family: Some(family.to_owned()),
+            subfamily: subfamily.map(str::to_owned),
+            ..FontMetadata::default()
+        };
+
+        assert_eq!(
+            metadata_file_stem(&metadata("Inter", Some("SemiBold Italic"))).as_deref(),
+            Some("Inter-SemiBoldItalic")
+        );
+        assert_eq!(
+            metadata_file_stem(&metadata("Source Sans 3", Some("Regular"))).as_deref(),
+            Some("SourceSans3-Regular")
+        );
+        assert_eq!(
+            metadata_file_stem(&metadata("Brand/../Sans", None)).as_deref(),
+            Some("BrandSans")
+        );
+        assert_eq!(metadata_file_stem(&metadata("ＭＳ", Some("Bold"))), None);
+        assert_eq!(metadata_file_stem(&FontMetadata::default()), None);
+    }
+
     #[test]
     fn path_generation_sanitizes_names_and_allocates_unique_sequential_paths() {
         let font = make_font("My Font!.woff2");
         assert_eq!(file_stem_for_font(&font), "my-font-400-italic");
 
         let temp_dir = make_temp_dir();
+        let sink = DirectorySink::new(&temp_dir);
+        let directory = Path::new("acme");
         let mut used_paths = HashSet::new();
 
-        let first = unique_output_path(&temp_dir, "my-font", "woff2", &mut used_paths);
-        assert_eq!(first, temp_dir.join("my-font.woff2"));
-        fs::write(&first, b"seed").expect("failed to create initial file");
+        let first = unique_output_path(&sink, directory, "my-font", "woff2", &mut used_paths);
+        assert_eq!(first, directory.join("my-font.woff2"));
+        sink.write(&first, b"seed")
+            .expect("failed to create initial file");
 
-        let second = unique_output_path(&temp_dir, "my-font", "woff2", &mut used_paths);
-        assert_eq!(second, temp_dir.join("my-font-1.woff2"));
+        let second = unique_output_path(&sink, directory, "my-font", "woff2", &mut used_paths);
+        assert_eq!(second, directory.join("my-font-1.woff2"));
 
-        let third = unique_output_path(&temp_dir, "my-font", "woff2", &mut used_paths);
-        assert_eq!(third, temp_dir.join("my-font-2.woff2"));
+        let third = unique_output_path(&sink, directory, "my-font", "woff2", &mut used_paths);
+        assert_eq!(third, directory.join("my-font-2.woff2"));
 
         fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
     }
+
+    #[test]
+    fn sha256_hex_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn conflict_policies_control_existing_files() {
+        let temp_dir = make_temp_dir();
+        let sink = DirectorySink::new(&temp_dir);
+        let root = Path::new("");
+        let existing = PathBuf::from("my-font.woff2");
+        fs::write(temp_dir.join(&existing), b"seed").expect("failed to create existing file");
+
+        let resolve = |policy| {
+            resolve_output_path(&sink, root, "my-font", "woff2", policy, &mut HashSet::new())
+        };
+        assert_eq!(
+            resolve(ConflictPolicy::Rename).unwrap(),
+            OutputTarget::Write(PathBuf::from("my-font-1.woff2"))
+        );
+        assert_eq!(
+            resolve(ConflictPolicy::Skip).unwrap(),
+            OutputTarget::Skip(existing.clone())
+        );
+        assert_eq!(
+            resolve(ConflictPolicy::Overwrite).unwrap(),
+            OutputTarget::Write(existing.clone())
+        );
+        assert!(matches!(
+            resolve(ConflictPolicy::Error),
+            Err(DownloadError::Exists { path }) if path == temp_dir.join(&existing)
+        ));
+
+        let mut used_paths = HashSet::new();
+        let first = resolve_output_path(
+            &sink,
+            root,
+            "my-font",
+            "woff2",
+            ConflictPolicy::Overwrite,
+            &mut used_paths,
+        );
+        let second = resolve_output_path(
+            &sink,
+            root,
+            "my-font",
+            "woff2",
+            ConflictPolicy::Overwrite,
+            &mut used_paths,
+        );
+        assert_eq!(first.unwrap(), OutputTarget::Write(existing));
+        assert_eq!(
+            second.unwrap(),
+            OutputTarget::Write(PathBuf::from("my-font-1.woff2"))
+        );
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[test]
+    fn name_templates_report_fonts_that_share_a_target() {
+        let regular = make_font("acme-regular.woff2");
+        let mut bold = make_font("acme-bold.woff2");
+        bold.weight = "700".to_owned();
+        let fonts = vec![regular.clone(), bold, regular];
+
+        let layout = DownloadLayout {
+            file_name: Some(PathTemplate::parse("{family}-{weight}{style_suffix}.{ext}").unwrap()),
+            ..DownloadLayout::default()
+        };
+        assert_eq!(
+            colliding_targets(&fonts, &layout),
+            vec![PathBuf::from("acme-sans/acme-sans-400-italic.woff2")]
+        );
+
+        let by_family = DownloadLayout {
+            file_name: Some(PathTemplate::parse("{family}.{ext}").unwrap()),
+            ..DownloadLayout::default()
+        };
+        assert_eq!(colliding_targets(&fonts, &by_family).len(), 1);
+        assert!(colliding_targets(&fonts[..1], &by_family).is_empty());
+    }
+
+    /// Answers with the request's referer and origin joined by a space.
+    struct EchoHeaders;
+
+    impl Fetcher for EchoHeaders {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            let header = |name: &str| {
+                request
+                    .headers
+                    .iter()
+                    .find(|(header, _)| header == name)
+                    .map_or("-", |(_, value)| value.as_str())
+            };
+            Ok(FetchResponse::ok(format!(
+                "{} {}",
+                header(REFERER),
+                header(ORIGIN)
+            )))
+        }
+    }
+
+    #[test]
+    fn referer_policies_control_request_headers() {
+        let font = make_font("acme.woff2");
+        let headers = |referer: &str, omit_origin| {
+            let layout = DownloadLayout {
+                referer: referer.parse().expect("valid referer policy"),
+                omit_origin,
+                ..DownloadLayout::default()
+            };
+            let fetched = fetch_remote_font(&EchoHeaders, &font, &layout, &mut |_, _| {}).unwrap();
+            String::from_utf8(fetched.bytes).unwrap()
+        };
+
+        assert_eq!(
+            headers("page", false),
+            "https://example.com https://example.com"
+        );
+        assert_eq!(headers("none", false), "- https://example.com");
+        assert_eq!(headers("none", true), "- -");
+        assert_eq!(
+            headers("custom:https://shop.example/page", false),
+            "https://shop.example/page https://shop.example"
+        );
+
+        assert!("custom:ftp://example.com".parse::<RefererPolicy>().is_err());
+        assert!("origin".parse::<RefererPolicy>().is_err());
+    }
+
+    struct FontServer;
+
+    impl Fetcher for FontServer {
+        fn fetch(&self, _request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            Ok(FetchResponse {
+                headers: vec![
+                    ("ETag".to_owned(), "\"abc\"".to_owned()),
+                    ("Content-Type".to_owned(), "font/woff2".to_owned()),
+                ],
+                ..FetchResponse::ok(b"wOF2\0\x01\0\0".to_vec())
+            })
+        }
+    }
+
+    #[test]
+    fn sidecars_record_provenance_next_to_saved_fonts() {
+        let temp_dir = make_temp_dir();
+        let mut font = make_font("acme.woff2");
+        font.conditions = vec!["@media print".to_owned()];
+        let layout = DownloadLayout {
+            sidecar: true,
+            ..DownloadLayout::default()
+        };
+
+        let report = download_fonts_with_fetcher(
+            &[font],
+            &temp_dir,
+            &layout,
+            &FontServer,
+            &CancellationToken::new(),
+            |_| {},
+        );
+
+        let saved = &report.saved_files[0];
+        let json = fs::read_to_string(sidecar_path(&saved.path)).expect("sidecar should exist");
+        let sidecar = serde_json::from_str::<FontSidecar>(&json).expect("sidecar should parse");
+        assert_eq!(
+            sidecar.url.as_deref(),
+            Some("https://cdn.example/font.woff2")
+        );
+        assert_eq!(sidecar.referer.as_deref(), Some("https://example.com"));
+        assert_eq!(sidecar.headers.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(sidecar.headers.last_modified, None);
+        assert_eq!(sidecar.descriptors.font_style, "Italic");
+        assert_eq!(sidecar.descriptors.conditions, vec!["@media print"]);
+        assert_eq!(sidecar.sha256, saved.sha256);
+        assert!(sidecar.downloaded_at.ends_with('Z'));
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    /// Reports the body in two halves, as a streaming fetcher would.
+    struct ChunkedServer;
+
+    impl Fetcher for ChunkedServer {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            if let Some(on_body) = &request.on_body {
+                on_body.report(4, Some(8));
+                on_body.report(8, Some(8));
+            }
+            Ok(FetchResponse::ok(b"wOF2\0\x01\0\0".to_vec()))
+        }
+    }
+
+    #[test]
+    fn progress_reports_bytes_as_bodies_arrive() {
+        let temp_dir = make_temp_dir();
+        let mut font = make_font("acme.woff2");
+        font.content_length = Some(9);
+        let mut events = Vec::new();
+
+        for fetcher in [&ChunkedServer as &dyn Fetcher, &FontServer] {
+            download_fonts_with_fetcher(
+                std::slice::from_ref(&font),
+                &temp_dir.join(events.len().to_string()),
+                &DownloadLayout::default(),
+                fetcher,
+                &CancellationToken::new(),
+                |progress| {
+                    events.push((progress.current, progress.received, progress.content_length))
+                },
+            );
+        }
+
+        assert_eq!(
+            events,
+            [
+                // Until the response arrives, the scan's size is all there is.
+                (1, 0, Some(9)),
+                (1, 4, Some(8)),
+                (1, 8, Some(8)),
+                // Bodies the fetcher did not stream are reported once.
+                (1, 0, Some(9)),
+                (1, 8, Some(8)),
+            ]
+        );
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    /// Counts the fonts it serves.
+    #[derive(Default)]
+    struct CountingServer(AtomicUsize);
+
+    impl Fetcher for CountingServer {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            FontServer.fetch(request)
+        }
+    }
+
+    #[test]
+    fn skipped_and_refused_targets_are_not_downloaded() {
+        let temp_dir = make_temp_dir();
+        let font = make_font("acme.woff2");
+        let server = CountingServer::default();
+        let download = |on_conflict| {
+            let layout = DownloadLayout {
+                on_conflict,
+                ..DownloadLayout::default()
+            };
+            download_fonts_with_fetcher(
+                std::slice::from_ref(&font),
+                &temp_dir,
+                &layout,
+                &server,
+                &CancellationToken::new(),
+                |_| {},
+            )
+        };
+
+        assert_eq!(download(ConflictPolicy::Skip).saved_files.len(), 1);
+        assert_eq!(download(ConflictPolicy::Skip).skipped_files.len(), 1);
+        assert!(matches!(
+            download(ConflictPolicy::Error).failures[0].error,
+            DownloadError::Exists { .. }
+        ));
+        assert_eq!(server.0.load(Ordering::SeqCst), 1);
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    /// Last changed at 2025-01-01T00:00:00Z.
+    struct DatedServer;
+
+    impl Fetcher for DatedServer {
+        fn fetch(&self, _request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            Ok(FetchResponse {
+                headers: vec![(
+                    "Last-Modified".to_owned(),
+                    "Wed, 01 Jan 2025 00:00:00 GMT".to_owned(),
+                )],
+                ..FetchResponse::ok(b"wOF2\0\x01\0\0".to_vec())
+            })
+        }
+    }
+
+    #[test]
+    fn saved_fonts_keep_the_server_modification_time() {
+        let temp_dir = make_temp_dir();
+        let last_modified = UNIX_EPOCH + Duration::from_secs(1_735_689_600);
+        let modified = |omit_server_mtime| {
+            let layout = DownloadLayout {
+                omit_server_mtime,
+                ..DownloadLayout::default()
+            };
+            let output = temp_dir.join(omit_server_mtime.to_string());
+            let report = download_fonts_with_fetcher(
+                &[make_font("acme.woff2")],
+                &output,
+                &layout,
+                &DatedServer,
+                &CancellationToken::new(),
+                |_| {},
+            );
+            fs::metadata(&report.saved_files[0].path)
+                .and_then(|metadata| metadata.modified())
+                .expect("saved font should have a modification time")
+        };
+
+        assert_eq!(modified(false), last_modified);
+        assert!(modified(true) > last_modified);
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[test]
+    fn modification_times_that_cannot_be_set_only_warn() {
+        struct TimelessSink;
+
+        impl OutputSink for TimelessSink {
+            fn location(&self, relative: &Path) -> PathBuf {
+                relative.to_path_buf()
+            }
+
+            fn exists(&self, _relative: &Path) -> bool {
+                false
+            }
+
+            fn write(&self, _relative: &Path, _bytes: &[u8]) -> Result<(), DownloadError> {
+                Ok(())
+            }
+
+            fn set_modified(
+                &self,
+                relative: &Path,
+                _time: SystemTime,
+            ) -> Result<(), DownloadError> {
+                Err(DownloadError::SetModified {
+                    path: relative.to_path_buf(),
+                    source: std::io::Error::other("read-only file system"),
+                })
+            }
+        }
+
+        let report = download_fonts_to_sink(
+            &[make_font("acme.woff2")],
+            &TimelessSink,
+            &DownloadLayout::default(),
+            &DatedServer,
+            &CancellationToken::new(),
+            |_| {},
+        );
+
+        assert_eq!(report.saved_files.len(), 1);
+        assert!(report.failures.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+        assert!(matches!(
+            report.warnings[0].error,
+            DownloadError::SetModified { .. }
+        ));
+    }
+
+    #[test]
+    fn existing_downloads_match_predicted_paths_and_sidecars() {
+        let temp_dir = make_temp_dir();
+        let saved = make_font("acme.woff2");
+        let mut missing = make_font("acme-bold.woff2");
+        missing.url = "https://cdn.example/bold.woff2".to_owned();
+        missing.weight = "700".to_owned();
+        let layout = DownloadLayout {
+            sidecar: true,
+            ..DownloadLayout::default()
+        };
+        download_fonts_with_fetcher(
+            std::slice::from_ref(&saved),
+            &temp_dir,
+            &layout,
+            &FontServer,
+            &CancellationToken::new(),
+            |_| {},
+        );
+        let expected = temp_dir.join("acme-sans/acme-400-italic.woff2");
+
+        let fonts = [saved, missing];
+        assert_eq!(
+            existing_downloads(&fonts, &layout, &temp_dir),
+            vec![Some(expected.clone()), None]
+        );
+
+        // Renamed by a different layout, but still found through its sidecar.
+        let renamed = DownloadLayout {
+            file_name: Some(PathTemplate::parse("{family}.{ext}").unwrap()),
+            ..DownloadLayout::default()
+        };
+        assert_eq!(
+            existing_downloads(&fonts, &renamed, &temp_dir),
+            vec![Some(expected), None]
+        );
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[test]
+    fn directory_sinks_rename_finished_files_into_place() {
+        let temp_dir = make_temp_dir();
+        let sink = DirectorySink::new(&temp_dir);
+        let font = Path::new("acme/acme-400.woff2");
+        let path = temp_dir.join(font);
+        // Left behind by an interrupted run.
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(partial_path(&path), b"wO").unwrap();
+
+        sink.write(font, b"wOF2").expect("write should succeed");
+        assert_eq!(fs::read(&path).unwrap(), b"wOF2");
+        assert!(!partial_path(&path).exists());
+
+        // A failed rename leaves neither the font nor its partial file.
+        let blocked = Path::new("acme/blocked.woff2");
+        fs::create_dir_all(temp_dir.join(blocked).join("inner")).unwrap();
+        assert!(matches!(
+            sink.write(blocked, b"wOF2"),
+            Err(DownloadError::Write { .. })
+        ));
+        assert!(!partial_path(&temp_dir.join(blocked)).exists());
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_sinks_pipe_each_file_with_its_destination() {
+        let temp_dir = make_temp_dir();
+        let log = temp_dir.join("uploads.txt");
+        let sink = CommandSink::new(format!(
+            "printf '%s %s %s\\n' \"$TYPOPOTAMUS_PATH\" \"$TYPOPOTAMUS_CONTENT_TYPE\" \"$(wc -c)\" >> '{}'",
+            log.display()
+        ));
+        let layout = DownloadLayout {
+            sidecar: true,
+            ..DownloadLayout::default()
+        };
+
+        let report = download_fonts_to_sink(
+            &[make_font("acme.woff2")],
+            &sink,
+            &layout,
+            &FontServer,
+            &CancellationToken::new(),
+            |_| {},
+        );
+
+        assert!(report.failures.is_empty(), "{:?}", report.failures);
+        let saved = &report.saved_files[0].path;
+        assert_eq!(saved, Path::new("acme-sans/acme-400-italic.woff2"));
+        let uploads = fs::read_to_string(&log).expect("the command should have run");
+        let lines = uploads.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["acme-sans/acme-400-italic.woff2", "font/woff2", "8"]
+        );
+        assert!(lines[1].starts_with("acme-sans/acme-400-italic.woff2.json application/json"));
+
+        let failing = download_fonts_to_sink(
+            &[make_font("acme.woff2")],
+            &CommandSink::new("echo denied >&2; exit 3"),
+            &DownloadLayout::default(),
+            &FontServer,
+            &CancellationToken::new(),
+            |_| {},
+        );
+        assert!(matches!(
+            &failing.failures[0].error,
+            DownloadError::Command { stderr, .. } if stderr == "denied"
+        ));
+
+        let s3 = CommandSink::s3("s3://fonts-bucket/ci/").unwrap();
+        assert_eq!(
+            s3.location(Path::new("acme/acme.woff2")),
+            Path::new("s3://fonts-bucket/ci/acme/acme.woff2")
+        );
+        assert!(CommandSink::s3("s3://").is_err());
+        assert!(CommandSink::s3("https://fonts-bucket").is_err());
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[test]
+    fn downloaders_apply_builder_settings() {
+        let temp_dir = make_temp_dir();
+        let downloader = Downloader::builder()
+            .directory(PathTemplate::parse("{format}").unwrap())
+            .on_conflict(ConflictPolicy::Skip)
+            .fetcher(FontServer)
+            .build()
+            .unwrap();
+        let fonts = [make_font("acme.woff2")];
+
+        let first = downloader.download(&fonts, &temp_dir, &CancellationToken::new(), |_| {});
+        let second = downloader.download(&fonts, &temp_dir, &CancellationToken::new(), |_| {});
+
+        assert_eq!(
+            first.saved_files[0].path,
+            temp_dir.join("woff2/acme-400-italic.woff2")
+        );
+        assert!(second.saved_files.is_empty());
+        assert_eq!(second.skipped_files[0].path, first.saved_files[0].path);
+        assert_eq!(second.skipped_files[0].url, fonts[0].url);
+        assert_eq!(downloader.layout().on_conflict, ConflictPolicy::Skip);
+
+        fs::remove_dir_all(&temp_dir).expect("failed to clean up temp test directory");
+    }
+
+    #[test]
+    fn retries_replace_failures_and_keep_saved_files() {
+        let saved = |name: &str| SavedFile {
+            path: PathBuf::from(format!("out/{name}.woff2")),
+            url: format!("https://cdn.example/{name}.woff2"),
+            size: 4,
+            sha256: "aaa".to_owned(),
+        };
+        let failure = |name: &str| DownloadFailure {
+            name: format!("{name}.woff2"),
+            url: format!("https://cdn.example/{name}.woff2"),
+            error: DownloadError::Status {
+                url: format!("https://cdn.example/{name}.woff2"),
+                status: 503,
+            },
+        };
+        let mut report = DownloadReport {
+            attempted: 3,
+            saved_files: vec![saved("regular")],
+            skipped_files: Vec::new(),
+            failures: vec![failure("bold"), failure("italic")],
+            warnings: Vec::new(),
+            cancelled: false,
+        };
+        assert_eq!(
+            report.retryable_urls(),
+            vec![
+                "https://cdn.example/bold.woff2",
+                "https://cdn.example/italic.woff2"
+            ]
+        );
+
+        report.merge_retry(DownloadReport {
+            attempted: 2,
+            saved_files: vec![saved("bold")],
+            skipped_files: Vec::new(),
+            failures: vec![failure("italic")],
+            warnings: Vec::new(),
+            cancelled: false,
+        });
+
+        assert_eq!(report.attempted, 3);
+        assert_eq!(report.saved_files, vec![saved("regular"), saved("bold")]);
+        assert_eq!(
+            report.retryable_urls(),
+            vec!["https://cdn.example/italic.woff2"]
+        );
+    }
+
+    #[test]
+    fn reports_identical_bytes_saved_under_different_families() {
+        let saved = |name: &str, sha256: &str| SavedFile {
+            path: PathBuf::from(format!("out/{name}.woff2")),
+            url: format!("https://cdn.example/{name}.woff2"),
+            size: 4,
+            sha256: sha256.to_owned(),
+        };
+        let font = |name: &str, family: &str| FontInfo {
+            family: family.to_owned(),
+            url: format!("https://cdn.example/{name}.woff2"),
+            ..make_font(name)
+        };
+        let fonts = vec![
+            font("shop", "Shop Sans"),
+            font("blog", "Blog Sans"),
+            font("shop-alt", "Shop Sans"),
+            font("news", "News Serif"),
+        ];
+        let saved_files = vec![
+            saved("shop", "aaa"),
+            saved("blog", "aaa"),
+            saved("shop-alt", "bbb"),
+            saved("news", "ccc"),
+        ];
+
+        let identical =
+            identical_fonts_across_families(&fonts, &saved_files, &Inference::default());
+
+        assert_eq!(identical.len(), 1);
+        assert_eq!(identical[0].sha256, "aaa");
+        assert_eq!(
+            identical[0]
+                .copies
+                .iter()
+                .map(|copy| copy.family.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Shop Sans", "Blog Sans"]
+        );
+
+        let same_family = vec![saved("shop", "ddd"), saved("shop-alt", "ddd")];
+        assert!(
+            identical_fonts_across_families(&fonts, &same_family, &Inference::default()).is_empty()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn reports_round_trip_through_json() {
+        let report = DownloadReport {
+            attempted: 2,
+            saved_files: vec![SavedFile {
+                path: PathBuf::from("out/acme-sans/font.woff2"),
+                url: "https://cdn.example/font.woff2".to_owned(),
+                size: 4,
+                sha256: "aaa".to_owned(),
+            }],
+            skipped_files: Vec::new(),
+            failures: vec![DownloadFailure {
+                name: "gone.woff2".to_owned(),
+                url: "https://cdn.example/gone.woff2".to_owned(),
+                error: DownloadError::Status {
+                    url: "https://cdn.example/gone.woff2".to_owned(),
+                    status: 404,
+                },
+            }],
+            warnings: vec![DownloadFailure {
+                name: "font.woff2".to_owned(),
+                url: "https://cdn.example/font.woff2".to_owned(),
+                error: DownloadError::Recorded {
+                    message: "could not set modification time".to_owned(),
+                },
+            }],
+            cancelled: false,
+        };
+
+        let json = serde_json::to_value(&report).expect("report serializes");
+        assert_eq!(json["failures"][0]["error"], "HTTP 404");
+        assert_eq!(
+            json["warnings"][0]["error"],
+            "could not set modification time"
+        );
+
+        let parsed = serde_json::from_value::<DownloadReport>(json).expect("report parses");
+        assert_eq!(parsed.saved_files, report.saved_files);
+        assert_eq!(parsed.warnings[0].url, "https://cdn.example/font.woff2");
+        assert!(matches!(
+            &parsed.failures[0].error,
+            DownloadError::Recorded { message } if message == "HTTP 404"
+        ));
+
+        let mut font = make_font("font.woff2");
+        font.usage = Some(crate::model::FontUsageStatus::UnmatchedSelectors);
+        let json = serde_json::to_string(&font).expect("font serializes");
+        assert!(json.contains("\"usage\":\"unmatched-selectors\""));
+        assert_eq!(
+            serde_json::from_str::<FontInfo>(&json).expect("font parses"),
+            font
+        );
+    }
 }
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+
+use url::Url;
+
+use crate::cancel::CancellationToken;
+use crate::download::decode_data_url;
+use crate::extractor::DEFAULT_USER_AGENT;
+use crate::fetch::header::{
+    ACCEPT, ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE,
+    CONTENT_TYPE, EXPIRES, LAST_MODIFIED, ORIGIN, RANGE, REFERER, USER_AGENT,
+};
+use crate::fetch::{FetchRequest, FetchResponse, Fetcher};
+use crate::model::FontInfo;
+
+/// Requests in flight at once when none is configured.
+pub const DEFAULT_ENRICH_CONCURRENCY: usize = 8;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EnrichOptions {
+    /// Requests in flight at once; `0` is treated as `1`.
+    pub concurrency: usize,
+}
+
+impl Default for EnrichOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_ENRICH_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct RemoteHeaders {
+    content_length: Option<u64>,
+    last_modified: Option<String>,
+    content_type: Option<String>,
+    cache_control: Option<String>,
+    expires: Option<String>,
+    access_control_allow_origin: Option<String>,
+}
+
+/// Fills in [`FontInfo::content_length`], `last_modified`, `content_type`,
+/// `cache_control`, `expires` and `access_control_allow_origin` with one
+/// `HEAD` request per distinct URL, made in parallel. Servers that refuse
+/// `HEAD` are asked for the first byte instead. Requests send the page's
+/// `Origin`, as browsers do for fonts, so CORS headers show up. Embedded
+/// `data:` URLs are measured without a request. Fields already set are kept,
+/// and fonts whose requests fail keep `None`.
+pub fn enrich_fonts(
+    fonts: &mut [FontInfo],
+    fetcher: &dyn Fetcher,
+    options: &EnrichOptions,
+    cancel: &CancellationToken,
+) {
+    let mut pending = Vec::<(&str, &str)>::new();
+    for font in fonts.iter() {
+        if !font.url.starts_with("data:")
+            && font.content_length.is_none()
+            && !pending.iter().any(|(url, _)| *url == font.url)
+        {
+            pending.push((&font.url, &font.referer));
+        }
+    }
+
+    let queue = Mutex::new(pending.into_iter());
+    let results = Mutex::new(HashMap::<String, RemoteHeaders>::new());
+    thread::scope(|scope| {
+        for _ in 0..options.concurrency.max(1) {
+            scope.spawn(|| {
+                loop {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    let Some((url, referer)) = queue.lock().expect("enrich queue").next() else {
+                        break;
+                    };
+                    if let Some(headers) = request_headers(url, referer, fetcher) {
+                        results
+                            .lock()
+                            .expect("enrich results")
+                            .insert(url.to_owned(), headers);
+                    }
+                }
+            });
+        }
+    });
+    let results = results.into_inner().expect("enrich results");
+
+    for font in fonts {
+        let headers = if font.url.starts_with("data:") {
+            match decode_data_url(&font.url) {
+                Ok((bytes, content_type)) => RemoteHeaders {
+                    content_length: Some(bytes.len() as u64),
+                    content_type,
+                    ..RemoteHeaders::default()
+                },
+                Err(_) => continue,
+            }
+        } else {
+            match results.get(&font.url) {
+                Some(headers) => headers.clone(),
+                None => continue,
+            }
+        };
+
+        font.content_length = font.content_length.or(headers.content_length);
+        font.last_modified = font.last_modified.take().or(headers.last_modified);
+        font.content_type = font.content_type.take().or(headers.content_type);
+        font.cache_control = font.cache_control.take().or(headers.cache_control);
+        font.expires = font.expires.take().or(headers.expires);
+        font.access_control_allow_origin = font
+            .access_control_allow_origin
+            .take()
+            .or(headers.access_control_allow_origin);
+    }
+}
+
+/// Adds the headers a browser sends with a font request made by `referer`.
+pub(crate) fn font_request(request: FetchRequest, referer: &str) -> FetchRequest {
+    let request = request
+        .header(USER_AGENT, DEFAULT_USER_AGENT)
+        .header(ACCEPT, "*/*")
+        .header(REFERER, referer);
+    let origin = Url::parse(referer)
+        .ok()
+        .map(|url| url.origin().ascii_serialization())
+        .filter(|origin| origin != "null");
+    match origin {
+        Some(origin) => request.header(ORIGIN, origin),
+        None => request,
+    }
+}
+
+fn request_headers(url: &str, referer: &str, fetcher: &dyn Fetcher) -> Option<RemoteHeaders> {
+    let with_headers = |request: FetchRequest| font_request(request, referer);
+
+    let head = fetcher
+        .fetch(&with_headers(FetchRequest::head(url)))
+        .ok()
+        .filter(|response| response.is_success());
+    if let Some(response) = &head
+        && let Some(content_length) = response_length(response)
+    {
+        return Some(remote_headers(response, Some(content_length)));
+    }
+
+    // Some CDNs answer `HEAD` with 405 or without a length.
+    let probe = fetcher
+        .fetch(
+            &with_headers(FetchRequest::get(url))
+                .header(RANGE, "bytes=0-0")
+                .max_body_bytes(Some(1)),
+        )
+        .ok()
+        .filter(|response| response.is_success());
+    match (probe, head) {
+        (Some(response), _) => {
+            let length = response_length(&response);
+            Some(remote_headers(&response, length))
+        }
+        (None, Some(response)) => Some(remote_headers(&response, None)),
+        (None, None) => None,
+    }
+}
+
+/// The full file size: the total of a `Content-Range`, the `Content-Length`
+/// of a whole response, or the body of a fetcher that ignored the method.
+pub(crate) fn response_length(response: &FetchResponse) -> Option<u64> {
+    if response.status == 206 {
+        return response
+            .header(CONTENT_RANGE)
+            .and_then(|range| range.rsplit_once('/'))
+            .and_then(|(_, total)| total.trim().parse().ok());
+    }
+
+    response
+        .header(CONTENT_LENGTH)
+        .and_then(|length| length.trim().parse().ok())
+        .or_else(|| {
+            (!response.body.is_empty() && !response.truncated).then_some(response.body.len() as u64)
+        })
+}
+
+fn remote_headers(response: &FetchResponse, content_length: Option<u64>) -> RemoteHeaders {
+    RemoteHeaders {
+        content_length,
+        last_modified: response.header(LAST_MODIFIED).map(str::to_owned),
+        content_type: response.header(CONTENT_TYPE).map(str::to_owned),
+        cache_control: response.header(CACHE_CONTROL).map(str::to_owned),
+        expires: response.header(EXPIRES).map(str::to_owned),
+        access_control_allow_origin: response
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(str::to_owned),
+    }
+}
+
+/// Keeps fonts whose [`FontInfo::content_length`] falls within `min..=max`
+/// bytes. Fonts of unknown size are always kept.
+pub fn filter_indices_by_size(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    min: Option<u64>,
+    max: Option<u64>,
+) -> Vec<usize> {
+    indices
+        .iter()
+        .copied()
+        .filter(|index| {
+            fonts.get(*index).is_some_and(|font| {
+                font.content_length.is_none_or(|length| {
+                    min.is_none_or(|min| length >= min) && max.is_none_or(|max| length <= max)
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::{EnrichOptions, enrich_fonts, filter_indices_by_size};
+    use crate::cancel::CancellationToken;
+    use crate::fetch::{FetchError, FetchMethod, FetchRequest, FetchResponse, Fetcher};
+    use crate::model::FontInfo;
+
+    /// `a.woff2` answers `HEAD`; `b.woff2` only answers ranged `GET`s.
+    #[derive(Default)]
+    struct HeadServer {
+        requests: Mutex<Vec<(FetchMethod, String)>>,
+    }
+
+    impl Fetcher for HeadServer {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((request.method, request.url.clone()));
+            let header = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+            Ok(match (request.method, request.url.as_str()) {
+                (FetchMethod::Head, "https://cdn.example/a.woff2") => FetchResponse {
+                    status: 200,
+                    headers: vec![
+                        header("Content-Length", "48213"),
+                        header("Last-Modified", "Wed, 01 Jan 2025 00:00:00 GMT"),
+                        header("Content-Type", "font/woff2"),
+                        header("Cache-Control", "public, max-age=31536000, immutable"),
+                        header("Access-Control-Allow-Origin", "*"),
+                    ],
+                    ..FetchResponse::default()
+                },
+                (FetchMethod::Get, "https://cdn.example/b.woff2") => FetchResponse {
+                    status: 206,
+                    headers: vec![header("Content-Range", "bytes 0-0/1234")],
+                    body: vec![0],
+                    truncated: false,
+                    from_cache: false,
+                    final_url: None,
+                },
+                _ => FetchResponse {
+                    status: 405,
+                    ..FetchResponse::default()
+                },
+            })
+        }
+    }
+
+    fn font(url: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn fills_sizes_from_head_requests_and_range_fallbacks() {
+        let mut fonts = vec![
+            font("https://cdn.example/a.woff2"),
+            font("https://cdn.example/b.woff2"),
+            font("https://cdn.example/a.woff2"),
+            font("https://cdn.example/gone.woff2"),
+            font("data:font/woff2;base64,d09GMg=="),
+        ];
+        let server = HeadServer::default();
+
+        enrich_fonts(
+            &mut fonts,
+            &server,
+            &EnrichOptions::default(),
+            &CancellationToken::new(),
+        );
+
+        assert_eq!(fonts[0].content_length, Some(48213));
+        assert_eq!(fonts[0].content_type.as_deref(), Some("font/woff2"));
+        assert_eq!(
+            fonts[0].last_modified.as_deref(),
+            Some("Wed, 01 Jan 2025 00:00:00 GMT")
+        );
+        assert_eq!(
+            fonts[0].cache_control.as_deref(),
+            Some("public, max-age=31536000, immutable")
+        );
+        assert_eq!(fonts[0].access_control_allow_origin.as_deref(), Some("*"));
+        assert_eq!(fonts[0].expires, None);
+        assert_eq!(fonts[1].content_length, Some(1234));
+        assert_eq!(fonts[1].cache_control, None);
+        assert_eq!(fonts[2].content_length, Some(48213));
+        assert_eq!(fonts[3].content_length, None);
+        assert_eq!(fonts[4].content_length, Some(4));
+        assert_eq!(fonts[4].content_type.as_deref(), Some("font/woff2"));
+
+        let requests = server.requests.lock().unwrap();
+        let heads = requests
+            .iter()
+            .filter(|(method, url)| *method == FetchMethod::Head && url.ends_with("a.woff2"))
+            .count();
+        assert_eq!(heads, 1, "duplicate URLs are requested once");
+        assert!(!requests.iter().any(|(_, url)| url.starts_with("data:")));
+
+        assert_eq!(
+            filter_indices_by_size(&fonts, &[0, 1, 3, 4], Some(1000), Some(40_000)),
+            vec![1, 3]
+        );
+    }
+}
@@ -0,0 +1,494 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::fetch::FetchError;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FetchPhase {
+    Page,
+    Stylesheet,
+    Script,
+    Sitemap,
+    Font,
+}
+
+impl fmt::Display for FetchPhase {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Page => "page",
+            Self::Stylesheet => "stylesheet",
+            Self::Script => "script",
+            Self::Sitemap => "sitemap",
+            Self::Font => "font",
+        };
+        formatter.write_str(label)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("invalid URL {url}")]
+    InvalidUrl {
+        url: String,
+        #[source]
+        source: url::ParseError,
+    },
+
+    #[error("failed to create HTTP client")]
+    Client {
+        #[source]
+        source: FetchError,
+    },
+
+    #[error("failed to fetch {phase} {url}")]
+    Request {
+        url: String,
+        phase: FetchPhase,
+        #[source]
+        source: FetchError,
+    },
+
+    #[error("{phase} {url} returned HTTP {status}")]
+    Status {
+        url: String,
+        phase: FetchPhase,
+        status: u16,
+    },
+
+    #[error("extraction was cancelled")]
+    Cancelled,
+}
+
+impl ExtractError {
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::InvalidUrl { url, .. } | Self::Request { url, .. } | Self::Status { url, .. } => {
+                Some(url)
+            }
+            Self::Client { .. } | Self::Cancelled => None,
+        }
+    }
+
+    pub fn phase(&self) -> Option<FetchPhase> {
+        match self {
+            Self::Request { phase, .. } | Self::Status { phase, .. } => Some(*phase),
+            Self::InvalidUrl { .. } | Self::Client { .. } | Self::Cancelled => None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TargetUrlError {
+    #[error("no URL was given")]
+    Empty,
+
+    #[error("unsupported scheme \"{scheme}\" in {input}; only http and https pages can be scanned")]
+    UnsupportedScheme { input: String, scheme: String },
+
+    #[error("invalid URL {input}")]
+    Invalid {
+        input: String,
+        #[source]
+        source: url::ParseError,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("could not create directory {}", path.display())]
+    CreateDirectory {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not create HTTP client")]
+    Client {
+        #[source]
+        source: FetchError,
+    },
+
+    #[error("request failed")]
+    Request {
+        url: String,
+        #[source]
+        source: FetchError,
+    },
+
+    #[error("HTTP {status}")]
+    Status { url: String, status: u16 },
+
+    #[error("invalid data URL: {reason}")]
+    InvalidDataUrl { reason: &'static str },
+
+    #[error("failed to decode base64 font bytes")]
+    Base64 {
+        #[source]
+        source: base64::DecodeError,
+    },
+
+    #[error("response is not a font file; it looks like {detected}")]
+    NotAFont { detected: &'static str },
+
+    #[error("{} already exists", path.display())]
+    Exists { path: PathBuf },
+
+    #[error("failed writing file {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not set the modification time of {}", path.display())]
+    SetModified {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("output command failed for {} ({status}): {stderr}", path.display())]
+    Command {
+        path: PathBuf,
+        status: String,
+        stderr: String,
+    },
+
+    /// A failure read back from a serialized [`DownloadReport`](crate::download::DownloadReport),
+    /// which keeps only the message.
+    #[error("{message}")]
+    Recorded { message: String },
+}
+
+impl DownloadError {
+    /// Stable kebab-case name of the variant, for machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::CreateDirectory { .. } => "create-directory",
+            Self::Client { .. } => "client",
+            Self::Request { .. } => "request",
+            Self::Status { .. } => "status",
+            Self::InvalidDataUrl { .. } => "invalid-data-url",
+            Self::Base64 { .. } => "base64",
+            Self::NotAFont { .. } => "not-a-font",
+            Self::Exists { .. } => "exists",
+            Self::Write { .. } => "write",
+            Self::SetModified { .. } => "set-modified",
+            Self::Command { .. } => "command",
+            Self::Recorded { .. } => "recorded",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read config file {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("invalid config file {}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error("could not read lockfile {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("invalid lockfile {}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("lockfile {} has version {version}, which is newer than this typopotamus supports", path.display())]
+    Version { path: PathBuf, version: u32 },
+
+    #[error("could not write lockfile {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum InspectJsonError {
+    #[error("could not read inspect JSON {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("invalid inspect JSON")]
+    Parse {
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error(
+        "inspect JSON has schema version {version}, which is newer than this typopotamus supports"
+    )]
+    Version { version: u32 },
+
+    #[error(
+        "inspect JSON lists the {view} view, which leaves out most font fields; save it with `inspect --view font --format json`"
+    )]
+    View { view: &'static str },
+}
+
+#[derive(Debug, Error)]
+pub enum SelectionFileError {
+    #[error("could not read selection file {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error(
+        "line {line}: unknown selector \"{key}\"; expected all, family, font-name, font-url, index, id, min-weight, or max-weight"
+    )]
+    UnknownSelector { line: usize, key: String },
+
+    #[error("line {line}: invalid {key} \"{value}\"")]
+    InvalidValue {
+        line: usize,
+        key: String,
+        value: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum LocalScanError {
+    #[error("could not read directory {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("scan was cancelled")]
+    Cancelled,
+}
+
+#[derive(Debug, Error)]
+pub enum SpecimenError {
+    #[error("could not read saved font {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum FontsourceError {
+    #[error("Fontsource API request for {url} failed")]
+    Request {
+        url: String,
+        #[source]
+        source: FetchError,
+    },
+
+    #[error("Fontsource API returned HTTP {status} for {url}")]
+    Status { url: String, status: u16 },
+
+    #[error("unexpected Fontsource API response from {url}")]
+    Parse {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{name}}} in template \"{template}\"")]
+    UnknownPlaceholder { template: String, name: String },
+
+    #[error("unclosed placeholder in template \"{template}\"")]
+    Unclosed { template: String },
+}
+
+#[derive(Debug, Error)]
+pub enum SfntError {
+    #[error("{format} fonts cannot be rewritten")]
+    UnsupportedContainer { format: &'static str },
+
+    #[error("malformed {container} font")]
+    Malformed { container: &'static str },
+}
+
+#[derive(Debug, Error)]
+pub enum CompressError {
+    #[error("could not decode font")]
+    Decode {
+        #[source]
+        source: SfntError,
+    },
+
+    #[error("could not read saved font {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not write {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not remove original {}", path.display())]
+    Remove {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum SubsetError {
+    #[error("could not decode font")]
+    Decode {
+        #[source]
+        source: SfntError,
+    },
+
+    #[error("only TrueType outlines can be subset")]
+    UnsupportedOutlines,
+
+    #[error("malformed {table} table")]
+    Table { table: &'static str },
+
+    #[error("could not read saved font {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not write subset font {}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("could not fetch page text")]
+    Page {
+        #[source]
+        source: ExtractError,
+    },
+}
+
+#[derive(Debug, Error)]
+#[error("invalid rate \"{input}\"; expected bytes per second such as 500K or 2M")]
+pub struct RateLimitError {
+    pub input: String,
+}
+
+#[derive(Debug, Error)]
+#[error("invalid referer \"{input}\"; expected page, none, or custom:<http(s) URL>")]
+pub struct RefererPolicyError {
+    pub input: String,
+}
+
+#[derive(Debug, Error)]
+#[error("invalid S3 URL \"{input}\"; expected s3://bucket or s3://bucket/prefix")]
+pub struct S3UrlError {
+    pub input: String,
+}
+
+#[derive(Debug)]
+pub struct DownloadFailure {
+    pub name: String,
+    pub url: String,
+    pub error: DownloadError,
+}
+
+/// Serialized as `{ name, url, error }`, where `error` is the full
+/// [`error_chain`] message. Deserialized failures hold
+/// [`DownloadError::Recorded`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for DownloadFailure {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RecordedFailure {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            error: error_chain(&self.error),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DownloadFailure {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let failure = RecordedFailure::deserialize(deserializer)?;
+        Ok(Self {
+            name: failure.name,
+            url: failure.url,
+            error: DownloadError::Recorded {
+                message: failure.error,
+            },
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RecordedFailure {
+    name: String,
+    url: String,
+    error: String,
+}
+
+impl fmt::Display for DownloadFailure {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.url.is_empty() {
+            write!(formatter, "{}", error_chain(&self.error))
+        } else {
+            write!(
+                formatter,
+                "{} ({}) -> {}",
+                self.name,
+                self.url,
+                error_chain(&self.error)
+            )
+        }
+    }
+}
+
+pub fn error_chain(error: &dyn StdError) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+
+    message
+}
@@ -0,0 +1,414 @@
+use std::error::Error as StdError;
+use std::fmt;
+#[cfg(feature = "http")]
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::Arc;
+#[cfg(feature = "http")]
+use std::sync::Mutex;
+#[cfg(feature = "http")]
+use std::thread;
+#[cfg(feature = "http")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "http")]
+use reqwest::blocking::Client;
+
+use crate::error::RateLimitError;
+
+pub type FetchError = Box<dyn StdError + Send + Sync>;
+
+/// Request and response header names, so callers do not need an HTTP client
+/// crate to build a [`FetchRequest`].
+pub mod header {
+    pub const ACCEPT: &str = "accept";
+    pub const ACCESS_CONTROL_ALLOW_ORIGIN: &str = "access-control-allow-origin";
+    pub const CACHE_CONTROL: &str = "cache-control";
+    pub const CONTENT_LENGTH: &str = "content-length";
+    pub const CONTENT_RANGE: &str = "content-range";
+    pub const CONTENT_TYPE: &str = "content-type";
+    pub const ETAG: &str = "etag";
+    pub const EXPIRES: &str = "expires";
+    pub const LAST_MODIFIED: &str = "last-modified";
+    pub const ORIGIN: &str = "origin";
+    pub const RANGE: &str = "range";
+    pub const REFERER: &str = "referer";
+    pub const USER_AGENT: &str = "user-agent";
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FetchMethod {
+    #[default]
+    Get,
+    /// Headers only. Fetchers that cannot send `HEAD` may answer with a body.
+    Head,
+}
+
+impl FetchMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+        }
+    }
+}
+
+/// Told how much of a response body has arrived, with its `Content-Length`
+/// when the server sent one. Fetchers that read bodies in one go may never
+/// call it.
+#[derive(Clone)]
+pub struct BodyProgress(Arc<dyn Fn(u64, Option<u64>) + Send + Sync>);
+
+impl BodyProgress {
+    pub fn new(report: impl Fn(u64, Option<u64>) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, received: u64, content_length: Option<u64>) {
+        (self.0)(received, content_length);
+    }
+}
+
+impl fmt::Debug for BodyProgress {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("BodyProgress")
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FetchRequest {
+    pub method: FetchMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Stop reading the body after this many bytes and mark the response as
+    /// truncated, so one oversized resource cannot exhaust memory.
+    pub max_body_bytes: Option<u64>,
+    pub on_body: Option<BodyProgress>,
+}
+
+impl FetchRequest {
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: FetchMethod::Get,
+            url: url.into(),
+            headers: Vec::new(),
+            max_body_bytes: None,
+            on_body: None,
+        }
+    }
+
+    pub fn head(url: impl Into<String>) -> Self {
+        Self {
+            method: FetchMethod::Head,
+            ..Self::get(url)
+        }
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn max_body_bytes(mut self, limit: Option<u64>) -> Self {
+        self.max_body_bytes = limit;
+        self
+    }
+
+    pub fn on_body(mut self, progress: BodyProgress) -> Self {
+        self.on_body = Some(progress);
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// The body was cut off at the request's `max_body_bytes`.
+    pub truncated: bool,
+    /// Replayed by a [`Session`](crate::session::Session) without a request.
+    pub from_cache: bool,
+    /// Where redirects led, when that differs from the request's URL.
+    pub final_url: Option<String>,
+}
+
+impl FetchResponse {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            truncated: false,
+            from_cache: false,
+            final_url: None,
+        }
+    }
+
+    /// Cuts the body to `limit` bytes if it is longer, for fetchers that do
+    /// not stop reading on their own.
+    pub fn truncate_body(&mut self, limit: Option<u64>) {
+        if let Some(limit) = limit
+            && self.body.len() as u64 > limit
+        {
+            self.body.truncate(limit as usize);
+            self.truncated = true;
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+pub trait Fetcher: Send + Sync {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError>;
+}
+
+/// Transport settings for [`ReqwestFetcher`](crate::fetch::ReqwestFetcher).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HttpOptions {
+    /// Ask for gzip, brotli, or deflate encoded responses and decode them.
+    pub compression: bool,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self { compression: true }
+    }
+}
+
+/// A throughput cap in bytes per second, parsed from values such as `500K`,
+/// `2M` or `1048576` (suffixes are powers of 1024).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    bytes_per_second: u64,
+}
+
+impl RateLimit {
+    pub fn bytes_per_second(self) -> u64 {
+        self.bytes_per_second
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = RateLimitError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || RateLimitError {
+            input: input.to_owned(),
+        };
+        let trimmed = input.trim();
+        let (digits, multiplier) = match trimmed.char_indices().last().ok_or_else(invalid)? {
+            (index, 'k' | 'K') => (&trimmed[..index], 1024),
+            (index, 'm' | 'M') => (&trimmed[..index], 1024 * 1024),
+            (index, 'g' | 'G') => (&trimmed[..index], 1024 * 1024 * 1024),
+            _ => (trimmed, 1),
+        };
+
+        let value = digits.trim().parse::<u64>().map_err(|_| invalid())?;
+        match value.checked_mul(multiplier) {
+            Some(bytes_per_second) if bytes_per_second > 0 => Ok(Self { bytes_per_second }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} bytes/s", self.bytes_per_second)
+    }
+}
+
+/// Token accounting shared by every request made through one fetcher, so the
+/// cap applies to aggregate throughput rather than per response.
+#[cfg(feature = "http")]
+#[derive(Debug)]
+struct Throttle {
+    limit: RateLimit,
+    state: Mutex<(Instant, u64)>,
+}
+
+#[cfg(feature = "http")]
+impl Throttle {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    fn consume(&self, bytes: usize) {
+        let wait = {
+            let mut state = self
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let (started, consumed) = &mut *state;
+
+            // Forget idle time so a pause between files does not turn into a burst.
+            let allowed = started.elapsed().as_secs_f64() * self.limit.bytes_per_second as f64;
+            if allowed > *consumed as f64 + self.limit.bytes_per_second as f64 {
+                *started = Instant::now();
+                *consumed = 0;
+            }
+
+            *consumed += bytes as u64;
+            let due =
+                Duration::from_secs_f64(*consumed as f64 / self.limit.bytes_per_second as f64);
+            due.saturating_sub(started.elapsed())
+        };
+
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+#[derive(Clone, Debug)]
+pub struct ReqwestFetcher {
+    client: Client,
+    throttle: Option<Arc<Throttle>>,
+    timeout: Option<Duration>,
+}
+
+#[cfg(feature = "http")]
+impl ReqwestFetcher {
+    pub fn new(timeout: Duration) -> Result<Self, reqwest::Error> {
+        Self::with_options(timeout, &HttpOptions::default())
+    }
+
+    pub fn with_options(timeout: Duration, options: &HttpOptions) -> Result<Self, reqwest::Error> {
+        let mut builder = Client::builder()
+            .timeout(timeout)
+            .connect_timeout(Duration::from_secs(10));
+        if !options.compression {
+            builder = builder.no_gzip().no_brotli().no_deflate();
+        }
+        Ok(Self::from_client(builder.build()?))
+    }
+
+    pub fn from_client(client: Client) -> Self {
+        Self {
+            client,
+            throttle: None,
+            timeout: None,
+        }
+    }
+
+    /// Overrides the client's timeout for requests made through this fetcher.
+    /// Clones share the client and its connections, so a clone can use a
+    /// longer timeout without opening new ones.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Caps the combined body throughput of all requests made through this
+    /// fetcher and its clones.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.throttle = Some(Arc::new(Throttle::new(limit)));
+        self
+    }
+}
+
+#[cfg(feature = "http")]
+impl Fetcher for ReqwestFetcher {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+        let mut builder = match request.method {
+            FetchMethod::Get => self.client.get(&request.url),
+            FetchMethod::Head => self.client.head(&request.url),
+        };
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        let mut response = builder.send()?;
+        let status = response.status().as_u16();
+        let final_url =
+            Some(response.url().to_string()).filter(|final_url| *final_url != request.url);
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_owned(), value.to_owned()))
+            })
+            .collect();
+        let mut truncated = false;
+        let streamed = self.throttle.is_some()
+            || request.max_body_bytes.is_some()
+            || request.on_body.is_some();
+        let body = if streamed {
+            let limit = request.max_body_bytes.unwrap_or(u64::MAX);
+            let content_length = response.content_length();
+            let mut body = Vec::new();
+            let mut chunk = [0_u8; 16 * 1024];
+            loop {
+                let read = response.read(&mut chunk)?;
+                if read == 0 {
+                    break;
+                }
+                if let Some(throttle) = &self.throttle {
+                    throttle.consume(read);
+                }
+                let room = limit.saturating_sub(body.len() as u64);
+                if read as u64 > room {
+                    body.extend_from_slice(&chunk[..room as usize]);
+                    truncated = true;
+                    break;
+                }
+                body.extend_from_slice(&chunk[..read]);
+                if let Some(progress) = &request.on_body {
+                    progress.report(body.len() as u64, content_length);
+                }
+            }
+            body
+        } else {
+            response.bytes()?.to_vec()
+        };
+
+        Ok(FetchResponse {
+            status,
+            headers,
+            body,
+            truncated,
+            from_cache: false,
+            final_url,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimit;
+
+    #[test]
+    fn rate_limits_parse_binary_suffixes() {
+        let parse = |input: &str| input.parse::<RateLimit>().map(RateLimit::bytes_per_second);
+
+        assert_eq!(parse("500K").unwrap(), 500 * 1024);
+        assert_eq!(parse("2m").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse("4096").unwrap(), 4096);
+        assert!(parse("0").is_err());
+        assert!(parse("fast").is_err());
+        assert!(parse("").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+
+use crate::model::{FaceSource, FontInfo, group_faces};
+use crate::selection::canonical_format;
+
+/// Characters escaped in each segment of a [`relative_href`], so names with
+/// spaces, `#`, or `?` still resolve to the file.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'\'')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A saved font and the URL a generated stylesheet should load it from.
+#[derive(Clone, Copy, Debug)]
+pub struct LocalFont<'a> {
+    pub font: &'a FontInfo,
+    pub href: &'a str,
+}
+
+/// `@font-face` rules for locally saved fonts: one rule per family, weight,
+/// and style, listing every format in `src` with the best first. Metric
+/// overrides declared on any of a face's files are carried over.
+pub fn font_face_css(fonts: &[LocalFont<'_>]) -> String {
+    let mut families = BTreeMap::<&str, Vec<usize>>::new();
+    for (index, local) in fonts.iter().enumerate() {
+        families.entry(&local.font.family).or_default().push(index);
+    }
+
+    let mut css = String::new();
+    for (family, indices) in families {
+        let faces = group_faces(indices.iter().map(|index| {
+            let font = fonts[*index].font;
+            FaceSource {
+                index: *index,
+                weight: &font.weight,
+                style: &font.style,
+                format: &font.format,
+            }
+        }));
+
+        for face in faces {
+            let src = face
+                .font_indices
+                .iter()
+                .map(|index| {
+                    let local = fonts[*index];
+                    match css_format_hint(&local.font.format) {
+                        Some(hint) => {
+                            format!("url(\"{}\") format(\"{hint}\")", css_string(local.href))
+                        }
+                        None => format!("url(\"{}\")", css_string(local.href)),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let overrides = face
+                .font_indices
+                .iter()
+                .map(|index| &fonts[*index].font.metric_overrides)
+                .find(|overrides| !overrides.is_empty())
+                .map(|overrides| {
+                    overrides
+                        .declarations()
+                        .filter_map(|(name, value)| {
+                            Some(format!("  {name}: {};\n", css_value(value)?))
+                        })
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+            // Descriptors copied from the site are dropped rather than
+            // written when they could break out of the rule.
+            let variant = [("font-weight", &face.weight), ("font-style", &face.style)]
+                .into_iter()
+                .filter_map(|(name, value)| Some(format!("  {name}: {};\n", css_value(value)?)))
+                .collect::<String>();
+            css.push_str(&format!(
+                "@font-face {{\n  font-family: \"{}\";\n  src: {src};\n{variant}  font-display: swap;\n{overrides}}}\n\n",
+                css_string(family),
+            ));
+        }
+    }
+
+    css
+}
+
+/// The `format()` keyword for a [`FontInfo::format`] label.
+pub fn css_format_hint(format: &str) -> Option<&'static str> {
+    match canonical_format(format) {
+        "WOFF2" => Some("woff2"),
+        "WOFF" => Some("woff"),
+        "TRUETYPE" => Some("truetype"),
+        "OPENTYPE" => Some("opentype"),
+        "EOT" => Some("embedded-opentype"),
+        "SVG" => Some("svg"),
+        _ => None,
+    }
+}
+
+/// A relative URL from a stylesheet in `from_dir` to `to`, e.g.
+/// `../../assets/fonts/inter/inter.woff2`, with each segment percent-encoded.
+/// Both paths should be absolute, or relative to the same directory.
+pub fn relative_href(from_dir: &Path, to: &Path) -> String {
+    let from = lexical_components(from_dir);
+    let to = lexical_components(to);
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(left, right)| left == right)
+        .count();
+
+    std::iter::repeat_n("..".to_owned(), from.len() - common)
+        .chain(
+            to[common..]
+                .iter()
+                .map(|part| utf8_percent_encode(&part.to_string_lossy(), PATH_SEGMENT).to_string()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn lexical_components(path: &Path) -> Vec<PathBuf> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                components.pop();
+            }
+            other => components.push(PathBuf::from(other.as_os_str())),
+        }
+    }
+    components
+}
+
+/// `value` trimmed when it holds only keywords, numbers, and units, such as
+/// `oblique 10deg` or `107.4%`, and so cannot end the declaration.
+fn css_value(value: &str) -> Option<&str> {
+    let value = value.trim();
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '.' | '%' | '+' | '-'));
+    safe.then_some(value)
+}
+
+/// Escapes a value for a double-quoted CSS string.
+pub(crate) fn css_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{LocalFont, font_face_css, relative_href};
+    use crate::model::{FontInfo, MetricOverrides};
+
+    fn font(format: &str, weight: &str) -> FontInfo {
+        FontInfo {
+            name: format!("brand-{weight}.{}", format.to_ascii_lowercase()),
+            family: "Brand \"Sans\"".to_owned(),
+            format: format.to_owned(),
+            url: format!("https://cdn.example/brand-{weight}"),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn groups_formats_of_a_face_into_one_rule() {
+        let mut fonts = [
+            font("WOFF", "400"),
+            font("WOFF2", "400"),
+            font("TRUETYPE", "700"),
+        ];
+        fonts[1].metric_overrides = MetricOverrides {
+            ascent_override: Some("90%".to_owned()),
+            size_adjust: Some("107.4%".to_owned()),
+            ..MetricOverrides::default()
+        };
+        let hrefs = ["fonts/a.woff", "fonts/a.woff2", "fonts/b.ttf"];
+        let local = fonts
+            .iter()
+            .zip(hrefs)
+            .map(|(font, href)| LocalFont { font, href })
+            .collect::<Vec<_>>();
+
+        let css = font_face_css(&local);
+
+        assert_eq!(css.matches("@font-face").count(), 2);
+        assert!(css.contains("font-family: \"Brand \\\"Sans\\\"\";"));
+        assert!(css.contains(
+            "src: url(\"fonts/a.woff2\") format(\"woff2\"), url(\"fonts/a.woff\") format(\"woff\");"
+        ));
+        assert!(
+            css.contains("src: url(\"fonts/b.ttf\") format(\"truetype\");\n  font-weight: 700;")
+        );
+        assert!(
+            css.contains("font-display: swap;\n  ascent-override: 90%;\n  size-adjust: 107.4%;\n}")
+        );
+        assert_eq!(css.matches("ascent-override").count(), 1);
+    }
+
+    #[test]
+    fn descriptors_that_could_end_the_rule_are_dropped() {
+        let mut injected = font("WOFF2", "400;} body { display: none");
+        injected.style = "oblique 10deg".to_owned();
+        let local = [LocalFont {
+            font: &injected,
+            href: "fonts/a.woff2",
+        }];
+
+        let css = font_face_css(&local);
+
+        assert!(!css.contains("font-weight"));
+        assert!(!css.contains("display: none"));
+        assert!(css.contains("  font-style: oblique 10deg;\n"));
+    }
+
+    #[test]
+    fn relative_href_walks_up_to_the_common_directory() {
+        assert_eq!(
+            relative_href(
+                Path::new("/app/src/styles"),
+                Path::new("/app/assets/fonts/inter/inter.woff2")
+            ),
+            "../../assets/fonts/inter/inter.woff2"
+        );
+        assert_eq!(
+            relative_href(Path::new("public/./css"), Path::new("public/fonts/a.woff2")),
+            "../fonts/a.woff2"
+        );
+        assert_eq!(
+            relative_href(Path::new("out"), Path::new("out/brand/a.woff2")),
+            "brand/a.woff2"
+        );
+        assert_eq!(
+            relative_href(Path::new("out"), Path::new("out/Brand Sans/a#1.woff2")),
+            "Brand%20Sans/a%231.woff2"
+        );
+    }
+}
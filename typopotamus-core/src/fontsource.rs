@@ -0,0 +1,264 @@
+use serde::Deserialize;
+
+use crate::error::FontsourceError;
+use crate::fetch::header::{ACCEPT, USER_AGENT};
+use crate::fetch::{FetchRequest, Fetcher};
+
+/// Base URL of the Fontsource metadata API.
+pub const FONTSOURCE_API: &str = "https://api.fontsource.org/v1/fonts";
+
+const W100_900: &[u16] = &[100, 200, 300, 400, 500, 600, 700, 800, 900];
+const W100_800: &[u16] = &[100, 200, 300, 400, 500, 600, 700, 800];
+const W100_700: &[u16] = &[100, 200, 300, 400, 500, 600, 700];
+const W200_900: &[u16] = &[200, 300, 400, 500, 600, 700, 800, 900];
+const W200_800: &[u16] = &[200, 300, 400, 500, 600, 700, 800];
+const W200_700: &[u16] = &[200, 300, 400, 500, 600, 700];
+const W300_900: &[u16] = &[300, 400, 500, 600, 700, 800, 900];
+const W300_800: &[u16] = &[300, 400, 500, 600, 700, 800];
+const W300_700: &[u16] = &[300, 400, 500, 600, 700];
+const W400_900: &[u16] = &[400, 500, 600, 700, 800, 900];
+const W400_800: &[u16] = &[400, 500, 600, 700, 800];
+const W400_700: &[u16] = &[400, 500, 600, 700];
+const W400: &[u16] = &[400];
+const W400_700_ONLY: &[u16] = &[400, 700];
+
+/// `(family, weights, has italics, has a variable build)` for popular
+/// Fontsource packages, so suggestions work offline.
+const BUNDLED_INDEX: &[(&str, &[u16], bool, bool)] = &[
+    ("Abril Fatface", W400, false, false),
+    ("Archivo", W100_900, true, true),
+    ("Arimo", W400_700, true, true),
+    ("Barlow", W100_900, true, false),
+    ("Bebas Neue", W400, false, false),
+    ("Bitter", W100_900, true, true),
+    ("Cabin", W400_700, true, true),
+    ("Cormorant Garamond", W300_700, true, false),
+    ("Crimson Text", &[400, 600, 700], true, false),
+    ("Dancing Script", W400_700, false, true),
+    ("DM Sans", W100_900, true, true),
+    ("EB Garamond", W400_800, true, true),
+    ("Figtree", W300_900, true, true),
+    ("Fira Code", W300_700, false, true),
+    ("Fira Sans", W100_900, true, false),
+    ("Heebo", W100_900, false, true),
+    ("Hind", W300_700, false, false),
+    ("IBM Plex Mono", W100_700, true, false),
+    ("IBM Plex Sans", W100_700, true, false),
+    ("IBM Plex Serif", W100_700, true, false),
+    ("Inter", W100_900, true, true),
+    ("JetBrains Mono", W100_800, true, true),
+    ("Josefin Sans", W100_700, true, true),
+    ("Karla", W200_800, true, true),
+    ("Lato", &[100, 300, 400, 700, 900], true, false),
+    ("Lexend", W100_900, false, true),
+    ("Libre Baskerville", W400_700_ONLY, true, false),
+    ("Libre Franklin", W100_900, true, true),
+    ("Lora", W400_700, true, true),
+    ("Manrope", W200_800, false, true),
+    ("Merriweather", &[300, 400, 700, 900], true, false),
+    ("Montserrat", W100_900, true, true),
+    ("Mulish", W200_900, true, true),
+    ("Noto Sans", W100_900, true, true),
+    ("Noto Serif", W100_900, true, true),
+    ("Nunito", W200_900, true, true),
+    ("Open Sans", W300_800, true, true),
+    ("Oswald", W200_700, false, true),
+    ("Outfit", W100_900, false, true),
+    ("Pacifico", W400, false, false),
+    ("Playfair Display", W400_900, true, true),
+    ("Plus Jakarta Sans", W200_800, true, true),
+    ("Poppins", W100_900, true, false),
+    ("PT Sans", W400_700_ONLY, true, false),
+    ("PT Serif", W400_700_ONLY, true, false),
+    ("Quicksand", W300_700, false, true),
+    ("Raleway", W100_900, true, true),
+    ("Roboto", W100_900, true, true),
+    ("Roboto Mono", W100_700, true, true),
+    ("Roboto Slab", W100_900, false, true),
+    ("Rubik", W300_900, true, true),
+    ("Sora", W100_800, false, true),
+    ("Source Code Pro", W200_900, true, true),
+    ("Source Sans 3", W200_900, true, true),
+    ("Source Serif 4", W200_900, true, true),
+    ("Space Grotesk", W300_700, false, true),
+    ("Space Mono", W400_700_ONLY, true, false),
+    (
+        "Titillium Web",
+        &[200, 300, 400, 600, 700, 900],
+        true,
+        false,
+    ),
+    ("Ubuntu", &[300, 400, 500, 700], true, false),
+    ("Work Sans", W100_900, true, true),
+];
+
+/// A Fontsource package that provides a family.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FontsourcePackage {
+    /// Fontsource font id, e.g. `open-sans`.
+    pub id: String,
+    pub family: String,
+    pub weights: Vec<u16>,
+    pub styles: Vec<String>,
+    pub variable: bool,
+}
+
+impl FontsourcePackage {
+    /// npm package with static weights, e.g. `@fontsource/open-sans`.
+    pub fn npm_package(&self) -> String {
+        format!("@fontsource/{}", self.id)
+    }
+
+    /// npm package with the variable build, when there is one.
+    pub fn variable_npm_package(&self) -> Option<String> {
+        self.variable
+            .then(|| format!("@fontsource-variable/{}", self.id))
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiFont {
+    id: String,
+    family: String,
+    #[serde(default)]
+    weights: Vec<u16>,
+    #[serde(default)]
+    styles: Vec<String>,
+    #[serde(default)]
+    variable: bool,
+}
+
+/// The id Fontsource derives from a family name: lowercase words joined by
+/// `-`, e.g. `Source Sans 3` -> `source-sans-3`.
+pub fn fontsource_id(family: &str) -> String {
+    family
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Looks `family` up in the bundled index of popular packages.
+pub fn bundled_package(family: &str) -> Option<FontsourcePackage> {
+    let id = fontsource_id(family);
+    BUNDLED_INDEX
+        .iter()
+        .find(|(name, ..)| fontsource_id(name) == id)
+        .map(|(name, weights, italic, variable)| FontsourcePackage {
+            id,
+            family: (*name).to_owned(),
+            weights: weights.to_vec(),
+            styles: if *italic {
+                vec!["italic".to_owned(), "normal".to_owned()]
+            } else {
+                vec!["normal".to_owned()]
+            },
+            variable: *variable,
+        })
+}
+
+/// Asks the Fontsource API for `family`. Returns `Ok(None)` when Fontsource
+/// has no such font.
+pub fn fetch_package(
+    fetcher: &dyn Fetcher,
+    family: &str,
+) -> Result<Option<FontsourcePackage>, FontsourceError> {
+    let id = fontsource_id(family);
+    if id.is_empty() {
+        return Ok(None);
+    }
+
+    let url = format!("{FONTSOURCE_API}/{id}");
+    let request = FetchRequest::get(&url)
+        .header(
+            USER_AGENT,
+            concat!("typopotamus/", env!("CARGO_PKG_VERSION")),
+        )
+        .header(ACCEPT, "application/json");
+    let response = fetcher
+        .fetch(&request)
+        .map_err(|source| FontsourceError::Request {
+            url: url.clone(),
+            source,
+        })?;
+    if response.status == 404 {
+        return Ok(None);
+    }
+    if !response.is_success() {
+        return Err(FontsourceError::Status {
+            url,
+            status: response.status,
+        });
+    }
+
+    let font = serde_json::from_slice::<ApiFont>(&response.body)
+        .map_err(|source| FontsourceError::Parse { url, source })?;
+    Ok(Some(FontsourcePackage {
+        id: font.id,
+        family: font.family,
+        weights: font.weights,
+        styles: font.styles,
+        variable: font.variable,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bundled_package, fetch_package, fontsource_id};
+    use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+
+    struct ApiFetcher;
+
+    impl Fetcher for ApiFetcher {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            Ok(match request.url.as_str() {
+                "https://api.fontsource.org/v1/fonts/instrument-sans" => FetchResponse::ok(
+                    r#"{"id":"instrument-sans","family":"Instrument Sans","subsets":["latin"],
+                        "weights":[400,500,600,700],"styles":["italic","normal"],
+                        "variable":true,"type":"google"}"#,
+                ),
+                _ => FetchResponse {
+                    status: 404,
+                    ..FetchResponse::default()
+                },
+            })
+        }
+    }
+
+    #[test]
+    fn derives_fontsource_ids_from_family_names() {
+        assert_eq!(fontsource_id("Source Sans 3"), "source-sans-3");
+        assert_eq!(fontsource_id("  IBM Plex  Mono "), "ibm-plex-mono");
+        assert_eq!(fontsource_id("ＭＳ Gothic"), "gothic");
+    }
+
+    #[test]
+    fn finds_bundled_packages_case_insensitively() {
+        let package = bundled_package("open sans").expect("Open Sans is bundled");
+        assert_eq!(package.family, "Open Sans");
+        assert_eq!(package.npm_package(), "@fontsource/open-sans");
+        assert_eq!(
+            package.variable_npm_package().as_deref(),
+            Some("@fontsource-variable/open-sans")
+        );
+        assert_eq!(package.weights.first(), Some(&300));
+
+        assert!(bundled_package("Brand Sans").is_none());
+    }
+
+    #[test]
+    fn reads_packages_from_the_api() {
+        let package = fetch_package(&ApiFetcher, "Instrument Sans")
+            .expect("request should succeed")
+            .expect("package should exist");
+        assert_eq!(package.weights, vec![400, 500, 600, 700]);
+        assert!(package.variable);
+
+        assert!(
+            fetch_package(&ApiFetcher, "Brand Sans")
+                .expect("request should succeed")
+                .is_none()
+        );
+    }
+}
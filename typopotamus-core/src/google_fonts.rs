@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+
+static POPULAR_FAMILIES: &str = include_str!("../data/google-fonts-popular-families.txt");
+static FAMILIES: Lazy<HashMap<String, &'static str>> = Lazy::new(|| {
+    POPULAR_FAMILIES
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|family| (match_key(family), family))
+        .collect()
+});
+
+/// The Google Fonts family named like `family`, ignoring case, spaces, and
+/// punctuation, so a self-hosted `OpenSans` matches `Open Sans`. Only the
+/// bundled list of popular families is consulted, not the full catalog.
+pub fn google_fonts_family(family: &str) -> Option<&'static str> {
+    FAMILIES.get(&match_key(family)).copied()
+}
+
+/// The family's page on fonts.google.com.
+pub fn specimen_url(family: &str) -> String {
+    format!(
+        "https://fonts.google.com/specimen/{}",
+        family.split_whitespace().collect::<Vec<_>>().join("+")
+    )
+}
+
+fn match_key(family: &str) -> String {
+    family
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|character| character.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{google_fonts_family, specimen_url};
+
+    #[test]
+    fn matches_popular_family_names_loosely() {
+        assert_eq!(google_fonts_family("OpenSans"), Some("Open Sans"));
+        assert_eq!(google_fonts_family("source-sans-3"), Some("Source Sans 3"));
+        assert_eq!(google_fonts_family("Brand Grotesk"), None);
+        assert_eq!(
+            specimen_url("IBM Plex Sans"),
+            "https://fonts.google.com/specimen/IBM+Plex+Sans"
+        );
+    }
+}
@@ -1,8 +1,17 @@
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use crate::model::{FontFamily, FontInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::binary::FontMetadata;
+use crate::model::{
+    FaceSource, FontFace, FontFamily, FontInfo, FontSource, FontUsageStatus, SourceLocation,
+    group_faces,
+};
+use crate::provider::{FontProvider, host_of};
+use crate::selection::{MatchMode, SelectorKind, SelectorMatch};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InferredFontEntry {
     pub index: usize,
     pub name: String,
@@ -12,13 +21,37 @@ pub struct InferredFontEntry {
     pub format: String,
     pub url: String,
     pub referer: String,
+    pub sources: Vec<FontSource>,
+    pub source_stylesheet: String,
+    pub source_location: Option<SourceLocation>,
+    pub conditions: Vec<String>,
+    pub used_by: Vec<String>,
+    pub usage: Option<FontUsageStatus>,
+    pub found_on: Vec<String>,
+    pub provider: FontProvider,
+}
+
+/// A weight/style of an inferred family with all of its format sources,
+/// best format first.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InferredFace {
+    pub weight: String,
+    pub style: String,
+    pub formats: Vec<String>,
+    pub font_indices: Vec<usize>,
+    pub fonts: Vec<InferredFontEntry>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InferredFamilyGroup {
     pub key: String,
     pub name: String,
+    /// Declared families of the files, plus the names of groups merged in.
     pub aliases: Vec<String>,
+    /// Near-duplicate groups from other hosts that were folded into this one.
+    pub merged: Vec<FamilyMerge>,
     pub files: usize,
     pub variants: usize,
     pub weights: Vec<String>,
@@ -26,28 +59,252 @@ pub struct InferredFamilyGroup {
     pub formats: Vec<String>,
     pub font_indices: Vec<usize>,
     pub index_ranges: Vec<String>,
+    /// Selectors that use any of the family's source names.
+    pub used_by: Vec<String>,
+    /// The strongest usage among the family's files.
+    pub usage: Option<FontUsageStatus>,
+    /// Scanned pages that reference any of the family's files.
+    pub found_on: Vec<String>,
+    pub providers: Vec<FontProvider>,
+    pub faces: Vec<InferredFace>,
     pub fonts: Vec<InferredFontEntry>,
 }
 
-#[derive(Debug)]
-struct FamilyFingerprint {
-    key: String,
-    display: String,
-    weight_hint: Option<String>,
-    style_hint: Option<String>,
+/// The axis fonts are grouped on, e.g. by `infer_family_groups_by`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    /// The family inferred from names, aliases, and binary metadata.
+    #[default]
+    #[serde(rename = "family")]
+    InferredFamily,
+    /// The `font-family` declared in CSS, as written.
+    CssFamily,
+    Format,
+    Provider,
+    /// One group per font, in font order.
+    None,
+}
+
+impl GroupBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InferredFamily => "family",
+            Self::CssFamily => "css-family",
+            Self::Format => "format",
+            Self::Provider => "provider",
+            Self::None => "none",
+        }
+    }
+
+    /// Key and display name of the group `font` belongs to.
+    fn group(
+        self,
+        index: usize,
+        font: &FontInfo,
+        fingerprint: &FamilyFingerprint,
+    ) -> (String, String) {
+        match self {
+            Self::InferredFamily => (fingerprint.key.clone(), fingerprint.display.clone()),
+            Self::CssFamily => (normalize(&font.family), font.family.trim().to_owned()),
+            Self::Format => {
+                let format = font.format.trim().to_ascii_uppercase();
+                (format.to_ascii_lowercase(), format)
+            }
+            Self::Provider => {
+                let provider = FontProvider::for_font(font).as_str();
+                (provider.to_owned(), provider.to_owned())
+            }
+            Self::None => (index.to_string(), font.name.clone()),
+        }
+    }
+}
+
+/// Why a group from another host was folded into a family.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum MergeReason {
+    /// The names are nearly identical; the files were not compared.
+    SimilarName,
+    /// The names are nearly identical and both hosts serve an identical file.
+    SameContent,
+}
+
+impl MergeReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SimilarName => "similar-name",
+            Self::SameContent => "same-content",
+        }
+    }
+}
+
+/// A group folded into another family by the cross-host merge pass.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FamilyMerge {
+    /// Name the group would have had on its own.
+    pub name: String,
+    pub hosts: Vec<String>,
+    pub reason: MergeReason,
+}
+
+/// Extra naming rules layered on top of the built-in token tables.
+///
+/// Token keys are matched against lowercase tokens produced by
+/// [`tokenize_source`], so they should be lowercase single words.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InferenceOptions {
+    /// Additional weight words, e.g. `book` -> 400 or `demi` -> 600.
+    pub weight_synonyms: BTreeMap<String, u16>,
+    /// Additional style words mapped to a CSS style, e.g. `kursiv` -> `italic`.
+    pub style_tokens: BTreeMap<String, String>,
+    /// Tokens dropped from family names wherever they appear, e.g. `webfont`.
+    pub strip_tokens: BTreeSet<String>,
+    /// Display families forced onto fonts whose declared family, file name,
+    /// or URL matches a pattern, e.g. `brandfont-v7-*` -> `Acme Sans`. `*`
+    /// matches any run of characters and case is ignored.
+    pub family_aliases: BTreeMap<String, String>,
 }
 
+impl InferenceOptions {
+    /// Copy of the options with every token key lowercased and trimmed.
+    pub fn normalized(self) -> Self {
+        Self {
+            weight_synonyms: self
+                .weight_synonyms
+                .into_iter()
+                .map(|(token, weight)| (normalize(&token), weight))
+                .collect(),
+            style_tokens: self
+                .style_tokens
+                .into_iter()
+                .map(|(token, style)| (normalize(&token), style))
+                .collect(),
+            strip_tokens: self
+                .strip_tokens
+                .into_iter()
+                .map(|token| normalize(&token))
+                .collect(),
+            family_aliases: self
+                .family_aliases
+                .into_iter()
+                .map(|(pattern, family)| (normalize(&pattern), family.trim().to_owned()))
+                .collect(),
+        }
+    }
+
+    /// The display family the first matching alias forces onto `font`.
+    fn family_alias(&self, font: &FontInfo) -> Option<&str> {
+        let candidates = [&font.family, &font.name, &font.url].map(|value| normalize(value));
+        self.family_aliases
+            .iter()
+            .find(|(pattern, _)| {
+                candidates
+                    .iter()
+                    .any(|candidate| matches_pattern(pattern, candidate))
+            })
+            .map(|(_, family)| family.as_str())
+    }
+}
+
+/// The configured [`InferenceOptions`] plus what one run learned from the
+/// font files themselves.
+#[derive(Clone, Debug, Default)]
+pub struct Inference {
+    pub options: InferenceOptions,
+    /// Metadata read from font binaries, keyed by font URL. Fonts with an
+    /// entry are grouped by the family, weight, and style in their `name`
+    /// and `OS/2` tables instead of by file name heuristics.
+    pub binary_metadata: HashMap<String, FontMetadata>,
+    /// SHA-256 digests of font files, keyed by font URL. When both sides of
+    /// a cross-host merge have digests, they must share one to be merged.
+    pub content_hashes: HashMap<String, String>,
+}
+
+impl Inference {
+    /// Switches inference to binary-verified mode for the probed fonts.
+    pub fn with_binary_metadata(mut self, metadata: HashMap<String, FontMetadata>) -> Self {
+        self.binary_metadata = metadata;
+        self
+    }
+
+    /// Confirms cross-host merges with the digests of the downloaded files.
+    pub fn with_content_hashes(mut self, hashes: HashMap<String, String>) -> Self {
+        self.content_hashes = hashes;
+        self
+    }
+}
+
+impl From<InferenceOptions> for Inference {
+    fn from(options: InferenceOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+}
+
+/// Family identity inferred from a font's declared family and file name.
+///
+/// `key` is the lowercase token string used for grouping, `display` the
+/// title-cased name shown to users. Weight and style hints are taken from
+/// trailing tokens such as `bold` or `italic` that were stripped from the key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FamilyFingerprint {
+    pub key: String,
+    pub display: String,
+    pub weight_hint: Option<String>,
+    pub style_hint: Option<String>,
+    /// Whether the fingerprint came from the font binary, in which case the
+    /// hints take precedence over the declared CSS descriptors.
+    pub from_binary: bool,
+    /// Whether the family was forced by
+    /// [`family_aliases`](InferenceOptions::family_aliases).
+    pub from_alias: bool,
+}
+
+impl FamilyFingerprint {
+    /// Weight for `font`, preferring the declared weight over the name hint.
+    pub fn effective_weight(&self, font: &FontInfo) -> String {
+        match (&self.weight_hint, self.from_binary) {
+            (Some(weight), true) => weight.clone(),
+            _ => effective_weight(font, self.weight_hint.as_deref()),
+        }
+    }
+
+    /// Style for `font`, preferring the declared style over the name hint.
+    pub fn effective_style(&self, font: &FontInfo) -> String {
+        match (&self.style_hint, self.from_binary) {
+            (Some(style), true) => style.clone(),
+            _ => effective_style(font, self.style_hint.as_deref()),
+        }
+    }
+}
+
+/// Key of fonts whose family and file name leave no usable tokens.
+const UNKNOWN_FAMILY_KEY: &str = "unknown";
+
 #[derive(Debug)]
 struct FamilyAccumulator {
     key: String,
     name: String,
     aliases: BTreeSet<String>,
+    merged: Vec<FamilyMerge>,
+    hosts: BTreeSet<String>,
     files: usize,
     variant_keys: BTreeSet<String>,
     weights: BTreeSet<String>,
     styles: BTreeSet<String>,
     formats: BTreeSet<String>,
     indices: Vec<usize>,
+    used_by: BTreeSet<String>,
+    usage: Option<FontUsageStatus>,
+    found_on: BTreeSet<String>,
+    providers: BTreeSet<FontProvider>,
     fonts: Vec<InferredFontEntry>,
 }
 
@@ -57,12 +314,18 @@ impl FamilyAccumulator {
             key,
             name,
             aliases: BTreeSet::new(),
+            merged: Vec::new(),
+            hosts: BTreeSet::new(),
             files: 0,
             variant_keys: BTreeSet::new(),
             weights: BTreeSet::new(),
             styles: BTreeSet::new(),
             formats: BTreeSet::new(),
             indices: Vec::new(),
+            used_by: BTreeSet::new(),
+            usage: None,
+            found_on: BTreeSet::new(),
+            providers: BTreeSet::new(),
             fonts: Vec::new(),
         }
     }
@@ -71,11 +334,13 @@ impl FamilyAccumulator {
         self.indices.sort_unstable();
         self.fonts.sort_by_key(|font| font.index);
         let index_ranges = to_index_ranges(&self.indices);
+        let faces = self.faces();
 
         InferredFamilyGroup {
             key: self.key,
             name: self.name,
             aliases: self.aliases.into_iter().collect(),
+            merged: self.merged,
             files: self.files,
             variants: self.variant_keys.len(),
             weights: self.weights.into_iter().collect(),
@@ -83,19 +348,137 @@ impl FamilyAccumulator {
             formats: self.formats.into_iter().collect(),
             font_indices: self.indices,
             index_ranges,
+            used_by: self.used_by.into_iter().collect(),
+            usage: self.usage,
+            found_on: self.found_on.into_iter().collect(),
+            providers: self.providers.into_iter().collect(),
+            faces,
             fonts: self.fonts,
         }
     }
+
+    /// Folds `other` into this family, keeping this family's name.
+    fn absorb(&mut self, other: Self, reason: MergeReason) {
+        self.aliases.insert(other.name.clone());
+        self.aliases.extend(other.aliases);
+        self.merged.push(FamilyMerge {
+            name: other.name,
+            hosts: other.hosts.iter().cloned().collect(),
+            reason,
+        });
+        self.merged.extend(other.merged);
+        self.hosts.extend(other.hosts);
+        self.files += other.files;
+        self.variant_keys.extend(other.variant_keys);
+        self.weights.extend(other.weights);
+        self.styles.extend(other.styles);
+        self.formats.extend(other.formats);
+        self.indices.extend(other.indices);
+        self.used_by.extend(other.used_by);
+        self.usage = self.usage.max(other.usage);
+        self.found_on.extend(other.found_on);
+        self.providers.extend(other.providers);
+        self.fonts.extend(other.fonts);
+    }
+
+    /// Whether `other` looks like the same family served from another host,
+    /// and why. Families sharing a host are never merged.
+    fn merge_reason(&self, other: &Self, inference: &Inference) -> Option<MergeReason> {
+        if self.key == UNKNOWN_FAMILY_KEY
+            || other.key == UNKNOWN_FAMILY_KEY
+            || self.hosts.is_empty()
+            || other.hosts.is_empty()
+            || !self.hosts.is_disjoint(&other.hosts)
+            || !similar_family_keys(&self.key, &other.key)
+        {
+            return None;
+        }
+
+        let digests = |family: &Self| {
+            family
+                .fonts
+                .iter()
+                .filter_map(|font| inference.content_hashes.get(&font.url))
+                .collect::<HashSet<_>>()
+        };
+        let (ours, theirs) = (digests(self), digests(other));
+        if ours.is_empty() || theirs.is_empty() {
+            Some(MergeReason::SimilarName)
+        } else if ours.is_disjoint(&theirs) {
+            None
+        } else {
+            Some(MergeReason::SameContent)
+        }
+    }
+
+    fn faces(&self) -> Vec<InferredFace> {
+        let by_index = self
+            .fonts
+            .iter()
+            .map(|font| (font.index, font))
+            .collect::<HashMap<_, _>>();
+
+        group_faces(self.fonts.iter().map(|font| FaceSource {
+            index: font.index,
+            weight: &font.weight,
+            style: &font.style,
+            format: &font.format,
+        }))
+        .into_iter()
+        .map(|face| {
+            let fonts = face
+                .font_indices
+                .iter()
+                .filter_map(|index| by_index.get(index).map(|font| (*font).clone()))
+                .collect::<Vec<_>>();
+            let mut formats = Vec::new();
+            for font in &fonts {
+                let format = font.format.to_ascii_uppercase();
+                if !formats.contains(&format) {
+                    formats.push(format);
+                }
+            }
+
+            InferredFace {
+                weight: face.weight,
+                style: face.style,
+                formats,
+                font_indices: face.font_indices,
+                fonts,
+            }
+        })
+        .collect()
+    }
 }
 
-pub fn infer_family_groups_all(fonts: &[FontInfo]) -> Vec<InferredFamilyGroup> {
+/// Groups every font in `fonts` by inferred family.
+pub fn infer_family_groups_all(
+    fonts: &[FontInfo],
+    inference: &Inference,
+) -> Vec<InferredFamilyGroup> {
     let all_indices = (0..fonts.len()).collect::<Vec<_>>();
-    infer_family_groups(fonts, &all_indices)
+    infer_family_groups(fonts, &all_indices, inference)
 }
 
+/// Groups the fonts at `selected_indices` by inferred family, sorted by name.
+///
+/// Out-of-range and duplicate indices are ignored.
 pub fn infer_family_groups(
     fonts: &[FontInfo],
     selected_indices: &[usize],
+    inference: &Inference,
+) -> Vec<InferredFamilyGroup> {
+    infer_family_groups_by(fonts, selected_indices, inference, GroupBy::InferredFamily)
+}
+
+/// [`infer_family_groups`] on another axis. Weights and styles are still
+/// inferred, but only inferred families are merged across hosts, and
+/// [`GroupBy::None`] keeps the fonts in order instead of sorting by name.
+pub fn infer_family_groups_by(
+    fonts: &[FontInfo],
+    selected_indices: &[usize],
+    inference: &Inference,
+    group_by: GroupBy,
 ) -> Vec<InferredFamilyGroup> {
     let mut unique_indices: Vec<usize> = selected_indices
         .iter()
@@ -110,15 +493,17 @@ pub fn infer_family_groups(
 
     for index in unique_indices {
         let font = &fonts[index];
-        let fingerprint = infer_family_fingerprint(font);
-        let effective_style = effective_style(font, fingerprint.style_hint.as_deref());
-        let effective_weight = effective_weight(font, fingerprint.weight_hint.as_deref());
+        let fingerprint = infer_family_fingerprint(font, inference);
+        let effective_style = fingerprint.effective_style(font);
+        let effective_weight = fingerprint.effective_weight(font);
+        let (key, name) = group_by.group(index, font, &fingerprint);
 
         let accumulator = grouped
-            .entry(fingerprint.key.clone())
-            .or_insert_with(|| FamilyAccumulator::new(fingerprint.key, fingerprint.display));
+            .entry(key.clone())
+            .or_insert_with(|| FamilyAccumulator::new(key, name));
 
         accumulator.aliases.insert(font.family.clone());
+        accumulator.hosts.extend(host_of(&font.url));
         accumulator.files += 1;
         accumulator
             .variant_keys
@@ -127,6 +512,10 @@ pub fn infer_family_groups(
         accumulator.styles.insert(effective_style.clone());
         accumulator.formats.insert(font.format.to_ascii_uppercase());
         accumulator.indices.push(index);
+        accumulator.used_by.extend(font.used_by.iter().cloned());
+        accumulator.usage = accumulator.usage.max(font.usage);
+        accumulator.found_on.extend(font.found_on.iter().cloned());
+        accumulator.providers.insert(FontProvider::for_font(font));
         accumulator.fonts.push(InferredFontEntry {
             index,
             name: font.name.clone(),
@@ -136,80 +525,197 @@ pub fn infer_family_groups(
             format: font.format.clone(),
             url: font.url.clone(),
             referer: font.referer.clone(),
+            sources: font.sources.clone(),
+            source_stylesheet: font.source_stylesheet.clone(),
+            source_location: font.source_location,
+            conditions: font.conditions.clone(),
+            used_by: font.used_by.clone(),
+            usage: font.usage,
+            found_on: font.found_on.clone(),
+            provider: FontProvider::for_font(font),
         });
     }
 
-    let mut families = grouped
-        .into_values()
+    let families = match group_by {
+        GroupBy::InferredFamily => merge_across_hosts(grouped.into_values(), inference),
+        _ => grouped.into_values().collect(),
+    };
+    let mut families = families
+        .into_iter()
         .map(FamilyAccumulator::into_output)
         .collect::<Vec<_>>();
 
-    families.sort_by(|a, b| {
-        a.name
-            .to_ascii_lowercase()
-            .cmp(&b.name.to_ascii_lowercase())
-            .then_with(|| a.key.cmp(&b.key))
-    });
+    if group_by == GroupBy::None {
+        families.sort_by_key(|family| family.font_indices.first().copied());
+    } else {
+        families.sort_by(|a, b| {
+            a.name
+                .to_ascii_lowercase()
+                .cmp(&b.name.to_ascii_lowercase())
+                .then_with(|| a.key.cmp(&b.key))
+        });
+    }
 
     families
 }
 
-pub fn group_by_inferred_family(fonts: &[FontInfo]) -> Vec<FontFamily> {
-    infer_family_groups_all(fonts)
+/// Folds each family into the first larger-or-equal family from other hosts
+/// whose name is nearly the same, so one family split across CDNs shows up
+/// once.
+fn merge_across_hosts(
+    families: impl Iterator<Item = FamilyAccumulator>,
+    inference: &Inference,
+) -> Vec<FamilyAccumulator> {
+    let mut families = families.collect::<Vec<_>>();
+    families.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.key.cmp(&b.key)));
+
+    let mut merged = Vec::<FamilyAccumulator>::with_capacity(families.len());
+    for family in families {
+        let target = merged
+            .iter()
+            .enumerate()
+            .find_map(|(position, kept)| Some((position, kept.merge_reason(&family, inference)?)));
+        match target {
+            Some((position, reason)) => merged[position].absorb(family, reason),
+            None => merged.push(family),
+        }
+    }
+    merged
+}
+
+/// Indices of fonts in families that the cross-host merge pass would merge
+/// by name alone, i.e. the fonts worth hashing to confirm those merges.
+pub fn merge_candidate_indices(fonts: &[FontInfo], inference: &Inference) -> Vec<usize> {
+    let unconfirmed = Inference {
+        content_hashes: HashMap::new(),
+        ..inference.clone()
+    };
+    let mut indices = infer_family_groups_all(fonts, &unconfirmed)
+        .into_iter()
+        .filter(|family| !family.merged.is_empty())
+        .flat_map(|family| family.font_indices)
+        .collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices
+}
+
+/// Inferred grouping in the lightweight [`FontFamily`] shape used by the TUI.
+pub fn group_by_inferred_family(fonts: &[FontInfo], inference: &Inference) -> Vec<FontFamily> {
+    let all_indices = (0..fonts.len()).collect::<Vec<_>>();
+    group_indices_by_inferred_family(fonts, &all_indices, inference)
+}
+
+/// [`group_by_inferred_family`] for the fonts at `indices` only. Indices in
+/// the result still point into `fonts`.
+pub fn group_indices_by_inferred_family(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    inference: &Inference,
+) -> Vec<FontFamily> {
+    infer_family_groups(fonts, indices, inference)
         .into_iter()
         .map(|family| FontFamily {
             name: family.name,
             font_indices: family.font_indices,
+            faces: family
+                .faces
+                .into_iter()
+                .map(|face| FontFace {
+                    weight: face.weight,
+                    style: face.style,
+                    font_indices: face.font_indices,
+                })
+                .collect(),
         })
         .collect()
 }
 
+/// Indices of fonts whose inferred family name or any source alias matches
+/// one of `family_names`, compared case-insensitively.
 pub fn select_indices_by_inferred_family_names(
     fonts: &[FontInfo],
     family_names: &[String],
+    inference: &Inference,
 ) -> Vec<usize> {
+    match_inferred_family_names(fonts, family_names, inference, MatchMode::Exact)
+        .into_iter()
+        .flat_map(|matched| matched.indices)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// What each of `family_names` matched, compared with the inferred family
+/// names and their source aliases as `mode` says.
+pub fn match_inferred_family_names(
+    fonts: &[FontInfo],
+    family_names: &[String],
+    inference: &Inference,
+    mode: MatchMode,
+) -> Vec<SelectorMatch> {
     if family_names.is_empty() {
         return Vec::new();
     }
 
-    let requested = family_names
+    let groups = infer_family_groups_all(fonts, inference);
+    family_names
         .iter()
-        .map(|name| normalize(name))
-        .collect::<HashSet<_>>();
-
-    let groups = infer_family_groups_all(fonts);
-    let mut selected = HashSet::new();
-
-    for group in groups {
-        let mut matches = requested.contains(&normalize(&group.name));
-        if !matches {
-            matches = group
-                .aliases
+        .map(|family_name| {
+            let mut indices = groups
                 .iter()
-                .any(|alias| requested.contains(&normalize(alias)));
-        }
-
-        if matches {
-            for index in group.font_indices {
-                selected.insert(index);
+                .filter(|group| {
+                    std::iter::once(&group.name)
+                        .chain(&group.aliases)
+                        .any(|name| mode.matches(family_name, name))
+                })
+                .flat_map(|group| group.font_indices.iter().copied())
+                .collect::<Vec<_>>();
+            indices.sort_unstable();
+            SelectorMatch {
+                kind: SelectorKind::Family,
+                selector: family_name.clone(),
+                indices,
             }
-        }
-    }
+        })
+        .collect()
+}
 
-    let mut indices = selected.into_iter().collect::<Vec<_>>();
-    indices.sort_unstable();
-    indices
+/// Infers the family fingerprint for a single font.
+///
+/// The declared family is tokenized first; when nothing survives the cleanup
+/// (hash suffixes, weight and style tokens) the file name is used instead.
+/// A matching family alias replaces the family but keeps the variant hints.
+pub fn infer_family_fingerprint(font: &FontInfo, inference: &Inference) -> FamilyFingerprint {
+    let fingerprint = inferred_fingerprint(font, inference);
+    match inference.options.family_alias(font) {
+        Some(family) => FamilyFingerprint {
+            key: tokenize_source(family).join(" "),
+            display: family.to_owned(),
+            from_alias: true,
+            ..fingerprint
+        },
+        None => fingerprint,
+    }
 }
 
-fn infer_family_fingerprint(font: &FontInfo) -> FamilyFingerprint {
+fn inferred_fingerprint(font: &FontInfo, inference: &Inference) -> FamilyFingerprint {
+    if let Some(fingerprint) = inference
+        .binary_metadata
+        .get(&font.url)
+        .and_then(binary_fingerprint)
+    {
+        return fingerprint;
+    }
+
     let mut tokens = tokenize_source(&font.family);
-    cleanup_file_tokens(&mut tokens);
-    let (mut weight_hint, mut style_hint) = strip_variant_tokens(&mut tokens);
+    cleanup_file_tokens(&mut tokens, &inference.options);
+    let (mut weight_hint, mut style_hint) = strip_variant_tokens(&mut tokens, &inference.options);
 
     if tokens.is_empty() {
         tokens = tokenize_source(&font.name);
-        cleanup_file_tokens(&mut tokens);
-        let (fallback_weight, fallback_style) = strip_variant_tokens(&mut tokens);
+        cleanup_file_tokens(&mut tokens, &inference.options);
+        let (fallback_weight, fallback_style) =
+            strip_variant_tokens(&mut tokens, &inference.options);
         if weight_hint.is_none() {
             weight_hint = fallback_weight;
         }
@@ -219,7 +725,7 @@ fn infer_family_fingerprint(font: &FontInfo) -> FamilyFingerprint {
     }
 
     if tokens.is_empty() {
-        tokens.push("unknown".to_owned());
+        tokens.push(UNKNOWN_FAMILY_KEY.to_owned());
     }
 
     let key = tokens.join(" ");
@@ -234,10 +740,31 @@ fn infer_family_fingerprint(font: &FontInfo) -> FamilyFingerprint {
         display,
         weight_hint,
         style_hint,
+        from_binary: false,
+        from_alias: false,
     }
 }
 
-fn tokenize_source(input: &str) -> Vec<String> {
+fn binary_fingerprint(metadata: &FontMetadata) -> Option<FamilyFingerprint> {
+    let family = metadata.family.as_deref()?;
+    let tokens = tokenize_source(family);
+    if tokens.is_empty() {
+        return None;
+    }
+
+    Some(FamilyFingerprint {
+        key: tokens.join(" "),
+        display: family.to_owned(),
+        weight_hint: metadata.weight.map(|weight| weight.to_string()),
+        style_hint: metadata.style.clone(),
+        from_binary: true,
+        from_alias: false,
+    })
+}
+
+/// Splits a family or file name into lowercase tokens on punctuation and
+/// camel-case boundaries, dropping a trailing font file extension.
+pub fn tokenize_source(input: &str) -> Vec<String> {
     let source = strip_known_extension(input);
 
     let mut tokens = Vec::new();
@@ -310,7 +837,9 @@ fn strip_known_extension(input: &str) -> String {
     input.to_owned()
 }
 
-fn cleanup_file_tokens(tokens: &mut Vec<String>) {
+fn cleanup_file_tokens(tokens: &mut Vec<String>, options: &InferenceOptions) {
+    tokens.retain(|token| !options.strip_tokens.contains(token));
+
     while let Some(last) = tokens.last() {
         if is_hash_token(last) || last == "s" || last == "p" {
             tokens.pop();
@@ -320,17 +849,16 @@ fn cleanup_file_tokens(tokens: &mut Vec<String>) {
     }
 }
 
-fn strip_variant_tokens(tokens: &mut Vec<String>) -> (Option<String>, Option<String>) {
+fn strip_variant_tokens(
+    tokens: &mut Vec<String>,
+    options: &InferenceOptions,
+) -> (Option<String>, Option<String>) {
     let mut weight_hint = None;
     let mut style_hint = None;
 
-    loop {
-        let Some(last) = tokens.last().cloned() else {
-            break;
-        };
-
+    while let Some(last) = tokens.last().cloned() {
         if style_hint.is_none()
-            && let Some(style) = style_hint_from_token(&last)
+            && let Some(style) = configured_style_hint(&last, options)
         {
             style_hint = Some(style);
             tokens.pop();
@@ -338,7 +866,7 @@ fn strip_variant_tokens(tokens: &mut Vec<String>) -> (Option<String>, Option<Str
         }
 
         if weight_hint.is_none()
-            && let Some(weight) = weight_hint_from_token(&last)
+            && let Some(weight) = configured_weight_hint(&last, options)
         {
             weight_hint = Some(weight);
             tokens.pop();
@@ -351,6 +879,22 @@ fn strip_variant_tokens(tokens: &mut Vec<String>) -> (Option<String>, Option<Str
     (weight_hint, style_hint)
 }
 
+fn configured_style_hint(token: &str, options: &InferenceOptions) -> Option<String> {
+    options
+        .style_tokens
+        .get(token)
+        .map(|style| normalize_style(style))
+        .or_else(|| style_hint_from_token(token))
+}
+
+fn configured_weight_hint(token: &str, options: &InferenceOptions) -> Option<String> {
+    options
+        .weight_synonyms
+        .get(token)
+        .map(|weight| weight.to_string())
+        .or_else(|| weight_hint_from_token(token))
+}
+
 fn style_hint_from_token(token: &str) -> Option<String> {
     match token {
         "italic" => Some("italic".to_owned()),
@@ -482,13 +1026,63 @@ fn format_index_range(start: usize, end: usize) -> String {
     }
 }
 
+/// Whether two family keys differ by at most one edit per eight characters,
+/// ignoring spaces, e.g. `brand sans` and `brandsans` or `brand sanz`.
+fn similar_family_keys(a: &str, b: &str) -> bool {
+    let compact = |key: &str| key.chars().filter(|c| *c != ' ').collect::<Vec<_>>();
+    let (a, b) = (compact(a), compact(b));
+    let longest = a.len().max(b.len());
+    longest >= 4 && edit_distance(&a, &b) * 8 <= longest
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, left) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, right) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 fn normalize(input: &str) -> String {
     input.trim().to_ascii_lowercase()
 }
 
+/// Whether `value` matches `pattern` as a whole, where `*` stands for any
+/// run of characters.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{infer_family_groups_all, select_indices_by_inferred_family_names};
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::{
+        GroupBy, Inference, InferenceOptions, MergeReason, infer_family_fingerprint,
+        infer_family_groups_all, infer_family_groups_by, matches_pattern, merge_candidate_indices,
+        select_indices_by_inferred_family_names, tokenize_source,
+    };
+    use crate::binary::FontMetadata;
     use crate::model::FontInfo;
 
     fn make_font(family: &str, name: &str, url: &str) -> FontInfo {
@@ -500,6 +1094,8 @@ mod tests {
             weight: "400".to_owned(),
             style: "normal".to_owned(),
             referer: "https://example.com".to_owned(),
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
         }
     }
 
@@ -533,7 +1129,7 @@ mod tests {
             ),
         ];
 
-        let groups = infer_family_groups_all(&fonts);
+        let groups = infer_family_groups_all(&fonts, &Inference::default());
         assert_eq!(groups.len(), 2);
 
         let academica = groups
@@ -548,6 +1144,204 @@ mod tests {
         assert_eq!(academica.aliases.len(), 4);
     }
 
+    #[test]
+    fn family_aliases_force_matching_fonts_into_one_family() {
+        let fonts = vec![
+            make_font(
+                "brandfont-v7-bold",
+                "brandfont-v7-bold.woff2",
+                "https://cdn.test/0.woff2",
+            ),
+            make_font(
+                "bf7",
+                "bf7-regular.woff2",
+                "https://cdn.test/legacy/1.woff2",
+            ),
+            make_font("Other", "other.woff2", "https://cdn.test/2.woff2"),
+        ];
+        let options = Inference::from(
+            InferenceOptions {
+                family_aliases: BTreeMap::from([
+                    ("BrandFont-V7-*".to_owned(), "Acme Sans".to_owned()),
+                    (
+                        "https://cdn.test/legacy/*".to_owned(),
+                        " Acme Sans".to_owned(),
+                    ),
+                ]),
+                ..InferenceOptions::default()
+            }
+            .normalized(),
+        );
+
+        let groups = infer_family_groups_all(&fonts, &options);
+        assert_eq!(groups.len(), 2);
+        let acme = groups
+            .iter()
+            .find(|group| group.name == "Acme Sans")
+            .expect("expected the aliased family");
+        assert_eq!(acme.font_indices, vec![0, 1]);
+        assert_eq!(acme.weights, vec!["400", "700"]);
+
+        let fingerprint = infer_family_fingerprint(&fonts[0], &options);
+        assert_eq!(fingerprint.key, "acme sans");
+        assert!(fingerprint.from_alias);
+        assert!(!infer_family_fingerprint(&fonts[2], &options).from_alias);
+
+        assert!(matches_pattern("a*c*e", "abcde"));
+        assert!(matches_pattern("*.woff2", "x.woff2"));
+        assert!(!matches_pattern("a*c", "abcd"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn near_duplicate_families_on_other_hosts_are_merged() {
+        let fonts = vec![
+            make_font(
+                "Brand Sans",
+                "brand-400.woff2",
+                "https://cdn1.example.com/a.woff2",
+            ),
+            make_font(
+                "Brand Sans",
+                "brand-700.woff2",
+                "https://cdn1.example.com/b.woff2",
+            ),
+            make_font(
+                "Brand Sanz",
+                "brand-400.woff2",
+                "https://assets.example.net/a.woff2",
+            ),
+            make_font(
+                "Brand Sanx",
+                "brand-400.woff2",
+                "https://cdn1.example.com/c.woff2",
+            ),
+            make_font("Helvetica", "h.woff2", "https://cdn1.example.com/h.woff2"),
+            make_font(
+                "Helvetica Neue",
+                "hn.woff2",
+                "https://assets.example.net/hn.woff2",
+            ),
+        ];
+
+        let groups = infer_family_groups_all(&fonts, &Inference::default());
+        let names = groups
+            .iter()
+            .map(|group| group.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["Brand Sans", "Brand Sanx", "Helvetica", "Helvetica Neue"]
+        );
+        let brand = &groups[0];
+        assert_eq!(brand.font_indices, vec![0, 1, 2]);
+        assert!(brand.aliases.contains(&"Brand Sanz".to_owned()));
+        assert_eq!(brand.merged.len(), 1);
+        assert_eq!(brand.merged[0].hosts, vec!["assets.example.net"]);
+        assert_eq!(brand.merged[0].reason, MergeReason::SimilarName);
+        assert_eq!(
+            select_indices_by_inferred_family_names(
+                &fonts,
+                &["brand sanz".to_owned()],
+                &Inference::default()
+            ),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            merge_candidate_indices(&fonts, &Inference::default()),
+            vec![0, 1, 2]
+        );
+
+        let hashed = |digests: [(&str, &str); 2]| {
+            Inference::default().with_content_hashes(
+                digests
+                    .into_iter()
+                    .map(|(url, digest)| (url.to_owned(), digest.to_owned()))
+                    .collect(),
+            )
+        };
+        let confirmed = hashed([
+            ("https://cdn1.example.com/a.woff2", "aa"),
+            ("https://assets.example.net/a.woff2", "aa"),
+        ]);
+        let groups = infer_family_groups_all(&fonts, &confirmed);
+        assert_eq!(groups[0].merged[0].reason, MergeReason::SameContent);
+
+        let refuted = hashed([
+            ("https://cdn1.example.com/a.woff2", "aa"),
+            ("https://assets.example.net/a.woff2", "bb"),
+        ]);
+        let groups = infer_family_groups_all(&fonts, &refuted);
+        assert_eq!(groups[0].font_indices, vec![0, 1]);
+        assert!(groups[0].merged.is_empty());
+    }
+
+    #[test]
+    fn groups_on_the_requested_axis() {
+        let mut fonts = vec![
+            make_font("Brand-Bold", "brand-bold.woff2", "https://cdn.test/0.woff2"),
+            make_font("Brand", "brand.woff2", "https://cdn.test/1.woff2"),
+            make_font("Brand", "brand.ttf", "https://cdn.test/2.ttf"),
+        ];
+        fonts[2].format = "truetype".to_owned();
+        let names = |group_by| {
+            infer_family_groups_by(&fonts, &[2, 0, 1], &Inference::default(), group_by)
+                .into_iter()
+                .map(|group| (group.name, group.font_indices))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(GroupBy::InferredFamily),
+            [("Brand".to_owned(), vec![0, 1, 2])]
+        );
+        assert_eq!(
+            names(GroupBy::CssFamily),
+            [
+                ("Brand".to_owned(), vec![1, 2]),
+                ("Brand-Bold".to_owned(), vec![0])
+            ]
+        );
+        assert_eq!(
+            names(GroupBy::Format),
+            [
+                ("TRUETYPE".to_owned(), vec![2]),
+                ("WOFF2".to_owned(), vec![0, 1])
+            ]
+        );
+        assert_eq!(names(GroupBy::Provider).len(), 1);
+        assert_eq!(
+            names(GroupBy::None),
+            [
+                ("brand-bold.woff2".to_owned(), vec![0]),
+                ("brand.woff2".to_owned(), vec![1]),
+                ("brand.ttf".to_owned(), vec![2])
+            ]
+        );
+    }
+
+    #[test]
+    fn fingerprint_exposes_key_display_and_variant_hints() {
+        assert_eq!(
+            tokenize_source("HelveticaNowDisplay-BoldItalic.woff2"),
+            vec!["helvetica", "now", "display", "bold", "italic"]
+        );
+
+        let font = make_font(
+            "HelveticaNowDisplay-BoldItalic",
+            "HelveticaNowDisplay-BoldItalic.woff2",
+            "https://cdn.test/0.woff2",
+        );
+        let fingerprint = infer_family_fingerprint(&font, &Inference::default());
+
+        assert_eq!(fingerprint.key, "helvetica now display");
+        assert_eq!(fingerprint.display, "Helvetica Now Display");
+        assert_eq!(fingerprint.weight_hint.as_deref(), Some("700"));
+        assert_eq!(fingerprint.style_hint.as_deref(), Some("italic"));
+        assert_eq!(fingerprint.effective_weight(&font), "700");
+        assert_eq!(fingerprint.effective_style(&font), "italic");
+    }
+
     #[test]
     fn inferred_family_selection_accepts_display_name_and_alias() {
         let fonts = vec![
@@ -571,13 +1365,118 @@ mod tests {
         let by_display = select_indices_by_inferred_family_names(
             &fonts,
             &[String::from("Academica Book")],
+            &Inference::default(),
         );
         assert_eq!(by_display, vec![0, 1]);
 
         let by_alias = select_indices_by_inferred_family_names(
             &fonts,
             &[String::from("academica_book_regular-s.p.ec9218b1")],
+            &Inference::default(),
         );
         assert_eq!(by_alias, vec![0, 1]);
     }
+
+    #[test]
+    fn inference_options_add_synonyms_style_tokens_and_stoplist() {
+        let fonts = vec![
+            make_font(
+                "Foundry Sans Book Webfont",
+                "foundry-sans-book-webfont.woff2",
+                "https://cdn.test/0.woff2",
+            ),
+            make_font(
+                "Foundry Sans Demi Kursiv",
+                "foundry-sans-demi-kursiv.woff2",
+                "https://cdn.test/1.woff2",
+            ),
+        ];
+
+        let default_groups = infer_family_groups_all(&fonts, &Inference::default());
+        assert_eq!(default_groups.len(), 2);
+
+        let mut options = InferenceOptions::default();
+        options.weight_synonyms.insert("book".to_owned(), 400);
+        options.weight_synonyms.insert("demi".to_owned(), 600);
+        options
+            .style_tokens
+            .insert("kursiv".to_owned(), "italic".to_owned());
+        options.strip_tokens.insert("webfont".to_owned());
+
+        let groups = infer_family_groups_all(&fonts, &options.into());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Foundry Sans");
+        assert_eq!(groups[0].weights, vec!["400", "600"]);
+        assert_eq!(groups[0].styles, vec!["italic", "normal"]);
+    }
+
+    #[test]
+    fn binary_metadata_overrides_file_name_heuristics() {
+        let fonts = vec![
+            make_font("a1b2c3d4e5", "a1b2c3d4e5.woff2", "https://cdn.test/0.woff2"),
+            make_font("f6e5d4c3b2", "f6e5d4c3b2.woff2", "https://cdn.test/1.woff2"),
+        ];
+
+        let mut metadata = HashMap::new();
+        for (url, weight, style) in [
+            ("https://cdn.test/0.woff2", 400, "normal"),
+            ("https://cdn.test/1.woff2", 700, "italic"),
+        ] {
+            metadata.insert(
+                url.to_owned(),
+                FontMetadata {
+                    family: Some("Brand Grotesk".to_owned()),
+                    weight: Some(weight),
+                    style: Some(style.to_owned()),
+                    ..FontMetadata::default()
+                },
+            );
+        }
+
+        let options = Inference::default().with_binary_metadata(metadata);
+        let groups = infer_family_groups_all(&fonts, &options);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Brand Grotesk");
+        assert_eq!(groups[0].weights, vec!["400", "700"]);
+        assert_eq!(groups[0].styles, vec!["italic", "normal"]);
+    }
+
+    #[test]
+    fn redundant_formats_collapse_into_one_face() {
+        let mut fonts = vec![
+            make_font("Inter", "Inter-Bold.ttf", "https://cdn.test/Inter-Bold.ttf"),
+            make_font(
+                "Inter",
+                "Inter-Bold.woff",
+                "https://cdn.test/Inter-Bold.woff",
+            ),
+            make_font(
+                "Inter",
+                "Inter-Bold.woff2",
+                "https://cdn.test/Inter-Bold.woff2",
+            ),
+            make_font(
+                "Inter",
+                "Inter-Regular.woff2",
+                "https://cdn.test/Inter-Regular.woff2",
+            ),
+        ];
+        fonts[0].format = "TRUETYPE".to_owned();
+        fonts[1].format = "WOFF".to_owned();
+        for font in &mut fonts[..3] {
+            font.weight = "700".to_owned();
+        }
+
+        let groups = infer_family_groups_all(&fonts, &Inference::default());
+        assert_eq!(groups.len(), 1);
+
+        let faces = &groups[0].faces;
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].weight, "400");
+        assert_eq!(faces[0].font_indices, vec![3]);
+        assert_eq!(faces[1].weight, "700");
+        assert_eq!(faces[1].formats, vec!["WOFF2", "WOFF", "TRUETYPE"]);
+        assert_eq!(faces[1].font_indices, vec![2, 1, 0]);
+    }
 }
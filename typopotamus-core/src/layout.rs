@@ -0,0 +1,292 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use url::Url;
+
+use crate::download::{ConflictPolicy, RefererPolicy, extension_for_font, sanitize_component};
+use crate::error::TemplateError;
+use crate::fetch::HttpOptions;
+use crate::inspect::{Inference, infer_family_fingerprint};
+use crate::model::FontInfo;
+use crate::provider::FontProvider;
+
+pub const DEFAULT_DIRECTORY_TEMPLATE: &str = "{family}";
+
+const PLACEHOLDERS: [&str; 10] = [
+    "family",
+    "inferred_family",
+    "name",
+    "weight",
+    "style",
+    "style_suffix",
+    "format",
+    "ext",
+    "provider",
+    "host",
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(&'static str),
+}
+
+/// A `/`-separated output path with `{placeholder}` substitutions, such as
+/// `{provider}/{inferred_family}/{weight}-{style}`.
+///
+/// Substituted values are sanitized into lowercase, dash-separated path
+/// components; empty, `.` and `..` components are dropped, so a rendered
+/// template always stays inside the output directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PathTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl PathTemplate {
+    pub fn parse(input: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut rest = input;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_owned()));
+            }
+
+            let after = &rest[start + 1..];
+            let end = after.find('}').ok_or_else(|| TemplateError::Unclosed {
+                template: input.to_owned(),
+            })?;
+            let name = after[..end].trim();
+            let placeholder = PLACEHOLDERS
+                .iter()
+                .find(|candidate| **candidate == name)
+                .ok_or_else(|| TemplateError::UnknownPlaceholder {
+                    template: input.to_owned(),
+                    name: name.to_owned(),
+                })?;
+            segments.push(Segment::Placeholder(placeholder));
+            rest = &after[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_owned()));
+        }
+
+        Ok(Self {
+            source: input.to_owned(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn render(&self, vars: &TemplateVars) -> PathBuf {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder("style_suffix") => {
+                    let style = sanitize_component(&vars.style);
+                    if !style.is_empty() && style != "normal" {
+                        rendered.push('-');
+                        rendered.push_str(&style);
+                    }
+                }
+                Segment::Placeholder(name) => {
+                    let value = sanitize_component(vars.value(name));
+                    rendered.push_str(if value.is_empty() { "unknown" } else { &value });
+                }
+            }
+        }
+
+        rendered
+            .split(['/', '\\'])
+            .map(str::trim)
+            .filter(|component| !component.is_empty() && *component != "." && *component != "..")
+            .collect()
+    }
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_DIRECTORY_TEMPLATE).expect("default template is valid")
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = TemplateError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.source)
+    }
+}
+
+/// Values available to a [`PathTemplate`] for one font.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateVars {
+    pub family: String,
+    pub inferred_family: String,
+    pub name: String,
+    pub weight: String,
+    pub style: String,
+    pub format: String,
+    pub ext: String,
+    pub provider: String,
+    pub host: String,
+}
+
+impl TemplateVars {
+    pub fn for_font(font: &FontInfo, inference: &Inference) -> Self {
+        let fingerprint = infer_family_fingerprint(font, inference);
+        let host = if font.url.starts_with("data:") {
+            "inline".to_owned()
+        } else {
+            Url::parse(&font.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_owned))
+                .unwrap_or_default()
+        };
+
+        Self {
+            family: if fingerprint.from_alias {
+                fingerprint.display.clone()
+            } else {
+                font.family.clone()
+            },
+            inferred_family: fingerprint.display.clone(),
+            name: Path::new(&font.name)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(&font.name)
+                .to_owned(),
+            weight: fingerprint.effective_weight(font),
+            style: fingerprint.effective_style(font),
+            format: font.format.clone(),
+            ext: extension_for_font(font, None).to_owned(),
+            provider: FontProvider::for_font(font).as_str().to_owned(),
+            host,
+        }
+    }
+
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        extension.clone_into(&mut self.ext);
+        self
+    }
+
+    fn value(&self, name: &str) -> &str {
+        match name {
+            "family" => &self.family,
+            "inferred_family" => &self.inferred_family,
+            "name" => &self.name,
+            "weight" => &self.weight,
+            "style" => &self.style,
+            "format" => &self.format,
+            "ext" => &self.ext,
+            "provider" => &self.provider,
+            "host" => &self.host,
+            _ => "",
+        }
+    }
+}
+
+/// Where downloaded fonts are placed below the output root and what they are
+/// called. Without a `file_name` template files keep their source name with
+/// the weight and style appended.
+#[derive(Clone, Debug, Default)]
+pub struct DownloadLayout {
+    pub directory: PathTemplate,
+    pub file_name: Option<PathTemplate>,
+    pub on_conflict: ConflictPolicy,
+    pub inference: Inference,
+    /// The `Referer` sent with font requests.
+    pub referer: RefererPolicy,
+    /// Send no `Origin` header with font requests.
+    pub omit_origin: bool,
+    /// Write a [`FontSidecar`](crate::sidecar::FontSidecar) next to each
+    /// saved font.
+    pub sidecar: bool,
+    /// Name files after the family and subfamily in their `name` table, e.g.
+    /// `Inter-SemiBoldItalic`, instead of `file_name` or the source name.
+    /// Files without readable names keep their usual name.
+    pub rename_from_metadata: bool,
+    /// Leave saved files with the time they were written instead of the
+    /// server's `Last-Modified`.
+    pub omit_server_mtime: bool,
+    /// Transport settings for the built-in HTTP client.
+    pub http: HttpOptions,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{PathTemplate, TemplateVars};
+    use crate::error::TemplateError;
+    use crate::inspect::Inference;
+    use crate::model::FontInfo;
+
+    #[test]
+    fn templates_render_sanitized_components_inside_the_root() {
+        let font = FontInfo {
+            name: "Inter-BoldItalic.woff2".to_owned(),
+            family: "Inter Var".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: "https://fonts.gstatic.com/s/inter/v1/abc.woff2".to_owned(),
+            weight: "700".to_owned(),
+            style: "italic".to_owned(),
+            referer: "https://example.com".to_owned(),
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        };
+        let vars = TemplateVars::for_font(&font, &Inference::default());
+
+        let template = PathTemplate::parse("{provider}/{family}/{weight}-{style}").unwrap();
+        assert_eq!(
+            template.render(&vars),
+            PathBuf::from("google/inter-var/700-italic")
+        );
+
+        let escaping = PathTemplate::parse("../{host}//./{format}").unwrap();
+        assert_eq!(
+            escaping.render(&vars),
+            PathBuf::from("fonts-gstatic-com/woff2")
+        );
+
+        let file_name =
+            PathTemplate::parse("{inferred_family}-{weight}{style_suffix}.{ext}").unwrap();
+        assert_eq!(
+            file_name.render(&vars),
+            PathBuf::from("inter-var-700-italic.woff2")
+        );
+        let upright = TemplateVars {
+            style: "normal".to_owned(),
+            ..vars
+        };
+        assert_eq!(
+            file_name.render(&upright),
+            PathBuf::from("inter-var-700.woff2")
+        );
+    }
+
+    #[test]
+    fn template_parse_rejects_unknown_and_unclosed_placeholders() {
+        assert!(matches!(
+            PathTemplate::parse("{family}/{foundry}"),
+            Err(TemplateError::UnknownPlaceholder { name, .. }) if name == "foundry"
+        ));
+        assert!(matches!(
+            PathTemplate::parse("{family"),
+            Err(TemplateError::Unclosed { .. })
+        ));
+    }
+}
@@ -1,5 +1,44 @@
+pub mod audit;
+pub mod binary;
+#[cfg(feature = "wasm")]
+pub mod browser;
+pub mod cancel;
+pub mod charset;
+pub mod check;
+pub mod compare;
+pub mod compress;
+pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod doctor;
 pub mod download;
+pub mod enrich;
+pub mod error;
 pub mod extractor;
+pub mod fetch;
+pub mod fontface;
+pub mod fontsource;
+pub mod google_fonts;
 pub mod inspect;
+pub mod layout;
+pub mod local;
+pub mod lock;
+pub mod mirror;
 pub mod model;
+pub mod output;
+pub mod perf;
+pub mod provider;
+pub mod script;
 pub mod selection;
+pub mod session;
+pub mod sfnt;
+pub mod sidecar;
+pub mod sitemap;
+pub mod snippet;
+pub mod specimen;
+pub mod stats;
+pub mod subset;
+pub mod sync;
+pub mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod trace;
+pub mod usage;
@@ -0,0 +1,336 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::cancel::CancellationToken;
+use crate::error::LocalScanError;
+use crate::extractor::{ExtractEvent, ExtractOptions, extract_fonts_from_documents};
+use crate::fetch::header::CONTENT_TYPE;
+use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+use crate::model::FontInfo;
+
+/// Base URL of a scanned directory. Its files get `file:///<relative path>`
+/// URLs, so root-relative references such as `/fonts/a.woff2` resolve inside
+/// the directory rather than against the filesystem root.
+pub const LOCAL_ROOT_URL: &str = "file:///";
+
+/// Serves `file:///` URLs from a directory, as [`LOCAL_ROOT_URL`] maps them.
+/// Missing files answer HTTP 404; other schemes are refused.
+#[derive(Clone, Debug)]
+pub struct DirectoryFetcher {
+    root: PathBuf,
+}
+
+impl DirectoryFetcher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The file a `file:///` URL points at, or `None` for other URLs.
+    pub fn path_for(&self, url: &str) -> Option<PathBuf> {
+        let url = Url::parse(url).ok()?;
+        if url.scheme() != "file" {
+            return None;
+        }
+
+        let mut path = self.root.clone();
+        for segment in url.path_segments()? {
+            let segment = percent_decode_str(segment).decode_utf8().ok()?;
+            if segment.is_empty() || segment == "." {
+                continue;
+            }
+            if segment == ".." || segment.contains(['/', '\\']) {
+                return None;
+            }
+            path.push(segment.as_ref());
+        }
+        Some(path)
+    }
+}
+
+impl Fetcher for DirectoryFetcher {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+        let Some(path) = self.path_for(&request.url) else {
+            return Err(format!("{} is outside the scanned directory", request.url).into());
+        };
+
+        match fs::read(&path) {
+            Ok(body) => Ok(FetchResponse {
+                headers: content_type(&path)
+                    .map(|value| vec![(CONTENT_TYPE.to_owned(), value.to_owned())])
+                    .unwrap_or_default(),
+                ..FetchResponse::ok(body)
+            }),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::IsADirectory
+                ) =>
+            {
+                Ok(FetchResponse {
+                    status: 404,
+                    ..FetchResponse::default()
+                })
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Fonts referenced by the HTML and CSS files of a build output directory.
+#[derive(Clone, Debug, Default)]
+pub struct DirectoryScan {
+    /// `file:///` URLs of the HTML files scanned.
+    pub pages: Vec<String>,
+    /// `file:///` URLs of every CSS file in the directory.
+    pub stylesheets: Vec<String>,
+    pub fonts: Vec<FontInfo>,
+    /// Referenced font files that do not exist in the directory.
+    pub missing: Vec<MissingFont>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingFont {
+    pub url: String,
+    pub path: PathBuf,
+    /// The stylesheets or pages declaring the font.
+    pub referenced_from: Vec<String>,
+}
+
+/// Walks `root` for `.html`, `.htm` and `.css` files and scans them as if the
+/// directory were served at the site root. Stylesheets that no page links to
+/// are scanned too. Hidden files and directories are skipped.
+pub fn scan_directory(
+    root: &Path,
+    options: &ExtractOptions,
+    cancel: &CancellationToken,
+    mut on_event: impl FnMut(&ExtractEvent),
+) -> Result<DirectoryScan, LocalScanError> {
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    let base = Url::parse(LOCAL_ROOT_URL).expect("valid local root URL");
+    let mut pages = Vec::new();
+    let mut stylesheets = Vec::new();
+    for relative in files {
+        let extension = relative
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let target = match extension.as_deref() {
+            Some("html" | "htm") => &mut pages,
+            Some("css") => &mut stylesheets,
+            _ => continue,
+        };
+
+        let mut url = base.clone();
+        url.path_segments_mut()
+            .expect("file URLs have a path")
+            .pop_if_empty()
+            .extend(
+                relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy()),
+            );
+        target.push(url);
+    }
+
+    let fetcher = DirectoryFetcher::new(root);
+    let fonts = extract_fonts_from_documents(
+        &pages,
+        &stylesheets,
+        &fetcher,
+        options,
+        cancel,
+        &mut on_event,
+    )
+    .map_err(|_| LocalScanError::Cancelled)?;
+
+    let mut missing = Vec::<MissingFont>::new();
+    for font in &fonts {
+        let referenced_from = if font.source_stylesheet.is_empty() {
+            font.found_on.clone()
+        } else {
+            vec![font.source_stylesheet.clone()]
+        };
+        let urls = std::iter::once(&font.url).chain(font.sources.iter().map(|source| &source.url));
+        for url in urls {
+            let Some(path) = fetcher.path_for(url) else {
+                continue;
+            };
+            if path.is_file() || missing.iter().any(|entry| entry.url == *url) {
+                continue;
+            }
+            missing.push(MissingFont {
+                url: url.clone(),
+                path,
+                referenced_from: referenced_from.clone(),
+            });
+        }
+    }
+
+    Ok(DirectoryScan {
+        pages: pages.iter().map(Url::to_string).collect(),
+        stylesheets: stylesheets.iter().map(Url::to_string).collect(),
+        fonts,
+        missing,
+    })
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), LocalScanError> {
+    let read_error = |source| LocalScanError::Read {
+        path: dir.to_owned(),
+        source,
+    };
+
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let entry = entry.map_err(read_error)?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(read_error)?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_owned());
+        }
+    }
+    Ok(())
+}
+
+fn content_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => Some("text/html"),
+        "css" => Some("text/css"),
+        "js" | "mjs" => Some("text/javascript"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{DirectoryFetcher, scan_directory};
+    use crate::cancel::CancellationToken;
+    use crate::extractor::ExtractOptions;
+
+    fn temp_site(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("typopotamus-local-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("css")).expect("create css dir");
+        fs::create_dir_all(root.join("fonts")).expect("create fonts dir");
+        fs::create_dir_all(root.join("blog")).expect("create blog dir");
+        root
+    }
+
+    #[test]
+    fn maps_root_relative_urls_into_the_directory() {
+        let fetcher = DirectoryFetcher::new("/srv/dist");
+        assert_eq!(
+            fetcher.path_for("file:///fonts/Brand%20Sans.woff2"),
+            Some(PathBuf::from("/srv/dist/fonts/Brand Sans.woff2"))
+        );
+        assert_eq!(fetcher.path_for("https://cdn.example/a.woff2"), None);
+        assert_eq!(fetcher.path_for("file:///fonts/..%2Fsecret"), None);
+    }
+
+    #[test]
+    fn reports_referenced_and_missing_font_files() {
+        let root = temp_site("scan");
+        fs::write(
+            root.join("index.html"),
+            r#"<link rel="stylesheet" href="css/site.css"><style>
+                @font-face { font-family: Inline; src: url(/fonts/inline.woff2); }
+            </style>"#,
+        )
+        .expect("write index");
+        fs::write(
+            root.join("blog/post.html"),
+            r#"<link rel="stylesheet" href="../css/site.css">"#,
+        )
+        .expect("write post");
+        fs::write(
+            root.join("css/site.css"),
+            "@font-face { font-family: Brand; src: url(../fonts/brand.woff2) format('woff2'), url(../fonts/brand.woff) format('woff'); }",
+        )
+        .expect("write site.css");
+        fs::write(
+            root.join("css/orphan.css"),
+            "@font-face { font-family: Orphan; src: url('/fonts/gone.woff'); }",
+        )
+        .expect("write orphan.css");
+        fs::write(root.join("fonts/brand.woff2"), b"wOF2").expect("write brand");
+        fs::write(root.join("fonts/inline.woff2"), b"wOF2").expect("write inline");
+
+        let scan = scan_directory(
+            &root,
+            &ExtractOptions::default(),
+            &CancellationToken::new(),
+            |_| {},
+        )
+        .expect("scan should succeed");
+
+        assert_eq!(
+            scan.pages,
+            vec!["file:///blog/post.html", "file:///index.html"]
+        );
+        assert_eq!(scan.stylesheets.len(), 2);
+
+        let mut urls = scan
+            .fonts
+            .iter()
+            .map(|font| font.url.as_str())
+            .collect::<Vec<_>>();
+        urls.sort_unstable();
+        assert_eq!(
+            urls,
+            vec![
+                "file:///fonts/brand.woff2",
+                "file:///fonts/gone.woff",
+                "file:///fonts/inline.woff2"
+            ]
+        );
+
+        let brand = scan
+            .fonts
+            .iter()
+            .find(|font| font.family == "Brand")
+            .expect("Brand should be found");
+        assert_eq!(
+            brand.found_on,
+            vec!["file:///blog/post.html", "file:///index.html"]
+        );
+
+        let missing = scan
+            .missing
+            .iter()
+            .map(|font| (font.url.as_str(), font.referenced_from.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![
+                (
+                    "file:///fonts/brand.woff",
+                    vec!["file:///css/site.css".to_owned()]
+                ),
+                (
+                    "file:///fonts/gone.woff",
+                    vec!["file:///css/orphan.css".to_owned()]
+                ),
+            ]
+        );
+        assert_eq!(scan.missing[1].path, root.join("fonts").join("gone.woff"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
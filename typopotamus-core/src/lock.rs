@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::download::SavedFile;
+use crate::error::LockError;
+use crate::model::FontInfo;
+
+pub const LOCK_FILE_NAME: &str = "typopotamus.lock";
+/// The lockfile format written by this version.
+pub const LOCK_VERSION: u32 = 1;
+
+const LOCK_HEADER: &str = "# Written by typopotamus. Lists the fonts a download selected and the\n# SHA-256 of each file, so later downloads can be checked with --locked.\n\n";
+
+/// The fonts a download selected, for checking later downloads against.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FontLock {
+    pub version: u32,
+    /// The page the fonts were selected from.
+    pub source: String,
+    #[serde(default, rename = "font")]
+    pub fonts: Vec<LockedFont>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LockedFont {
+    pub url: String,
+    pub family: String,
+    pub weight: String,
+    pub style: String,
+    pub format: String,
+    /// Lowercase hex SHA-256 of the file as downloaded.
+    pub sha256: String,
+}
+
+impl LockedFont {
+    fn descriptors(&self) -> String {
+        descriptors(&self.family, &self.weight, &self.style, &self.format)
+    }
+}
+
+fn descriptors(family: &str, weight: &str, style: &str, format: &str) -> String {
+    format!("{family} {weight} {style} {format}")
+}
+
+/// A difference between a lockfile and the live site.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LockMismatch {
+    /// Locked, but no longer selected on the site.
+    Removed { url: String },
+    /// Selected on the site, but not locked.
+    Added { url: String },
+    /// Declared with another family, weight, style or format.
+    Descriptors {
+        url: String,
+        locked: String,
+        live: String,
+    },
+    /// Downloaded bytes that differ from the locked hash.
+    Content {
+        url: String,
+        locked: String,
+        live: String,
+    },
+}
+
+impl LockMismatch {
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Removed { url }
+            | Self::Added { url }
+            | Self::Descriptors { url, .. }
+            | Self::Content { url, .. } => url,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Removed { .. } => "removed",
+            Self::Added { .. } => "added",
+            Self::Descriptors { .. } => "redeclared",
+            Self::Content { .. } => "changed",
+        }
+    }
+}
+
+impl fmt::Display for LockMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Removed { url } => write!(f, "{url} is locked but no longer selected"),
+            Self::Added { url } => write!(f, "{url} is selected but not locked"),
+            Self::Descriptors { url, locked, live } => {
+                write!(f, "{url} is now declared as {live} (locked: {locked})")
+            }
+            Self::Content { url, locked, live } => {
+                write!(f, "{url} has SHA-256 {live} (locked: {locked})")
+            }
+        }
+    }
+}
+
+impl FontLock {
+    /// Locks the `fonts` that were saved, with the hashes of their files.
+    pub fn new(source: &str, fonts: &[FontInfo], saved_files: &[SavedFile]) -> Self {
+        let mut locked = Vec::<LockedFont>::new();
+        for font in fonts {
+            let Some(saved) = saved_files.iter().find(|saved| saved.url == font.url) else {
+                continue;
+            };
+            if locked.iter().any(|entry| entry.url == font.url) {
+                continue;
+            }
+            locked.push(LockedFont {
+                url: font.url.clone(),
+                family: font.family.clone(),
+                weight: font.weight.clone(),
+                style: font.style.clone(),
+                format: font.format.clone(),
+                sha256: saved.sha256.clone(),
+            });
+        }
+
+        Self {
+            version: LOCK_VERSION,
+            source: source.to_owned(),
+            fonts: locked,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LockError> {
+        let contents = fs::read_to_string(path).map_err(|source| LockError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let lock: Self = toml::from_str(&contents).map_err(|source| LockError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if lock.version > LOCK_VERSION {
+            return Err(LockError::Version {
+                path: path.to_path_buf(),
+                version: lock.version,
+            });
+        }
+        Ok(lock)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), LockError> {
+        let contents = toml::to_string(self).expect("lockfile serializes to TOML");
+        fs::write(path, format!("{LOCK_HEADER}{contents}")).map_err(|source| LockError::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Differences between the locked fonts and the `fonts` selected now.
+    pub fn compare_fonts(&self, fonts: &[FontInfo]) -> Vec<LockMismatch> {
+        self.compare(
+            fonts
+                .iter()
+                .map(|font| {
+                    let live = descriptors(&font.family, &font.weight, &font.style, &font.format);
+                    (font.url.as_str(), live, None)
+                })
+                .collect(),
+        )
+    }
+
+    /// Differences between this lock and a `newer` one, hashes included.
+    pub fn compare_lock(&self, newer: &FontLock) -> Vec<LockMismatch> {
+        self.compare(
+            newer
+                .fonts
+                .iter()
+                .map(|font| {
+                    (
+                        font.url.as_str(),
+                        font.descriptors(),
+                        Some(font.sha256.as_str()),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// `live` holds each URL with its descriptors and, when known, hash.
+    fn compare(&self, live: Vec<(&str, String, Option<&str>)>) -> Vec<LockMismatch> {
+        let mut mismatches = Vec::new();
+        for locked in &self.fonts {
+            let Some((_, descriptors, sha256)) = live.iter().find(|(url, ..)| *url == locked.url)
+            else {
+                mismatches.push(LockMismatch::Removed {
+                    url: locked.url.clone(),
+                });
+                continue;
+            };
+            if *descriptors != locked.descriptors() {
+                mismatches.push(LockMismatch::Descriptors {
+                    url: locked.url.clone(),
+                    locked: locked.descriptors(),
+                    live: descriptors.clone(),
+                });
+            }
+            if let Some(sha256) = sha256
+                && *sha256 != locked.sha256
+            {
+                mismatches.push(LockMismatch::Content {
+                    url: locked.url.clone(),
+                    locked: locked.sha256.clone(),
+                    live: (*sha256).to_owned(),
+                });
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for (url, ..) in &live {
+            if seen.insert(*url) && !self.fonts.iter().any(|locked| locked.url == *url) {
+                mismatches.push(LockMismatch::Added {
+                    url: (*url).to_owned(),
+                });
+            }
+        }
+        mismatches
+    }
+
+    /// Indices of the `fonts`, scanned again from [`source`](Self::source),
+    /// that an update locks: the locked URLs still declared, plus new faces
+    /// of the locked families in the formats already locked for them.
+    pub fn live_selection(&self, fonts: &[FontInfo]) -> Vec<usize> {
+        let locked_formats = self
+            .fonts
+            .iter()
+            .map(|locked| {
+                (
+                    locked.family.to_lowercase(),
+                    locked.format.to_ascii_uppercase(),
+                )
+            })
+            .collect::<HashSet<_>>();
+
+        fonts
+            .iter()
+            .enumerate()
+            .filter(|(_, font)| {
+                self.fonts.iter().any(|locked| locked.url == font.url)
+                    || locked_formats
+                        .contains(&(font.family.to_lowercase(), font.format.to_ascii_uppercase()))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Saved files whose bytes differ from the locked hash.
+    pub fn compare_saved(&self, saved_files: &[SavedFile]) -> Vec<LockMismatch> {
+        saved_files
+            .iter()
+            .filter_map(|saved| {
+                let locked = self.fonts.iter().find(|locked| locked.url == saved.url)?;
+                (locked.sha256 != saved.sha256).then(|| LockMismatch::Content {
+                    url: saved.url.clone(),
+                    locked: locked.sha256.clone(),
+                    live: saved.sha256.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{FontLock, LockMismatch};
+    use crate::download::SavedFile;
+    use crate::model::FontInfo;
+
+    fn font(name: &str, weight: &str) -> FontInfo {
+        FontInfo {
+            name: format!("{name}.woff2"),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: format!("https://cdn.example/{name}.woff2"),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    fn saved(font: &FontInfo, sha256: &str) -> SavedFile {
+        SavedFile {
+            path: PathBuf::from(&font.name),
+            url: font.url.clone(),
+            size: 4,
+            sha256: sha256.to_owned(),
+        }
+    }
+
+    #[test]
+    fn lockfiles_round_trip_and_report_drift() {
+        let regular = font("regular", "400");
+        let bold = font("bold", "700");
+        let lock = FontLock::new(
+            "https://www.example.com/",
+            &[regular.clone(), bold.clone()],
+            &[saved(&regular, "aa"), saved(&bold, "bb")],
+        );
+
+        let path = std::env::temp_dir().join(format!("typopotamus-{}.lock", std::process::id()));
+        lock.write(&path).expect("write lockfile");
+        let contents = fs::read_to_string(&path).expect("read lockfile");
+        assert!(contents.contains("[[font]]"));
+        assert_eq!(FontLock::load(&path).expect("load lockfile"), lock);
+        fs::remove_file(&path).expect("clean up lockfile");
+
+        assert!(
+            lock.compare_fonts(&[regular.clone(), bold.clone()])
+                .is_empty()
+        );
+
+        let mut heavier = regular.clone();
+        heavier.weight = "500".to_owned();
+        let added = font("italic", "400");
+        assert_eq!(
+            lock.compare_fonts(&[heavier, added.clone()]),
+            vec![
+                LockMismatch::Descriptors {
+                    url: regular.url.clone(),
+                    locked: "Brand 400 normal WOFF2".to_owned(),
+                    live: "Brand 500 normal WOFF2".to_owned(),
+                },
+                LockMismatch::Removed {
+                    url: bold.url.clone()
+                },
+                LockMismatch::Added { url: added.url },
+            ]
+        );
+
+        assert_eq!(
+            lock.compare_saved(&[saved(&regular, "aa"), saved(&bold, "cc")]),
+            vec![LockMismatch::Content {
+                url: bold.url,
+                locked: "bb".to_owned(),
+                live: "cc".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn updates_relock_new_faces_of_locked_families() {
+        let regular = font("regular", "400");
+        let bold = font("bold", "700");
+        let lock = FontLock::new(
+            "https://www.example.com/",
+            &[regular.clone(), bold.clone()],
+            &[saved(&regular, "aa"), saved(&bold, "bb")],
+        );
+
+        let mut italic = font("italic", "400");
+        italic.style = "italic".to_owned();
+        let mut legacy = font("regular-legacy", "400");
+        legacy.format = "WOFF".to_owned();
+        let mut other = font("mono", "400");
+        other.family = "Mono".to_owned();
+        let scanned = [other, regular.clone(), italic.clone(), legacy];
+        assert_eq!(lock.live_selection(&scanned), vec![1, 2]);
+
+        let newer = FontLock::new(
+            &lock.source,
+            &[regular.clone(), italic.clone()],
+            &[saved(&regular, "a2"), saved(&italic, "cc")],
+        );
+        let changes = lock.compare_lock(&newer);
+        assert_eq!(
+            changes.iter().map(LockMismatch::kind).collect::<Vec<_>>(),
+            ["changed", "removed", "added"]
+        );
+        assert_eq!(changes[2].url(), italic.url);
+    }
+}
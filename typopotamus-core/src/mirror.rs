@@ -0,0 +1,399 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use url::Url;
+
+use crate::cancel::CancellationToken;
+use crate::charset::{TextKind, decode_text};
+use crate::download::{DownloadReport, sanitize_component};
+use crate::error::{DownloadError, DownloadFailure};
+use crate::extractor::DEFAULT_USER_AGENT;
+use crate::fetch::header::{ACCEPT, CONTENT_TYPE, REFERER, USER_AGENT};
+use crate::fetch::{FetchRequest, Fetcher};
+use crate::fontface::{LocalFont, css_string, font_face_css, relative_href};
+use crate::model::FontInfo;
+
+/// Directory below the output root that mirrored stylesheets are saved to.
+pub const STYLESHEET_DIR: &str = "css";
+/// Entry stylesheet that imports every mirrored stylesheet.
+pub const INDEX_STYLESHEET: &str = "fonts.css";
+
+static CSS_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^'"()\s]*))\s*\)"#)
+        .expect("valid CSS url() regex")
+});
+static IMPORT_STRING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(@import\s+)(?:"([^"]*)"|'([^']*)')"#).expect("valid @import string regex")
+});
+static CHARSET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\A@charset "[^"]*";\s*"#).expect("valid @charset regex"));
+
+/// A declaring stylesheet saved with its references rewritten.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MirroredStylesheet {
+    pub url: String,
+    pub path: PathBuf,
+    /// `url(...)` and `@import` references now pointing at local files.
+    pub rewritten: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct MirrorReport {
+    pub stylesheets: Vec<MirroredStylesheet>,
+    /// Fonts given `@font-face` rules in the index because their stylesheet
+    /// could not be mirrored, such as inline `<style>` blocks.
+    pub generated_fonts: usize,
+    pub index_path: Option<PathBuf>,
+    pub failures: Vec<DownloadFailure>,
+    pub cancelled: bool,
+}
+
+/// Saves the stylesheets declaring `fonts` below `output_root`, with every
+/// reference to a font `downloaded` saved or kept (or to another mirrored
+/// stylesheet)
+/// rewritten to a relative local path, and writes an index stylesheet that
+/// imports them. Other references are made absolute so they keep working.
+pub fn mirror_stylesheets(
+    fonts: &[FontInfo],
+    downloaded: &DownloadReport,
+    output_root: &Path,
+    fetcher: &dyn Fetcher,
+    cancel: &CancellationToken,
+) -> MirrorReport {
+    let mut report = MirrorReport::default();
+    let local_fonts = downloaded
+        .local_files()
+        .map(|(url, path)| (url.to_owned(), relative_href(output_root, path)))
+        .collect::<HashMap<_, _>>();
+
+    let mut fetched = Vec::new();
+    let mut used_names = HashSet::new();
+    for font in fonts {
+        if cancel.is_cancelled() {
+            report.cancelled = true;
+            return report;
+        }
+        let Some(url) = stylesheet_url(&font.source_stylesheet) else {
+            continue;
+        };
+        if fetched
+            .iter()
+            .any(|(fetched_url, _, _)| *fetched_url == url)
+        {
+            continue;
+        }
+
+        match fetch_stylesheet(fetcher, &url, &font.referer) {
+            Ok(css) => {
+                let name = unique_stylesheet_name(&url, &mut used_names);
+                fetched.push((url, name, css));
+            }
+            Err(error) => report.failures.push(DownloadFailure {
+                name: font.source_stylesheet.clone(),
+                url: font.source_stylesheet.clone(),
+                error,
+            }),
+        }
+    }
+
+    let local_stylesheets = fetched
+        .iter()
+        .map(|(url, name, _)| (url.to_string(), name.clone()))
+        .collect::<HashMap<_, _>>();
+    let stylesheet_dir = output_root.join(STYLESHEET_DIR);
+    if !fetched.is_empty()
+        && let Err(source) = fs::create_dir_all(&stylesheet_dir)
+    {
+        report.failures.push(DownloadFailure {
+            name: String::new(),
+            url: String::new(),
+            error: DownloadError::CreateDirectory {
+                path: stylesheet_dir,
+                source,
+            },
+        });
+        return report;
+    }
+
+    for (url, name, css) in &fetched {
+        let (css, rewritten) = rewrite_stylesheet(css, url, &local_fonts, &local_stylesheets);
+        let path = stylesheet_dir.join(name);
+        match fs::write(&path, css) {
+            Ok(()) => report.stylesheets.push(MirroredStylesheet {
+                url: url.to_string(),
+                path,
+                rewritten,
+            }),
+            Err(source) => report.failures.push(DownloadFailure {
+                name: name.clone(),
+                url: url.to_string(),
+                error: DownloadError::Write { path, source },
+            }),
+        }
+    }
+
+    let mirrored = report
+        .stylesheets
+        .iter()
+        .map(|stylesheet| stylesheet.url.as_str())
+        .collect::<HashSet<_>>();
+    let mut index = String::new();
+    for stylesheet in &report.stylesheets {
+        index.push_str(&format!(
+            "@import \"{}\";\n",
+            css_string(&relative_href(output_root, &stylesheet.path))
+        ));
+    }
+    let generated = fonts
+        .iter()
+        .filter(|font| {
+            !stylesheet_url(&font.source_stylesheet)
+                .is_some_and(|url| mirrored.contains(url.as_str()))
+        })
+        .filter_map(|font| {
+            let href = local_fonts.get(&font.url)?;
+            Some(LocalFont { font, href })
+        })
+        .collect::<Vec<_>>();
+    if !generated.is_empty() {
+        index.push('\n');
+        index.push_str(&font_face_css(&generated));
+        report.generated_fonts = generated.len();
+    }
+
+    if !index.is_empty() {
+        let path = output_root.join(INDEX_STYLESHEET);
+        match fs::write(&path, index) {
+            Ok(()) => report.index_path = Some(path),
+            Err(source) => report.failures.push(DownloadFailure {
+                name: INDEX_STYLESHEET.to_owned(),
+                url: String::new(),
+                error: DownloadError::Write { path, source },
+            }),
+        }
+    }
+
+    report
+}
+
+fn stylesheet_url(source: &str) -> Option<Url> {
+    Url::parse(source)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+fn fetch_stylesheet(
+    fetcher: &dyn Fetcher,
+    url: &Url,
+    referer: &str,
+) -> Result<String, DownloadError> {
+    let mut request = FetchRequest::get(url.as_str())
+        .header(USER_AGENT, DEFAULT_USER_AGENT)
+        .header(ACCEPT, "text/css,*/*;q=0.1");
+    if !referer.is_empty() {
+        request = request.header(REFERER, referer);
+    }
+
+    let response = fetcher
+        .fetch(&request)
+        .map_err(|source| DownloadError::Request {
+            url: url.to_string(),
+            source,
+        })?;
+    if !response.is_success() {
+        return Err(DownloadError::Status {
+            url: url.to_string(),
+            status: response.status,
+        });
+    }
+
+    let decoded = decode_text(&response.body, response.header(CONTENT_TYPE), TextKind::Css);
+    // The mirror is written as UTF-8, so an old declaration would be wrong.
+    Ok(CHARSET_RE.replace(&decoded.text, "").into_owned())
+}
+
+fn unique_stylesheet_name(url: &Url, used_names: &mut HashSet<String>) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or_default();
+    let stem = sanitize_component(segment.strip_suffix(".css").unwrap_or(segment));
+    let stem = if stem.is_empty() {
+        sanitize_component(url.host_str().unwrap_or("stylesheet"))
+    } else {
+        stem
+    };
+
+    let mut name = format!("{stem}.css");
+    let mut counter = 1;
+    while !used_names.insert(name.clone()) {
+        name = format!("{stem}-{counter}.css");
+        counter += 1;
+    }
+    name
+}
+
+/// Points references at local copies where there is one and makes the rest
+/// absolute. Returns the new text and how many references became local.
+fn rewrite_stylesheet(
+    css: &str,
+    base: &Url,
+    local_fonts: &HashMap<String, String>,
+    local_stylesheets: &HashMap<String, String>,
+) -> (String, usize) {
+    let mut rewritten = 0;
+    let mut target = |raw: &str| -> Option<String> {
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with("data:") || raw.starts_with('#') {
+            return None;
+        }
+        let resolved = base.join(raw).ok()?.to_string();
+        if let Some(local) = local_fonts.get(&resolved) {
+            rewritten += 1;
+            Some(format!("../{local}"))
+        } else if let Some(local) = local_stylesheets.get(&resolved) {
+            rewritten += 1;
+            Some(local.clone())
+        } else {
+            Some(resolved)
+        }
+    };
+
+    let css = CSS_URL_RE.replace_all(css, |capture: &Captures<'_>| {
+        let raw = capture
+            .get(1)
+            .or_else(|| capture.get(2))
+            .or_else(|| capture.get(3))
+            .map_or("", |raw| raw.as_str());
+        match target(raw) {
+            Some(url) => format!("url(\"{}\")", css_string(&url)),
+            None => capture[0].to_owned(),
+        }
+    });
+    let css = IMPORT_STRING_RE.replace_all(&css, |capture: &Captures<'_>| {
+        let raw = capture
+            .get(2)
+            .or_else(|| capture.get(3))
+            .map_or("", |raw| raw.as_str());
+        match target(raw) {
+            Some(url) => format!("{}\"{}\"", &capture[1], css_string(&url)),
+            None => capture[0].to_owned(),
+        }
+    });
+
+    (css.into_owned(), rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{INDEX_STYLESHEET, mirror_stylesheets};
+    use crate::cancel::CancellationToken;
+    use crate::download::{DownloadReport, SavedFile, SkippedFile};
+    use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+    use crate::model::FontInfo;
+
+    struct StaticFetcher(HashMap<&'static str, &'static str>);
+
+    impl Fetcher for StaticFetcher {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            Ok(self
+                .0
+                .get(request.url.as_str())
+                .map(|body| FetchResponse::ok(*body))
+                .unwrap_or(FetchResponse {
+                    status: 404,
+                    ..FetchResponse::default()
+                }))
+        }
+    }
+
+    fn font(url: &str, stylesheet: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand Sans".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://example.com/".to_owned(),
+            source_stylesheet: stylesheet.to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn rewrites_stylesheets_to_downloaded_fonts_and_writes_an_index() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after epoch")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!(
+            "typopotamus-core-mirror-tests-{}-{nanos}",
+            std::process::id()
+        ));
+
+        let fetcher = StaticFetcher(HashMap::from([(
+            "https://example.com/assets/site.css",
+            "@charset \"utf-8\";\n@import 'https://cdn.example/reset.css';\n\
+             @font-face { font-family: 'Brand Sans'; src: url(../fonts/brand.woff2) format('woff2'), url(\"../fonts/brand.woff\"); }\n\
+             .hero { background: url(data:image/png;base64,AAAA); }",
+        )]));
+        let fonts = [
+            font(
+                "https://example.com/fonts/brand.woff2",
+                "https://example.com/assets/site.css",
+            ),
+            font(
+                "https://example.com/fonts/inline.woff2",
+                "inline <style> #1",
+            ),
+        ];
+        // The inline font was kept from an earlier run.
+        let downloaded = DownloadReport {
+            saved_files: vec![SavedFile {
+                path: root.join("brand-sans").join(&fonts[0].name),
+                url: fonts[0].url.clone(),
+                size: 0,
+                sha256: String::new(),
+            }],
+            skipped_files: vec![SkippedFile {
+                path: root.join("brand-sans").join(&fonts[1].name),
+                url: fonts[1].url.clone(),
+            }],
+            ..DownloadReport::default()
+        };
+
+        let report = mirror_stylesheets(
+            &fonts,
+            &downloaded,
+            &root,
+            &fetcher,
+            &CancellationToken::new(),
+        );
+
+        assert!(report.failures.is_empty());
+        assert_eq!(report.stylesheets.len(), 1);
+        assert_eq!(report.stylesheets[0].rewritten, 1);
+        assert_eq!(report.generated_fonts, 1);
+
+        let css = fs::read_to_string(root.join("css/site.css")).expect("mirrored stylesheet");
+        assert!(!css.contains("@charset"));
+        assert!(css.contains("@import \"https://cdn.example/reset.css\";"));
+        assert!(css.contains("url(\"../brand-sans/brand.woff2\") format('woff2')"));
+        assert!(css.contains("url(\"https://example.com/fonts/brand.woff\")"));
+        assert!(css.contains("url(data:image/png;base64,AAAA)"));
+
+        let index = fs::read_to_string(root.join(INDEX_STYLESHEET)).expect("index stylesheet");
+        assert!(index.starts_with("@import \"css/site.css\";\n"));
+        assert!(index.contains("src: url(\"brand-sans/inline.woff2\") format(\"woff2\");"));
+
+        fs::remove_dir_all(&root).expect("failed to clean up temp test directory");
+    }
+}
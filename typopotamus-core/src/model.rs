@@ -1,7 +1,12 @@
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+use sha2::{Digest, Sha256};
+
+use crate::selection::format_rank;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontInfo {
     pub name: String,
     pub family: String,
@@ -10,12 +15,245 @@ pub struct FontInfo {
     pub weight: String,
     pub style: String,
     pub referer: String,
+    /// Every `src` candidate of the declaring rule, best format first. The
+    /// first entry is the one `url` and `format` describe.
+    pub sources: Vec<FontSource>,
+    /// Where the font was declared: a stylesheet URL, or a label such as
+    /// `inline <style> #2` or `<link rel=preload>`.
+    pub source_stylesheet: String,
+    /// Position of the declaring `@font-face` block within that source.
+    pub source_location: Option<SourceLocation>,
+    /// Enclosing `@media`, `@supports`, and `@layer` rules, outermost first,
+    /// including those on the `@import` chain and `<link media>`.
+    pub conditions: Vec<String>,
+    /// Selectors whose `font-family` names this font's family.
+    pub used_by: Vec<String>,
+    /// Whether the declared family is used by the page. `None` when the
+    /// family was guessed from a file name (preloads, script URLs).
+    pub usage: Option<FontUsageStatus>,
+    /// Every scanned page that references this font.
+    pub found_on: Vec<String>,
+    /// The declared `font-display` descriptor.
+    pub font_display: Option<String>,
+    /// A `<link rel=preload as=font>` (or prefetch) hint points at the file.
+    pub preloaded: bool,
+    /// Every preload hint for the file sets `crossorigin`, which fonts need
+    /// for the preloaded copy to be used instead of downloaded again.
+    pub preload_crossorigin: bool,
+    /// File size from `Content-Length`, filled in by
+    /// [`enrich_fonts`](crate::enrich::enrich_fonts).
+    pub content_length: Option<u64>,
+    /// The `Last-Modified` response header, filled in by `enrich_fonts`.
+    pub last_modified: Option<String>,
+    /// The `Content-Type` response header, filled in by `enrich_fonts`.
+    pub content_type: Option<String>,
+    /// The `Cache-Control` response header, filled in by `enrich_fonts`.
+    pub cache_control: Option<String>,
+    /// The `Expires` response header, filled in by `enrich_fonts`.
+    pub expires: Option<String>,
+    /// The `Access-Control-Allow-Origin` response header, filled in by
+    /// `enrich_fonts`.
+    pub access_control_allow_origin: Option<String>,
+    /// Copyright and license comments written next to the `@font-face` rule
+    /// or at the top of its stylesheet.
+    pub license_notices: Vec<String>,
+    /// License and terms URLs from those comments, then the provider's.
+    pub license_urls: Vec<String>,
+    /// Declared descriptors that resize the face or override its metrics.
+    pub metric_overrides: MetricOverrides,
+    /// Every other declaration of the `@font-face` rule, such as
+    /// `unicode-range` or `font-feature-settings`, keyed by lowercase name.
+    pub extra_descriptors: BTreeMap<String, String>,
+}
+
+/// Hex digits in a [`FontInfo::stable_id`].
+const STABLE_ID_LENGTH: usize = 10;
+
+impl FontInfo {
+    /// A color or emoji font: some `src` candidate declares a color
+    /// technology, or the family is named as an emoji font.
+    pub fn is_color(&self) -> bool {
+        self.sources.iter().any(FontSource::is_color)
+            || self.family.to_ascii_lowercase().contains("emoji")
+    }
+
+    /// A short id hashed from the rule's first `src` URL in sort order,
+    /// family, weight, and style. Unlike an index it survives other fonts
+    /// being added to or removed from the site, and picking another `src`
+    /// format (which moves that candidate first) does not change it.
+    pub fn stable_id(&self) -> String {
+        let url = self
+            .sources
+            .iter()
+            .map(|source| source.url.as_str())
+            .min()
+            .unwrap_or(self.url.as_str());
+        let mut hasher = Sha256::new();
+        for part in [url, &self.family, &self.weight, &self.style] {
+            hasher.update(part.trim().as_bytes());
+            hasher.update([0]);
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()[..STABLE_ID_LENGTH]
+            .to_owned()
+    }
+}
+
+/// How a declared family is used, weakest first.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum FontUsageStatus {
+    /// No style rule names the family.
+    Unused,
+    /// Only rules whose classes or ids appear nowhere on the page name it.
+    UnmatchedSelectors,
+    Used,
+}
+
+impl FontUsageStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unused => "unused",
+            Self::UnmatchedSelectors => "unmatched-selectors",
+            Self::Used => "used",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontSource {
+    pub url: String,
+    pub format: String,
+    /// Technologies from `tech()`, or the `-variations` suffix of a legacy
+    /// format, such as `variations` or `color-COLRv1`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tech: Vec<String>,
+}
+
+impl FontSource {
+    /// Whether the source declares a color font technology (`color-COLRv0`,
+    /// `color-COLRv1`, `color-SVG`, `color-sbix`, or `color-CBDT`).
+    pub fn is_color(&self) -> bool {
+        self.tech
+            .iter()
+            .any(|tech| tech.to_ascii_lowercase().starts_with("color-"))
+    }
+}
+
+/// The `@font-face` descriptors that tune a face's vertical metrics and
+/// scale, as emitted for metric-compatible fallbacks by `next/font` and
+/// similar tools. Values are kept as declared, e.g. `90%` or `normal`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MetricOverrides {
+    pub ascent_override: Option<String>,
+    pub descent_override: Option<String>,
+    pub line_gap_override: Option<String>,
+    pub size_adjust: Option<String>,
+}
+
+impl MetricOverrides {
+    /// Descriptor names paired with their field, in declaration order.
+    pub const DESCRIPTORS: [&str; 4] = [
+        "ascent-override",
+        "descent-override",
+        "line-gap-override",
+        "size-adjust",
+    ];
+
+    /// Reads the descriptors out of `lookup`, which maps a lowercase
+    /// descriptor name to its value.
+    pub fn from_descriptors<'a>(lookup: impl Fn(&str) -> Option<&'a str>) -> Self {
+        let [ascent, descent, line_gap, size] = Self::DESCRIPTORS.map(|name| {
+            lookup(name)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        });
+        Self {
+            ascent_override: ascent,
+            descent_override: descent,
+            line_gap_override: line_gap,
+            size_adjust: size,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations().next().is_none()
+    }
+
+    /// `(descriptor, value)` for each override that is set.
+    pub fn declarations(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        Self::DESCRIPTORS
+            .into_iter()
+            .zip([
+                &self.ascent_override,
+                &self.descent_override,
+                &self.line_gap_override,
+                &self.size_adjust,
+            ])
+            .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+    }
+}
+
+/// A byte offset into a stylesheet and the 1-based line it falls on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    pub byte: usize,
+    pub line: usize,
+}
+
+impl SourceLocation {
+    pub fn in_text(text: &str, byte: usize) -> Self {
+        let line = text.as_bytes()[..byte.min(text.len())]
+            .iter()
+            .filter(|character| **character == b'\n')
+            .count()
+            + 1;
+        Self { byte, line }
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontFamily {
     pub name: String,
     pub font_indices: Vec<usize>,
+    pub faces: Vec<FontFace>,
+}
+
+/// One weight/style of a family, with every file that provides it.
+///
+/// `font_indices` is ordered by format preference, so the first index is the
+/// file to use when only one format is wanted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontFace {
+    pub weight: String,
+    pub style: String,
+    pub font_indices: Vec<usize>,
+}
+
+impl FontFace {
+    pub fn preferred_index(&self) -> Option<usize> {
+        self.font_indices.first().copied()
+    }
+}
+
+/// A font file as seen by [`group_faces`].
+pub struct FaceSource<'a> {
+    pub index: usize,
+    pub weight: &'a str,
+    pub style: &'a str,
+    pub format: &'a str,
 }
 
 pub fn sort_fonts(fonts: &mut [FontInfo]) {
@@ -31,7 +269,54 @@ pub fn group_by_family(fonts: &[FontInfo]) -> Vec<FontFamily> {
 
     grouped
         .into_iter()
-        .map(|(name, font_indices)| FontFamily { name, font_indices })
+        .map(|(name, font_indices)| {
+            let faces = group_faces(font_indices.iter().map(|index| {
+                let font = &fonts[*index];
+                FaceSource {
+                    index: *index,
+                    weight: &font.weight,
+                    style: &font.style,
+                    format: &font.format,
+                }
+            }));
+            FontFamily {
+                name,
+                font_indices,
+                faces,
+            }
+        })
+        .collect()
+}
+
+/// Collapses font files that share a weight and style into faces, ordered
+/// upright first and then by weight.
+pub fn group_faces<'a>(sources: impl IntoIterator<Item = FaceSource<'a>>) -> Vec<FontFace> {
+    type FaceKey<'a> = (u8, i32, &'a str, &'a str);
+    let mut grouped: BTreeMap<FaceKey<'a>, Vec<(usize, usize)>> = BTreeMap::new();
+
+    for source in sources {
+        let key = (
+            is_italic(source.style),
+            weight_value(source.weight),
+            source.weight,
+            source.style,
+        );
+        grouped
+            .entry(key)
+            .or_default()
+            .push((format_rank(source.format), source.index));
+    }
+
+    grouped
+        .into_iter()
+        .map(|((_, _, weight, style), mut ranked)| {
+            ranked.sort_unstable();
+            FontFace {
+                weight: weight.to_owned(),
+                style: style.to_owned(),
+                font_indices: ranked.into_iter().map(|(_, index)| index).collect(),
+            }
+        })
         .collect()
 }
 
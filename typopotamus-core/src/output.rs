@@ -0,0 +1,724 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::download::embedded_font_size;
+use crate::error::InspectJsonError;
+use crate::fontsource::FontsourcePackage;
+use crate::google_fonts::{google_fonts_family, specimen_url};
+use crate::inspect::{FamilyMerge, GroupBy, InferredFamilyGroup, InferredFontEntry};
+use crate::local::MissingFont;
+use crate::model::{FontInfo, FontSource, FontUsageStatus, MetricOverrides, SourceLocation};
+use crate::provider::FontProvider;
+
+/// Version of the inspect and download JSON documents, bumped whenever a
+/// field is renamed, removed, or changes meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Which listing an inspect document fills in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum InspectView {
+    Family,
+    Face,
+    Font,
+}
+
+impl InspectView {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Family => "family",
+            Self::Face => "face",
+            Self::Font => "font",
+        }
+    }
+}
+
+/// The `inspect --format json` document.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InspectOutput {
+    /// Bumped whenever a field is renamed, removed, or changes meaning.
+    pub schema_version: u32,
+    pub source: String,
+    pub pages: Vec<String>,
+    pub total_found: usize,
+    pub selected_count: usize,
+    pub view: InspectView,
+    pub group_by: GroupBy,
+    pub family_count: usize,
+    pub families: Vec<FamilyOutput>,
+    pub faces: Vec<FaceOutput>,
+    pub fonts: Vec<FontOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fontsource: Vec<FontsourceOutput>,
+    /// Font files referenced by a `--dir` scan that are not in the directory.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<MissingFontOutput>,
+}
+
+impl InspectOutput {
+    /// The document for `groups`, listing them as `view` asks. Indices in
+    /// the groups point into `all_fonts`.
+    pub fn new(
+        source_urls: &[String],
+        all_fonts: &[FontInfo],
+        view: InspectView,
+        group_by: GroupBy,
+        groups: Vec<InferredFamilyGroup>,
+    ) -> Self {
+        let selected_count = groups.iter().map(|group| group.files).sum();
+        let families = groups
+            .iter()
+            .map(|group| FamilyOutput::new(group, group_by))
+            .collect::<Vec<_>>();
+        let family_count = families.len();
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            source: source_urls.join(", "),
+            pages: source_urls.to_vec(),
+            total_found: all_fonts.len(),
+            selected_count,
+            view,
+            group_by,
+            family_count,
+            families: if view == InspectView::Family {
+                families
+            } else {
+                Vec::new()
+            },
+            faces: if view == InspectView::Face {
+                groups.iter().flat_map(FaceOutput::for_group).collect()
+            } else {
+                Vec::new()
+            },
+            fonts: if view == InspectView::Font {
+                groups
+                    .into_iter()
+                    .flat_map(|group| {
+                        let family = group.name;
+                        group
+                            .fonts
+                            .into_iter()
+                            .map(move |font| FontOutput::new(&family, font, all_fonts))
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            fontsource: Vec::new(),
+            missing: Vec::new(),
+        }
+    }
+
+    /// The document for a scan that found no fonts.
+    pub fn empty(source_urls: &[String], view: InspectView, group_by: GroupBy) -> Self {
+        Self::new(source_urls, &[], view, group_by, Vec::new())
+    }
+}
+
+/// The fonts of a saved inspect document in the font view, the only view
+/// that lists every field of each file.
+#[derive(Clone, Debug)]
+pub struct InspectFonts {
+    pub pages: Vec<String>,
+    /// In the order of their original indices. Preload hints and
+    /// `font-display` are not saved.
+    pub fonts: Vec<FontInfo>,
+    /// The index each font had in the scan, which has gaps when the
+    /// document was filtered.
+    pub indices: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct VersionInput {
+    schema_version: u32,
+}
+
+#[derive(Deserialize)]
+struct InspectInput {
+    pages: Vec<String>,
+    view: InspectView,
+    fonts: Vec<FontOutput>,
+}
+
+impl InspectFonts {
+    pub fn from_json(json: &str) -> Result<Self, InspectJsonError> {
+        let version = serde_json::from_str::<VersionInput>(json)
+            .map_err(|source| InspectJsonError::Parse { source })?
+            .schema_version;
+        if version > SCHEMA_VERSION {
+            return Err(InspectJsonError::Version { version });
+        }
+
+        let input = serde_json::from_str::<InspectInput>(json)
+            .map_err(|source| InspectJsonError::Parse { source })?;
+        if input.view != InspectView::Font {
+            return Err(InspectJsonError::View {
+                view: input.view.as_str(),
+            });
+        }
+
+        let mut rows = input.fonts;
+        rows.sort_by_key(|row| row.index);
+        Ok(Self {
+            pages: input.pages,
+            indices: rows.iter().map(|row| row.index).collect(),
+            fonts: rows.into_iter().map(FontInfo::from).collect(),
+        })
+    }
+
+    /// Reads the document at `path`, or from standard input for `-`.
+    pub fn read(path: &Path) -> Result<Self, InspectJsonError> {
+        let read = if path == Path::new("-") {
+            let mut json = String::new();
+            io::stdin().read_to_string(&mut json).map(|_| json)
+        } else {
+            fs::read_to_string(path)
+        };
+        let json = read.map_err(|source| InspectJsonError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_json(&json)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MissingFontOutput {
+    pub url: String,
+    pub path: String,
+    pub referenced_from: Vec<String>,
+}
+
+impl MissingFontOutput {
+    pub fn new(missing: &MissingFont) -> Self {
+        Self {
+            url: missing.url.clone(),
+            path: missing.path.display().to_string(),
+            referenced_from: missing.referenced_from.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FontsourceOutput {
+    pub family: String,
+    pub package: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable_package: Option<String>,
+    pub weights: Vec<u16>,
+    pub styles: Vec<String>,
+    /// Weights the site serves that neither package provides.
+    pub missing_weights: Vec<String>,
+}
+
+impl FontsourceOutput {
+    /// How `package`, if Fontsource has one, covers the group's weights.
+    pub fn new(group: &InferredFamilyGroup, package: Option<FontsourcePackage>) -> Self {
+        let Some(package) = package else {
+            return Self {
+                family: group.name.clone(),
+                package: None,
+                variable_package: None,
+                weights: Vec::new(),
+                styles: Vec::new(),
+                missing_weights: Vec::new(),
+            };
+        };
+
+        let missing_weights = group
+            .weights
+            .iter()
+            .filter(|weight| {
+                weight
+                    .parse::<u16>()
+                    .is_ok_and(|weight| !package_covers_weight(&package, weight))
+            })
+            .cloned()
+            .collect();
+        Self {
+            family: group.name.clone(),
+            package: Some(package.npm_package()),
+            variable_package: package.variable_npm_package(),
+            weights: package.weights,
+            styles: package.styles,
+            missing_weights,
+        }
+    }
+}
+
+/// Static packages ship listed weights; variable ones anything in between.
+fn package_covers_weight(package: &FontsourcePackage, weight: u16) -> bool {
+    if package.variable
+        && let (Some(min), Some(max)) = (package.weights.iter().min(), package.weights.iter().max())
+    {
+        return (*min..=*max).contains(&weight);
+    }
+    package.weights.contains(&weight)
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FamilyMergeOutput {
+    pub name: String,
+    pub hosts: Vec<String>,
+    pub reason: &'static str,
+}
+
+impl From<&FamilyMerge> for FamilyMergeOutput {
+    fn from(merge: &FamilyMerge) -> Self {
+        Self {
+            name: merge.name.clone(),
+            hosts: merge.hosts.clone(),
+            reason: merge.reason.as_str(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FamilyOutput {
+    pub key: String,
+    pub name: String,
+    pub aliases: Vec<String>,
+    /// Groups from other hosts folded into this family.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub merged: Vec<FamilyMergeOutput>,
+    pub files: usize,
+    pub variants: usize,
+    pub weights: Vec<String>,
+    pub styles: Vec<String>,
+    pub formats: Vec<String>,
+    pub indices: Vec<usize>,
+    pub index_ranges: Vec<String>,
+    pub used_by: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<&'static str>,
+    pub found_on: Vec<String>,
+    pub providers: Vec<&'static str>,
+    /// Open-source Google Fonts family with the same name, for self-hosted
+    /// families.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub google_fonts: Option<GoogleFontsOutput>,
+}
+
+impl FamilyOutput {
+    /// Google Fonts equivalents are only looked up when `group_by` groups
+    /// by family.
+    pub fn new(group: &InferredFamilyGroup, group_by: GroupBy) -> Self {
+        Self {
+            key: group.key.clone(),
+            name: group.name.clone(),
+            aliases: group.aliases.clone(),
+            merged: group.merged.iter().map(FamilyMergeOutput::from).collect(),
+            files: group.files,
+            variants: group.variants,
+            weights: group.weights.clone(),
+            styles: group.styles.clone(),
+            formats: group.formats.clone(),
+            indices: group.font_indices.clone(),
+            index_ranges: group.index_ranges.clone(),
+            used_by: group.used_by.clone(),
+            usage: group.usage.map(FontUsageStatus::as_str),
+            found_on: group.found_on.clone(),
+            providers: group
+                .providers
+                .iter()
+                .map(|provider| provider.as_str())
+                .collect(),
+            google_fonts: matches!(group_by, GroupBy::InferredFamily | GroupBy::CssFamily)
+                .then(|| GoogleFontsOutput::for_group(group))
+                .flatten(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GoogleFontsOutput {
+    pub family: &'static str,
+    pub specimen_url: String,
+}
+
+impl GoogleFontsOutput {
+    /// The Google Fonts family a self-hosted group's name or aliases match.
+    pub fn for_group(group: &InferredFamilyGroup) -> Option<Self> {
+        if !group.providers.contains(&FontProvider::SelfHosted) {
+            return None;
+        }
+
+        std::iter::once(&group.name)
+            .chain(&group.aliases)
+            .find_map(|name| google_fonts_family(name))
+            .map(|family| Self {
+                family,
+                specimen_url: specimen_url(family),
+            })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FaceOutput {
+    pub family: String,
+    pub weight: String,
+    pub style: String,
+    pub formats: Vec<String>,
+    pub indices: Vec<usize>,
+    pub sources: Vec<FaceSourceOutput>,
+}
+
+impl FaceOutput {
+    /// One entry per face of `group`, best format first.
+    pub fn for_group(group: &InferredFamilyGroup) -> Vec<Self> {
+        group
+            .faces
+            .iter()
+            .map(|face| Self {
+                family: group.name.clone(),
+                weight: face.weight.clone(),
+                style: face.style.clone(),
+                formats: face.formats.clone(),
+                indices: face.font_indices.clone(),
+                sources: face
+                    .fonts
+                    .iter()
+                    .map(|font| FaceSourceOutput {
+                        index: font.index,
+                        name: font.name.clone(),
+                        format: font.format.clone(),
+                        url: font.url.clone(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FaceSourceOutput {
+    pub index: usize,
+    pub name: String,
+    pub format: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SourceOutput {
+    pub url: String,
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tech: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FontOutput {
+    pub index: usize,
+    pub id: String,
+    pub family: String,
+    pub source_family: String,
+    pub name: String,
+    pub weight: String,
+    pub style: String,
+    /// The `font-weight` descriptor, before inference.
+    #[serde(default)]
+    pub source_weight: String,
+    /// The `font-style` descriptor, before inference.
+    #[serde(default)]
+    pub source_style: String,
+    pub format: String,
+    pub url: String,
+    pub referer: String,
+    pub sources: Vec<SourceOutput>,
+    pub source_stylesheet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_offset: Option<usize>,
+    pub conditions: Vec<String>,
+    pub used_by: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<String>,
+    pub found_on: Vec<String>,
+    pub provider: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedded_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_control_allow_origin: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_notices: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_urls: Vec<String>,
+    /// A color or emoji font.
+    pub color: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric_overrides: Option<MetricOverridesOutput>,
+    /// `@font-face` declarations without a field of their own.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_descriptors: BTreeMap<String, String>,
+}
+
+impl FontOutput {
+    /// A row for `font` of the group named `family`; response headers and
+    /// descriptors not kept on the entry are read from `all_fonts`.
+    pub fn new(family: &str, font: InferredFontEntry, all_fonts: &[FontInfo]) -> Self {
+        let info = all_fonts.get(font.index);
+        Self {
+            index: font.index,
+            id: info.map(FontInfo::stable_id).unwrap_or_default(),
+            embedded_size: embedded_font_size(&font.url),
+            content_length: info.and_then(|info| info.content_length),
+            last_modified: info.and_then(|info| info.last_modified.clone()),
+            content_type: info.and_then(|info| info.content_type.clone()),
+            cache_control: info.and_then(|info| info.cache_control.clone()),
+            expires: info.and_then(|info| info.expires.clone()),
+            access_control_allow_origin: info
+                .and_then(|info| info.access_control_allow_origin.clone()),
+            license_notices: info
+                .map(|info| info.license_notices.clone())
+                .unwrap_or_default(),
+            license_urls: info
+                .map(|info| info.license_urls.clone())
+                .unwrap_or_default(),
+            color: info.is_some_and(FontInfo::is_color),
+            metric_overrides: info
+                .map(|info| &info.metric_overrides)
+                .filter(|overrides| !overrides.is_empty())
+                .map(MetricOverridesOutput::from),
+            extra_descriptors: info
+                .map(|info| info.extra_descriptors.clone())
+                .unwrap_or_default(),
+            source_weight: info.map(|info| info.weight.clone()).unwrap_or_default(),
+            source_style: info.map(|info| info.style.clone()).unwrap_or_default(),
+            family: family.to_owned(),
+            source_family: font.source_family,
+            name: font.name,
+            weight: font.weight,
+            style: font.style,
+            format: font.format,
+            url: font.url,
+            referer: font.referer,
+            sources: font
+                .sources
+                .into_iter()
+                .map(|source| SourceOutput {
+                    url: source.url,
+                    format: source.format,
+                    tech: source.tech,
+                })
+                .collect(),
+            source_stylesheet: font.source_stylesheet,
+            source_line: font.source_location.map(|location| location.line),
+            source_offset: font.source_location.map(|location| location.byte),
+            conditions: font.conditions,
+            used_by: font.used_by,
+            usage: font.usage.map(|usage| usage.as_str().to_owned()),
+            found_on: font.found_on,
+            provider: font.provider.as_str().to_owned(),
+        }
+    }
+}
+
+impl From<FontOutput> for FontInfo {
+    /// Documents saved before the descriptors were recorded fall back to
+    /// the inferred weight and style.
+    fn from(row: FontOutput) -> Self {
+        let or_inferred = |source: String, inferred: String| {
+            if source.is_empty() { inferred } else { source }
+        };
+        Self {
+            name: row.name,
+            family: row.source_family,
+            format: row.format,
+            url: row.url,
+            weight: or_inferred(row.source_weight, row.weight),
+            style: or_inferred(row.source_style, row.style),
+            referer: row.referer,
+            sources: row
+                .sources
+                .into_iter()
+                .map(|source| FontSource {
+                    url: source.url,
+                    format: source.format,
+                    tech: source.tech,
+                })
+                .collect(),
+            source_stylesheet: row.source_stylesheet,
+            source_location: row
+                .source_line
+                .zip(row.source_offset)
+                .map(|(line, byte)| SourceLocation { byte, line }),
+            conditions: row.conditions,
+            used_by: row.used_by,
+            usage: row.usage.as_deref().and_then(|usage| {
+                [
+                    FontUsageStatus::Unused,
+                    FontUsageStatus::UnmatchedSelectors,
+                    FontUsageStatus::Used,
+                ]
+                .into_iter()
+                .find(|status| status.as_str() == usage)
+            }),
+            found_on: row.found_on,
+            font_display: None,
+            preloaded: false,
+            preload_crossorigin: false,
+            content_length: row.content_length,
+            last_modified: row.last_modified,
+            content_type: row.content_type,
+            cache_control: row.cache_control,
+            expires: row.expires,
+            access_control_allow_origin: row.access_control_allow_origin,
+            license_notices: row.license_notices,
+            license_urls: row.license_urls,
+            metric_overrides: row
+                .metric_overrides
+                .map(|overrides| MetricOverrides {
+                    ascent_override: overrides.ascent_override,
+                    descent_override: overrides.descent_override,
+                    line_gap_override: overrides.line_gap_override,
+                    size_adjust: overrides.size_adjust,
+                })
+                .unwrap_or_default(),
+            extra_descriptors: row.extra_descriptors,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MetricOverridesOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ascent_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descent_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_gap_override: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_adjust: Option<String>,
+}
+
+impl From<&MetricOverrides> for MetricOverridesOutput {
+    fn from(overrides: &MetricOverrides) -> Self {
+        Self {
+            ascent_override: overrides.ascent_override.clone(),
+            descent_override: overrides.descent_override.clone(),
+            line_gap_override: overrides.line_gap_override.clone(),
+            size_adjust: overrides.size_adjust.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InspectFonts, InspectOutput, InspectView, SCHEMA_VERSION};
+    use crate::error::InspectJsonError;
+    use crate::inspect::{GroupBy, Inference, infer_family_groups, infer_family_groups_all};
+    use crate::model::FontInfo;
+
+    fn font(weight: &str) -> FontInfo {
+        FontInfo {
+            name: format!("brand-{weight}.woff2"),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: format!("https://www.example.com/fonts/brand-{weight}.woff2"),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            content_length: Some(1200),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn fills_only_the_requested_listing() {
+        let fonts = [font("400"), font("700")];
+        let groups = || infer_family_groups_all(&fonts, &Inference::default());
+        let sources = ["https://www.example.com/".to_owned()];
+
+        let families = InspectOutput::new(
+            &sources,
+            &fonts,
+            InspectView::Family,
+            GroupBy::InferredFamily,
+            groups(),
+        );
+        assert_eq!(families.schema_version, SCHEMA_VERSION);
+        assert_eq!(families.family_count, 1);
+        assert_eq!(families.families[0].indices, vec![0, 1]);
+        assert!(families.faces.is_empty() && families.fonts.is_empty());
+
+        let rows = InspectOutput::new(
+            &sources,
+            &fonts,
+            InspectView::Font,
+            GroupBy::InferredFamily,
+            groups(),
+        );
+        assert_eq!(rows.selected_count, 2);
+        assert_eq!(rows.fonts[1].family, "Brand");
+        assert_eq!(rows.fonts[1].id, fonts[1].stable_id());
+        assert_eq!(rows.fonts[1].content_length, Some(1200));
+
+        let json = serde_json::to_value(&rows).expect("serialize inspect output");
+        assert_eq!(json["view"], "font");
+        assert_eq!(json["group_by"], "family");
+        assert!(json.get("missing").is_none());
+    }
+
+    #[test]
+    fn reads_fonts_back_from_the_font_view() {
+        // Inferred as 700, but the ID hashes the descriptor as declared.
+        let mut fonts = [font("400"), font("bold")];
+        fonts[1].license_urls = vec!["https://www.example.com/license".to_owned()];
+        let sources = ["https://www.example.com/".to_owned()];
+        let document = |view, indices: &[usize]| {
+            let groups = infer_family_groups(&fonts, indices, &Inference::default());
+            let output =
+                InspectOutput::new(&sources, &fonts, view, GroupBy::InferredFamily, groups);
+            serde_json::to_string(&output).expect("serialize inspect output")
+        };
+
+        let read =
+            InspectFonts::from_json(&document(InspectView::Font, &[0, 1])).expect("read fonts");
+        assert_eq!(read.pages, sources);
+        assert_eq!(read.fonts, fonts);
+        assert_eq!(read.indices, vec![0, 1]);
+
+        let filtered =
+            InspectFonts::from_json(&document(InspectView::Font, &[1])).expect("read fonts");
+        assert_eq!(filtered.indices, vec![1]);
+        assert_eq!(filtered.fonts[0].stable_id(), fonts[1].stable_id());
+
+        assert!(matches!(
+            InspectFonts::from_json(&document(InspectView::Face, &[0, 1])),
+            Err(InspectJsonError::View { view: "face" })
+        ));
+        assert!(matches!(
+            InspectFonts::from_json(r#"{"schema_version": 99}"#),
+            Err(InspectJsonError::Version { version: 99 })
+        ));
+    }
+}
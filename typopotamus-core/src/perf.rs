@@ -0,0 +1,632 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use url::Url;
+
+use crate::audit::{above_the_fold_selector, is_regular_face};
+use crate::download::embedded_font_size;
+use crate::model::{FontInfo, FontUsageStatus};
+
+/// Bytes of font files a page may load before the budget check fails.
+pub const DEFAULT_PERF_BUDGET_BYTES: u64 = 250 * 1024;
+
+/// Faces of one family a page may use before the variant check warns.
+pub const DEFAULT_MAX_VARIANTS: usize = 4;
+
+/// Share of the budget, in percent, above which the budget check warns.
+const BUDGET_WARN_PERCENT: u64 = 80;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum PerfStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl PerfStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PerfCheck {
+    /// Preload hints match declared fonts, and the faces above the fold
+    /// are preloaded.
+    Preload,
+    /// Every face sets a `font-display` that shows fallback text.
+    FontDisplay,
+    /// No face is downloaded twice in different files or formats.
+    DuplicateDownloads,
+    /// Fonts from another origin send `Access-Control-Allow-Origin` for the
+    /// page, and font preloads set `crossorigin`.
+    Cors,
+    /// No family uses more than [`PerfOptions::max_variants`] faces.
+    Variants,
+    /// The files the page loads fit [`PerfOptions::budget_bytes`].
+    Budget,
+}
+
+impl PerfCheck {
+    pub const ALL: [Self; 6] = [
+        Self::Preload,
+        Self::FontDisplay,
+        Self::DuplicateDownloads,
+        Self::Cors,
+        Self::Variants,
+        Self::Budget,
+    ];
+
+    /// Stable kebab-case id, for JSON output.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Preload => "preload",
+            Self::FontDisplay => "font-display",
+            Self::DuplicateDownloads => "duplicate-downloads",
+            Self::Cors => "cors",
+            Self::Variants => "variants",
+            Self::Budget => "budget",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PerfFinding {
+    pub check: PerfCheck,
+    pub status: PerfStatus,
+    pub summary: String,
+    /// One line per offending font, family, or hint.
+    pub details: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PerfReport {
+    /// One finding per check, in [`PerfCheck::ALL`] order.
+    pub findings: Vec<PerfFinding>,
+    /// Bytes of the files the page loads whose size is known.
+    pub total_bytes: u64,
+    /// Files the page loads whose size is unknown.
+    pub unmeasured: usize,
+}
+
+impl PerfReport {
+    /// The worst status of any check.
+    pub fn status(&self) -> PerfStatus {
+        self.findings
+            .iter()
+            .map(|finding| finding.status)
+            .max()
+            .unwrap_or(PerfStatus::Pass)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PerfOptions {
+    pub budget_bytes: u64,
+    pub max_variants: usize,
+}
+
+impl Default for PerfOptions {
+    fn default() -> Self {
+        Self {
+            budget_bytes: DEFAULT_PERF_BUDGET_BYTES,
+            max_variants: DEFAULT_MAX_VARIANTS,
+        }
+    }
+}
+
+/// Checks how a page loads its fonts. Sizes and response headers come from
+/// [`enrich_fonts`](crate::enrich::enrich_fonts), and sizes also from
+/// embedded `data:` URLs; files of unknown size are counted in
+/// [`PerfReport::unmeasured`] instead of the total.
+pub fn check_font_performance(fonts: &[FontInfo], options: &PerfOptions) -> PerfReport {
+    let (total_bytes, unmeasured) = transfer_weight(fonts);
+    let findings = vec![
+        check_preloads(fonts),
+        check_font_display(fonts),
+        check_duplicate_downloads(fonts),
+        check_cors(fonts),
+        check_variants(fonts, options.max_variants),
+        check_budget(total_bytes, unmeasured, options.budget_bytes),
+    ];
+    PerfReport {
+        findings,
+        total_bytes,
+        unmeasured,
+    }
+}
+
+fn finding(check: PerfCheck, status: PerfStatus, summary: String) -> PerfFinding {
+    PerfFinding {
+        check,
+        status,
+        summary,
+        details: Vec::new(),
+    }
+}
+
+fn is_used(font: &FontInfo) -> bool {
+    font.usage == Some(FontUsageStatus::Used)
+}
+
+/// A preload hint no `@font-face` declares: the guessed family comes from
+/// the file name, so `usage` is unknown.
+fn is_orphan_preload(font: &FontInfo) -> bool {
+    font.usage.is_none() && font.preloaded
+}
+
+fn label(font: &FontInfo) -> String {
+    format!("{} {} {}", font.family, font.weight, font.style)
+}
+
+fn check_preloads(fonts: &[FontInfo]) -> PerfFinding {
+    let mut details = Vec::new();
+    let fallback_urls = fonts
+        .iter()
+        .flat_map(|font| font.sources.iter().skip(1))
+        .map(|source| source.url.as_str())
+        .collect::<BTreeSet<_>>();
+    for font in fonts.iter().filter(|font| is_orphan_preload(font)) {
+        if !fallback_urls.contains(font.url.as_str()) {
+            details.push(format!(
+                "{} is preloaded but no @font-face uses it",
+                font.url
+            ));
+        }
+    }
+    let orphans = details.len();
+
+    for font in fonts.iter().filter(|font| is_used(font) && !font.preloaded) {
+        if let Some(selector) = above_the_fold_selector(font)
+            && is_regular_face(font)
+        {
+            details.push(format!(
+                "{} is used by {selector} but not preloaded",
+                label(font)
+            ));
+        }
+    }
+
+    let preloaded = fonts
+        .iter()
+        .filter(|font| font.preloaded && font.usage.is_some())
+        .count();
+    let status = if details.is_empty() {
+        PerfStatus::Pass
+    } else {
+        PerfStatus::Warn
+    };
+    let summary = if details.is_empty() {
+        format!("{preloaded} preloaded font(s) match declared faces")
+    } else {
+        format!(
+            "{orphans} unused preload(s), {} face(s) above the fold not preloaded",
+            details.len() - orphans
+        )
+    };
+    PerfFinding {
+        details,
+        ..finding(PerfCheck::Preload, status, summary)
+    }
+}
+
+fn check_font_display(fonts: &[FontInfo]) -> PerfFinding {
+    let mut details = Vec::new();
+    let declared = fonts.iter().filter(|font| font.usage.is_some());
+    for font in declared.clone() {
+        match font.font_display.as_deref().map(str::trim) {
+            None => details.push(format!("{} has no font-display", label(font))),
+            Some(display) if display.eq_ignore_ascii_case("block") => details.push(format!(
+                "{} uses font-display: block, hiding text while it loads",
+                label(font)
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let total = declared.count();
+    let (status, summary) = if details.is_empty() {
+        (
+            PerfStatus::Pass,
+            format!("all {total} declared face(s) show fallback text while loading"),
+        )
+    } else {
+        (
+            PerfStatus::Warn,
+            format!(
+                "{} of {total} declared face(s) may hide text while loading",
+                details.len()
+            ),
+        )
+    };
+    PerfFinding {
+        details,
+        ..finding(PerfCheck::FontDisplay, status, summary)
+    }
+}
+
+fn check_duplicate_downloads(fonts: &[FontInfo]) -> PerfFinding {
+    let mut failures = Vec::new();
+    for preload in fonts.iter().filter(|font| is_orphan_preload(font)) {
+        if let Some(font) = fonts.iter().find(|font| {
+            is_used(font)
+                && font
+                    .sources
+                    .iter()
+                    .skip(1)
+                    .any(|source| source.url == preload.url)
+        }) {
+            failures.push(format!(
+                "{} preloads {} but browsers download {} for it",
+                label(font),
+                preload.url,
+                font.url
+            ));
+        }
+    }
+
+    let mut faces = BTreeMap::<(String, &str, &str, Option<&str>, String), BTreeSet<&str>>::new();
+    for font in fonts.iter().filter(|font| is_used(font)) {
+        let key = (
+            font.family.trim().to_lowercase(),
+            font.weight.trim(),
+            font.style.trim(),
+            font.extra_descriptors
+                .get("unicode-range")
+                .map(String::as_str),
+            font.conditions.join(" "),
+        );
+        faces.entry(key).or_default().insert(&font.url);
+    }
+    let warnings = faces
+        .iter()
+        .filter(|(_, urls)| urls.len() > 1)
+        .map(|((family, weight, style, _, _), urls)| {
+            format!(
+                "{family} {weight} {style} is declared with {} files: {}",
+                urls.len(),
+                urls.iter().copied().collect::<Vec<_>>().join(", ")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let status = if !failures.is_empty() {
+        PerfStatus::Fail
+    } else if !warnings.is_empty() {
+        PerfStatus::Warn
+    } else {
+        PerfStatus::Pass
+    };
+    let summary = match status {
+        PerfStatus::Pass => "each face is downloaded once".to_owned(),
+        _ => format!(
+            "{} face(s) downloaded in more than one file or format",
+            failures.len() + warnings.len()
+        ),
+    };
+    failures.extend(warnings);
+    PerfFinding {
+        details: failures,
+        ..finding(PerfCheck::DuplicateDownloads, status, summary)
+    }
+}
+
+fn check_cors(fonts: &[FontInfo]) -> PerfFinding {
+    let mut failures = Vec::new();
+    let mut warnings = Vec::new();
+    let mut cross_origin_files = 0;
+    let mut unchecked = 0;
+    let mut urls = BTreeSet::new();
+    for font in fonts.iter().filter(|font| is_used(font) || font.preloaded) {
+        if font.url.starts_with("data:") || !urls.insert(font.url.as_str()) {
+            continue;
+        }
+        let Some(page_origin) = origin(&font.referer) else {
+            continue;
+        };
+        let cross_origin = origin(&font.url).is_some_and(|origin| origin != page_origin);
+
+        if cross_origin {
+            cross_origin_files += 1;
+            if !has_response_headers(font) {
+                unchecked += 1;
+            } else {
+                match font.access_control_allow_origin.as_deref().map(str::trim) {
+                    None => failures.push(format!(
+                        "{} is served from another origin without Access-Control-Allow-Origin",
+                        font.url
+                    )),
+                    Some(allowed) if allowed != "*" && allowed != page_origin => {
+                        failures.push(format!(
+                            "{} allows {allowed}, not the page's origin {page_origin}",
+                            font.url
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if font.preloaded && !font.preload_crossorigin {
+            let message = format!(
+                "{} is preloaded without crossorigin, so the font downloads it again",
+                font.url
+            );
+            if cross_origin {
+                failures.push(message);
+            } else {
+                warnings.push(message);
+            }
+        }
+    }
+
+    let status = if !failures.is_empty() {
+        PerfStatus::Fail
+    } else if !warnings.is_empty() {
+        PerfStatus::Warn
+    } else {
+        PerfStatus::Pass
+    };
+    let mut summary = match status {
+        PerfStatus::Pass => format!("{cross_origin_files} cross-origin file(s) allow the page"),
+        _ => format!(
+            "{} CORS problem(s) across {cross_origin_files} cross-origin file(s)",
+            failures.len() + warnings.len()
+        ),
+    };
+    if unchecked > 0 {
+        summary.push_str(&format!(", {unchecked} without response headers"));
+    }
+    failures.extend(warnings);
+    PerfFinding {
+        details: failures,
+        ..finding(PerfCheck::Cors, status, summary)
+    }
+}
+
+fn origin(url: &str) -> Option<String> {
+    let origin = Url::parse(url).ok()?.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
+
+/// Whether `enrich_fonts` got an answer for the file.
+fn has_response_headers(font: &FontInfo) -> bool {
+    font.content_length.is_some()
+        || font.content_type.is_some()
+        || font.last_modified.is_some()
+        || font.cache_control.is_some()
+        || font.access_control_allow_origin.is_some()
+}
+
+fn check_variants(fonts: &[FontInfo], max_variants: usize) -> PerfFinding {
+    let mut families = BTreeMap::<String, (&str, BTreeSet<(&str, &str)>)>::new();
+    for font in fonts.iter().filter(|font| is_used(font)) {
+        families
+            .entry(font.family.trim().to_lowercase())
+            .or_insert_with(|| (font.family.trim(), BTreeSet::new()))
+            .1
+            .insert((font.weight.trim(), font.style.trim()));
+    }
+
+    let mut status = PerfStatus::Pass;
+    let mut details = Vec::new();
+    for (family, faces) in families.values() {
+        if faces.len() > max_variants {
+            status = status.max(if faces.len() > max_variants * 2 {
+                PerfStatus::Fail
+            } else {
+                PerfStatus::Warn
+            });
+            details.push(format!(
+                "{family} uses {} faces; a variable font or fewer weights would load less",
+                faces.len()
+            ));
+        }
+    }
+
+    let summary = if details.is_empty() {
+        format!(
+            "{} family(ies) use at most {max_variants} face(s) each",
+            families.len()
+        )
+    } else {
+        format!(
+            "{} family(ies) use more than {max_variants} faces",
+            details.len()
+        )
+    };
+    PerfFinding {
+        details,
+        ..finding(PerfCheck::Variants, status, summary)
+    }
+}
+
+fn check_budget(total_bytes: u64, unmeasured: usize, budget_bytes: u64) -> PerfFinding {
+    let status = if total_bytes > budget_bytes {
+        PerfStatus::Fail
+    } else if total_bytes * 100 > budget_bytes * BUDGET_WARN_PERCENT {
+        PerfStatus::Warn
+    } else {
+        PerfStatus::Pass
+    };
+    let mut summary = format!(
+        "{} KiB of {} KiB budget",
+        total_bytes.div_ceil(1024),
+        budget_bytes / 1024
+    );
+    if unmeasured > 0 {
+        summary.push_str(&format!(", {unmeasured} file(s) of unknown size"));
+    }
+    finding(PerfCheck::Budget, status, summary)
+}
+
+/// Bytes of each distinct file the page loads: the files of used faces and
+/// every preloaded file.
+fn transfer_weight(fonts: &[FontInfo]) -> (u64, usize) {
+    let mut urls = BTreeSet::new();
+    let mut total = 0;
+    let mut unmeasured = 0;
+    for font in fonts.iter().filter(|font| is_used(font) || font.preloaded) {
+        if !urls.insert(font.url.as_str()) {
+            continue;
+        }
+        match font
+            .content_length
+            .or_else(|| embedded_font_size(&font.url).map(|size| size as u64))
+        {
+            Some(size) => total += size,
+            None => unmeasured += 1,
+        }
+    }
+    (total, unmeasured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PerfCheck, PerfOptions, PerfReport, PerfStatus, check_font_performance};
+    use crate::model::{FontInfo, FontSource, FontUsageStatus};
+
+    fn font(url: &str, weight: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            sources: vec![FontSource {
+                url: url.to_owned(),
+                format: "WOFF2".to_owned(),
+                tech: Vec::new(),
+            }],
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            used_by: vec![".brand".to_owned()],
+            usage: Some(FontUsageStatus::Used),
+            font_display: Some("swap".to_owned()),
+            content_length: Some(20 * 1024),
+            ..FontInfo::default()
+        }
+    }
+
+    fn preload(url: &str) -> FontInfo {
+        FontInfo {
+            usage: None,
+            used_by: Vec::new(),
+            font_display: None,
+            preloaded: true,
+            preload_crossorigin: false,
+            source_stylesheet: "<link rel=preload>".to_owned(),
+            ..font(url, "400")
+        }
+    }
+
+    fn status(report: &PerfReport, check: PerfCheck) -> (PerfStatus, usize) {
+        let finding = report
+            .findings
+            .iter()
+            .find(|finding| finding.check == check)
+            .unwrap();
+        (finding.status, finding.details.len())
+    }
+
+    #[test]
+    fn passes_a_lean_setup() {
+        let mut regular = font("https://www.example.com/brand.woff2", "400");
+        regular.used_by = vec!["body".to_owned()];
+        regular.preloaded = true;
+        regular.preload_crossorigin = true;
+        let mut bold = font("https://cdn.example/brand-bold.woff2", "700");
+        bold.access_control_allow_origin = Some("https://www.example.com".to_owned());
+        let fonts = [regular, bold];
+
+        let report = check_font_performance(&fonts, &PerfOptions::default());
+
+        assert_eq!(report.status(), PerfStatus::Pass);
+        assert_eq!(report.total_bytes, 40 * 1024);
+        assert_eq!(report.findings.len(), PerfCheck::ALL.len());
+    }
+
+    #[test]
+    fn flags_preloads_display_duplicates_variants_and_budget() {
+        let mut regular = font("https://www.example.com/brand.woff2", "400");
+        regular.used_by = vec!["body".to_owned()];
+        regular.font_display = None;
+        regular.sources.push(FontSource {
+            url: "https://www.example.com/brand.woff".to_owned(),
+            format: "WOFF".to_owned(),
+            tech: Vec::new(),
+        });
+        let mut blocking = font("https://www.example.com/brand-500.woff2", "500");
+        blocking.font_display = Some("block".to_owned());
+        let mut redeclared = font("https://cdn.example/brand-500.woff2", "500");
+        redeclared.content_length = None;
+        let mut fonts = vec![
+            regular,
+            blocking,
+            redeclared,
+            preload("https://www.example.com/brand.woff"),
+            preload("https://www.example.com/old.woff2"),
+        ];
+        for weight in ["100", "200", "300", "600", "800", "900"] {
+            fonts.push(font(
+                &format!("https://www.example.com/brand-{weight}.woff2"),
+                weight,
+            ));
+        }
+
+        let report = check_font_performance(
+            &fonts,
+            &PerfOptions {
+                budget_bytes: 200 * 1024,
+                max_variants: 4,
+            },
+        );
+
+        assert_eq!(status(&report, PerfCheck::Preload), (PerfStatus::Warn, 2));
+        assert_eq!(
+            status(&report, PerfCheck::FontDisplay),
+            (PerfStatus::Warn, 2)
+        );
+        assert_eq!(
+            status(&report, PerfCheck::DuplicateDownloads),
+            (PerfStatus::Fail, 2)
+        );
+        assert_eq!(status(&report, PerfCheck::Cors), (PerfStatus::Warn, 2));
+        assert_eq!(status(&report, PerfCheck::Variants), (PerfStatus::Warn, 1));
+        assert_eq!(report.total_bytes, 10 * 20 * 1024);
+        assert_eq!(report.unmeasured, 1);
+        assert_eq!(status(&report, PerfCheck::Budget), (PerfStatus::Warn, 0));
+        assert_eq!(report.status(), PerfStatus::Fail);
+    }
+
+    #[test]
+    fn flags_cross_origin_fonts_without_cors_headers_or_crossorigin() {
+        let mut missing = font("https://cdn.example/missing.woff2", "400");
+        missing.content_type = Some("font/woff2".to_owned());
+        let mut wrong = font("https://cdn.example/wrong.woff2", "700");
+        wrong.access_control_allow_origin = Some("https://other.example".to_owned());
+        let mut preloaded = font("https://cdn.example/preloaded.woff2", "500");
+        preloaded.access_control_allow_origin = Some("*".to_owned());
+        preloaded.preloaded = true;
+        let mut unknown = font("https://cdn.example/unknown.woff2", "600");
+        unknown.content_length = None;
+
+        let report = check_font_performance(
+            &[missing, wrong, preloaded, unknown],
+            &PerfOptions::default(),
+        );
+
+        let cors = &report.findings[3];
+        assert_eq!(cors.check, PerfCheck::Cors);
+        assert_eq!(cors.status, PerfStatus::Fail);
+        assert_eq!(cors.details.len(), 3);
+        assert!(cors.details[0].contains("without Access-Control-Allow-Origin"));
+        assert!(cors.details[1].contains("allows https://other.example"));
+        assert!(cors.details[2].contains("without crossorigin"));
+        assert!(cors.summary.ends_with("1 without response headers"));
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+
+use url::Url;
+
+use crate::model::FontInfo;
+
+/// Where a font is served from, inferred from the font and stylesheet hosts.
+/// Serialized with the names [`as_str`](Self::as_str) returns.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontProvider {
+    #[cfg_attr(feature = "serde", serde(rename = "google"))]
+    GoogleFonts,
+    #[cfg_attr(feature = "serde", serde(rename = "adobe"))]
+    AdobeFonts,
+    #[cfg_attr(feature = "serde", serde(rename = "bunny"))]
+    BunnyFonts,
+    #[cfg_attr(feature = "serde", serde(rename = "fontshare"))]
+    Fontshare,
+    #[cfg_attr(feature = "serde", serde(rename = "fontawesome"))]
+    FontAwesome,
+    /// A general-purpose package CDN such as jsDelivr, unpkg, or cdnjs.
+    #[cfg_attr(feature = "serde", serde(rename = "cdn"))]
+    PublicCdn,
+    /// Served from the page's own site, or embedded in its CSS.
+    #[cfg_attr(feature = "serde", serde(rename = "self-hosted"))]
+    SelfHosted,
+    #[cfg_attr(feature = "serde", serde(rename = "unknown"))]
+    Unknown,
+}
+
+impl FontProvider {
+    pub const ALL: [Self; 8] = [
+        Self::GoogleFonts,
+        Self::AdobeFonts,
+        Self::BunnyFonts,
+        Self::Fontshare,
+        Self::FontAwesome,
+        Self::PublicCdn,
+        Self::SelfHosted,
+        Self::Unknown,
+    ];
+
+    /// Classifies by the font's host, falling back to the host of the
+    /// declaring stylesheet for embedded fonts and for unrecognized font hosts
+    /// behind a known provider's CSS.
+    pub fn for_font(font: &FontInfo) -> Self {
+        let page_host = host_of(&font.referer);
+        let stylesheet = Url::parse(&font.source_stylesheet).ok();
+        let stylesheet_provider = stylesheet
+            .as_ref()
+            .map(|url| Self::for_url(url, page_host.as_deref()));
+
+        if font.url.starts_with("data:") {
+            return stylesheet_provider.unwrap_or(Self::SelfHosted);
+        }
+
+        let provider = Url::parse(&font.url)
+            .map(|url| Self::for_url(&url, page_host.as_deref()))
+            .unwrap_or(Self::Unknown);
+        match (provider, stylesheet_provider) {
+            (Self::Unknown, Some(fallback)) if fallback != Self::SelfHosted => fallback,
+            _ => provider,
+        }
+    }
+
+    fn for_url(url: &Url, page_host: Option<&str>) -> Self {
+        let Some(host) = url.host_str() else {
+            return Self::Unknown;
+        };
+        let host = host.to_ascii_lowercase();
+        let path = url.path().to_ascii_lowercase();
+
+        match host.as_str() {
+            "fonts.googleapis.com" | "fonts.gstatic.com" => Self::GoogleFonts,
+            "use.typekit.net" | "p.typekit.net" | "use.typekit.com" => Self::AdobeFonts,
+            "fonts.bunny.net" => Self::BunnyFonts,
+            "api.fontshare.com" | "cdn.fontshare.com" => Self::Fontshare,
+            _ if host == "fontawesome.com" || host.ends_with(".fontawesome.com") => {
+                Self::FontAwesome
+            }
+            "cdn.jsdelivr.net" | "unpkg.com" | "cdnjs.cloudflare.com" => {
+                if path.contains("font-awesome") || path.contains("fontawesome") {
+                    Self::FontAwesome
+                } else {
+                    Self::PublicCdn
+                }
+            }
+            _ if page_host.is_some_and(|page_host| same_site(&host, page_host)) => Self::SelfHosted,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// The provider's license or terms page for the fonts it serves.
+    pub fn license_url(self) -> Option<&'static str> {
+        match self {
+            Self::GoogleFonts => Some("https://fonts.google.com/attribution"),
+            Self::AdobeFonts => Some("https://www.adobe.com/products/eulas/tou_typekit"),
+            Self::BunnyFonts => Some("https://fonts.bunny.net/about"),
+            Self::Fontshare => Some("https://www.fontshare.com/licenses/itf-ffl"),
+            Self::FontAwesome => Some("https://fontawesome.com/license/free"),
+            Self::PublicCdn | Self::SelfHosted | Self::Unknown => None,
+        }
+    }
+
+    /// Short lowercase name used in path templates and filters.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::GoogleFonts => "google",
+            Self::AdobeFonts => "adobe",
+            Self::BunnyFonts => "bunny",
+            Self::Fontshare => "fontshare",
+            Self::FontAwesome => "fontawesome",
+            Self::PublicCdn => "cdn",
+            Self::SelfHosted => "self-hosted",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for FontProvider {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::GoogleFonts => "Google Fonts",
+            Self::AdobeFonts => "Adobe Fonts",
+            Self::BunnyFonts => "Bunny Fonts",
+            Self::Fontshare => "Fontshare",
+            Self::FontAwesome => "Font Awesome",
+            Self::PublicCdn => "Public CDN",
+            Self::SelfHosted => "Self-hosted",
+            Self::Unknown => "Unknown",
+        };
+        formatter.write_str(label)
+    }
+}
+
+/// A foundry or reseller whose web fonts are licensed to one website, so
+/// copying them elsewhere usually breaches the license.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CommercialFoundry {
+    pub name: &'static str,
+    /// CDN hosts, matched together with their subdomains.
+    pub hosts: &'static [&'static str],
+    /// Lowercase words that identify the foundry in a license comment, as
+    /// self-hosted webfont kits carry.
+    pub notice_markers: &'static [&'static str],
+}
+
+pub const COMMERCIAL_FOUNDRIES: &[CommercialFoundry] = &[
+    CommercialFoundry {
+        name: "Monotype / MyFonts",
+        hosts: &[
+            "fonts.net",
+            "fonts.com",
+            "myfonts.net",
+            "myfonts.com",
+            "monotype.com",
+        ],
+        notice_markers: &["myfonts", "monotype", "fonts.com"],
+    },
+    CommercialFoundry {
+        name: "Hoefler&Co.",
+        hosts: &["typography.com"],
+        notice_markers: &["hoefler", "typography.com"],
+    },
+    CommercialFoundry {
+        name: "Fontspring",
+        hosts: &["fontspring.com"],
+        notice_markers: &["fontspring"],
+    },
+];
+
+impl CommercialFoundry {
+    /// The commercial foundry serving the font or its stylesheet, or named in
+    /// one of its [`license_notices`](FontInfo::license_notices).
+    pub fn for_font(font: &FontInfo) -> Option<&'static Self> {
+        let hosts = [host_of(&font.url), host_of(&font.source_stylesheet)];
+        let notices = font
+            .license_notices
+            .iter()
+            .map(|notice| notice.to_lowercase())
+            .collect::<Vec<_>>();
+
+        COMMERCIAL_FOUNDRIES.iter().find(|foundry| {
+            hosts.iter().flatten().any(|host| {
+                foundry
+                    .hosts
+                    .iter()
+                    .any(|known| host == known || host.ends_with(&format!(".{known}")))
+            }) || notices.iter().any(|notice| {
+                foundry
+                    .notice_markers
+                    .iter()
+                    .any(|marker| notice.contains(marker))
+            })
+        })
+    }
+}
+
+pub(crate) fn host_of(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+}
+
+/// Hosts share a site when their last two labels match, so `cdn.example.com`
+/// counts as self-hosted for `www.example.com`.
+fn same_site(host: &str, page_host: &str) -> bool {
+    let site = |host: &str| {
+        let labels = host.rsplit('.').take(2).collect::<Vec<_>>();
+        labels.into_iter().rev().collect::<Vec<_>>().join(".")
+    };
+    host == page_host || site(host) == site(page_host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommercialFoundry, FontProvider};
+    use crate::model::FontInfo;
+
+    fn font(url: &str, stylesheet: &str) -> FontInfo {
+        FontInfo {
+            name: "font.woff2".to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: stylesheet.to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn classifies_fonts_by_font_and_stylesheet_host() {
+        let cases = [
+            (
+                "https://fonts.gstatic.com/s/inter/v1/a.woff2",
+                "https://fonts.googleapis.com/css2",
+                FontProvider::GoogleFonts,
+            ),
+            (
+                "https://use.typekit.net/af/abc/000/l",
+                "https://use.typekit.net/xyz.css",
+                FontProvider::AdobeFonts,
+            ),
+            (
+                "https://cdnjs.cloudflare.com/ajax/libs/font-awesome/6.0.0/webfonts/fa-solid-900.woff2",
+                "",
+                FontProvider::FontAwesome,
+            ),
+            (
+                "https://cdn.jsdelivr.net/npm/@fontsource/inter/files/inter-latin-400-normal.woff2",
+                "",
+                FontProvider::PublicCdn,
+            ),
+            (
+                "https://static.example.com/fonts/brand.woff2",
+                "https://www.example.com/site.css",
+                FontProvider::SelfHosted,
+            ),
+            (
+                "data:font/woff2;base64,AAAA",
+                "inline <style> #1",
+                FontProvider::SelfHosted,
+            ),
+            (
+                "https://assets.vendor-cdn.net/brand.woff2",
+                "https://www.example.com/site.css",
+                FontProvider::Unknown,
+            ),
+            (
+                "https://f.fontshare-edge.net/a.woff2",
+                "https://api.fontshare.com/v2/css?f=satoshi",
+                FontProvider::Fontshare,
+            ),
+        ];
+
+        for (url, stylesheet, expected) in cases {
+            assert_eq!(
+                FontProvider::for_font(&font(url, stylesheet)),
+                expected,
+                "{url}"
+            );
+        }
+    }
+    #[test]
+    fn flags_fonts_from_commercial_foundries() {
+        let foundry = |font: &FontInfo| CommercialFoundry::for_font(font).map(|found| found.name);
+
+        assert_eq!(
+            foundry(&font(
+                "https://fast.fonts.net/dv2/14/abc.woff2",
+                "https://fast.fonts.net/cssapi/xyz.css"
+            )),
+            Some("Monotype / MyFonts")
+        );
+        assert_eq!(
+            foundry(&font(
+                "https://cloud.typography.com/123/456/woff2/font.woff2",
+                "https://www.example.com/site.css"
+            )),
+            Some("Hoefler&Co.")
+        );
+        assert_eq!(
+            foundry(&font(
+                "https://www.example.com/fonts/brand.woff2",
+                "https://www.example.com/site.css"
+            )),
+            None
+        );
+
+        let mut kit = font(
+            "https://www.example.com/fonts/brand.woff2",
+            "https://www.example.com/site.css",
+        );
+        kit.license_notices = vec!["@license\nMyFonts Webfont Build ID 123".to_owned()];
+        assert_eq!(foundry(&kit), Some("Monotype / MyFonts"));
+        assert_eq!(
+            foundry(&font("https://notfonts.net/a.woff2", "")),
+            None,
+            "hosts match whole labels"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashSet;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use url::Url;
+
+const STRING_LITERAL: &str = r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|`[^`]*`"#;
+const GOOGLE_FONTS_CSS_URL: &str = "https://fonts.googleapis.com/css";
+
+static FONT_URL_LITERAL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)["'`]([^"'`\s<>()]+?\.(?:woff2|woff|ttf|otf|eot)(?:[?#][^"'`\s<>()]*)?)["'`]"#,
+    )
+    .expect("valid font URL literal regex")
+});
+static STRING_LITERAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(STRING_LITERAL).expect("valid string literal regex"));
+static FONT_FACE_CALL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"new\s+FontFace\s*\(\s*({STRING_LITERAL})\s*,\s*({STRING_LITERAL})\s*(?:,\s*\{{([^}}]*)\}})?"
+    ))
+    .expect("valid FontFace constructor regex")
+});
+static DESCRIPTOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\b(weight|style)\s*:\s*(?:"([^"]*)"|'([^']*)'|(\d+))"#)
+        .expect("valid FontFace descriptor regex")
+});
+static WEB_FONT_CONFIG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"WebFont\s*\.\s*load\s*\(|WebFontConfig\s*=").expect("valid WebFont config regex")
+});
+static GOOGLE_FAMILIES_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"google\s*:\s*\{[^}]*?families\s*:\s*\[([^\]]*)\]")
+        .expect("valid WebFont google regex")
+});
+static TYPEKIT_ID_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"typekit\s*:\s*\{[^}]*?id\s*:\s*["']([\w-]+)["']"#)
+        .expect("valid WebFont typekit regex")
+});
+static CUSTOM_URLS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"custom\s*:\s*\{[^}]*?urls\s*:\s*\[([^\]]*)\]").expect("valid WebFont custom regex")
+});
+
+/// A `new FontFace(family, source, descriptors)` call with literal arguments.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FontFaceCall {
+    pub family: String,
+    /// The CSS `src` value, e.g. `url(/fonts/a.woff2) format("woff2")`.
+    pub source: String,
+    pub weight: Option<String>,
+    pub style: Option<String>,
+}
+
+/// Quoted string literals in a script that look like font file URLs, in
+/// order of first appearance and without duplicates.
+pub fn font_urls_in_script(source: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    FONT_URL_LITERAL_RE
+        .captures_iter(source)
+        .filter_map(|capture| capture.get(1))
+        .map(|literal| unescape_js_slashes(literal.as_str()))
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// `FontFace` constructor calls whose family and source are string literals.
+/// Calls built from variables, interpolated templates, or `ArrayBuffer`
+/// sources cannot be resolved statically and are skipped.
+pub fn font_face_calls(source: &str) -> Vec<FontFaceCall> {
+    FONT_FACE_CALL_RE
+        .captures_iter(source)
+        .filter_map(|capture| {
+            let family = unquote(capture.get(1)?.as_str());
+            let src = unquote(capture.get(2)?.as_str());
+            if family.trim().is_empty() || family.contains("${") || src.contains("${") {
+                return None;
+            }
+
+            let mut call = FontFaceCall {
+                family: family.trim().to_owned(),
+                source: src,
+                weight: None,
+                style: None,
+            };
+            let descriptors = capture.get(3).map_or("", |m| m.as_str());
+            for descriptor in DESCRIPTOR_RE.captures_iter(descriptors) {
+                let value = descriptor
+                    .get(2)
+                    .or_else(|| descriptor.get(3))
+                    .or_else(|| descriptor.get(4))
+                    .map(|m| m.as_str().trim().to_owned());
+                match &descriptor[1] {
+                    "weight" => call.weight = value,
+                    _ => call.style = value,
+                }
+            }
+            Some(call)
+        })
+        .collect()
+}
+
+/// Stylesheets that a Web Font Loader config (`WebFont.load({...})` or a
+/// `WebFontConfig` global) would inject: a Google Fonts CSS URL for the
+/// `google` families, the Typekit kit CSS, and any `custom` URLs, which are
+/// returned unresolved.
+pub fn web_font_loader_stylesheets(source: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut stylesheets = Vec::new();
+
+    for config_start in WEB_FONT_CONFIG_RE.find_iter(source) {
+        let Some(config) = balanced_object(&source[config_start.end()..]) else {
+            continue;
+        };
+
+        if let Some(families) = GOOGLE_FAMILIES_RE.captures(config) {
+            let families = string_literals(&families[1])
+                .iter()
+                .map(|family| google_family_spec(family))
+                .filter(|family| !family.is_empty())
+                .collect::<Vec<_>>();
+            if !families.is_empty()
+                && let Ok(url) =
+                    Url::parse_with_params(GOOGLE_FONTS_CSS_URL, [("family", families.join("|"))])
+            {
+                stylesheets.push(url.to_string());
+            }
+        }
+
+        if let Some(kit) = TYPEKIT_ID_RE.captures(config) {
+            stylesheets.push(format!("https://use.typekit.net/{}.css", &kit[1]));
+        }
+
+        if let Some(urls) = CUSTOM_URLS_RE.captures(config) {
+            stylesheets.extend(string_literals(&urls[1]));
+        }
+    }
+
+    stylesheets.retain(|url| seen.insert(url.clone()));
+    stylesheets
+}
+
+// Bundlers often emit JSON-escaped paths such as "\/static\/font.woff2".
+fn unescape_js_slashes(input: &str) -> String {
+    input.replace("\\/", "/")
+}
+
+fn unquote(literal: &str) -> String {
+    let inner = literal
+        .get(1..literal.len().saturating_sub(1))
+        .unwrap_or_default();
+    unescape_js_slashes(inner)
+        .replace("\\\"", "\"")
+        .replace("\\'", "'")
+}
+
+fn string_literals(input: &str) -> Vec<String> {
+    STRING_LITERAL_RE
+        .find_iter(input)
+        .map(|literal| unquote(literal.as_str()))
+        .collect()
+}
+
+/// Web Font Loader accepts `Family:variants:subsets`; the CSS API only takes
+/// the family and its variants.
+fn google_family_spec(family: &str) -> String {
+    family
+        .splitn(3, ':')
+        .take(2)
+        .collect::<Vec<_>>()
+        .join(":")
+        .trim()
+        .to_owned()
+}
+
+/// The `{...}` object literal at the start of `input`, matched by brace depth.
+fn balanced_object(input: &str) -> Option<&str> {
+    let start = input.find(|ch: char| !ch.is_whitespace())?;
+    if !input[start..].starts_with('{') {
+        return None;
+    }
+
+    let mut depth = 0_usize;
+    for (offset, ch) in input[start..].char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&input[start..=start + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FontFaceCall, font_face_calls, font_urls_in_script, web_font_loader_stylesheets};
+
+    #[test]
+    fn finds_quoted_font_urls_in_bundles() {
+        let bundle = r#"
+            var a="/static/media/inter.8c1f.woff2",b='fonts/brand.woff?v=3';
+            const manifest = {"icons":"\/assets\/icons.ttf","img":"/logo.png"};
+            load(`https://cdn.example.com/x.otf`); load("/static/media/inter.8c1f.woff2");
+        "#;
+
+        assert_eq!(
+            font_urls_in_script(bundle),
+            vec![
+                "/static/media/inter.8c1f.woff2",
+                "fonts/brand.woff?v=3",
+                "/assets/icons.ttf",
+                "https://cdn.example.com/x.otf",
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_font_face_constructor_calls_with_literal_arguments() {
+        let script = r#"
+            const bold = new FontFace("Brand Sans", "url(/fonts/brand-bold.woff2) format('woff2')", { weight: "700", style: 'italic' });
+            const plain = new FontFace('Brand Sans', 'url("/fonts/brand.woff")');
+            const dynamic = new FontFace(name, `url(${base}/x.woff2)`);
+            const buffer = new FontFace("Buffered", data);
+        "#;
+
+        assert_eq!(
+            font_face_calls(script),
+            vec![
+                FontFaceCall {
+                    family: "Brand Sans".to_owned(),
+                    source: "url(/fonts/brand-bold.woff2) format('woff2')".to_owned(),
+                    weight: Some("700".to_owned()),
+                    style: Some("italic".to_owned()),
+                },
+                FontFaceCall {
+                    family: "Brand Sans".to_owned(),
+                    source: "url(\"/fonts/brand.woff\")".to_owned(),
+                    weight: None,
+                    style: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_web_font_loader_configs_to_stylesheets() {
+        let script = r#"
+            WebFont.load({
+                google: { families: ["Roboto:300,700italic:latin", "Open Sans"] },
+                typekit: { id: "abc1def" },
+                custom: { families: ["Brand"], urls: ["/css/brand.css"] }
+            });
+            WebFontConfig = { google: { families: ['Roboto:300,700italic'] } };
+        "#;
+
+        assert_eq!(
+            web_font_loader_stylesheets(script),
+            vec![
+                "https://fonts.googleapis.com/css?family=Roboto%3A300%2C700italic%7COpen+Sans",
+                "https://use.typekit.net/abc1def.css",
+                "/css/brand.css",
+                "https://fonts.googleapis.com/css?family=Roboto%3A300%2C700italic",
+            ]
+        );
+    }
+}
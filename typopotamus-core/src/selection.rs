@@ -1,6 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
 
-use crate::model::FontInfo;
+use url::Url;
+
+use crate::error::SelectionFileError;
+use crate::model::{FaceSource, FontInfo, group_faces};
+use crate::provider::FontProvider;
 
 #[derive(Clone, Debug, Default)]
 pub struct FontSelection {
@@ -9,6 +15,73 @@ pub struct FontSelection {
     pub names: Vec<String>,
     pub urls: Vec<String>,
     pub indices: Vec<usize>,
+    /// [`FontInfo::stable_id`]s, matched case-insensitively.
+    pub ids: Vec<String>,
+    /// Drops fonts lighter than this weight from the selection.
+    pub min_weight: Option<u16>,
+    /// Drops fonts heavier than this weight from the selection.
+    pub max_weight: Option<u16>,
+    /// How `families` and `names` compare with fonts.
+    pub matching: MatchMode,
+}
+
+/// How family and file name selectors compare with fonts.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MatchMode {
+    /// The whole name, ignoring case.
+    #[default]
+    Exact,
+    /// The selector anywhere in the name, ignoring case and everything but
+    /// letters and digits: `inter` matches `Inter var` and `inter-v12`.
+    Substring,
+    /// Like `Substring`, allowing one typo per five characters.
+    Fuzzy,
+}
+
+impl MatchMode {
+    pub fn matches(self, selector: &str, name: &str) -> bool {
+        if self == Self::Exact {
+            return normalize(selector) == normalize(name);
+        }
+
+        let (selector, name) = (compact(selector), compact(name));
+        let allowed = match self {
+            Self::Fuzzy => selector.len() / 5,
+            _ => 0,
+        };
+        !selector.is_empty() && substring_distance(&selector, &name) <= allowed
+    }
+}
+
+/// The kind of selector a [`SelectorMatch`] reports on, named after its
+/// flag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectorKind {
+    Family,
+    Name,
+    Url,
+    Index,
+    Id,
+}
+
+impl SelectorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Family => "family",
+            Self::Name => "font-name",
+            Self::Url => "font-url",
+            Self::Index => "index",
+            Self::Id => "id",
+        }
+    }
+}
+
+/// The fonts one selector matched.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SelectorMatch {
+    pub kind: SelectorKind,
+    pub selector: String,
+    pub indices: Vec<usize>,
 }
 
 impl FontSelection {
@@ -18,48 +91,701 @@ impl FontSelection {
             || !self.names.is_empty()
             || !self.urls.is_empty()
             || !self.indices.is_empty()
+            || !self.ids.is_empty()
+    }
+
+    /// Parses a selection file: one `key: value` selector per line, where
+    /// the keys are `family`, `font-name`, `font-url`, `index`, `id`,
+    /// `min-weight` and `max-weight`, or a bare `all`. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn parse(text: &str) -> Result<Self, SelectionFileError> {
+        let mut selection = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line_number = number + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "all" {
+                selection.all = true;
+                continue;
+            }
+
+            let (key, value) = line.split_once(':').unwrap_or((line, ""));
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || SelectionFileError::InvalidValue {
+                line: line_number,
+                key: key.to_owned(),
+                value: value.to_owned(),
+            };
+            let weight = || {
+                value
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|weight| (1..=1000).contains(weight))
+                    .ok_or_else(invalid)
+            };
+            let text = || {
+                if value.is_empty() {
+                    Err(invalid())
+                } else {
+                    Ok(value.to_owned())
+                }
+            };
+            match key {
+                "family" => selection.families.push(text()?),
+                "font-name" => selection.names.push(text()?),
+                "font-url" => selection.urls.push(text()?),
+                "index" => selection
+                    .indices
+                    .push(value.parse().map_err(|_| invalid())?),
+                "id" => selection.ids.push(text()?),
+                "min-weight" => selection.min_weight = Some(weight()?),
+                "max-weight" => selection.max_weight = Some(weight()?),
+                _ => {
+                    return Err(SelectionFileError::UnknownSelector {
+                        line: line_number,
+                        key: key.to_owned(),
+                    });
+                }
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Reads and parses the selection file at `path`.
+    pub fn read(path: &Path) -> Result<Self, SelectionFileError> {
+        let text = fs::read_to_string(path).map_err(|source| SelectionFileError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
     }
 }
 
 pub fn select_font_indices(fonts: &[FontInfo], selection: &FontSelection) -> Vec<usize> {
+    let in_range = |indices: Vec<usize>| {
+        filter_indices_by_weight(fonts, &indices, selection.min_weight, selection.max_weight)
+    };
     if selection.all {
-        return (0..fonts.len()).collect();
+        return in_range((0..fonts.len()).collect());
+    }
+
+    let selected = match_selectors(fonts, selection)
+        .into_iter()
+        .flat_map(|matched| matched.indices)
+        .collect::<BTreeSet<_>>();
+    in_range(selected.into_iter().collect())
+}
+
+/// What each selector of `selection` matched, in the order they were given,
+/// before the weight range is applied. `all` is not reported.
+pub fn match_selectors(fonts: &[FontInfo], selection: &FontSelection) -> Vec<SelectorMatch> {
+    let matching =
+        |kind: SelectorKind, selector: &str, matches: &dyn Fn(&FontInfo) -> bool| SelectorMatch {
+            kind,
+            selector: selector.to_owned(),
+            indices: fonts
+                .iter()
+                .enumerate()
+                .filter(|(_, font)| matches(font))
+                .map(|(index, _)| index)
+                .collect(),
+        };
+
+    let mut report = Vec::new();
+    for family in &selection.families {
+        report.push(matching(SelectorKind::Family, family, &|font| {
+            selection.matching.matches(family, &font.family)
+        }));
     }
+    for name in &selection.names {
+        report.push(matching(SelectorKind::Name, name, &|font| {
+            selection.matching.matches(name, &font.name)
+        }));
+    }
+    for url in &selection.urls {
+        report.push(matching(SelectorKind::Url, url, &|font| font.url == *url));
+    }
+    for index in &selection.indices {
+        report.push(SelectorMatch {
+            kind: SelectorKind::Index,
+            selector: index.to_string(),
+            indices: (*index < fonts.len())
+                .then_some(*index)
+                .into_iter()
+                .collect(),
+        });
+    }
+    for id in &selection.ids {
+        let id = id.trim().to_ascii_lowercase();
+        report.push(matching(SelectorKind::Id, &id, &|font| {
+            font.stable_id() == id
+        }));
+    }
+    report
+}
+
+/// Keeps fonts whose `font-weight` falls within `min..=max`. Variable fonts
+/// are kept when their range overlaps it, and relative or unparseable
+/// weights are always kept.
+pub fn filter_indices_by_weight(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    min: Option<u16>,
+    max: Option<u16>,
+) -> Vec<usize> {
+    let min = min.unwrap_or(u16::MIN);
+    let max = max.unwrap_or(u16::MAX);
 
-    let family_set: HashSet<String> = selection
-        .families
+    indices
         .iter()
-        .map(|value| normalize(value))
-        .collect();
-    let name_set: HashSet<String> = selection
-        .names
+        .copied()
+        .filter(|index| {
+            fonts.get(*index).is_some_and(|font| {
+                weight_bounds(&font.weight).is_none_or(|(low, high)| low <= max && high >= min)
+            })
+        })
+        .collect()
+}
+
+/// `400` -> `(400, 400)`, `100 900` -> `(100, 900)`, `bold` -> `(700, 700)`.
+fn weight_bounds(weight: &str) -> Option<(u16, u16)> {
+    let mut values =
+        weight
+            .split_whitespace()
+            .map(|part| match part.to_ascii_lowercase().as_str() {
+                "normal" => Some(400),
+                "bold" => Some(700),
+                other => other.parse::<f32>().ok().map(|value| value.round() as u16),
+            });
+    let low = values.next()??;
+    let high = match values.next() {
+        Some(value) => value?,
+        None => low,
+    };
+    Some((low.min(high), low.max(high)))
+}
+
+pub fn filter_indices_by_format(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    formats: &[String],
+) -> Vec<usize> {
+    if formats.is_empty() {
+        return indices.to_vec();
+    }
+
+    let format_set: HashSet<&str> = formats
         .iter()
-        .map(|value| normalize(value))
+        .map(|value| canonical_format(value))
         .collect();
-    let url_set: HashSet<&str> = selection.urls.iter().map(String::as_str).collect();
 
-    let mut selected = HashSet::new();
+    indices
+        .iter()
+        .copied()
+        .filter(|index| {
+            fonts
+                .get(*index)
+                .is_some_and(|font| format_set.contains(canonical_format(&font.format)))
+        })
+        .collect()
+}
 
-    for index in &selection.indices {
-        if *index < fonts.len() {
-            selected.insert(*index);
-        }
+pub fn filter_indices_by_provider(
+    fonts: &[FontInfo],
+    indices: &[usize],
+    providers: &[FontProvider],
+) -> Vec<usize> {
+    if providers.is_empty() {
+        return indices.to_vec();
+    }
+
+    indices
+        .iter()
+        .copied()
+        .filter(|index| {
+            fonts
+                .get(*index)
+                .is_some_and(|font| providers.contains(&FontProvider::for_font(font)))
+        })
+        .collect()
+}
+
+/// Points each font at its best `src` candidate in one of `formats`, so a
+/// WOFF2-first rule can still be downloaded as TTF. Fonts without such a
+/// candidate are left unchanged.
+pub fn prefer_source_formats(fonts: &mut [FontInfo], formats: &[String]) {
+    if formats.is_empty() {
+        return;
     }
 
-    for (index, font) in fonts.iter().enumerate() {
-        if family_set.contains(&normalize(&font.family))
-            || name_set.contains(&normalize(&font.name))
-            || url_set.contains(font.url.as_str())
+    let format_set: HashSet<&str> = formats
+        .iter()
+        .map(|value| canonical_format(value))
+        .collect();
+
+    for font in fonts {
+        if format_set.contains(canonical_format(&font.format)) {
+            continue;
+        }
+        if let Some(source) = font
+            .sources
+            .iter()
+            .position(|source| format_set.contains(canonical_format(&source.format)))
         {
-            selected.insert(index);
+            use_source(font, source);
+        }
+    }
+}
+
+/// Points each font at the first format of `chain` it offers through any of
+/// its `src` candidates, as [`prefer_source_formats`] does for one format.
+pub fn prefer_format_chain(fonts: &mut [FontInfo], chain: &[String]) {
+    for format in chain.iter().rev() {
+        prefer_source_formats(fonts, std::slice::from_ref(format));
+    }
+}
+
+/// Keeps one file per face (family, weight, and style) of `indices`, in the
+/// first format of `chain` the face offers through any of its `src`
+/// candidates. Fonts are pointed at the chosen candidate as
+/// [`prefer_source_formats`] does. Faces offering none of the formats keep
+/// their best-ranked file; an empty chain keeps every file.
+pub fn one_file_per_face(
+    fonts: &mut [FontInfo],
+    indices: &[usize],
+    chain: &[String],
+) -> Vec<usize> {
+    if chain.is_empty() {
+        return indices.to_vec();
+    }
+    let chain = chain
+        .iter()
+        .map(|value| canonical_format(value))
+        .collect::<Vec<_>>();
+
+    let mut families = BTreeMap::<String, Vec<usize>>::new();
+    for index in indices.iter().copied().filter(|index| *index < fonts.len()) {
+        families
+            .entry(normalize(&fonts[index].family))
+            .or_default()
+            .push(index);
+    }
+    let faces = families
+        .values()
+        .flat_map(|members| {
+            group_faces(members.iter().map(|index| FaceSource {
+                index: *index,
+                weight: &fonts[*index].weight,
+                style: &fonts[*index].style,
+                format: &fonts[*index].format,
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    let mut kept = Vec::with_capacity(faces.len());
+    for face in faces {
+        let Some(preferred) = face.preferred_index() else {
+            continue;
+        };
+        let chosen = face
+            .font_indices
+            .iter()
+            .flat_map(|index| {
+                let font = &fonts[*index];
+                std::iter::once((*index, None))
+                    .chain((0..font.sources.len()).map(move |source| (*index, Some(source))))
+            })
+            .filter_map(|(index, source)| {
+                let format = match source {
+                    Some(source) => &fonts[index].sources[source].format,
+                    None => &fonts[index].format,
+                };
+                let rank = chain
+                    .iter()
+                    .position(|wanted| *wanted == canonical_format(format))?;
+                Some((rank, index, source))
+            })
+            .min_by_key(|(rank, ..)| *rank);
+
+        match chosen {
+            Some((_, index, source)) => {
+                if let Some(source) = source {
+                    use_source(&mut fonts[index], source);
+                }
+                kept.push(index);
+            }
+            None => kept.push(preferred),
         }
     }
 
-    let mut sorted = selected.into_iter().collect::<Vec<_>>();
-    sorted.sort_unstable();
-    sorted
+    kept.sort_unstable();
+    kept
+}
+
+/// Points `font` at its `src` candidate at `index` and moves that candidate
+/// first, so `sources[0]` always matches `url`.
+fn use_source(font: &mut FontInfo, index: usize) {
+    let source = font.sources.remove(index);
+    font.sources.insert(0, source.clone());
+    if let Some(file_name) = Url::parse(&source.url)
+        .ok()
+        .filter(|url| url.scheme() != "data")
+        .and_then(|url| url.path_segments()?.next_back().map(str::to_owned))
+        .filter(|segment| !segment.is_empty())
+    {
+        font.name = file_name;
+    }
+    font.url = source.url;
+    font.format = source.format;
+}
+
+pub fn canonical_format(input: &str) -> &'static str {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "woff2" => "WOFF2",
+        "woff" => "WOFF",
+        "truetype" | "ttf" => "TRUETYPE",
+        "opentype" | "otf" => "OPENTYPE",
+        "eot" | "embedded-opentype" => "EOT",
+        "svg" => "SVG",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Preference order used when the same face ships in several formats; lower
+/// ranks are smaller and better supported.
+pub fn format_rank(input: &str) -> usize {
+    match canonical_format(input) {
+        "WOFF2" => 0,
+        "WOFF" => 1,
+        "OPENTYPE" => 2,
+        "TRUETYPE" => 3,
+        "EOT" => 4,
+        "SVG" => 5,
+        _ => 6,
+    }
 }
 
 fn normalize(input: &str) -> String {
     input.trim().to_ascii_lowercase()
 }
+
+fn compact(input: &str) -> Vec<char> {
+    input
+        .chars()
+        .filter(|character| character.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Edit distance between `pattern` and its closest match anywhere in `text`.
+fn substring_distance(pattern: &[char], text: &[char]) -> usize {
+    let mut previous = vec![0; text.len() + 1];
+    for (i, left) in pattern.iter().enumerate() {
+        let mut current = vec![i + 1; text.len() + 1];
+        for (j, right) in text.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous.into_iter().min().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        FontSelection, MatchMode, SelectorKind, filter_indices_by_format, match_selectors,
+        one_file_per_face, prefer_format_chain, prefer_source_formats, select_font_indices,
+    };
+    use crate::error::SelectionFileError;
+    use crate::model::{FontInfo, FontSource};
+
+    #[test]
+    fn parses_one_selector_per_line() {
+        let selection = FontSelection::parse(
+            "# Brand fonts for the marketing site\n\
+             family: Brand Sans\n\
+             family: Brand Serif\n\
+             \n\
+             font-url: https://cdn.example/brand-mono.woff2\n\
+             id: 1BD292E363\n\
+             index: 4\n\
+             min-weight: 300\n\
+             max-weight: 700\n",
+        )
+        .expect("parse selection file");
+
+        assert!(!selection.all);
+        assert_eq!(selection.families, ["Brand Sans", "Brand Serif"]);
+        assert_eq!(selection.urls, ["https://cdn.example/brand-mono.woff2"]);
+        assert_eq!(selection.ids, ["1BD292E363"]);
+        assert_eq!(selection.indices, [4]);
+        assert_eq!(
+            (selection.min_weight, selection.max_weight),
+            (Some(300), Some(700))
+        );
+        assert!(FontSelection::parse("all").expect("parse all").all);
+
+        assert!(matches!(
+            FontSelection::parse("family: Brand\nfamliy: Brand Serif"),
+            Err(SelectionFileError::UnknownSelector { line: 2, .. })
+        ));
+        assert!(matches!(
+            FontSelection::parse("min-weight: heavy"),
+            Err(SelectionFileError::InvalidValue { line: 1, .. })
+        ));
+        assert!(matches!(
+            FontSelection::parse("family:"),
+            Err(SelectionFileError::InvalidValue { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn format_filters_can_pick_a_fallback_src_candidate() {
+        let source = |url: &str, format: &str| FontSource {
+            url: url.to_owned(),
+            format: format.to_owned(),
+            tech: Vec::new(),
+        };
+        let mut fonts = vec![FontInfo {
+            name: "brand.woff2".to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: "https://example.com/fonts/brand.woff2".to_owned(),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://example.com".to_owned(),
+            sources: vec![
+                source("https://example.com/fonts/brand.woff2", "WOFF2"),
+                source("https://example.com/fonts/brand.ttf?v=2", "TRUETYPE"),
+            ],
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }];
+        let ttf = vec!["ttf".to_owned()];
+
+        assert!(filter_indices_by_format(&fonts, &[0], &ttf).is_empty());
+
+        prefer_source_formats(&mut fonts, &ttf);
+        assert_eq!(filter_indices_by_format(&fonts, &[0], &ttf), vec![0]);
+        assert_eq!(fonts[0].name, "brand.ttf");
+        assert_eq!(fonts[0].url, "https://example.com/fonts/brand.ttf?v=2");
+        assert_eq!(fonts[0].format, "TRUETYPE");
+        assert_eq!(fonts[0].sources[0].url, fonts[0].url);
+        assert_eq!(fonts[0].sources[1].format, "WOFF2");
+
+        prefer_format_chain(&mut fonts, &["woff2".to_owned(), "ttf".to_owned()]);
+        assert_eq!(fonts[0].url, "https://example.com/fonts/brand.woff2");
+        assert_eq!(fonts[0].sources[0].url, fonts[0].url);
+    }
+
+    #[test]
+    fn format_filters_keep_fonts_in_any_listed_format() {
+        let font = |format: &str| FontInfo {
+            name: format!("brand.{}", format.to_ascii_lowercase()),
+            family: "Brand".to_owned(),
+            format: format.to_owned(),
+            url: format!(
+                "https://example.com/fonts/brand.{}",
+                format.to_ascii_lowercase()
+            ),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            ..FontInfo::default()
+        };
+        let fonts = ["WOFF2", "woff", "TRUETYPE", "OPENTYPE", "UNKNOWN"].map(font);
+        let all = [0, 1, 2, 3, 4, 9];
+
+        assert_eq!(filter_indices_by_format(&fonts, &all, &[]), all);
+        assert_eq!(
+            filter_indices_by_format(&fonts, &all, &["woff2".to_owned(), " WOFF ".to_owned()]),
+            vec![0, 1]
+        );
+        assert_eq!(
+            filter_indices_by_format(&fonts, &all, &["ttf".to_owned(), "otf".to_owned()]),
+            vec![2, 3]
+        );
+        assert_eq!(
+            filter_indices_by_format(&fonts, &[1, 2], &["WOFF2".to_owned()]),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn loose_modes_match_variants_of_a_name() {
+        let fonts = ["Inter var", "inter-v12", "Intel One Mono", "Helvetika Now"]
+            .into_iter()
+            .map(|family| FontInfo {
+                name: format!("{family}.woff2"),
+                family: family.to_owned(),
+                format: "WOFF2".to_owned(),
+                url: format!("https://example.com/fonts/{family}.woff2"),
+                weight: "400".to_owned(),
+                style: "normal".to_owned(),
+                referer: "https://example.com".to_owned(),
+                source_stylesheet: "https://example.com/site.css".to_owned(),
+                ..FontInfo::default()
+            })
+            .collect::<Vec<_>>();
+        let selection = |matching| FontSelection {
+            families: vec!["inter".to_owned(), "Helvetica".to_owned()],
+            matching,
+            ..FontSelection::default()
+        };
+
+        assert!(select_font_indices(&fonts, &selection(MatchMode::Exact)).is_empty());
+        assert_eq!(
+            select_font_indices(&fonts, &selection(MatchMode::Substring)),
+            vec![0, 1]
+        );
+
+        let report = match_selectors(&fonts, &selection(MatchMode::Fuzzy));
+        assert_eq!(report[0].kind, SelectorKind::Family);
+        assert_eq!(report[0].selector, "inter");
+        // "intel" is one typo away from "inter".
+        assert_eq!(report[0].indices, vec![0, 1, 2]);
+        assert_eq!(report[1].indices, vec![3]);
+    }
+
+    #[test]
+    fn weight_ranges_drop_the_extremes() {
+        let fonts = ["100", "400", "bold", "900", "100 900", "800 950", "bolder"]
+            .into_iter()
+            .map(|weight| FontInfo {
+                name: format!("brand-{weight}.woff2"),
+                family: "Brand".to_owned(),
+                format: "WOFF2".to_owned(),
+                url: format!("https://example.com/fonts/brand-{weight}.woff2"),
+                weight: weight.to_owned(),
+                style: "normal".to_owned(),
+                referer: "https://example.com".to_owned(),
+                source_stylesheet: "https://example.com/site.css".to_owned(),
+                ..FontInfo::default()
+            })
+            .collect::<Vec<_>>();
+
+        let selection = FontSelection {
+            all: true,
+            min_weight: Some(400),
+            max_weight: Some(700),
+            ..FontSelection::default()
+        };
+        assert_eq!(select_font_indices(&fonts, &selection), vec![1, 2, 4, 6]);
+
+        let heavy = FontSelection {
+            indices: vec![0, 3, 5],
+            min_weight: Some(850),
+            ..FontSelection::default()
+        };
+        assert_eq!(select_font_indices(&fonts, &heavy), vec![3, 5]);
+    }
+
+    #[test]
+    fn format_chains_keep_one_file_per_face() {
+        let source = |url: &str, format: &str| FontSource {
+            url: url.to_owned(),
+            format: format.to_owned(),
+            tech: Vec::new(),
+        };
+        let font = |url: &str, weight: &str, sources: Vec<FontSource>| FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: sources[0].format.clone(),
+            url: url.to_owned(),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://example.com".to_owned(),
+            sources,
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        };
+        let mut fonts = vec![
+            font(
+                "https://example.com/regular.woff2",
+                "400",
+                vec![source("https://example.com/regular.woff2", "woff2")],
+            ),
+            font(
+                "https://example.com/regular.woff",
+                "400",
+                vec![source("https://example.com/regular.woff", "woff")],
+            ),
+            font(
+                "https://example.com/bold.eot",
+                "700",
+                vec![
+                    source("https://example.com/bold.eot", "embedded-opentype"),
+                    source("https://example.com/bold.ttf", "truetype"),
+                ],
+            ),
+            font(
+                "https://example.com/black.svg",
+                "900",
+                vec![source("https://example.com/black.svg", "svg")],
+            ),
+        ];
+        let chain = ["woff".to_owned(), "ttf".to_owned()];
+
+        assert_eq!(
+            one_file_per_face(&mut fonts, &[0, 1, 2, 3], &chain),
+            vec![1, 2, 3]
+        );
+        assert_eq!(fonts[2].url, "https://example.com/bold.ttf");
+        assert_eq!(fonts[2].name, "bold.ttf");
+        assert_eq!(one_file_per_face(&mut fonts, &[0, 1], &[]), vec![0, 1]);
+        assert_eq!(
+            one_file_per_face(&mut fonts, &[0, 1], &["woff2".to_owned()]),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn stable_ids_survive_reordering_and_format_choice() {
+        let font = |url: &str, weight: &str| FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://example.com".to_owned(),
+            sources: vec![
+                FontSource {
+                    url: url.to_owned(),
+                    format: "WOFF2".to_owned(),
+                    tech: Vec::new(),
+                },
+                FontSource {
+                    url: url.replace(".woff2", ".ttf"),
+                    format: "TRUETYPE".to_owned(),
+                    tech: Vec::new(),
+                },
+            ],
+            source_stylesheet: "https://example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        };
+        let regular = font("https://example.com/regular.woff2", "400");
+        let bold = font("https://example.com/bold.woff2", "700");
+        let id = bold.stable_id();
+        assert_eq!(id.len(), 10);
+        assert_ne!(id, regular.stable_id());
+
+        let mut reordered = vec![bold.clone(), regular.clone()];
+        prefer_source_formats(&mut reordered, &["ttf".to_owned()]);
+        assert_eq!(reordered[0].url, "https://example.com/bold.ttf");
+        assert_eq!(reordered[0].stable_id(), id);
+
+        let selection = FontSelection {
+            ids: vec![id.to_ascii_uppercase()],
+            ..FontSelection::default()
+        };
+        assert!(selection.has_selectors());
+        assert_eq!(select_font_indices(&reordered, &selection), vec![0]);
+        assert_eq!(
+            select_font_indices(&[regular.clone(), bold.clone()], &selection),
+            vec![1]
+        );
+    }
+}
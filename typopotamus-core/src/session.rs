@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use url::Url;
+
+use crate::fetch::header::{RANGE, USER_AGENT};
+use crate::fetch::{FetchError, FetchMethod, FetchRequest, FetchResponse, Fetcher};
+
+/// Response bodies kept by a [`Session`] before it stops caching new ones.
+pub const DEFAULT_SESSION_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+const COOKIE: &str = "cookie";
+const SET_COOKIE: &str = "set-cookie";
+
+/// A fetcher shared across extraction and download, so connections stay warm,
+/// cookies set while scanning are sent with font requests, and files already
+/// fetched are not fetched again.
+///
+/// Clones share the same cookie jar and cache. Only complete `2xx` `GET`
+/// responses without a `Range` header are cached, keyed by URL and
+/// `User-Agent` since some providers serve each browser different files.
+#[derive(Clone, Debug)]
+pub struct Session<F> {
+    inner: F,
+    state: Arc<SessionState>,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    cookies: Mutex<Vec<Cookie>>,
+    cache: Mutex<ResponseCache>,
+}
+
+#[derive(Debug, Default)]
+struct ResponseCache {
+    responses: HashMap<String, FetchResponse>,
+    bytes: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    /// Set without a `Domain` attribute, so subdomains do not get it.
+    host_only: bool,
+    path: String,
+    secure: bool,
+}
+
+impl<F: Fetcher> Session<F> {
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            state: Arc::default(),
+        }
+    }
+
+    /// A session over another fetcher that shares this one's cookies and
+    /// cache, for example a rate-limited clone of the same client.
+    pub fn share<G: Fetcher>(&self, inner: G) -> Session<G> {
+        Session {
+            inner,
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    fn cookies(&self) -> MutexGuard<'_, Vec<Cookie>> {
+        self.state
+            .cookies
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn cache(&self) -> MutexGuard<'_, ResponseCache> {
+        self.state
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<F: Fetcher> Fetcher for Session<F> {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+        let cacheable = request.method == FetchMethod::Get
+            && !request
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(RANGE));
+        let key = cache_key(request);
+        if cacheable && let Some(cached) = self.cache().responses.get(&key).cloned() {
+            let mut response = FetchResponse {
+                from_cache: true,
+                ..cached
+            };
+            response.truncate_body(request.max_body_bytes);
+            return Ok(response);
+        }
+
+        let url = Url::parse(&request.url).ok();
+        let mut request = request.clone();
+        if let Some(url) = &url
+            && !request
+                .headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(COOKIE))
+            && let Some(header) = cookie_header(&self.cookies(), url)
+        {
+            request.headers.push((COOKIE.to_owned(), header));
+        }
+
+        let response = self.inner.fetch(&request)?;
+
+        if let Some(url) = &url {
+            let mut cookies = self.cookies();
+            for (_, value) in response
+                .headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case(SET_COOKIE))
+            {
+                store_cookie(&mut cookies, url, value);
+            }
+        }
+
+        if cacheable && response.is_success() && !response.truncated {
+            let mut cache = self.cache();
+            if cache.bytes + response.body.len() <= DEFAULT_SESSION_CACHE_BYTES
+                && !cache.responses.contains_key(&key)
+            {
+                cache.bytes += response.body.len();
+                cache.responses.insert(key, response.clone());
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+fn cache_key(request: &FetchRequest) -> String {
+    let user_agent = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(USER_AGENT))
+        .map_or("", |(_, value)| value.as_str());
+    format!("{user_agent}\n{}", request.url)
+}
+
+/// `name=value; name=value` for the cookies that apply to `url`.
+fn cookie_header(cookies: &[Cookie], url: &Url) -> Option<String> {
+    let host = url.host_str()?.to_ascii_lowercase();
+    let header = cookies
+        .iter()
+        .filter(|cookie| {
+            let domain_matches = if cookie.host_only {
+                host == cookie.domain
+            } else {
+                host == cookie.domain || host.ends_with(&format!(".{}", cookie.domain))
+            };
+            domain_matches
+                && path_matches(url.path(), &cookie.path)
+                && (!cookie.secure || url.scheme() == "https")
+        })
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect::<Vec<_>>()
+        .join("; ");
+    (!header.is_empty()).then_some(header)
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/'))
+}
+
+/// Parses a `Set-Cookie` value and adds, replaces, or removes the cookie.
+/// Expiry dates are ignored; `Max-Age=0` deletes.
+fn store_cookie(cookies: &mut Vec<Cookie>, url: &Url, header: &str) {
+    let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+        return;
+    };
+    let mut parts = header.split(';');
+    let Some((name, value)) = parts.next().and_then(|pair| pair.split_once('=')) else {
+        return;
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_owned(),
+        value: value.trim().to_owned(),
+        domain: host.clone(),
+        host_only: true,
+        path: default_path(url.path()),
+        secure: false,
+    };
+    let mut expired = false;
+    for attribute in parts {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "domain" => {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                if domain.is_empty() {
+                    continue;
+                }
+                // A site may only set cookies for itself or a parent domain.
+                if host != domain && !host.ends_with(&format!(".{domain}")) {
+                    return;
+                }
+                // Public suffixes such as `co.uk` and single labels would share
+                // the cookie with every site below them; one naming the host
+                // itself stays host-only, as RFC 6265 section 5.3 asks.
+                if psl::domain_str(&domain).is_none() {
+                    if domain == host {
+                        continue;
+                    }
+                    return;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            }
+            "path" if value.starts_with('/') => cookie.path = value.to_owned(),
+            "secure" => cookie.secure = true,
+            "max-age" => expired = value.parse::<i64>().is_ok_and(|seconds| seconds <= 0),
+            _ => {}
+        }
+    }
+
+    cookies.retain(|existing| {
+        existing.name != cookie.name
+            || existing.domain != cookie.domain
+            || existing.path != cookie.path
+    });
+    if !expired {
+        cookies.push(cookie);
+    }
+}
+
+/// The directory of the request path, per RFC 6265 section 5.1.4.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(index) => path[..index].to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use url::Url;
+
+    use super::{Session, cookie_header, store_cookie};
+    use crate::fetch::header::{RANGE, USER_AGENT};
+    use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+
+    /// Sets a cookie on the page, and records the `Cookie` header of every
+    /// request it sees.
+    #[derive(Default)]
+    struct Site {
+        requests: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    impl Fetcher for Site {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            let cookie = request
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+                .map(|(_, value)| value.clone());
+            self.requests
+                .lock()
+                .unwrap()
+                .push((request.url.clone(), cookie));
+
+            let header = |value: &str| ("Set-Cookie".to_owned(), value.to_owned());
+            Ok(match request.url.as_str() {
+                "https://www.example.com/" => FetchResponse {
+                    headers: vec![
+                        header("session=abc; Path=/; Domain=example.com; Secure"),
+                        header("page=home; Max-Age=3600"),
+                        header("tracking=; Max-Age=0"),
+                        header("other=1; Domain=elsewhere.example"),
+                    ],
+                    ..FetchResponse::ok(b"<html></html>".to_vec())
+                },
+                _ => FetchResponse::ok(b"wOF2 font bytes".to_vec()),
+            })
+        }
+    }
+
+    fn cookies_sent(site: &Site, url: &str) -> Option<String> {
+        site.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(requested, _)| requested == url)
+            .and_then(|(_, cookie)| cookie.clone())
+    }
+
+    #[test]
+    fn replays_cookies_set_during_extraction() {
+        let session = Session::new(Site::default());
+
+        session
+            .fetch(&FetchRequest::get("https://www.example.com/"))
+            .unwrap();
+        session
+            .fetch(&FetchRequest::get("https://cdn.example.com/a.woff2"))
+            .unwrap();
+        session
+            .fetch(&FetchRequest::get("https://www.example.com/b.woff2"))
+            .unwrap();
+        session
+            .fetch(&FetchRequest::get("http://cdn.example.com/c.woff2"))
+            .unwrap();
+        session
+            .fetch(&FetchRequest::get("https://elsewhere.example/d.woff2"))
+            .unwrap();
+
+        let site = session.inner();
+        assert_eq!(cookies_sent(site, "https://www.example.com/"), None);
+        assert_eq!(
+            cookies_sent(site, "https://cdn.example.com/a.woff2").as_deref(),
+            Some("session=abc")
+        );
+        assert_eq!(
+            cookies_sent(site, "https://www.example.com/b.woff2").as_deref(),
+            Some("session=abc; page=home")
+        );
+        assert_eq!(
+            cookies_sent(site, "http://cdn.example.com/c.woff2"),
+            None,
+            "secure cookies stay on https"
+        );
+        assert_eq!(
+            cookies_sent(site, "https://elsewhere.example/d.woff2"),
+            None
+        );
+    }
+
+    #[test]
+    fn public_suffix_cookie_domains_are_refused() {
+        let page = Url::parse("https://shop.example.co.uk/").unwrap();
+        let mut cookies = Vec::new();
+        store_cookie(&mut cookies, &page, "wide=1; Domain=co.uk");
+        store_cookie(&mut cookies, &page, "tld=1; Domain=uk");
+        assert!(cookies.is_empty());
+
+        store_cookie(&mut cookies, &page, "site=1; Domain=example.co.uk");
+        let sent = |url: &str| cookie_header(&cookies, &Url::parse(url).unwrap());
+        assert_eq!(
+            sent("https://cdn.example.co.uk/").as_deref(),
+            Some("site=1")
+        );
+        assert_eq!(sent("https://other.co.uk/"), None);
+
+        let intranet = Url::parse("http://intranet/").unwrap();
+        store_cookie(&mut cookies, &intranet, "lan=1; Domain=intranet");
+        let sent = |url: &str| cookie_header(&cookies, &Url::parse(url).unwrap());
+        assert_eq!(sent("http://intranet/").as_deref(), Some("lan=1"));
+        assert_eq!(sent("http://www.intranet/"), None, "kept host-only");
+    }
+
+    #[test]
+    fn caches_complete_gets_across_shared_sessions() {
+        let session = Session::new(Site::default());
+        let url = "https://cdn.example.com/a.woff2";
+
+        let first = session.fetch(&FetchRequest::get(url)).unwrap();
+        assert!(!first.from_cache);
+
+        let ranged = session
+            .fetch(&FetchRequest::get(url).header(RANGE, "bytes=0-0"))
+            .unwrap();
+        assert!(!ranged.from_cache, "range requests bypass the cache");
+
+        let mobile = session
+            .fetch(&FetchRequest::get(url).header(USER_AGENT, "iPhone"))
+            .unwrap();
+        assert!(!mobile.from_cache, "each user agent gets its own entry");
+
+        let shared = session.share(Site::default());
+        let cached = shared
+            .fetch(&FetchRequest::get(url).max_body_bytes(Some(4)))
+            .unwrap();
+        assert!(cached.from_cache);
+        assert!(cached.truncated);
+        assert_eq!(cached.body, b"wOF2");
+        assert!(shared.inner().requests.lock().unwrap().is_empty());
+
+        assert!(!session.fetch(&FetchRequest::head(url)).unwrap().from_cache);
+        assert_eq!(session.inner().requests.lock().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,823 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use brotli::enc::BrotliEncoderParams;
+use brotli::enc::backward_references::BrotliEncoderMode;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::binary::{FontContainer, WOFF2_KNOWN_TAGS, read_base128, read_tag, read_u16, read_u32};
+use crate::error::SfntError;
+
+pub(crate) const GLYF_TAG: [u8; 4] = *b"glyf";
+pub(crate) const LOCA_TAG: [u8; 4] = *b"loca";
+pub(crate) const HEAD_TAG: [u8; 4] = *b"head";
+const HMTX_TAG: [u8; 4] = *b"hmtx";
+const HHEA_TAG: [u8; 4] = *b"hhea";
+const MAXP_TAG: [u8; 4] = *b"maxp";
+const TTCF_TAG: [u8; 4] = *b"ttcf";
+
+/// Offset of `checkSumAdjustment` in the `head` table.
+const CHECKSUM_ADJUSTMENT: usize = 8;
+/// Offset of `indexToLocFormat` in the `head` table.
+pub(crate) const INDEX_TO_LOC_FORMAT: usize = 50;
+
+/// WOFF2 `glyf` and `loca` transform version meaning "stored as is".
+const WOFF2_NULL_GLYF_TRANSFORM: u8 = 3;
+
+/// A font's tables with any WOFF or WOFF2 wrapping removed, ready to be
+/// edited and written back in any of the three containers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SfntFont {
+    /// `0x00010000` for TrueType outlines, `OTTO` for CFF.
+    pub flavor: u32,
+    pub tables: BTreeMap<[u8; 4], Vec<u8>>,
+}
+
+impl SfntFont {
+    /// Reads a TrueType, OpenType, WOFF, or WOFF2 file. Collections, EOT,
+    /// and SVG fonts are not supported.
+    pub fn parse(bytes: &[u8]) -> Result<Self, SfntError> {
+        match FontContainer::sniff(bytes) {
+            Some(FontContainer::TrueType | FontContainer::OpenType) => parse_sfnt(bytes),
+            Some(FontContainer::Woff) => parse_woff(bytes),
+            Some(FontContainer::Woff2) => parse_woff2(bytes),
+            Some(container) => Err(SfntError::UnsupportedContainer {
+                format: container.format(),
+            }),
+            None => Err(SfntError::UnsupportedContainer { format: "unknown" }),
+        }
+    }
+
+    pub fn table(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        self.tables.get(tag).map(Vec::as_slice)
+    }
+
+    /// Writes the font in `container`, which must be TrueType, OpenType,
+    /// WOFF, or WOFF2.
+    pub fn encode(&self, container: FontContainer) -> Result<Vec<u8>, SfntError> {
+        match container {
+            FontContainer::TrueType | FontContainer::OpenType => Ok(self.to_sfnt()),
+            FontContainer::Woff => Ok(self.to_woff()),
+            FontContainer::Woff2 => Ok(self.to_woff2()),
+            other => Err(SfntError::UnsupportedContainer {
+                format: other.format(),
+            }),
+        }
+    }
+
+    /// A plain `.ttf`/`.otf` file with table checksums and the `head`
+    /// checksum adjustment filled in.
+    pub fn to_sfnt(&self) -> Vec<u8> {
+        let num_tables = self.tables.len() as u16;
+        let entry_selector = if num_tables == 0 {
+            0
+        } else {
+            15 - num_tables.leading_zeros() as u16
+        };
+        let search_range = (1_u16 << entry_selector) * 16;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.flavor.to_be_bytes());
+        bytes.extend_from_slice(&num_tables.to_be_bytes());
+        bytes.extend_from_slice(&search_range.to_be_bytes());
+        bytes.extend_from_slice(&entry_selector.to_be_bytes());
+        bytes.extend_from_slice(&(num_tables * 16).saturating_sub(search_range).to_be_bytes());
+
+        let mut offset = 12 + self.tables.len() * 16;
+        let mut head_offset = None;
+        for (tag, data) in &self.tables {
+            let mut data = data.clone();
+            if *tag == HEAD_TAG && data.len() >= CHECKSUM_ADJUSTMENT + 4 {
+                data[CHECKSUM_ADJUSTMENT..CHECKSUM_ADJUSTMENT + 4].fill(0);
+                head_offset = Some(offset);
+            }
+            bytes.extend_from_slice(tag);
+            bytes.extend_from_slice(&checksum(&data).to_be_bytes());
+            bytes.extend_from_slice(&(offset as u32).to_be_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            offset += padded(data.len());
+        }
+        for (tag, data) in &self.tables {
+            let start = bytes.len();
+            bytes.extend_from_slice(data);
+            if *tag == HEAD_TAG && data.len() >= CHECKSUM_ADJUSTMENT + 4 {
+                bytes[start + CHECKSUM_ADJUSTMENT..start + CHECKSUM_ADJUSTMENT + 4].fill(0);
+            }
+            bytes.resize(padded(bytes.len()), 0);
+        }
+
+        if let Some(head) = head_offset {
+            let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&bytes));
+            bytes[head + CHECKSUM_ADJUSTMENT..head + CHECKSUM_ADJUSTMENT + 4]
+                .copy_from_slice(&adjustment.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// A WOFF 1.0 file with each table zlib-compressed when that helps.
+    pub fn to_woff(&self) -> Vec<u8> {
+        let sfnt = self.to_sfnt();
+        let records = sfnt_records(&sfnt);
+
+        let mut directory = Vec::new();
+        let mut data = Vec::new();
+        let data_start = 44 + records.len() * 20;
+        for (tag, table_checksum, table) in &records {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+            let compressed = encoder
+                .write_all(table)
+                .and_then(|()| encoder.finish())
+                .ok()
+                .filter(|compressed| compressed.len() < table.len());
+            let stored = compressed.as_deref().unwrap_or(table);
+
+            directory.extend_from_slice(tag);
+            directory.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+            directory.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+            directory.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            directory.extend_from_slice(&table_checksum.to_be_bytes());
+            data.extend_from_slice(stored);
+            data.resize(padded(data.len()), 0);
+        }
+
+        let mut bytes = Vec::with_capacity(data_start + data.len());
+        bytes.extend_from_slice(b"wOFF");
+        bytes.extend_from_slice(&self.flavor.to_be_bytes());
+        bytes.extend_from_slice(&((data_start + data.len()) as u32).to_be_bytes());
+        bytes.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&0_u16.to_be_bytes());
+        bytes.extend_from_slice(&(sfnt.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&1_u16.to_be_bytes());
+        bytes.extend_from_slice(&0_u16.to_be_bytes());
+        // No metadata or private blocks.
+        bytes.extend_from_slice(&[0; 20]);
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    /// A WOFF2 file. Tables are stored untransformed and compressed together
+    /// with Brotli, which every WOFF2 decoder accepts.
+    pub fn to_woff2(&self) -> Vec<u8> {
+        let sfnt = self.to_sfnt();
+        let mut records = sfnt_records(&sfnt);
+        // `loca` must directly follow `glyf`.
+        if let Some(loca) = records.iter().position(|(tag, _, _)| *tag == LOCA_TAG) {
+            let loca = records.remove(loca);
+            let glyf = records
+                .iter()
+                .position(|(tag, _, _)| *tag == GLYF_TAG)
+                .map_or(records.len(), |glyf| glyf + 1);
+            records.insert(glyf, loca);
+        }
+
+        let mut directory = Vec::new();
+        let mut stream = Vec::new();
+        for (tag, _, table) in &records {
+            let transform = if *tag == GLYF_TAG || *tag == LOCA_TAG {
+                WOFF2_NULL_GLYF_TRANSFORM << 6
+            } else {
+                0
+            };
+            match WOFF2_KNOWN_TAGS.iter().position(|known| **known == *tag) {
+                Some(index) => directory.push(index as u8 | transform),
+                None => {
+                    directory.push(63 | transform);
+                    directory.extend_from_slice(tag);
+                }
+            }
+            push_base128(&mut directory, table.len() as u32);
+            stream.extend_from_slice(table);
+        }
+
+        let params = BrotliEncoderParams {
+            quality: 11,
+            lgwin: 22,
+            mode: BrotliEncoderMode::BROTLI_MODE_FONT,
+            size_hint: stream.len(),
+            ..BrotliEncoderParams::default()
+        };
+        let mut compressed = Vec::new();
+        brotli::BrotliCompress(&mut stream.as_slice(), &mut compressed, &params)
+            .expect("compressing into memory cannot fail");
+
+        let length = padded(48 + directory.len() + compressed.len());
+        let mut bytes = Vec::with_capacity(length);
+        bytes.extend_from_slice(b"wOF2");
+        bytes.extend_from_slice(&self.flavor.to_be_bytes());
+        bytes.extend_from_slice(&(length as u32).to_be_bytes());
+        bytes.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&0_u16.to_be_bytes());
+        bytes.extend_from_slice(&(sfnt.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&1_u16.to_be_bytes());
+        bytes.extend_from_slice(&0_u16.to_be_bytes());
+        // No metadata or private blocks.
+        bytes.extend_from_slice(&[0; 20]);
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&compressed);
+        bytes.resize(length, 0);
+        bytes
+    }
+}
+
+fn parse_sfnt(bytes: &[u8]) -> Result<SfntFont, SfntError> {
+    let malformed = || SfntError::Malformed { container: "sfnt" };
+    let flavor = read_u32(bytes, 0).ok_or_else(malformed)?;
+    let num_tables = read_u16(bytes, 4).ok_or_else(malformed)? as usize;
+
+    let mut tables = BTreeMap::new();
+    for index in 0..num_tables {
+        let record = 12 + index * 16;
+        let tag = read_tag(bytes, record).ok_or_else(malformed)?;
+        let offset = read_u32(bytes, record + 8).ok_or_else(malformed)? as usize;
+        let length = read_u32(bytes, record + 12).ok_or_else(malformed)? as usize;
+        let data = bytes
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(malformed)?;
+        tables.insert(tag, data.to_vec());
+    }
+
+    Ok(SfntFont { flavor, tables })
+}
+
+fn parse_woff(bytes: &[u8]) -> Result<SfntFont, SfntError> {
+    let malformed = || SfntError::Malformed { container: "WOFF" };
+    let flavor = read_u32(bytes, 4).ok_or_else(malformed)?;
+    let num_tables = read_u16(bytes, 12).ok_or_else(malformed)? as usize;
+
+    let mut tables = BTreeMap::new();
+    for index in 0..num_tables {
+        let record = 44 + index * 20;
+        let tag = read_tag(bytes, record).ok_or_else(malformed)?;
+        let offset = read_u32(bytes, record + 4).ok_or_else(malformed)? as usize;
+        let compressed_length = read_u32(bytes, record + 8).ok_or_else(malformed)? as usize;
+        let original_length = read_u32(bytes, record + 12).ok_or_else(malformed)? as usize;
+        let data = bytes
+            .get(offset..offset.saturating_add(compressed_length))
+            .ok_or_else(malformed)?;
+
+        let table = if compressed_length < original_length {
+            // Inflated no further than the length the directory declares.
+            let mut decoded = Vec::new();
+            ZlibDecoder::new(data)
+                .take(original_length as u64)
+                .read_to_end(&mut decoded)
+                .map_err(|_| malformed())?;
+            decoded
+        } else {
+            data.to_vec()
+        };
+        tables.insert(tag, table);
+    }
+
+    Ok(SfntFont { flavor, tables })
+}
+
+fn parse_woff2(bytes: &[u8]) -> Result<SfntFont, SfntError> {
+    let malformed = || SfntError::Malformed { container: "WOFF2" };
+    let flavor = read_u32(bytes, 4).ok_or_else(malformed)?;
+    if flavor.to_be_bytes() == TTCF_TAG {
+        return Err(SfntError::UnsupportedContainer {
+            format: "WOFF2 collection",
+        });
+    }
+    let num_tables = read_u16(bytes, 12).ok_or_else(malformed)? as usize;
+    let compressed_length = read_u32(bytes, 20).ok_or_else(malformed)? as usize;
+
+    let mut cursor = 48;
+    let mut entries = Vec::with_capacity(num_tables);
+    for _ in 0..num_tables {
+        let flags = *bytes.get(cursor).ok_or_else(malformed)?;
+        cursor += 1;
+        let tag = match flags & 0x3f {
+            63 => {
+                let tag = read_tag(bytes, cursor).ok_or_else(malformed)?;
+                cursor += 4;
+                tag
+            }
+            known => *WOFF2_KNOWN_TAGS[known as usize],
+        };
+
+        let original_length = read_base128(bytes, &mut cursor).ok_or_else(malformed)?;
+        let transform_version = flags >> 6;
+        let transformed = if tag == GLYF_TAG || tag == LOCA_TAG {
+            transform_version != WOFF2_NULL_GLYF_TRANSFORM
+        } else {
+            transform_version != 0
+        };
+        let stored_length = if transformed {
+            read_base128(bytes, &mut cursor).ok_or_else(malformed)?
+        } else {
+            original_length
+        };
+        entries.push((tag, stored_length as usize, transformed));
+    }
+
+    let compressed = bytes
+        .get(cursor..cursor.saturating_add(compressed_length))
+        .ok_or_else(malformed)?;
+    // Tables are stored back to back, so the stream never needs to decode
+    // past the lengths the directory declares.
+    let declared = entries
+        .iter()
+        .map(|(_, length, _)| *length as u64)
+        .sum::<u64>();
+    let mut stream = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(declared)
+        .read_to_end(&mut stream)
+        .map_err(|_| malformed())?;
+
+    let mut tables = BTreeMap::new();
+    let mut transformed_glyf = None;
+    let mut transformed_hmtx = None;
+    let mut offset = 0_usize;
+    for (tag, length, transformed) in entries {
+        let data = stream
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(malformed)?;
+        offset += length;
+        match (tag, transformed) {
+            (GLYF_TAG, true) => transformed_glyf = Some(data),
+            // Rebuilt together with `glyf`.
+            (LOCA_TAG, true) => {}
+            (HMTX_TAG, true) => transformed_hmtx = Some(data),
+            _ => {
+                tables.insert(tag, data.to_vec());
+            }
+        }
+    }
+
+    let mut x_mins = Vec::new();
+    if let Some(data) = transformed_glyf {
+        let glyphs = reconstruct_glyf(data).ok_or_else(malformed)?;
+        x_mins = glyphs.x_mins;
+        tables.insert(GLYF_TAG, glyphs.glyf);
+        tables.insert(LOCA_TAG, glyphs.loca);
+    }
+    if let Some(data) = transformed_hmtx {
+        let hmtx = reconstruct_hmtx(data, &tables, &x_mins).ok_or_else(malformed)?;
+        tables.insert(HMTX_TAG, hmtx);
+    }
+
+    Ok(SfntFont { flavor, tables })
+}
+
+/// `glyf` and `loca` rebuilt from the WOFF2 transformed `glyf` table.
+struct Glyphs {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    /// Each glyph's `xMin`, which a transformed `hmtx` may leave out.
+    x_mins: Vec<i16>,
+}
+
+/// Reverses the WOFF2 `glyf` transform (section 5.1 of the specification).
+fn reconstruct_glyf(data: &[u8]) -> Option<Glyphs> {
+    let mut header = Reader::new(data);
+    header.u16()?;
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+    let mut sizes = [0_usize; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+
+    let mut offset = header.position;
+    let mut next_stream = |size: usize| {
+        let stream = data.get(offset..offset.checked_add(size)?)?;
+        offset += size;
+        Some(Reader::new(stream))
+    };
+    let mut contour_counts = next_stream(sizes[0])?;
+    let mut point_counts = next_stream(sizes[1])?;
+    let mut flags = next_stream(sizes[2])?;
+    let mut coordinates = next_stream(sizes[3])?;
+    let mut components = next_stream(sizes[4])?;
+    let mut bboxes = next_stream(sizes[5])?;
+    let mut instructions = next_stream(sizes[6])?;
+    let overlaps = (option_flags & 1 != 0)
+        .then(|| next_stream(num_glyphs.div_ceil(8)))
+        .flatten();
+
+    let bbox_bitmap = bboxes.take(num_glyphs.div_ceil(32) * 4)?;
+    let has_bit = |bitmap: &[u8], glyph: usize| bitmap[glyph >> 3] & (0x80 >> (glyph & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    for glyph in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let explicit_bbox = has_bit(bbox_bitmap, glyph);
+        match contour_counts.i16()? {
+            0 => {
+                if explicit_bbox {
+                    return None;
+                }
+                x_mins.push(0);
+            }
+            -1 => {
+                if !explicit_bbox {
+                    return None;
+                }
+                let bbox = bboxes.take(8)?;
+                x_mins.push(i16::from_be_bytes([bbox[0], bbox[1]]));
+                glyf.extend_from_slice(&(-1_i16).to_be_bytes());
+                glyf.extend_from_slice(bbox);
+
+                let mut has_instructions = false;
+                loop {
+                    let component_flags = components.u16()?;
+                    let mut length = 2 + if component_flags & 0x0001 != 0 { 4 } else { 2 };
+                    if component_flags & 0x0008 != 0 {
+                        length += 2;
+                    } else if component_flags & 0x0040 != 0 {
+                        length += 4;
+                    } else if component_flags & 0x0080 != 0 {
+                        length += 8;
+                    }
+                    glyf.extend_from_slice(&component_flags.to_be_bytes());
+                    glyf.extend_from_slice(components.take(length)?);
+                    has_instructions |= component_flags & 0x0100 != 0;
+                    if component_flags & 0x0020 == 0 {
+                        break;
+                    }
+                }
+                if has_instructions {
+                    let length = coordinates.u255()?;
+                    glyf.extend_from_slice(&length.to_be_bytes());
+                    glyf.extend_from_slice(instructions.take(length as usize)?);
+                }
+            }
+            contours if contours > 0 => {
+                let mut end_points = Vec::with_capacity(contours as usize);
+                let mut total = 0_usize;
+                for _ in 0..contours {
+                    total += point_counts.u255()? as usize;
+                    end_points.push(u16::try_from(total.checked_sub(1)?).ok()?);
+                }
+
+                let mut points = Vec::with_capacity(total);
+                let (mut x, mut y) = (0_i32, 0_i32);
+                for _ in 0..total {
+                    let flag = flags.u8()?;
+                    let (dx, dy) = decode_triplet(flag & 0x7f, &mut coordinates)?;
+                    x += dx;
+                    y += dy;
+                    points.push((x, y, flag & 0x80 == 0));
+                }
+                let instruction_length = coordinates.u255()?;
+
+                let bbox = if explicit_bbox {
+                    bboxes.take(8)?.to_vec()
+                } else {
+                    let clamp = |value: i32| value.clamp(i16::MIN.into(), i16::MAX.into()) as i16;
+                    let x_min = points.iter().map(|point| point.0).min().unwrap_or(0);
+                    let y_min = points.iter().map(|point| point.1).min().unwrap_or(0);
+                    let x_max = points.iter().map(|point| point.0).max().unwrap_or(0);
+                    let y_max = points.iter().map(|point| point.1).max().unwrap_or(0);
+                    [x_min, y_min, x_max, y_max]
+                        .into_iter()
+                        .flat_map(|value| clamp(value).to_be_bytes())
+                        .collect()
+                };
+                x_mins.push(i16::from_be_bytes([bbox[0], bbox[1]]));
+
+                glyf.extend_from_slice(&contours.to_be_bytes());
+                glyf.extend_from_slice(&bbox);
+                for end_point in end_points {
+                    glyf.extend_from_slice(&end_point.to_be_bytes());
+                }
+                glyf.extend_from_slice(&instruction_length.to_be_bytes());
+                glyf.extend_from_slice(instructions.take(instruction_length as usize)?);
+                let overlap = overlaps
+                    .as_ref()
+                    .is_some_and(|bitmap| has_bit(bitmap.bytes, glyph));
+                push_simple_points(&mut glyf, &points, overlap);
+            }
+            _ => return None,
+        }
+        glyf.resize(padded(glyf.len()), 0);
+    }
+    offsets.push(glyf.len());
+
+    let loca = if index_format == 0 {
+        offsets
+            .iter()
+            .flat_map(|offset| ((offset / 2) as u16).to_be_bytes())
+            .collect()
+    } else {
+        offsets
+            .iter()
+            .flat_map(|offset| (*offset as u32).to_be_bytes())
+            .collect()
+    };
+    Some(Glyphs { glyf, loca, x_mins })
+}
+
+/// One point delta from the WOFF2 triplet encoding.
+fn decode_triplet(flag: u8, stream: &mut Reader<'_>) -> Option<(i32, i32)> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let flag_value = i32::from(flag);
+    Some(if flag < 10 {
+        let b0 = i32::from(stream.u8()?);
+        (0, with_sign(flag, ((flag_value & 14) << 7) + b0))
+    } else if flag < 20 {
+        let b0 = i32::from(stream.u8()?);
+        (with_sign(flag, (((flag_value - 10) & 14) << 7) + b0), 0)
+    } else if flag < 84 {
+        let b0 = flag_value - 20;
+        let b1 = i32::from(stream.u8()?);
+        (
+            with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+            with_sign(flag >> 1, 1 + ((b0 & 0x0c) << 2) + (b1 & 0x0f)),
+        )
+    } else if flag < 120 {
+        let b0 = flag_value - 84;
+        let b1 = i32::from(stream.u8()?);
+        let b2 = i32::from(stream.u8()?);
+        (
+            with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+            with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+        )
+    } else if flag < 124 {
+        let b1 = i32::from(stream.u8()?);
+        let b2 = i32::from(stream.u8()?);
+        let b3 = i32::from(stream.u8()?);
+        (
+            with_sign(flag, (b1 << 4) + (b2 >> 4)),
+            with_sign(flag >> 1, ((b2 & 0x0f) << 8) + b3),
+        )
+    } else {
+        let x = i32::from(stream.u16()?);
+        let y = i32::from(stream.u16()?);
+        (with_sign(flag, x), with_sign(flag >> 1, y))
+    })
+}
+
+/// Appends the flags and coordinates of a simple glyph, using one-byte
+/// deltas where they fit.
+fn push_simple_points(glyf: &mut Vec<u8>, points: &[(i32, i32, bool)], overlap: bool) {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let (mut previous_x, mut previous_y) = (0, 0);
+    for (index, (x, y, on_curve)) in points.iter().enumerate() {
+        let mut flag = u8::from(*on_curve);
+        if index == 0 && overlap {
+            flag |= 0x40;
+        }
+        for (delta, short_bit, same_bit, output) in [
+            (x - previous_x, 0x02, 0x10, &mut xs),
+            (y - previous_y, 0x04, 0x20, &mut ys),
+        ] {
+            if delta == 0 {
+                flag |= same_bit;
+            } else if delta.unsigned_abs() < 256 {
+                flag |= short_bit;
+                if delta > 0 {
+                    flag |= same_bit;
+                }
+                output.push(delta.unsigned_abs() as u8);
+            } else {
+                output.extend_from_slice(&(delta as i16).to_be_bytes());
+            }
+        }
+        flags.push(flag);
+        (previous_x, previous_y) = (*x, *y);
+    }
+    glyf.extend_from_slice(&flags);
+    glyf.extend_from_slice(&xs);
+    glyf.extend_from_slice(&ys);
+}
+
+/// Reverses the WOFF2 `hmtx` transform, filling omitted left side bearings
+/// with each glyph's `xMin`.
+fn reconstruct_hmtx(
+    data: &[u8],
+    tables: &BTreeMap<[u8; 4], Vec<u8>>,
+    x_mins: &[i16],
+) -> Option<Vec<u8>> {
+    let num_glyphs = read_u16(tables.get(&MAXP_TAG)?, 4)? as usize;
+    let num_h_metrics = read_u16(tables.get(&HHEA_TAG)?, 34)? as usize;
+    if num_h_metrics == 0 || num_h_metrics > num_glyphs || x_mins.len() < num_glyphs {
+        return None;
+    }
+
+    let mut stream = Reader::new(data);
+    let flags = stream.u8()?;
+    let advances = (0..num_h_metrics)
+        .map(|_| stream.u16())
+        .collect::<Option<Vec<_>>>()?;
+    let mut bearings = |range: std::ops::Range<usize>, omitted: bool| {
+        range
+            .map(|glyph| {
+                if omitted {
+                    Some(x_mins[glyph])
+                } else {
+                    stream.i16()
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+    let proportional = bearings(0..num_h_metrics, flags & 1 != 0)?;
+    let monospaced = bearings(num_h_metrics..num_glyphs, flags & 2 != 0)?;
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 4 + monospaced.len() * 2);
+    for (advance, bearing) in advances.iter().zip(&proportional) {
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&bearing.to_be_bytes());
+    }
+    for bearing in monospaced {
+        hmtx.extend_from_slice(&bearing.to_be_bytes());
+    }
+    Some(hmtx)
+}
+
+/// `(tag, checksum, data)` for each table of an sfnt written by
+/// [`SfntFont::to_sfnt`].
+fn sfnt_records(sfnt: &[u8]) -> Vec<([u8; 4], u32, &[u8])> {
+    let num_tables = read_u16(sfnt, 4).unwrap_or(0) as usize;
+    (0..num_tables)
+        .filter_map(|index| {
+            let record = 12 + index * 16;
+            let tag = read_tag(sfnt, record)?;
+            let table_checksum = read_u32(sfnt, record + 4)?;
+            let offset = read_u32(sfnt, record + 8)? as usize;
+            let length = read_u32(sfnt, record + 12)? as usize;
+            Some((tag, table_checksum, sfnt.get(offset..offset + length)?))
+        })
+        .collect()
+}
+
+/// The OpenType table checksum: the sum of big-endian `u32`s, zero padded.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0_u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn padded(length: usize) -> usize {
+    length.next_multiple_of(4)
+}
+
+fn push_base128(bytes: &mut Vec<u8>, value: u32) {
+    let groups = (1..5).take_while(|shift| value >> (7 * shift) != 0).count() + 1;
+    for group in (0..groups).rev() {
+        let byte = ((value >> (7 * group)) & 0x7f) as u8;
+        bytes.push(if group == 0 { byte } else { byte | 0x80 });
+    }
+}
+
+/// A big-endian cursor over one of the WOFF2 glyph streams.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let slice = self
+            .bytes
+            .get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        Some(slice)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.u16().map(|value| value as i16)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// The WOFF2 `255UInt16` variable-length encoding.
+    fn u255(&mut self) -> Option<u16> {
+        const WORD_CODE: u8 = 253;
+        const ONE_MORE_BYTE_CODE_2: u8 = 254;
+        const ONE_MORE_BYTE_CODE_1: u8 = 255;
+        const LOWEST_U_CODE: u16 = 253;
+        match self.u8()? {
+            WORD_CODE => self.u16(),
+            ONE_MORE_BYTE_CODE_1 => Some(u16::from(self.u8()?) + LOWEST_U_CODE),
+            ONE_MORE_BYTE_CODE_2 => Some(u16::from(self.u8()?) + LOWEST_U_CODE * 2),
+            code => Some(u16::from(code)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{SfntFont, reconstruct_glyf};
+    use crate::binary::FontContainer;
+
+    fn font() -> SfntFont {
+        let mut head = vec![0_u8; 54];
+        head[18..20].copy_from_slice(&1000_u16.to_be_bytes());
+        SfntFont {
+            flavor: 0x0001_0000,
+            tables: BTreeMap::from([
+                (*b"head", head),
+                (*b"glyf", vec![7; 20]),
+                (*b"loca", vec![0, 0, 0, 10]),
+                (*b"name", b"Brand Sans Brand Sans Brand Sans".to_vec()),
+                (*b"Xtra", vec![1, 2, 3]),
+            ]),
+        }
+    }
+
+    #[test]
+    fn sfnt_checksums_add_up_to_the_magic_number() {
+        let bytes = font().to_sfnt();
+
+        let sum = bytes.chunks(4).fold(0_u32, |sum, word| {
+            sum.wrapping_add(u32::from_be_bytes(word.try_into().unwrap()))
+        });
+        assert_eq!(sum, 0xB1B0_AFBA);
+        assert_eq!(bytes.len() % 4, 0);
+    }
+
+    #[test]
+    fn round_trips_through_each_container() {
+        let font = font();
+        let expected = SfntFont::parse(&font.to_sfnt()).unwrap();
+
+        for container in [
+            FontContainer::TrueType,
+            FontContainer::Woff,
+            FontContainer::Woff2,
+        ] {
+            let bytes = font.encode(container).unwrap();
+            assert_eq!(FontContainer::sniff(&bytes), Some(container));
+            assert_eq!(SfntFont::parse(&bytes).unwrap(), expected);
+        }
+        assert!(font.encode(FontContainer::Eot).is_err());
+    }
+
+    #[test]
+    fn woff_tables_inflate_no_further_than_their_declared_length() {
+        let mut font = font();
+        font.tables.insert(*b"name", vec![0; 64 * 1024]);
+        let mut bytes = font.to_woff();
+
+        let record = (0..font.tables.len())
+            .map(|index| 44 + index * 20)
+            .find(|record| &bytes[*record..*record + 4] == b"name")
+            .unwrap();
+        bytes[record + 12..record + 16].copy_from_slice(&1024_u32.to_be_bytes());
+
+        let parsed = SfntFont::parse(&bytes).unwrap();
+        assert_eq!(parsed.table(b"name"), Some(&[0; 1024][..]));
+    }
+
+    #[test]
+    fn rebuilds_transformed_woff2_glyphs() {
+        let mut transformed = Vec::new();
+        // Two glyphs, short loca; seven stream sizes.
+        for field in [0_u16, 0, 2, 0] {
+            transformed.extend_from_slice(&field.to_be_bytes());
+        }
+        for size in [4_u32, 1, 3, 5, 0, 4, 0] {
+            transformed.extend_from_slice(&size.to_be_bytes());
+        }
+        // Contour counts, point counts, flags, coordinates, bbox bitmap.
+        transformed.extend_from_slice(&[0, 0, 0, 1]);
+        transformed.push(3);
+        transformed.extend_from_slice(&[1, 11, 86]);
+        transformed.extend_from_slice(&[0, 100, 99, 99, 0]);
+        transformed.extend_from_slice(&[0; 4]);
+
+        let glyphs = reconstruct_glyf(&transformed).unwrap();
+
+        let mut triangle = Vec::new();
+        for field in [1_i16, 0, 0, 100, 100, 2, 0] {
+            triangle.extend_from_slice(&field.to_be_bytes());
+        }
+        triangle.extend_from_slice(&[0x31, 0x33, 0x27, 100, 100, 100]);
+        assert_eq!(glyphs.glyf, triangle);
+        assert_eq!(glyphs.loca, vec![0, 0, 0, 0, 0, 10]);
+        assert_eq!(glyphs.x_mins, vec![0, 0]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::download::SavedFile;
+use crate::model::FontInfo;
+
+/// Provenance of a saved font, written as `<file>.json` next to it when
+/// [`DownloadLayout::sidecar`](crate::layout::DownloadLayout::sidecar) is set.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FontSidecar {
+    /// The font URL, or `None` for fonts embedded as `data:` URLs.
+    pub url: Option<String>,
+    /// The `Referer` the request was sent with.
+    pub referer: Option<String>,
+    /// The page the font was found on.
+    pub page: String,
+    /// The stylesheet or label the font was declared in.
+    pub source_stylesheet: String,
+    pub descriptors: SidecarDescriptors,
+    /// Copyright and license comments from the declaring stylesheet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_notices: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub license_urls: Vec<String>,
+    pub headers: SidecarHeaders,
+    pub size: u64,
+    pub sha256: String,
+    /// UTC time the download finished, in RFC 3339 format.
+    pub downloaded_at: String,
+}
+
+/// The `@font-face` descriptors that declared the font.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SidecarDescriptors {
+    pub font_family: String,
+    pub font_weight: String,
+    pub font_style: String,
+    pub format: String,
+    /// Enclosing `@media`, `@supports`, and `@layer` rules.
+    pub conditions: Vec<String>,
+}
+
+/// Response headers useful for revalidating or dating a download.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SidecarHeaders {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl FontSidecar {
+    pub(crate) fn new(
+        font: &FontInfo,
+        referer: Option<&str>,
+        headers: SidecarHeaders,
+        bytes: &[u8],
+        sha256: String,
+    ) -> Self {
+        Self {
+            url: (!font.url.starts_with("data:")).then(|| font.url.clone()),
+            referer: referer.map(str::to_owned),
+            page: font.referer.clone(),
+            source_stylesheet: font.source_stylesheet.clone(),
+            descriptors: SidecarDescriptors {
+                font_family: font.family.clone(),
+                font_weight: font.weight.clone(),
+                font_style: font.style.clone(),
+                format: font.format.clone(),
+                conditions: font.conditions.clone(),
+            },
+            license_notices: font.license_notices.clone(),
+            license_urls: font.license_urls.clone(),
+            headers,
+            size: bytes.len() as u64,
+            sha256,
+            downloaded_at: rfc3339_utc(SystemTime::now()),
+        }
+    }
+}
+
+/// `fonts/inter.woff2` -> `fonts/inter.woff2.json`.
+pub fn sidecar_path(font_path: &Path) -> PathBuf {
+    let mut path = font_path.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// The sidecar at `path`, or `None` when it is missing or unreadable.
+pub(crate) fn read_sidecar(path: &Path) -> Option<FontSidecar> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Writes the sidecar of `original` next to `saved` with its new size and
+/// checksum, after a saved font was rewritten or converted. Returns the
+/// path written, or `None` when `original` has no readable sidecar.
+pub(crate) fn copy_sidecar(original: &Path, saved: &SavedFile) -> io::Result<Option<PathBuf>> {
+    let Some(mut sidecar) = read_sidecar(&sidecar_path(original)) else {
+        return Ok(None);
+    };
+    sidecar.size = saved.size;
+    sidecar.sha256 = saved.sha256.clone();
+    let path = sidecar_path(&saved.path);
+    let json = serde_json::to_string_pretty(&sidecar).expect("sidecar serializes to JSON");
+    fs::write(&path, json + "\n")?;
+    Ok(Some(path))
+}
+
+/// Formats a time as `YYYY-MM-DDTHH:MM:SSZ`.
+pub(crate) fn rfc3339_utc(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, remainder) = (seconds / 86_400, seconds % 86_400);
+
+    // Howard Hinnant's civil_from_days, for days since 1970-01-01.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        remainder / 3_600,
+        remainder % 3_600 / 60,
+        remainder % 60
+    )
+}
+
+/// Parses an HTTP date such as a `Last-Modified` header, in the
+/// `Sun, 06 Nov 1994 08:49:37 GMT` form servers send. The obsolete RFC 850
+/// and asctime forms, and dates before 1970, give `None`.
+pub(crate) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let [_, day, month, year, time, "GMT"] = value.split_whitespace().collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    let day = day
+        .parse::<i64>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| *name == month)? as i64 + 1;
+    let year = year.parse::<i64>().ok().filter(|year| *year >= 1970)?;
+    let [hour, minute, second] = time
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?
+        .try_into()
+        .ok()?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Howard Hinnant's days_from_civil, the inverse of the one above.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146_097 + day_of_era - 719_468).ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86_400 + hour * 3_600 + minute * 60 + second))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{parse_http_date, rfc3339_utc, sidecar_path};
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(rfc3339_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339_utc(UNIX_EPOCH + Duration::from_secs(951_782_400 + 3_661)),
+            "2000-02-29T01:01:01Z"
+        );
+        assert_eq!(
+            rfc3339_utc(UNIX_EPOCH + Duration::from_secs(1_798_761_599)),
+            "2026-12-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    fn parses_http_dates() {
+        let parsed = |value| parse_http_date(value).map(rfc3339_utc);
+        assert_eq!(
+            parsed("Wed, 01 Jan 2025 00:00:00 GMT").as_deref(),
+            Some("2025-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            parsed("Tue, 29 Feb 2000 01:01:01 GMT").as_deref(),
+            Some("2000-02-29T01:01:01Z")
+        );
+        assert_eq!(
+            parsed("Thu, 31 Dec 2026 23:59:59 GMT").as_deref(),
+            Some("2026-12-31T23:59:59Z")
+        );
+        assert_eq!(parsed("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parsed("Wed, 01 Jan 2025 00:00:00 +0100"), None);
+        assert_eq!(parsed("Wed, 01 Foo 2025 00:00:00 GMT"), None);
+        assert_eq!(parsed("Wed, 01 Jan 1969 00:00:00 GMT"), None);
+    }
+
+    #[test]
+    fn sidecars_sit_next_to_the_font() {
+        assert_eq!(
+            sidecar_path(Path::new("out/inter/inter-400.woff2")),
+            Path::new("out/inter/inter-400.woff2.json")
+        );
+    }
+}
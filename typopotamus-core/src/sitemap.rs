@@ -0,0 +1,152 @@
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The sitemaps protocol's limit for one uncompressed sitemap.
+pub const MAX_SITEMAP_BYTES: u64 = 50 * 1024 * 1024;
+
+static LOC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<loc>\s*(.*?)\s*</loc>").expect("valid sitemap loc regex"));
+static SITEMAP_INDEX_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<sitemapindex[\s>]").expect("valid sitemap index regex"));
+
+/// The `<loc>` entries of one sitemap document. A sitemap index lists
+/// further sitemaps; a `<urlset>` lists pages.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Sitemap {
+    pub pages: Vec<String>,
+    pub sitemaps: Vec<String>,
+    /// Only the first `max_bytes` were read, so entries may be missing.
+    pub truncated: bool,
+}
+
+/// Parses a sitemap or sitemap index, gunzipping `.xml.gz` bodies first.
+/// At most `max_bytes` of XML are read, however far a gzipped body inflates.
+pub fn parse_sitemap(body: &[u8], max_bytes: u64) -> Sitemap {
+    let mut decompressed = Vec::new();
+    let xml = if body.starts_with(&GZIP_MAGIC) {
+        if GzDecoder::new(body)
+            .take(max_bytes.saturating_add(1))
+            .read_to_end(&mut decompressed)
+            .is_err()
+        {
+            return Sitemap::default();
+        }
+        decompressed.as_slice()
+    } else {
+        body
+    };
+    let truncated = xml.len() as u64 > max_bytes;
+    let xml = &String::from_utf8_lossy(&xml[..xml.len().min(max_bytes as usize)]);
+
+    let locations = LOC_RE
+        .captures_iter(xml)
+        .map(|capture| unescape_xml(&capture[1]))
+        .filter(|location| !location.is_empty())
+        .collect::<Vec<_>>();
+
+    if SITEMAP_INDEX_RE.is_match(xml) {
+        Sitemap {
+            pages: Vec::new(),
+            sitemaps: locations,
+            truncated,
+        }
+    } else {
+        Sitemap {
+            pages: locations,
+            sitemaps: Vec::new(),
+            truncated,
+        }
+    }
+}
+
+/// Up to `limit` items spread evenly over `items`, so a sample of a large
+/// sitemap covers its later sections (often other templates) as well as the
+/// first few entries.
+pub fn sample_evenly<T: Clone>(items: &[T], limit: usize) -> Vec<T> {
+    if items.len() <= limit {
+        return items.to_vec();
+    }
+
+    (0..limit)
+        .map(|position| items[position * items.len() / limit].clone())
+        .collect()
+}
+
+fn unescape_xml(input: &str) -> String {
+    let trimmed = input.trim();
+    let value = trimmed
+        .strip_prefix("<![CDATA[")
+        .and_then(|rest| rest.strip_suffix("]]>"))
+        .unwrap_or(trimmed);
+
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use super::{MAX_SITEMAP_BYTES, Sitemap, parse_sitemap, sample_evenly};
+
+    #[test]
+    fn parses_url_sets_indexes_and_gzipped_sitemaps() {
+        let urlset = br#"<?xml version="1.0"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc>https://example.com/</loc></url>
+              <url><loc> https://example.com/shop?a=1&amp;b=2 </loc></url>
+            </urlset>"#;
+        assert_eq!(
+            parse_sitemap(urlset, MAX_SITEMAP_BYTES),
+            Sitemap {
+                pages: vec![
+                    "https://example.com/".to_owned(),
+                    "https://example.com/shop?a=1&b=2".to_owned(),
+                ],
+                sitemaps: Vec::new(),
+                truncated: false,
+            }
+        );
+
+        let index = br#"<sitemapindex><sitemap><loc><![CDATA[https://example.com/blog.xml.gz]]></loc></sitemap></sitemapindex>"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(index).unwrap();
+        assert_eq!(
+            parse_sitemap(&encoder.finish().unwrap(), MAX_SITEMAP_BYTES).sitemaps,
+            vec!["https://example.com/blog.xml.gz"]
+        );
+    }
+
+    #[test]
+    fn gzipped_sitemaps_inflate_no_further_than_the_limit() {
+        let entry = "<url><loc>https://example.com/page</loc></url>";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(b"<urlset>").unwrap();
+        for _ in 0..100_000 {
+            encoder.write_all(entry.as_bytes()).unwrap();
+        }
+        let body = encoder.finish().unwrap();
+
+        let sitemap = parse_sitemap(&body, 10 * entry.len() as u64 + 8);
+        assert!(sitemap.truncated);
+        assert_eq!(sitemap.pages.len(), 10);
+    }
+
+    #[test]
+    fn samples_spread_across_the_whole_list() {
+        let items = (0..10).collect::<Vec<_>>();
+        assert_eq!(sample_evenly(&items, 4), vec![0, 2, 5, 7]);
+        assert_eq!(sample_evenly(&items[..3], 4), vec![0, 1, 2]);
+    }
+}
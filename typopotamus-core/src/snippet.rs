@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+
+use crate::fontface::{LocalFont, css_string, font_face_css};
+use crate::model::{FaceSource, group_faces};
+use crate::selection::canonical_format;
+use crate::theme::theme_families;
+
+/// A framework to generate font loading code for.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Framework {
+    /// A `next/font/local` module exporting one loader per family.
+    Next,
+    /// An Astro component importing the files and declaring them.
+    Astro,
+    /// `<link rel="preload">` tags and a `<style>` block.
+    Html,
+}
+
+impl Framework {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Next => "next",
+            Self::Astro => "astro",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// One face of a family and the file that should load it.
+struct SnippetFace<'a> {
+    /// Position of `preferred` in the fonts given.
+    index: usize,
+    weight: String,
+    style: String,
+    preferred: LocalFont<'a>,
+}
+
+/// Ready-to-paste code loading `fonts` in `framework`. Hrefs are used as
+/// given, so they should be relative to where the snippet will live.
+pub fn framework_snippet(framework: Framework, fonts: &[LocalFont<'_>]) -> String {
+    match framework {
+        Framework::Next => next_snippet(fonts),
+        Framework::Astro => astro_snippet(fonts),
+        Framework::Html => html_snippet(fonts),
+    }
+}
+
+fn next_snippet(fonts: &[LocalFont<'_>]) -> String {
+    let mut code =
+        String::from("// Generated by typopotamus.\nimport localFont from \"next/font/local\";\n");
+    let families = faces_by_family(fonts);
+    let themes = theme_families(families.keys().copied());
+    for theme in &themes {
+        let Some(faces) = families.get(theme.name.as_str()) else {
+            continue;
+        };
+        code.push_str(&format!(
+            "\nexport const {} = localFont({{\n  src: [\n",
+            identifier(&[&theme.key, "font"])
+        ));
+        for face in faces {
+            code.push_str(&format!(
+                "    {{ path: {}, weight: {}, style: {} }},\n",
+                js_string(&module_path(face.preferred.href)),
+                js_string(&face.weight),
+                js_string(&face.style)
+            ));
+        }
+        code.push_str("  ],\n");
+        code.push_str(&format!(
+            "  variable: {},\n  display: \"swap\",\n",
+            js_string(&format!("--font-{}", theme.key))
+        ));
+        let fallback = theme.stack[1..]
+            .iter()
+            .map(|entry| js_string(entry))
+            .collect::<Vec<_>>()
+            .join(", ");
+        code.push_str(&format!("  fallback: [{fallback}],\n"));
+        if let Some(overrides) = faces
+            .iter()
+            .map(|face| &face.preferred.font.metric_overrides)
+            .find(|overrides| !overrides.is_empty())
+        {
+            let declarations = overrides
+                .declarations()
+                .map(|(prop, value)| {
+                    format!(
+                        "{{ prop: {}, value: {} }}",
+                        js_string(prop),
+                        js_string(value)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            code.push_str(&format!("  declarations: [{declarations}],\n"));
+        }
+        code.push_str("});\n");
+    }
+    code
+}
+
+fn astro_snippet(fonts: &[LocalFont<'_>]) -> String {
+    let mut imports = String::new();
+    let mut names = Vec::with_capacity(fonts.len());
+    for local in fonts {
+        let name = identifier(&[&local.font.family, &local.font.weight, &local.font.style]);
+        let mut unique = name.clone();
+        let mut counter = 2;
+        while names.contains(&unique) {
+            unique = format!("{name}{counter}");
+            counter += 1;
+        }
+        imports.push_str(&format!(
+            "import {unique} from {};\n",
+            js_string(&format!("{}?url", module_path(local.href)))
+        ));
+        names.push(unique);
+    }
+
+    // `${name}` survives CSS string escaping and is filled in by the
+    // template literal.
+    let placeholders = names
+        .iter()
+        .map(|name| format!("${{{name}}}"))
+        .collect::<Vec<_>>();
+    let templated = fonts
+        .iter()
+        .zip(&placeholders)
+        .map(|(local, href)| LocalFont {
+            font: local.font,
+            href,
+        })
+        .collect::<Vec<_>>();
+    let css = font_face_css(&templated).replace('`', "\\`");
+
+    let mut preloads = String::new();
+    for face in faces_by_family(fonts).values().flatten() {
+        if let Some(kind) = preload_type(&face.preferred.font.format) {
+            preloads.push_str(&format!(
+                "<link rel=\"preload\" href={{{}}} as=\"font\" type=\"{kind}\" crossorigin />\n",
+                names[face.index]
+            ));
+        }
+    }
+
+    format!(
+        "---\n// Generated by typopotamus.\n{imports}\nconst fontFaces = `\n{css}`;\n---\n{preloads}<style is:inline set:html={{fontFaces}}></style>\n"
+    )
+}
+
+fn html_snippet(fonts: &[LocalFont<'_>]) -> String {
+    let mut html = String::from("<!-- Generated by typopotamus. -->\n");
+    for face in faces_by_family(fonts).values().flatten() {
+        if let Some(kind) = preload_type(&face.preferred.font.format) {
+            html.push_str(&format!(
+                "<link rel=\"preload\" href=\"{}\" as=\"font\" type=\"{kind}\" crossorigin>\n",
+                html_attribute(face.preferred.href)
+            ));
+        }
+    }
+    html.push_str(&format!("<style>\n{}</style>\n", font_face_css(fonts)));
+    html
+}
+
+/// Faces per family, in the same order as [`font_face_css`].
+fn faces_by_family<'a>(fonts: &[LocalFont<'a>]) -> BTreeMap<&'a str, Vec<SnippetFace<'a>>> {
+    let mut families = BTreeMap::<&str, Vec<usize>>::new();
+    for (index, local) in fonts.iter().enumerate() {
+        families
+            .entry(local.font.family.as_str())
+            .or_default()
+            .push(index);
+    }
+
+    families
+        .into_iter()
+        .map(|(family, indices)| {
+            let faces = group_faces(indices.iter().map(|index| {
+                let font = fonts[*index].font;
+                FaceSource {
+                    index: *index,
+                    weight: &font.weight,
+                    style: &font.style,
+                    format: &font.format,
+                }
+            }))
+            .into_iter()
+            .filter_map(|face| {
+                let index = face.preferred_index()?;
+                Some(SnippetFace {
+                    index,
+                    preferred: fonts[index],
+                    weight: face.weight,
+                    style: face.style,
+                })
+            })
+            .collect();
+            (family, faces)
+        })
+        .collect()
+}
+
+/// The `type` of a font preload; EOT and SVG fonts are not worth
+/// preloading.
+fn preload_type(format: &str) -> Option<&'static str> {
+    match canonical_format(format) {
+        "WOFF2" => Some("font/woff2"),
+        "WOFF" => Some("font/woff"),
+        "TRUETYPE" => Some("font/ttf"),
+        "OPENTYPE" => Some("font/otf"),
+        _ => None,
+    }
+}
+
+/// Bundlers only resolve relative imports that start with `./` or `../`.
+fn module_path(href: &str) -> String {
+    if href.starts_with("./") || href.starts_with("../") || href.starts_with('/') {
+        href.to_owned()
+    } else {
+        format!("./{href}")
+    }
+}
+
+/// A camelCase JavaScript identifier from words such as a family name.
+fn identifier(parts: &[&str]) -> String {
+    let mut name = String::new();
+    let mut upper = false;
+    for character in parts.iter().flat_map(|part| part.chars().chain([' '])) {
+        if character.is_ascii_alphanumeric() {
+            if name.is_empty() && character.is_ascii_digit() {
+                name.push_str("font");
+                upper = true;
+            }
+            if upper && !name.is_empty() {
+                name.push(character.to_ascii_uppercase());
+            } else {
+                name.push(character.to_ascii_lowercase());
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if name.is_empty() {
+        name.push_str("font");
+    }
+    name
+}
+
+fn js_string(value: &str) -> String {
+    format!("\"{}\"", css_string(value))
+}
+
+fn html_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Framework, framework_snippet};
+    use crate::fontface::LocalFont;
+    use crate::model::FontInfo;
+
+    fn font(format: &str, weight: &str) -> FontInfo {
+        FontInfo {
+            name: format!("brand-{weight}.{}", format.to_ascii_lowercase()),
+            family: "Brand Sans".to_owned(),
+            format: format.to_owned(),
+            url: format!("https://cdn.example/brand-{weight}.{format}"),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    fn snippet(framework: Framework) -> String {
+        let mut fonts = [
+            font("WOFF", "400"),
+            font("WOFF2", "400"),
+            font("WOFF2", "700"),
+        ];
+        fonts[2].metric_overrides.size_adjust = Some("105%".to_owned());
+        let hrefs = [
+            "fonts/brand-400.woff",
+            "fonts/brand-400.woff2",
+            "fonts/brand-700.woff2",
+        ];
+        let local = fonts
+            .iter()
+            .zip(hrefs)
+            .map(|(font, href)| LocalFont { font, href })
+            .collect::<Vec<_>>();
+        framework_snippet(framework, &local)
+    }
+
+    #[test]
+    fn next_exports_one_local_font_per_family() {
+        let code = snippet(Framework::Next);
+
+        assert!(code.contains("import localFont from \"next/font/local\";"));
+        assert!(code.contains("export const brandSansFont = localFont({"));
+        assert!(code.contains(
+            "{ path: \"./fonts/brand-400.woff2\", weight: \"400\", style: \"normal\" },"
+        ));
+        assert!(!code.contains("brand-400.woff\""), "only the best format");
+        assert!(code.contains("variable: \"--font-brand-sans\","));
+        assert!(code.contains("fallback: [\"ui-sans-serif\""));
+        assert!(code.contains("declarations: [{ prop: \"size-adjust\", value: \"105%\" }],"));
+    }
+
+    #[test]
+    fn astro_imports_each_file_and_preloads_each_face() {
+        let code = snippet(Framework::Astro);
+
+        assert!(code.starts_with("---\n"));
+        assert!(code.contains("import brandSans400Normal from \"./fonts/brand-400.woff?url\";"));
+        assert!(code.contains("import brandSans400Normal2 from \"./fonts/brand-400.woff2?url\";"));
+        assert!(code.contains(
+            "src: url(\"${brandSans400Normal2}\") format(\"woff2\"), url(\"${brandSans400Normal}\") format(\"woff\");"
+        ));
+        assert!(code.contains(
+            "<link rel=\"preload\" href={brandSans700Normal} as=\"font\" type=\"font/woff2\" crossorigin />"
+        ));
+        assert_eq!(code.matches("rel=\"preload\"").count(), 2);
+        assert!(code.ends_with("<style is:inline set:html={fontFaces}></style>\n"));
+    }
+
+    #[test]
+    fn html_preloads_and_declares_faces() {
+        let html = snippet(Framework::Html);
+
+        assert!(html.contains(
+            "<link rel=\"preload\" href=\"fonts/brand-400.woff2\" as=\"font\" type=\"font/woff2\" crossorigin>"
+        ));
+        assert_eq!(html.matches("rel=\"preload\"").count(), 2);
+        assert!(html.contains("<style>\n@font-face {\n  font-family: \"Brand Sans\";"));
+        assert!(html.ends_with("</style>\n"));
+    }
+}
@@ -0,0 +1,375 @@
+use std::fmt::Write as _;
+use std::fs;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::binary::{FontContainer, font_characters};
+use crate::download::{SavedFile, sanitize_component};
+use crate::error::SpecimenError;
+use crate::model::{FontInfo, group_by_family};
+
+/// Set at several sizes when the regular face covers it.
+pub const PANGRAM: &str = "The quick brown fox jumps over the lazy dog";
+
+const SIZES: [u32; 6] = [72, 48, 32, 24, 16, 12];
+const MAX_GRID_CHARACTERS: usize = 512;
+/// Characters shown instead of the pangram for fonts without Latin letters.
+const SAMPLE_CHARACTERS: usize = 32;
+
+const WIDTH: u32 = 1200;
+const MARGIN: u32 = 40;
+const GRID_COLUMNS: u32 = 24;
+const GRID_CELL: u32 = 46;
+const LABEL_STYLE: &str = r##"font-family="sans-serif" font-size="12" fill="#888""##;
+
+/// A self-contained SVG specimen sheet for one family.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Specimen {
+    pub family: String,
+    /// Unique file name, e.g. `brand-sans.svg`.
+    pub file_name: String,
+    pub svg: String,
+}
+
+struct SpecimenFace {
+    label: String,
+    regular_distance: (bool, u32),
+    data_url: String,
+    characters: Vec<char>,
+}
+
+/// Renders a specimen per family with saved files: a pangram at several
+/// sizes, a weight ladder with one line per face, and a grid of the regular
+/// face's characters. Each face is embedded as a `data:` URL, so the sheet
+/// renders without the font files next to it. EOT, SVG, and collection files
+/// cannot be embedded and are passed over for another format of the face.
+pub fn render_specimens(
+    fonts: &[FontInfo],
+    saved_files: &[SavedFile],
+) -> Result<Vec<Specimen>, SpecimenError> {
+    let mut specimens = Vec::<Specimen>::new();
+
+    for family in group_by_family(fonts) {
+        let mut faces = Vec::new();
+        for face in &family.faces {
+            for index in &face.font_indices {
+                let Some(saved) = saved_files
+                    .iter()
+                    .find(|saved| saved.url == fonts[*index].url)
+                else {
+                    continue;
+                };
+                let bytes = fs::read(&saved.path).map_err(|source| SpecimenError::Read {
+                    path: saved.path.clone(),
+                    source,
+                })?;
+                let Some(mime) = embeddable_mime(&bytes) else {
+                    continue;
+                };
+
+                faces.push(SpecimenFace {
+                    label: format!("{} {}", face.weight, face.style),
+                    regular_distance: regular_distance(&face.weight, &face.style),
+                    data_url: format!("data:{mime};base64,{}", STANDARD.encode(&bytes)),
+                    characters: font_characters(&bytes),
+                });
+                break;
+            }
+        }
+        if faces.is_empty() {
+            continue;
+        }
+
+        let key = match sanitize_component(&family.name) {
+            key if key.is_empty() => "font".to_owned(),
+            key => key,
+        };
+        let mut file_name = format!("{key}.svg");
+        let mut counter = 1;
+        while specimens
+            .iter()
+            .any(|specimen| specimen.file_name == file_name)
+        {
+            file_name = format!("{key}-{counter}.svg");
+            counter += 1;
+        }
+
+        specimens.push(Specimen {
+            svg: specimen_svg(&family.name, &faces),
+            family: family.name,
+            file_name,
+        });
+    }
+
+    Ok(specimens)
+}
+
+fn specimen_svg(family: &str, faces: &[SpecimenFace]) -> String {
+    let regular = faces
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, face)| face.regular_distance)
+        .map_or(0, |(index, _)| index);
+    let grid = faces[regular]
+        .characters
+        .iter()
+        .copied()
+        .filter(|character| is_visible(*character))
+        .take(MAX_GRID_CHARACTERS)
+        .collect::<Vec<_>>();
+
+    let mut body = String::new();
+    let mut y = MARGIN + 32;
+    let _ = writeln!(
+        body,
+        r#"<text x="{MARGIN}" y="{y}" font-family="sans-serif" font-size="32" font-weight="bold">{}</text>"#,
+        xml_escape(family)
+    );
+    y += 24;
+    let _ = writeln!(
+        body,
+        r#"<text x="{MARGIN}" y="{y}" {LABEL_STYLE}>{} face(s), {} character(s)</text>"#,
+        faces.len(),
+        faces[regular].characters.len()
+    );
+
+    y += 48;
+    section_heading(&mut body, "Sizes", y);
+    let sample = sample_text(&faces[regular].characters);
+    for size in SIZES {
+        y += size * 13 / 10 + 8;
+        let _ = writeln!(
+            body,
+            r#"<text x="{MARGIN}" y="{y}" {LABEL_STYLE}>{size}px</text><text x="{}" y="{y}" font-family="face-{regular}" font-size="{size}">{}</text>"#,
+            MARGIN + 56,
+            xml_escape(&sample)
+        );
+    }
+
+    y += 48;
+    section_heading(&mut body, "Weights", y);
+    for (index, face) in faces.iter().enumerate() {
+        y += 44;
+        let _ = writeln!(
+            body,
+            r#"<text x="{MARGIN}" y="{y}" {LABEL_STYLE}>{}</text><text x="{}" y="{y}" font-family="face-{index}" font-size="28">{}</text>"#,
+            xml_escape(&face.label),
+            MARGIN + 120,
+            xml_escape(&sample_text(&face.characters))
+        );
+    }
+
+    if !grid.is_empty() {
+        y += 48;
+        section_heading(&mut body, "Characters", y);
+        y += 16;
+        for (index, character) in grid.iter().enumerate() {
+            let index = index as u32;
+            let x = MARGIN + index % GRID_COLUMNS * GRID_CELL;
+            let top = y + index / GRID_COLUMNS * GRID_CELL;
+            let _ = writeln!(
+                body,
+                r##"<rect x="{x}" y="{top}" width="{GRID_CELL}" height="{GRID_CELL}" fill="none" stroke="#ddd"/><text x="{}" y="{}" font-family="face-{regular}" font-size="26" text-anchor="middle">{}</text>"##,
+                x + GRID_CELL / 2,
+                top + GRID_CELL * 7 / 10,
+                xml_escape(&character.to_string())
+            );
+        }
+        y += (grid.len() as u32).div_ceil(GRID_COLUMNS) * GRID_CELL;
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{height}\" viewBox=\"0 0 {WIDTH} {height}\">\n<!-- Generated by typopotamus. -->\n<style>\n",
+        height = y + MARGIN
+    );
+    for (index, face) in faces.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "@font-face {{ font-family: \"face-{index}\"; src: url(\"{}\"); }}",
+            face.data_url
+        );
+    }
+    svg.push_str("</style>\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n");
+    svg.push_str(&body);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn section_heading(body: &mut String, title: &str, y: u32) {
+    let _ = writeln!(
+        body,
+        r##"<text x="{MARGIN}" y="{y}" font-family="sans-serif" font-size="14" font-weight="bold" fill="#444">{title}</text>"##
+    );
+}
+
+/// The pangram, or the font's first characters when it lacks any of its
+/// letters. Fonts whose characters could not be read get the pangram.
+fn sample_text(characters: &[char]) -> String {
+    let covered = PANGRAM
+        .chars()
+        .filter(|character| *character != ' ')
+        .all(|character| characters.binary_search(&character).is_ok());
+    if characters.is_empty() || covered {
+        return PANGRAM.to_owned();
+    }
+
+    characters
+        .iter()
+        .copied()
+        .filter(|character| is_visible(*character))
+        .take(SAMPLE_CHARACTERS)
+        .collect()
+}
+
+fn embeddable_mime(bytes: &[u8]) -> Option<&'static str> {
+    match FontContainer::sniff(bytes)? {
+        FontContainer::Woff2 => Some("font/woff2"),
+        FontContainer::Woff => Some("font/woff"),
+        FontContainer::TrueType => Some("font/ttf"),
+        FontContainer::OpenType => Some("font/otf"),
+        FontContainer::Collection | FontContainer::Eot | FontContainer::Svg => None,
+    }
+}
+
+/// Sorts upright faces first, then by distance from weight 400.
+fn regular_distance(weight: &str, style: &str) -> (bool, u32) {
+    let style = style.to_ascii_lowercase();
+    let weight = weight
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(if weight.contains("bold") { 700 } else { 400 });
+    (
+        style.contains("italic") || style.contains("oblique"),
+        weight.abs_diff(400),
+    )
+}
+
+fn is_visible(character: char) -> bool {
+    !character.is_control()
+        && !character.is_whitespace()
+        && !matches!(character, '\u{fffe}' | '\u{ffff}')
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::{render_specimens, sample_text};
+    use crate::download::SavedFile;
+    use crate::model::{FontInfo, FontSource};
+
+    fn font(family: &str, url: &str, weight: &str, style: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: family.to_owned(),
+            format: "WOFF2".to_owned(),
+            url: url.to_owned(),
+            weight: weight.to_owned(),
+            style: style.to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            sources: vec![FontSource {
+                url: url.to_owned(),
+                format: "WOFF2".to_owned(),
+                tech: Vec::new(),
+            }],
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    fn save(root: &Path, font: &FontInfo, bytes: &[u8]) -> SavedFile {
+        let path = root.join(&font.name);
+        fs::write(&path, bytes).expect("write font");
+        SavedFile {
+            path,
+            url: font.url.clone(),
+            size: bytes.len() as u64,
+            sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_fonts_own_characters() {
+        assert_eq!(sample_text(&[]), super::PANGRAM);
+        assert_eq!(
+            sample_text(&['\u{e000}', '\n', '\u{e001}']),
+            "\u{e000}\u{e001}"
+        );
+    }
+
+    #[test]
+    fn renders_one_self_contained_sheet_per_family() {
+        let root =
+            std::env::temp_dir().join(format!("typopotamus-specimen-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create temp dir");
+
+        let fonts = vec![
+            font(
+                "Brand & Co",
+                "https://cdn.example/brand-700.woff2",
+                "700",
+                "normal",
+            ),
+            font(
+                "Brand & Co",
+                "https://cdn.example/brand-400.woff2",
+                "400",
+                "normal",
+            ),
+            font(
+                "Brand & Co",
+                "https://cdn.example/brand-400i.woff2",
+                "400",
+                "italic",
+            ),
+            font("Legacy", "https://cdn.example/legacy.eot", "400", "normal"),
+            font(
+                "Unsaved",
+                "https://cdn.example/unsaved.woff2",
+                "400",
+                "normal",
+            ),
+        ];
+        let saved = vec![
+            save(&root, &fonts[0], b"wOF2bold"),
+            save(&root, &fonts[1], b"wOF2regular"),
+            save(&root, &fonts[2], b"wOF2italic"),
+            save(&root, &fonts[3], &[0; 64]),
+        ];
+
+        let specimens = render_specimens(&fonts, &saved).expect("render specimens");
+        assert_eq!(specimens.len(), 1);
+        let specimen = &specimens[0];
+        assert_eq!(specimen.file_name, "brand-co.svg");
+
+        let svg = &specimen.svg;
+        assert!(svg.contains(">Brand &amp; Co</text>"));
+        assert_eq!(svg.matches("src: url(\"data:font/woff2;base64,").count(), 3);
+        // Faces are laddered upright first, so the regular face is face-0.
+        assert!(svg.contains(">400 normal</text>"));
+        assert!(svg.contains(
+            "font-family=\"face-0\" font-size=\"72\">The quick brown fox jumps over the lazy dog<"
+        ));
+        assert!(svg.contains(">700 normal</text>"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::binary::FontMetadata;
+use crate::download::embedded_font_size;
+use crate::inspect::InferredFamilyGroup;
+use crate::model::FontInfo;
+use crate::provider::FontProvider;
+
+/// Aggregate numbers for a set of scanned fonts, for a quick performance
+/// snapshot. Byte totals only cover files whose size is known: fonts probed
+/// with [`probe_font_metadata`](crate::binary::probe_font_metadata), fonts
+/// with a `content_length`, and embedded fonts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FontStats {
+    pub files: usize,
+    pub families: usize,
+    pub variable_files: usize,
+    pub static_files: usize,
+    pub sized_files: usize,
+    pub bytes: u64,
+    /// Most files first.
+    pub formats: Vec<FormatStats>,
+    /// Most files first.
+    pub providers: Vec<ProviderStats>,
+    /// Most bytes first, then most files.
+    pub heaviest_families: Vec<FamilyStats>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormatStats {
+    pub format: String,
+    pub files: usize,
+    pub sized_files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProviderStats {
+    pub provider: FontProvider,
+    pub files: usize,
+    pub families: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FamilyStats {
+    pub name: String,
+    pub files: usize,
+    pub sized_files: usize,
+    pub bytes: u64,
+}
+
+/// Summarizes the fonts in `groups`, keeping the `top` heaviest families.
+pub fn font_stats(
+    fonts: &[FontInfo],
+    groups: &[InferredFamilyGroup],
+    metadata: &HashMap<String, FontMetadata>,
+    top: usize,
+) -> FontStats {
+    let mut stats = FontStats {
+        families: groups.len(),
+        ..FontStats::default()
+    };
+    let mut formats = BTreeMap::<String, FormatStats>::new();
+    let mut providers = BTreeMap::<FontProvider, ProviderStats>::new();
+
+    for group in groups {
+        let mut family = FamilyStats {
+            name: group.name.clone(),
+            files: 0,
+            sized_files: 0,
+            bytes: 0,
+        };
+
+        for font in group
+            .font_indices
+            .iter()
+            .filter_map(|index| fonts.get(*index))
+        {
+            let probed = metadata.get(&font.url);
+            let size = probed
+                .and_then(|metadata| metadata.size)
+                .or(font.content_length)
+                .or_else(|| embedded_font_size(&font.url).map(|size| size as u64));
+
+            stats.files += 1;
+            family.files += 1;
+            if probed.is_some_and(|metadata| metadata.variable) || is_weight_range(&font.weight) {
+                stats.variable_files += 1;
+            } else {
+                stats.static_files += 1;
+            }
+
+            let format = formats
+                .entry(font.format.clone())
+                .or_insert_with(|| FormatStats {
+                    format: font.format.clone(),
+                    files: 0,
+                    sized_files: 0,
+                    bytes: 0,
+                });
+            format.files += 1;
+
+            if let Some(size) = size {
+                stats.sized_files += 1;
+                stats.bytes += size;
+                family.sized_files += 1;
+                family.bytes += size;
+                format.sized_files += 1;
+                format.bytes += size;
+            }
+
+            let provider = FontProvider::for_font(font);
+            providers
+                .entry(provider)
+                .or_insert_with(|| ProviderStats {
+                    provider,
+                    files: 0,
+                    families: 0,
+                })
+                .files += 1;
+        }
+
+        for provider in &group.providers {
+            if let Some(entry) = providers.get_mut(provider) {
+                entry.families += 1;
+            }
+        }
+        stats.heaviest_families.push(family);
+    }
+
+    stats.formats = formats.into_values().collect();
+    stats.formats.sort_by_key(|entry| Reverse(entry.files));
+    stats.providers = providers.into_values().collect();
+    stats.providers.sort_by_key(|entry| Reverse(entry.files));
+    stats.heaviest_families.sort_by(|left, right| {
+        right
+            .bytes
+            .cmp(&left.bytes)
+            .then(right.files.cmp(&left.files))
+            .then_with(|| left.name.cmp(&right.name))
+    });
+    stats.heaviest_families.truncate(top);
+    stats
+}
+
+/// A `font-weight` descriptor spanning two different weights, such as
+/// `100 900`, which only variable fonts declare.
+fn is_weight_range(weight: &str) -> bool {
+    let mut bounds = weight.split_whitespace();
+    matches!(
+        (bounds.next(), bounds.next(), bounds.next()),
+        (Some(low), Some(high), None) if low != high
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::font_stats;
+    use crate::binary::FontMetadata;
+    use crate::inspect::{Inference, infer_family_groups};
+    use crate::model::FontInfo;
+    use crate::provider::FontProvider;
+
+    fn font(family: &str, url: &str, format: &str, weight: &str) -> FontInfo {
+        FontInfo {
+            name: url.rsplit('/').next().unwrap_or(url).to_owned(),
+            family: family.to_owned(),
+            format: format.to_owned(),
+            url: url.to_owned(),
+            weight: weight.to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    #[test]
+    fn totals_formats_providers_and_heaviest_families() {
+        let fonts = vec![
+            font(
+                "Brand",
+                "https://www.example.com/brand-regular.woff2",
+                "WOFF2",
+                "400",
+            ),
+            font(
+                "Brand",
+                "https://www.example.com/brand-regular.woff",
+                "WOFF",
+                "400",
+            ),
+            font(
+                "Inter",
+                "https://fonts.gstatic.com/s/inter/v1/inter.woff2",
+                "WOFF2",
+                "100 900",
+            ),
+        ];
+        let metadata = HashMap::from([
+            (
+                fonts[0].url.clone(),
+                FontMetadata {
+                    size: Some(20_000),
+                    ..FontMetadata::default()
+                },
+            ),
+            (
+                fonts[2].url.clone(),
+                FontMetadata {
+                    size: Some(90_000),
+                    variable: true,
+                    ..FontMetadata::default()
+                },
+            ),
+        ]);
+        let groups = infer_family_groups(&fonts, &[0, 1, 2], &Inference::default());
+
+        let stats = font_stats(&fonts, &groups, &metadata, 1);
+
+        assert_eq!((stats.files, stats.families), (3, 2));
+        assert_eq!((stats.variable_files, stats.static_files), (1, 2));
+        assert_eq!((stats.sized_files, stats.bytes), (2, 110_000));
+
+        assert_eq!(stats.formats[0].format, "WOFF2");
+        assert_eq!(
+            (stats.formats[0].files, stats.formats[0].bytes),
+            (2, 110_000)
+        );
+        assert_eq!(
+            (stats.formats[1].files, stats.formats[1].sized_files),
+            (1, 0)
+        );
+
+        assert_eq!(stats.providers[0].provider, FontProvider::SelfHosted);
+        assert_eq!(
+            (stats.providers[0].files, stats.providers[0].families),
+            (2, 1)
+        );
+        assert_eq!(stats.providers[1].provider, FontProvider::GoogleFonts);
+
+        assert_eq!(stats.heaviest_families.len(), 1);
+        assert_eq!(stats.heaviest_families[0].name, "Inter");
+    }
+
+    #[test]
+    fn sizes_fall_back_to_content_length() {
+        let mut fonts = vec![
+            font(
+                "Brand",
+                "https://www.example.com/brand.woff2",
+                "WOFF2",
+                "400",
+            ),
+            font("Brand", "https://www.example.com/brand.woff", "WOFF", "400"),
+        ];
+        fonts[0].content_length = Some(204);
+        fonts[1].content_length = Some(300);
+        let metadata = HashMap::from([(
+            fonts[1].url.clone(),
+            FontMetadata {
+                size: Some(320),
+                ..FontMetadata::default()
+            },
+        )]);
+        let groups = infer_family_groups(&fonts, &[0, 1], &Inference::default());
+
+        let stats = font_stats(&fonts, &groups, &metadata, 1);
+
+        assert_eq!((stats.sized_files, stats.bytes), (2, 524));
+    }
+}
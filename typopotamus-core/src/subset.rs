@@ -0,0 +1,705 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use scraper::{ElementRef, Html, Node};
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::{Face, GlyphId};
+use url::Url;
+
+use crate::binary::{FontContainer, read_u16, read_u32};
+use crate::charset::TextKind;
+use crate::download::{SavedFile, rewrite_font_file, sha256_hex};
+use crate::error::{ExtractError, FetchPhase, SfntError, SubsetError};
+use crate::extractor::fetch_text;
+use crate::fetch::Fetcher;
+use crate::sfnt::{GLYF_TAG, HEAD_TAG, INDEX_TO_LOC_FORMAT, LOCA_TAG, SfntFont};
+use crate::sidecar::{copy_sidecar, sidecar_path};
+
+const CMAP_TAG: [u8; 4] = *b"cmap";
+const GVAR_TAG: [u8; 4] = *b"gvar";
+const DSIG_TAG: [u8; 4] = *b"DSIG";
+
+/// Elements whose text is never rendered.
+const HIDDEN_ELEMENTS: [&str; 6] = ["head", "script", "style", "template", "noscript", "svg"];
+/// Attributes whose values are drawn in the page's fonts.
+const TEXT_ATTRIBUTES: [&str; 2] = ["placeholder", "value"];
+
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// A saved font rewritten to the characters a page uses.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubsetFile {
+    pub path: PathBuf,
+    pub original_size: u64,
+    pub subset_size: u64,
+}
+
+impl SubsetFile {
+    pub fn saved_bytes(&self) -> u64 {
+        self.original_size.saturating_sub(self.subset_size)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SubsetReport {
+    pub subset: Vec<SubsetFile>,
+    /// Fonts left as downloaded, with the reason.
+    pub skipped: Vec<(PathBuf, SubsetError)>,
+}
+
+impl SubsetReport {
+    pub fn original_size(&self) -> u64 {
+        self.subset.iter().map(|file| file.original_size).sum()
+    }
+
+    pub fn subset_size(&self) -> u64 {
+        self.subset.iter().map(|file| file.subset_size).sum()
+    }
+}
+
+/// Fetches each page and collects the characters of its visible text, see
+/// [`text_characters`].
+pub fn page_characters(
+    pages: &[String],
+    fetcher: &dyn Fetcher,
+    user_agent: &str,
+) -> Result<BTreeSet<char>, SubsetError> {
+    let mut characters = BTreeSet::new();
+    for page in pages {
+        let url = Url::parse(page).map_err(|source| SubsetError::Page {
+            source: ExtractError::InvalidUrl {
+                url: page.clone(),
+                source,
+            },
+        })?;
+        let html = fetch_text(
+            fetcher,
+            user_agent,
+            &url,
+            Some(url.as_str()),
+            FetchPhase::Page,
+            TextKind::Html,
+        )
+        .map_err(|source| SubsetError::Page { source })?;
+        characters.extend(text_characters(&visible_text(&html.text)));
+    }
+    Ok(characters)
+}
+
+/// The text of an HTML document that can end up on screen: text nodes
+/// outside `<head>`, scripts, styles, and templates, plus `placeholder`
+/// and `value` attributes.
+pub fn visible_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut text = String::new();
+    collect_text(document.root_element(), &mut text);
+    text
+}
+
+fn collect_text(element: ElementRef<'_>, text: &mut String) {
+    if HIDDEN_ELEMENTS.contains(&element.value().name()) {
+        return;
+    }
+    for attribute in TEXT_ATTRIBUTES {
+        if let Some(value) = element.value().attr(attribute) {
+            text.push_str(value);
+            text.push(' ');
+        }
+    }
+    for child in element.children() {
+        match child.value() {
+            Node::Text(node) => text.push_str(node),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    collect_text(child, text);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The characters of `text` plus their other-case forms, since CSS
+/// `text-transform` can change case after the page is parsed. Always
+/// includes a space; control characters are dropped.
+pub fn text_characters(text: &str) -> BTreeSet<char> {
+    let mut characters = BTreeSet::from([' ']);
+    for character in text.chars().filter(|character| !character.is_control()) {
+        characters.insert(character);
+        characters.extend(character.to_lowercase());
+        characters.extend(character.to_uppercase());
+    }
+    characters
+}
+
+/// Rewrites each saved font to the glyphs `characters` need, keeping the
+/// file only when it got smaller. Sizes, checksums, and sidecars are updated
+/// in place.
+pub fn subset_saved_files(files: &mut [SavedFile], characters: &BTreeSet<char>) -> SubsetReport {
+    let mut report = SubsetReport::default();
+    for file in files {
+        match subset_saved_file(file, characters) {
+            Ok(Some(subset)) => report.subset.push(subset),
+            Ok(None) => {}
+            Err(error) => report.skipped.push((file.path.clone(), error)),
+        }
+    }
+    report
+}
+
+fn subset_saved_file(
+    file: &mut SavedFile,
+    characters: &BTreeSet<char>,
+) -> Result<Option<SubsetFile>, SubsetError> {
+    let bytes = fs::read(&file.path).map_err(|source| SubsetError::Read {
+        path: file.path.clone(),
+        source,
+    })?;
+    let subset = subset_font(&bytes, characters)?;
+    if subset.len() >= bytes.len() {
+        return Ok(None);
+    }
+
+    rewrite_font_file(&file.path, &file.path, &subset).map_err(|source| SubsetError::Write {
+        path: file.path.clone(),
+        source,
+    })?;
+    file.size = subset.len() as u64;
+    file.sha256 = sha256_hex(&subset);
+
+    copy_sidecar(&file.path, file).map_err(|source| SubsetError::Write {
+        path: sidecar_path(&file.path),
+        source,
+    })?;
+
+    Ok(Some(SubsetFile {
+        path: file.path.clone(),
+        original_size: bytes.len() as u64,
+        subset_size: file.size,
+    }))
+}
+
+/// Drops the outlines of every glyph `characters` cannot reach, directly or
+/// through `GSUB` substitutions and composite glyphs, and trims the `cmap`
+/// to match. Glyph ids are kept, so layout and metrics tables stay valid.
+/// The result is written in the same container as the input. Only TrueType
+/// outlines are supported.
+pub fn subset_font(bytes: &[u8], characters: &BTreeSet<char>) -> Result<Vec<u8>, SubsetError> {
+    let container = FontContainer::sniff(bytes).ok_or(SubsetError::Decode {
+        source: SfntError::UnsupportedContainer { format: "unknown" },
+    })?;
+    let mut font = SfntFont::parse(bytes).map_err(|source| SubsetError::Decode { source })?;
+    if font.table(&GLYF_TAG).is_none() {
+        return Err(SubsetError::UnsupportedOutlines);
+    }
+
+    let sfnt = font.to_sfnt();
+    let face = Face::parse(&sfnt, 0).map_err(|_| SubsetError::Decode {
+        source: SfntError::Malformed { container: "sfnt" },
+    })?;
+    let mapping = characters
+        .iter()
+        .filter_map(|character| Some((*character, face.glyph_index(*character)?)))
+        .collect::<Vec<_>>();
+    let mut glyphs = mapping
+        .iter()
+        .map(|(_, glyph)| glyph.0)
+        .collect::<BTreeSet<_>>();
+    glyphs.insert(0);
+    close_over_substitutions(&face, &mut glyphs);
+
+    let long_offsets = font
+        .table(&HEAD_TAG)
+        .and_then(|head| read_u16(head, INDEX_TO_LOC_FORMAT))
+        .ok_or(SubsetError::Table { table: "head" })?
+        != 0;
+    let num_glyphs = face.number_of_glyphs() as usize;
+    let ranges = glyph_ranges(
+        font.table(&LOCA_TAG).unwrap_or_default(),
+        long_offsets,
+        num_glyphs,
+    )
+    .ok_or(SubsetError::Table { table: "loca" })?;
+    let glyf = font.table(&GLYF_TAG).unwrap_or_default();
+    close_over_components(glyf, &ranges, &mut glyphs)
+        .ok_or(SubsetError::Table { table: "glyf" })?;
+
+    let (glyf, loca, long_offsets) = subset_glyf(glyf, &ranges, &glyphs, long_offsets);
+    font.tables.insert(GLYF_TAG, glyf);
+    font.tables.insert(LOCA_TAG, loca);
+    if let Some(head) = font.tables.get_mut(&HEAD_TAG) {
+        head[INDEX_TO_LOC_FORMAT..INDEX_TO_LOC_FORMAT + 2]
+            .copy_from_slice(&u16::from(long_offsets).to_be_bytes());
+    }
+    if let Some(gvar) = font.table(&GVAR_TAG) {
+        let gvar = subset_gvar(gvar, &glyphs).ok_or(SubsetError::Table { table: "gvar" })?;
+        font.tables.insert(GVAR_TAG, gvar);
+    }
+    font.tables.insert(CMAP_TAG, build_cmap(&mapping));
+    // The signature no longer matches.
+    font.tables.remove(&DSIG_TAG);
+
+    font.encode(container)
+        .map_err(|source| SubsetError::Decode { source })
+}
+
+/// Adds every glyph a `GSUB` lookup can turn the kept glyphs into, until
+/// nothing changes. Contextual lookups only point at other lookups, which
+/// are visited anyway.
+fn close_over_substitutions(face: &Face<'_>, glyphs: &mut BTreeSet<u16>) {
+    let Some(gsub) = face.tables().gsub else {
+        return;
+    };
+    loop {
+        let mut added = Vec::new();
+        for lookup in gsub.lookups {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable<'_>>() {
+                for glyph in glyphs.iter().copied() {
+                    substitutes(&subtable, glyph, glyphs, &mut added);
+                }
+            }
+        }
+        let before = glyphs.len();
+        glyphs.extend(added);
+        if glyphs.len() == before {
+            return;
+        }
+    }
+}
+
+fn substitutes(
+    subtable: &SubstitutionSubtable<'_>,
+    glyph: u16,
+    glyphs: &BTreeSet<u16>,
+    added: &mut Vec<u16>,
+) {
+    let Some(index) = subtable.coverage().get(GlyphId(glyph)) else {
+        return;
+    };
+    match subtable {
+        SubstitutionSubtable::Single(SingleSubstitution::Format1 { delta, .. }) => {
+            added.push(glyph.wrapping_add_signed(*delta));
+        }
+        SubstitutionSubtable::Single(SingleSubstitution::Format2 { substitutes, .. }) => {
+            added.extend(substitutes.get(index).map(|glyph| glyph.0));
+        }
+        SubstitutionSubtable::Multiple(multiple) => {
+            if let Some(sequence) = multiple.sequences.get(index) {
+                added.extend(sequence.substitutes.into_iter().map(|glyph| glyph.0));
+            }
+        }
+        SubstitutionSubtable::Alternate(alternate) => {
+            if let Some(set) = alternate.alternate_sets.get(index) {
+                added.extend(set.alternates.into_iter().map(|glyph| glyph.0));
+            }
+        }
+        SubstitutionSubtable::Ligature(ligature) => {
+            if let Some(set) = ligature.ligature_sets.get(index) {
+                for ligature in set {
+                    if ligature
+                        .components
+                        .into_iter()
+                        .all(|component| glyphs.contains(&component.0))
+                    {
+                        added.push(ligature.glyph.0);
+                    }
+                }
+            }
+        }
+        SubstitutionSubtable::ReverseChainSingle(reverse) => {
+            added.extend(reverse.substitutes.get(index).map(|glyph| glyph.0));
+        }
+        SubstitutionSubtable::Context(_) | SubstitutionSubtable::ChainContext(_) => {}
+    }
+}
+
+/// The byte range of each glyph in `glyf`.
+fn glyph_ranges(loca: &[u8], long_offsets: bool, num_glyphs: usize) -> Option<Vec<Range<usize>>> {
+    let offset = |index: usize| {
+        if long_offsets {
+            read_u32(loca, index * 4).map(|offset| offset as usize)
+        } else {
+            read_u16(loca, index * 2).map(|offset| offset as usize * 2)
+        }
+    };
+    (0..num_glyphs)
+        .map(|glyph| {
+            let (start, end) = (offset(glyph)?, offset(glyph + 1)?);
+            (start <= end).then_some(start..end)
+        })
+        .collect()
+}
+
+/// Adds the components of kept composite glyphs, recursively.
+fn close_over_components(
+    glyf: &[u8],
+    ranges: &[Range<usize>],
+    glyphs: &mut BTreeSet<u16>,
+) -> Option<()> {
+    let mut pending = glyphs.iter().copied().collect::<Vec<_>>();
+    while let Some(glyph) = pending.pop() {
+        let Some(range) = ranges.get(glyph as usize) else {
+            continue;
+        };
+        let data = glyf.get(range.clone())?;
+        if data.is_empty() || read_u16(data, 0)? as i16 >= 0 {
+            continue;
+        }
+
+        let mut offset = 10;
+        loop {
+            let flags = read_u16(data, offset)?;
+            let component = read_u16(data, offset + 2)?;
+            if glyphs.insert(component) {
+                pending.push(component);
+            }
+            offset += 4 + if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                4
+            } else {
+                2
+            };
+            if flags & WE_HAVE_A_SCALE != 0 {
+                offset += 2;
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                offset += 4;
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                offset += 8;
+            }
+            if flags & MORE_COMPONENTS == 0 {
+                break;
+            }
+        }
+    }
+    Some(())
+}
+
+/// `glyf` with every glyph outside `glyphs` emptied, the matching `loca`,
+/// and whether it needs long offsets.
+fn subset_glyf(
+    glyf: &[u8],
+    ranges: &[Range<usize>],
+    glyphs: &BTreeSet<u16>,
+    long_offsets: bool,
+) -> (Vec<u8>, Vec<u8>, bool) {
+    let mut subset = Vec::new();
+    let mut offsets = Vec::with_capacity(ranges.len() + 1);
+    for (glyph, range) in ranges.iter().enumerate() {
+        offsets.push(subset.len());
+        if glyphs.contains(&(glyph as u16)) {
+            subset.extend_from_slice(&glyf[range.clone()]);
+            // Short offsets count 2-byte words.
+            subset.resize(subset.len().next_multiple_of(2), 0);
+        }
+    }
+    offsets.push(subset.len());
+
+    let long_offsets = long_offsets || subset.len() / 2 > usize::from(u16::MAX);
+    let loca = if long_offsets {
+        offsets
+            .iter()
+            .flat_map(|offset| (*offset as u32).to_be_bytes())
+            .collect()
+    } else {
+        offsets
+            .iter()
+            .flat_map(|offset| ((offset / 2) as u16).to_be_bytes())
+            .collect()
+    };
+    (subset, loca, long_offsets)
+}
+
+/// `gvar` with the variation data of dropped glyphs removed. Always written
+/// with long offsets.
+fn subset_gvar(gvar: &[u8], glyphs: &BTreeSet<u16>) -> Option<Vec<u8>> {
+    let axis_count = read_u16(gvar, 4)? as usize;
+    let shared_tuple_count = read_u16(gvar, 6)? as usize;
+    let shared_tuples_offset = read_u32(gvar, 8)? as usize;
+    let glyph_count = read_u16(gvar, 12)? as usize;
+    let flags = read_u16(gvar, 14)?;
+    let data_offset = read_u32(gvar, 16)? as usize;
+
+    let offset = |index: usize| {
+        if flags & 1 != 0 {
+            read_u32(gvar, 20 + index * 4).map(|offset| offset as usize)
+        } else {
+            read_u16(gvar, 20 + index * 2).map(|offset| offset as usize * 2)
+        }
+    };
+    let shared_tuples =
+        gvar.get(shared_tuples_offset..shared_tuples_offset + shared_tuple_count * axis_count * 2)?;
+
+    let mut data = Vec::new();
+    let mut offsets = Vec::with_capacity(glyph_count + 1);
+    for glyph in 0..glyph_count {
+        offsets.push(data.len() as u32);
+        if glyphs.contains(&(glyph as u16)) {
+            let (start, end) = (offset(glyph)?, offset(glyph + 1)?);
+            data.extend_from_slice(gvar.get(data_offset + start..data_offset + end)?);
+        }
+    }
+    offsets.push(data.len() as u32);
+
+    let new_shared_tuples_offset = 20 + offsets.len() * 4;
+    let new_data_offset = (new_shared_tuples_offset + shared_tuples.len()).next_multiple_of(4);
+    let mut subset = Vec::with_capacity(new_data_offset + data.len());
+    subset.extend_from_slice(&gvar[..8]);
+    subset.extend_from_slice(&(new_shared_tuples_offset as u32).to_be_bytes());
+    subset.extend_from_slice(&(glyph_count as u16).to_be_bytes());
+    subset.extend_from_slice(&(flags | 1).to_be_bytes());
+    subset.extend_from_slice(&(new_data_offset as u32).to_be_bytes());
+    for offset in offsets {
+        subset.extend_from_slice(&offset.to_be_bytes());
+    }
+    subset.extend_from_slice(shared_tuples);
+    subset.resize(new_data_offset, 0);
+    subset.extend_from_slice(&data);
+    Some(subset)
+}
+
+/// A `cmap` with a format 4 subtable for the Basic Multilingual Plane and,
+/// when other characters are mapped, a format 12 subtable for all of them.
+fn build_cmap(mapping: &[(char, GlyphId)]) -> Vec<u8> {
+    let bmp = mapping
+        .iter()
+        .filter_map(|(character, glyph)| {
+            Some((u16::try_from(u32::from(*character)).ok()?, glyph.0))
+        })
+        .filter(|(code, _)| *code != 0xFFFF)
+        .collect::<Vec<_>>();
+    let format_4 = cmap_format_4(&bmp);
+    let format_12 = (bmp.len() < mapping.len()).then(|| cmap_format_12(mapping));
+
+    let mut records = vec![(0_u16, 3_u16, 0_usize), (3, 1, 0)];
+    if format_12.is_some() {
+        records.insert(1, (0, 4, 1));
+        records.push((3, 10, 1));
+    }
+
+    let subtables_offset = 4 + records.len() * 8;
+    let mut cmap = Vec::new();
+    cmap.extend_from_slice(&0_u16.to_be_bytes());
+    cmap.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    for (platform, encoding, subtable) in records {
+        let offset = if subtable == 0 {
+            subtables_offset
+        } else {
+            subtables_offset + format_4.len()
+        };
+        cmap.extend_from_slice(&platform.to_be_bytes());
+        cmap.extend_from_slice(&encoding.to_be_bytes());
+        cmap.extend_from_slice(&(offset as u32).to_be_bytes());
+    }
+    cmap.extend_from_slice(&format_4);
+    cmap.extend(format_12.unwrap_or_default());
+    cmap
+}
+
+/// Runs of consecutive codes mapped to consecutive glyphs, as
+/// `(first code, last code, first glyph)`.
+fn runs<T: Copy + Into<u32>>(mapping: impl Iterator<Item = (T, u16)>) -> Vec<(T, T, u16)> {
+    let mut runs = Vec::<(T, T, u16)>::new();
+    for (code, glyph) in mapping {
+        match runs.last_mut() {
+            Some((first, last, first_glyph))
+                if code.into() == (*last).into() + 1
+                    && u32::from(glyph)
+                        == u32::from(*first_glyph) + code.into() - (*first).into() =>
+            {
+                *last = code;
+            }
+            _ => runs.push((code, code, glyph)),
+        }
+    }
+    runs
+}
+
+fn cmap_format_4(mapping: &[(u16, u16)]) -> Vec<u8> {
+    let mut segments = runs(mapping.iter().copied());
+    segments.push((0xFFFF, 0xFFFF, 1));
+
+    let segment_count = segments.len() as u16;
+    let entry_selector = 15 - segment_count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+    let length = 16 + segments.len() * 8;
+
+    let mut subtable = Vec::with_capacity(length);
+    for value in [
+        4,
+        length as u16,
+        0,
+        segment_count * 2,
+        search_range,
+        entry_selector,
+        segment_count * 2 - search_range,
+    ] {
+        subtable.extend_from_slice(&value.to_be_bytes());
+    }
+    for (_, end, _) in &segments {
+        subtable.extend_from_slice(&end.to_be_bytes());
+    }
+    subtable.extend_from_slice(&0_u16.to_be_bytes());
+    for (start, _, _) in &segments {
+        subtable.extend_from_slice(&start.to_be_bytes());
+    }
+    for (start, _, glyph) in &segments {
+        subtable.extend_from_slice(&glyph.wrapping_sub(*start).to_be_bytes());
+    }
+    for _ in &segments {
+        subtable.extend_from_slice(&0_u16.to_be_bytes());
+    }
+    subtable
+}
+
+fn cmap_format_12(mapping: &[(char, GlyphId)]) -> Vec<u8> {
+    let groups = runs(
+        mapping
+            .iter()
+            .map(|(character, glyph)| (u32::from(*character), glyph.0)),
+    );
+
+    let mut subtable = Vec::with_capacity(16 + groups.len() * 12);
+    subtable.extend_from_slice(&12_u16.to_be_bytes());
+    subtable.extend_from_slice(&0_u16.to_be_bytes());
+    subtable.extend_from_slice(&((16 + groups.len() * 12) as u32).to_be_bytes());
+    subtable.extend_from_slice(&0_u32.to_be_bytes());
+    subtable.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+    for (start, end, glyph) in groups {
+        subtable.extend_from_slice(&start.to_be_bytes());
+        subtable.extend_from_slice(&end.to_be_bytes());
+        subtable.extend_from_slice(&u32::from(glyph).to_be_bytes());
+    }
+    subtable
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use ttf_parser::{Face, GlyphId};
+
+    use super::{build_cmap, subset_font, text_characters, visible_text};
+    use crate::binary::FontContainer;
+    use crate::sfnt::SfntFont;
+
+    fn triangle() -> Vec<u8> {
+        let mut glyph = Vec::new();
+        for field in [1_i16, 0, 0, 100, 100, 2, 0] {
+            glyph.extend_from_slice(&field.to_be_bytes());
+        }
+        glyph.extend_from_slice(&[0x31, 0x33, 0x27, 100, 100, 100]);
+        glyph
+    }
+
+    /// A composite glyph drawing `component` unchanged.
+    fn composite(component: u16) -> Vec<u8> {
+        let mut glyph = Vec::new();
+        for field in [-1_i16, 0, 0, 100, 100] {
+            glyph.extend_from_slice(&field.to_be_bytes());
+        }
+        for field in [0x0002_u16, component, 0] {
+            glyph.extend_from_slice(&field.to_be_bytes());
+        }
+        glyph
+    }
+
+    /// `A` and `C` map to simple glyphs 1 and 4; `B` maps to glyph 2, which
+    /// is built from the unmapped glyph 3.
+    fn font() -> Vec<u8> {
+        let glyphs = [Vec::new(), triangle(), composite(3), triangle(), triangle()];
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+        for glyph in &glyphs {
+            loca.extend_from_slice(&((glyf.len() / 2) as u16).to_be_bytes());
+            glyf.extend_from_slice(glyph);
+        }
+        loca.extend_from_slice(&((glyf.len() / 2) as u16).to_be_bytes());
+
+        let mut head = vec![0_u8; 54];
+        head[18..20].copy_from_slice(&1000_u16.to_be_bytes());
+        let mut hhea = vec![0_u8; 36];
+        hhea[34..36].copy_from_slice(&1_u16.to_be_bytes());
+        let mut maxp = 0x0000_5000_u32.to_be_bytes().to_vec();
+        maxp.extend_from_slice(&(glyphs.len() as u16).to_be_bytes());
+        let mut hmtx = 500_u16.to_be_bytes().to_vec();
+        hmtx.resize(4 + (glyphs.len() - 1) * 2, 0);
+
+        SfntFont {
+            flavor: 0x0001_0000,
+            tables: BTreeMap::from([
+                (
+                    *b"cmap",
+                    build_cmap(&[('A', GlyphId(1)), ('B', GlyphId(2)), ('C', GlyphId(4))]),
+                ),
+                (*b"glyf", glyf),
+                (*b"head", head),
+                (*b"hhea", hhea),
+                (*b"hmtx", hmtx),
+                (*b"loca", loca),
+                (*b"maxp", maxp),
+                (*b"DSIG", vec![0; 8]),
+            ]),
+        }
+        .to_sfnt()
+    }
+
+    #[test]
+    fn keeps_mapped_glyphs_and_their_components() {
+        let original = font();
+        let characters = BTreeSet::from(['B', 'Z']);
+
+        let subset = subset_font(&original, &characters).unwrap();
+
+        assert!(subset.len() < original.len());
+        let face = Face::parse(&subset, 0).unwrap();
+        assert_eq!(face.glyph_index('A'), None);
+        assert_eq!(face.glyph_index('B'), Some(GlyphId(2)));
+        assert_eq!(face.glyph_index('C'), None);
+        assert!(face.glyph_bounding_box(GlyphId(2)).is_some());
+        assert!(face.glyph_bounding_box(GlyphId(3)).is_some());
+        assert!(face.glyph_bounding_box(GlyphId(1)).is_none());
+        assert!(face.glyph_bounding_box(GlyphId(4)).is_none());
+        assert!(
+            face.raw_face()
+                .table(ttf_parser::Tag::from_bytes(b"DSIG"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn subsets_in_the_original_container() {
+        let woff2 = SfntFont::parse(&font()).unwrap().to_woff2();
+
+        let subset = subset_font(&woff2, &BTreeSet::from(['A'])).unwrap();
+
+        assert_eq!(FontContainer::sniff(&subset), Some(FontContainer::Woff2));
+        let sfnt = SfntFont::parse(&subset).unwrap().to_sfnt();
+        let face = Face::parse(&sfnt, 0).unwrap();
+        assert_eq!(face.glyph_index('A'), Some(GlyphId(1)));
+        assert_eq!(face.glyph_index('B'), None);
+    }
+
+    #[test]
+    fn collects_rendered_text_and_case_variants() {
+        let text = visible_text(
+            r#"<html><head><title>Hidden</title><style>p { color: red }</style></head>
+            <body><h1>Menu</h1><script>var secret = 1;</script>
+            <input placeholder="Søk"><template>Never</template></body></html>"#,
+        );
+
+        assert!(text.contains("Menu"));
+        assert!(text.contains("Søk"));
+        for hidden in ["Hidden", "color", "secret", "Never"] {
+            assert!(!text.contains(hidden), "{hidden} should not be visible");
+        }
+
+        let characters = text_characters("Añ\n");
+        assert_eq!(characters, BTreeSet::from([' ', 'A', 'a', 'Ñ', 'ñ']));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cancel::CancellationToken;
+use crate::check::{CheckOptions, FontCheck, check_fonts};
+use crate::download::{existing_downloads, recorded_downloads};
+use crate::fetch::Fetcher;
+use crate::layout::DownloadLayout;
+use crate::model::FontInfo;
+use crate::sidecar::{read_sidecar, sidecar_path};
+
+/// How a font compares with the copy saved by an earlier sync.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyncStatus {
+    /// Not saved yet.
+    New,
+    /// Saved, but the server now answers with another `ETag`,
+    /// `Last-Modified` or size.
+    Changed,
+    Unchanged,
+    /// Saved, but the server could not be asked about it.
+    Unchecked,
+}
+
+impl SyncStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::New => "new",
+            Self::Changed => "changed",
+            Self::Unchanged => "unchanged",
+            Self::Unchecked => "unchecked",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyncEntry {
+    pub font_index: usize,
+    pub status: SyncStatus,
+    /// The saved copy, for fonts that are not new.
+    pub path: Option<PathBuf>,
+}
+
+/// What a sync of the fonts into a directory would change.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncPlan {
+    /// One entry per font, in order.
+    pub entries: Vec<SyncEntry>,
+    /// Saved fonts, tracked by their sidecars, whose URL none of the site's
+    /// fonts has any more.
+    pub stale: Vec<PathBuf>,
+}
+
+impl SyncPlan {
+    pub fn indices(&self, status: SyncStatus) -> Vec<usize> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .map(|entry| entry.font_index)
+            .collect()
+    }
+
+    pub fn count(&self, status: SyncStatus) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+}
+
+/// Compares `fonts` with the copies under `output_root`: fonts found at the
+/// path `layout` gives them or through a sidecar are checked with a `HEAD`
+/// request against the validators and size saved with them.
+///
+/// `site_fonts` is everything the scan found, of which `fonts` may be a
+/// selection; saved files are only stale when none of them has their URL.
+pub fn plan_sync(
+    fonts: &[FontInfo],
+    site_fonts: &[FontInfo],
+    layout: &DownloadLayout,
+    output_root: &Path,
+    fetcher: &dyn Fetcher,
+    cancel: &CancellationToken,
+) -> SyncPlan {
+    let existing = existing_downloads(fonts, layout, output_root);
+    let saved = existing
+        .iter()
+        .enumerate()
+        .filter_map(|(index, path)| Some((index, path.clone()?)))
+        .collect::<Vec<_>>();
+    let saved_fonts = saved
+        .iter()
+        .map(|(index, _)| fonts[*index].clone())
+        .collect::<Vec<_>>();
+    let checks = check_fonts(&saved_fonts, fetcher, &CheckOptions::default(), cancel);
+
+    let mut entries = existing
+        .iter()
+        .enumerate()
+        .map(|(font_index, path)| SyncEntry {
+            font_index,
+            status: if path.is_some() {
+                SyncStatus::Unchecked
+            } else {
+                SyncStatus::New
+            },
+            path: path.clone(),
+        })
+        .collect::<Vec<_>>();
+    for check in &checks {
+        for saved_index in &check.font_indices {
+            let (font_index, path) = &saved[*saved_index];
+            entries[*font_index].status = compare_saved(check, path);
+        }
+    }
+
+    let urls = fonts
+        .iter()
+        .chain(site_fonts)
+        .flat_map(|font| {
+            std::iter::once(font.url.as_str())
+                .chain(font.sources.iter().map(|source| source.url.as_str()))
+        })
+        .collect::<HashSet<_>>();
+    let mut stale = recorded_downloads(output_root)
+        .into_iter()
+        .filter(|(url, _)| !urls.contains(url.as_str()))
+        .map(|(_, path)| path)
+        .collect::<Vec<_>>();
+    stale.sort();
+
+    SyncPlan { entries, stale }
+}
+
+/// Prefers the `ETag`, then `Last-Modified`, then the size, using the first
+/// one known on both sides.
+fn compare_saved(check: &FontCheck, path: &Path) -> SyncStatus {
+    if !check.is_available() {
+        return SyncStatus::Unchecked;
+    }
+
+    let recorded = read_sidecar(&sidecar_path(path))
+        .map(|sidecar| sidecar.headers)
+        .unwrap_or_default();
+    let changed = match (&check.etag, &recorded.etag) {
+        (Some(current), Some(saved)) => current != saved,
+        _ => match (&check.last_modified, &recorded.last_modified) {
+            (Some(current), Some(saved)) => current != saved,
+            _ => match (check.size, fs::metadata(path).ok()) {
+                (Some(current), Some(saved)) => current != saved.len(),
+                _ => return SyncStatus::Unchecked,
+            },
+        },
+    };
+
+    if changed {
+        SyncStatus::Changed
+    } else {
+        SyncStatus::Unchanged
+    }
+}
+
+/// Deletes a saved font and its sidecar, if it has one.
+pub fn remove_saved_font(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)?;
+    match fs::remove_file(sidecar_path(path)) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{SyncStatus, plan_sync, remove_saved_font};
+    use crate::cancel::CancellationToken;
+    use crate::fetch::{FetchError, FetchRequest, FetchResponse, Fetcher};
+    use crate::layout::DownloadLayout;
+    use crate::model::FontInfo;
+    use crate::sidecar::sidecar_path;
+
+    /// `same.woff2` still has ETag `"1"`, `edited.woff2` moved on to `"2"`.
+    struct Server;
+
+    impl Fetcher for Server {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            let etag = if request.url.ends_with("edited.woff2") {
+                "\"2\""
+            } else {
+                "\"1\""
+            };
+            Ok(FetchResponse {
+                status: 200,
+                headers: vec![
+                    ("Content-Length".to_owned(), "4".to_owned()),
+                    ("ETag".to_owned(), etag.to_owned()),
+                ],
+                ..FetchResponse::default()
+            })
+        }
+    }
+
+    fn font(name: &str) -> FontInfo {
+        FontInfo {
+            name: format!("{name}.woff2"),
+            family: "Brand".to_owned(),
+            format: "WOFF2".to_owned(),
+            url: format!("https://cdn.example/{name}.woff2"),
+            weight: "400".to_owned(),
+            style: "normal".to_owned(),
+            referer: "https://www.example.com/".to_owned(),
+            source_stylesheet: "https://www.example.com/site.css".to_owned(),
+            ..FontInfo::default()
+        }
+    }
+
+    fn save(root: &Path, name: &str, etag: &str) -> PathBuf {
+        let path = root.join(format!("{name}.woff2"));
+        fs::write(&path, b"wOF2").expect("write font");
+        let sidecar = format!(
+            r#"{{"url":"https://cdn.example/{name}.woff2","referer":null,"page":"","source_stylesheet":"",
+            "descriptors":{{"font_family":"Brand","font_weight":"400","font_style":"normal","format":"WOFF2","conditions":[]}},
+            "headers":{{"etag":"{}","last_modified":null,"content_type":null}},
+            "size":4,"sha256":"","downloaded_at":"2026-01-01T00:00:00Z"}}"#,
+            etag.replace('"', "\\\"")
+        );
+        fs::write(sidecar_path(&path), sidecar).expect("write sidecar");
+        path
+    }
+
+    #[test]
+    fn plans_new_changed_unchanged_and_stale_fonts() {
+        let root = std::env::temp_dir().join(format!("typopotamus-sync-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create output dir");
+        let same = save(&root, "same", "\"1\"");
+        let edited = save(&root, "edited", "\"1\"");
+        let removed = save(&root, "removed", "\"1\"");
+
+        // Saved under another layout, so only their sidecars find them.
+        let fonts = [font("same"), font("edited"), font("added")];
+        let plan = plan_sync(
+            &fonts,
+            &fonts,
+            &DownloadLayout::default(),
+            &root,
+            &Server,
+            &CancellationToken::new(),
+        );
+
+        let statuses = plan
+            .entries
+            .iter()
+            .map(|entry| entry.status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [SyncStatus::Unchanged, SyncStatus::Changed, SyncStatus::New]
+        );
+        assert_eq!(plan.entries[0].path.as_ref(), Some(&same));
+        assert_eq!(plan.entries[1].path.as_ref(), Some(&edited));
+        assert_eq!(plan.indices(SyncStatus::New), vec![2]);
+        assert_eq!(plan.stale, vec![removed.clone()]);
+
+        remove_saved_font(&removed).expect("remove stale font");
+        assert!(!removed.exists());
+        assert!(!sidecar_path(&removed).exists());
+
+        fs::remove_dir_all(&root).expect("clean up output dir");
+    }
+
+    #[test]
+    fn fonts_left_out_of_the_selection_are_not_stale() {
+        let root =
+            std::env::temp_dir().join(format!("typopotamus-sync-select-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("create output dir");
+        save(&root, "same", "\"1\"");
+        save(&root, "other", "\"1\"");
+        let removed = save(&root, "removed", "\"1\"");
+
+        // Only `same` was selected, but the site still serves `other`.
+        let site = [font("same"), font("other")];
+        let plan = plan_sync(
+            &site[..1],
+            &site,
+            &DownloadLayout::default(),
+            &root,
+            &Server,
+            &CancellationToken::new(),
+        );
+
+        assert_eq!(plan.entries.len(), 1);
+        assert_eq!(plan.stale, vec![removed]);
+
+        fs::remove_dir_all(&root).expect("clean up output dir");
+    }
+}
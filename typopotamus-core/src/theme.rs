@@ -0,0 +1,213 @@
+use crate::download::sanitize_component;
+use crate::fontface::css_string;
+
+const SANS_FALLBACK: &[&str] = &[
+    "ui-sans-serif",
+    "system-ui",
+    "sans-serif",
+    "Apple Color Emoji",
+    "Segoe UI Emoji",
+    "Segoe UI Symbol",
+    "Noto Color Emoji",
+];
+const SERIF_FALLBACK: &[&str] = &[
+    "ui-serif",
+    "Georgia",
+    "Cambria",
+    "Times New Roman",
+    "Times",
+    "serif",
+];
+const MONO_FALLBACK: &[&str] = &[
+    "ui-monospace",
+    "SFMono-Regular",
+    "Menlo",
+    "Monaco",
+    "Consolas",
+    "Liberation Mono",
+    "Courier New",
+    "monospace",
+];
+
+const MONO_HINTS: &[&str] = &["mono", "code", "courier", "consol", "console"];
+const SERIF_HINTS: &[&str] = &[
+    "serif",
+    "slab",
+    "garamond",
+    "times",
+    "georgia",
+    "baskerville",
+    "caslon",
+    "bodoni",
+    "didot",
+    "playfair",
+    "merriweather",
+    "lora",
+    "crimson",
+    "tiempos",
+];
+
+/// Generic keywords, which must stay unquoted in a `font-family` list.
+const GENERIC_FAMILIES: &[&str] = &[
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+];
+
+/// A discovered family with a key for theme tokens and a fallback stack.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ThemeFamily {
+    /// Lowercase kebab-case token name, e.g. `brand-sans`.
+    pub key: String,
+    pub name: String,
+    /// `name` followed by generic fallbacks matching its classification.
+    pub stack: Vec<String>,
+}
+
+/// One entry per distinct family name, in first-seen order.
+pub fn theme_families<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<ThemeFamily> {
+    let mut families = Vec::<ThemeFamily>::new();
+
+    for name in names {
+        let name = name.trim();
+        let key = sanitize_component(name);
+        if key.is_empty() || families.iter().any(|family| family.name == name) {
+            continue;
+        }
+
+        let mut unique_key = key.clone();
+        let mut counter = 1;
+        while families.iter().any(|family| family.key == unique_key) {
+            unique_key = format!("{key}-{counter}");
+            counter += 1;
+        }
+
+        let mut stack = vec![name.to_owned()];
+        stack.extend(fallback_stack(name).iter().map(|entry| (*entry).to_owned()));
+        families.push(ThemeFamily {
+            key: unique_key,
+            name: name.to_owned(),
+            stack,
+        });
+    }
+
+    families
+}
+
+/// Generic fallbacks for a family, guessed from words in its name and
+/// defaulting to sans-serif. They match Tailwind's default stacks.
+pub fn fallback_stack(family: &str) -> &'static [&'static str] {
+    let lower = family.to_ascii_lowercase();
+    if MONO_HINTS.iter().any(|hint| lower.contains(hint)) {
+        MONO_FALLBACK
+    } else if SERIF_HINTS.iter().any(|hint| lower.contains(hint)) && !lower.contains("sans") {
+        SERIF_FALLBACK
+    } else {
+        SANS_FALLBACK
+    }
+}
+
+/// A CommonJS module exporting a Tailwind `fontFamily` theme section, to be
+/// spread into `theme.extend.fontFamily`.
+pub fn tailwind_config(families: &[ThemeFamily]) -> String {
+    let mut config =
+        String::from("// Generated by typopotamus.\nmodule.exports = {\n  fontFamily: {\n");
+    for family in families {
+        let stack = family
+            .stack
+            .iter()
+            .map(|entry| js_string(&font_family_entry(entry)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        config.push_str(&format!("    {}: [{stack}],\n", js_string(&family.key)));
+    }
+    config.push_str("  },\n};\n");
+    config
+}
+
+/// A `:root` rule declaring a `--font-<key>` custom property per family.
+pub fn css_custom_properties(families: &[ThemeFamily]) -> String {
+    let mut css = String::from("/* Generated by typopotamus. */\n:root {\n");
+    for family in families {
+        let stack = family
+            .stack
+            .iter()
+            .map(|entry| font_family_entry(entry))
+            .collect::<Vec<_>>()
+            .join(", ");
+        css.push_str(&format!("  --font-{}: {stack};\n", family.key));
+    }
+    css.push_str("}\n");
+    css
+}
+
+/// Quotes a family name for a `font-family` list unless it is a generic
+/// keyword.
+fn font_family_entry(name: &str) -> String {
+    if GENERIC_FAMILIES.contains(&name) {
+        name.to_owned()
+    } else {
+        format!("\"{}\"", css_string(name))
+    }
+}
+
+/// A JavaScript string literal for `value`. JSON escapes control characters
+/// and line breaks too, and every JSON string is valid JavaScript.
+fn js_string(value: &str) -> String {
+    serde_json::to_string(value).expect("strings always serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        css_custom_properties, fallback_stack, js_string, tailwind_config, theme_families,
+    };
+
+    #[test]
+    fn classifies_fallbacks_from_family_names() {
+        assert_eq!(fallback_stack("JetBrains Mono").last(), Some(&"monospace"));
+        assert_eq!(fallback_stack("Source Serif 4").last(), Some(&"serif"));
+        assert_eq!(
+            fallback_stack("PT Sans Serif").last(),
+            Some(&"Noto Color Emoji")
+        );
+        assert_eq!(fallback_stack("Inter").last(), Some(&"Noto Color Emoji"));
+    }
+
+    #[test]
+    fn escapes_line_breaks_and_controls_in_js_strings() {
+        assert_eq!(js_string("Brand\nSans"), r#""Brand\nSans""#);
+        assert_eq!(js_string("a\u{1}\"b\\"), r#""a\u0001\"b\\""#);
+    }
+
+    #[test]
+    fn writes_tailwind_and_custom_property_stacks() {
+        let families = theme_families(["Brand Sans", "Brand Sans", "Brand sans", "Fira Code"]);
+        assert_eq!(
+            families
+                .iter()
+                .map(|family| family.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["brand-sans", "brand-sans-1", "fira-code"]
+        );
+
+        let tailwind = tailwind_config(&families);
+        assert!(tailwind.starts_with("// Generated by typopotamus.\nmodule.exports = {"));
+        assert!(tailwind.contains(
+            "    \"brand-sans\": [\"\\\"Brand Sans\\\"\", \"ui-sans-serif\", \"system-ui\", \"sans-serif\","
+        ));
+        assert!(tailwind.contains("\"\\\"Courier New\\\"\", \"monospace\"],"));
+
+        let css = css_custom_properties(&families);
+        assert!(
+            css.contains("  --font-fira-code: \"Fira Code\", ui-monospace, \"SFMono-Regular\",")
+        );
+    }
+}
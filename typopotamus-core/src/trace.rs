@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::fetch::{FetchError, FetchMethod, FetchRequest, FetchResponse, Fetcher};
+
+/// CDN headers that report whether the response came from an edge cache.
+const CACHE_STATUS_HEADERS: &[&str] = &["cf-cache-status", "x-cache", "x-vercel-cache"];
+
+/// One request seen by a [`TracingFetcher`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NetworkEvent {
+    pub method: FetchMethod,
+    pub url: String,
+    /// `None` when the request failed before a response arrived.
+    pub status: Option<u16>,
+    pub duration: Duration,
+    /// Body bytes received.
+    pub bytes: u64,
+    /// Whether a session or CDN cache answered, when the response says so.
+    pub cache_hit: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// Requests recorded by every [`TracingFetcher`] sharing it, in the order
+/// they finished.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkTrace {
+    events: Arc<Mutex<Vec<NetworkEvent>>>,
+}
+
+impl NetworkTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<NetworkEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn record(&self, event: NetworkEvent) {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(event);
+    }
+}
+
+/// Wraps a fetcher and records each request it makes into a [`NetworkTrace`].
+#[derive(Clone, Debug)]
+pub struct TracingFetcher<F> {
+    inner: F,
+    trace: NetworkTrace,
+}
+
+impl<F: Fetcher> TracingFetcher<F> {
+    pub fn new(inner: F, trace: NetworkTrace) -> Self {
+        Self { inner, trace }
+    }
+}
+
+impl<F: Fetcher> Fetcher for TracingFetcher<F> {
+    fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+        let started = Instant::now();
+        let result = self.inner.fetch(request);
+        let duration = started.elapsed();
+
+        let event = match &result {
+            Ok(response) => NetworkEvent {
+                method: request.method,
+                url: request.url.clone(),
+                status: Some(response.status),
+                duration,
+                bytes: response.body.len() as u64,
+                cache_hit: cache_hit(response),
+                error: None,
+            },
+            Err(error) => NetworkEvent {
+                method: request.method,
+                url: request.url.clone(),
+                status: None,
+                duration,
+                bytes: 0,
+                cache_hit: None,
+                error: Some(error.to_string()),
+            },
+        };
+        self.trace.record(event);
+
+        result
+    }
+}
+
+/// A [`Session`](crate::session::Session) replay, `HIT` in a CDN cache
+/// header, or a `304 Not Modified`.
+fn cache_hit(response: &FetchResponse) -> Option<bool> {
+    if response.from_cache || response.status == 304 {
+        return Some(true);
+    }
+
+    CACHE_STATUS_HEADERS
+        .iter()
+        .find_map(|name| response.header(name))
+        .map(|value| value.to_ascii_uppercase().contains("HIT"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetworkTrace, TracingFetcher};
+    use crate::fetch::{FetchError, FetchMethod, FetchRequest, FetchResponse, Fetcher};
+
+    struct Cdn;
+
+    impl Fetcher for Cdn {
+        fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse, FetchError> {
+            match request.url.as_str() {
+                "https://cdn.example/a.woff2" => Ok(FetchResponse {
+                    headers: vec![("CF-Cache-Status".to_owned(), "HIT".to_owned())],
+                    ..FetchResponse::ok(b"wOF2".to_vec())
+                }),
+                "https://cdn.example/missing.woff2" => Ok(FetchResponse {
+                    status: 404,
+                    ..FetchResponse::default()
+                }),
+                _ => Err("connection refused".into()),
+            }
+        }
+    }
+
+    #[test]
+    fn records_status_bytes_cache_hits_and_errors() {
+        let trace = NetworkTrace::new();
+        let fetcher = TracingFetcher::new(Cdn, trace.clone());
+
+        let _ = fetcher.fetch(&FetchRequest::get("https://cdn.example/a.woff2"));
+        let _ = fetcher.fetch(&FetchRequest::head("https://cdn.example/missing.woff2"));
+        let _ = fetcher.fetch(&FetchRequest::get("https://down.example/"));
+
+        let events = trace.events();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].status, Some(200));
+        assert_eq!(events[0].bytes, 4);
+        assert_eq!(events[0].cache_hit, Some(true));
+        assert_eq!(events[1].method, FetchMethod::Head);
+        assert_eq!(events[1].status, Some(404));
+        assert_eq!(events[1].cache_hit, None);
+        assert_eq!(events[2].status, None);
+        assert_eq!(events[2].error.as_deref(), Some("connection refused"));
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{Html, Selector};
+
+use crate::extractor::parse_css_declarations;
+use crate::model::FontUsageStatus;
+
+const MAX_VAR_DEPTH: usize = 8;
+
+static COMMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)/\*.*?\*/").expect("valid CSS comment regex"));
+static CLASS_OR_ID_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([.#])(-?[_a-zA-Z][\w-]*)").expect("valid selector token regex"));
+static VAR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"var\(\s*(--[\w-]+)\s*(?:,\s*([^()]*(?:\([^()]*\)[^()]*)*))?\)")
+        .expect("valid CSS var() regex")
+});
+
+/// `font-family` references collected from style rules, used to tell which
+/// selectors render each declared family.
+#[derive(Clone, Debug, Default)]
+pub struct FontUsage {
+    custom_properties: HashMap<String, String>,
+    references: Vec<(String, String)>,
+}
+
+impl FontUsage {
+    /// Records the `font-family` and `font` declarations of every style rule
+    /// in `css`, including rules nested in `@media` and `@supports`, plus any
+    /// custom properties they may refer to through `var()`.
+    pub fn add_stylesheet(&mut self, css: &str) {
+        let css = COMMENT_RE.replace_all(css, "");
+
+        for (selector, block) in style_rules(&css) {
+            self.add_declarations(&selector, block);
+        }
+    }
+
+    /// Records one declaration block, such as an element's `style` attribute.
+    /// At-rule blocks only contribute custom properties.
+    pub fn add_declarations(&mut self, selector: &str, block: &str) {
+        let declarations = parse_css_declarations(block);
+        for (name, value) in &declarations {
+            if name.starts_with("--") {
+                self.custom_properties.insert(name.clone(), value.clone());
+            }
+        }
+        if selector.starts_with('@') {
+            return;
+        }
+
+        let value = declarations.get("font-family").cloned().or_else(|| {
+            declarations
+                .get("font")
+                .and_then(|font| shorthand_families(font))
+        });
+        if let Some(value) = value {
+            self.references.push((selector.to_owned(), value));
+        }
+    }
+
+    /// Selectors whose font stack names `family`, in stylesheet order.
+    pub fn selectors_for(&self, family: &str) -> Vec<String> {
+        let family = normalize_family(family);
+        let mut selectors = Vec::new();
+
+        for (selector, value) in &self.references {
+            let stack = self.resolve(value, 0);
+            if family_stack(&stack).any(|candidate| candidate == family)
+                && !selectors.contains(selector)
+            {
+                selectors.push(selector.clone());
+            }
+        }
+
+        selectors
+    }
+
+    fn resolve(&self, value: &str, depth: usize) -> String {
+        if depth >= MAX_VAR_DEPTH || !value.contains("var(") {
+            return value.to_owned();
+        }
+
+        let substituted = VAR_RE.replace_all(value, |capture: &regex::Captures<'_>| {
+            self.custom_properties
+                .get(&capture[1].to_ascii_lowercase())
+                .cloned()
+                .or_else(|| capture.get(2).map(|fallback| fallback.as_str().to_owned()))
+                .unwrap_or_default()
+        });
+        self.resolve(&substituted, depth + 1)
+    }
+}
+
+/// The classes and ids present in a page's markup, used to judge whether a
+/// selector can match anything. Pages that build their DOM in JavaScript
+/// will report more selectors as unmatched than a browser would.
+#[derive(Clone, Debug, Default)]
+pub struct PageVocabulary {
+    classes: HashSet<String>,
+    ids: HashSet<String>,
+}
+
+impl PageVocabulary {
+    pub fn from_document(document: &Html) -> Self {
+        let mut vocabulary = Self::default();
+        vocabulary.add_document(document);
+        vocabulary
+    }
+
+    pub fn add_document(&mut self, document: &Html) {
+        let any = Selector::parse("*").expect("valid selector: *");
+
+        for element in document.select(&any) {
+            self.classes
+                .extend(element.value().classes().map(str::to_owned));
+            if let Some(id) = element.value().id() {
+                self.ids.insert(id.to_owned());
+            }
+        }
+    }
+
+    /// A selector list could match when any of its selectors only needs
+    /// classes and ids that occur on the page. Type selectors always could.
+    pub fn could_match(&self, selector: &str) -> bool {
+        selector.split(',').any(|branch| {
+            CLASS_OR_ID_RE
+                .captures_iter(branch)
+                .all(|token| match &token[1] {
+                    "." => self.classes.contains(&token[2]),
+                    _ => self.ids.contains(&token[2]),
+                })
+        })
+    }
+
+    pub fn usage_status(&self, selectors: &[String]) -> FontUsageStatus {
+        if selectors.is_empty() {
+            FontUsageStatus::Unused
+        } else if selectors.iter().any(|selector| self.could_match(selector)) {
+            FontUsageStatus::Used
+        } else {
+            FontUsageStatus::UnmatchedSelectors
+        }
+    }
+}
+
+/// Innermost `prelude { block }` pairs, so rules inside `@media` are returned
+/// on their own and the at-rule wrapper is skipped.
+fn style_rules(css: &str) -> Vec<(String, &str)> {
+    let mut rules = Vec::new();
+    let mut stack: Vec<(String, usize, bool)> = Vec::new();
+    let mut prelude_start = 0;
+
+    for (offset, character) in css.char_indices() {
+        match character {
+            '{' => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.2 = true;
+                }
+                let prelude = css[prelude_start..offset]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                stack.push((prelude, offset + 1, false));
+                prelude_start = offset + 1;
+            }
+            '}' => {
+                if let Some((prelude, block_start, has_children)) = stack.pop()
+                    && !has_children
+                {
+                    rules.push((prelude, &css[block_start..offset]));
+                }
+                prelude_start = offset + 1;
+            }
+            ';' => prelude_start = offset + 1,
+            _ => {}
+        }
+    }
+
+    rules
+}
+
+/// The family list at the end of a `font` shorthand such as
+/// `italic 700 1rem/1.2 "Brand Sans", serif`.
+fn shorthand_families(value: &str) -> Option<String> {
+    let (head, rest) = match value.find(',') {
+        Some(comma) => (&value[..comma], &value[comma..]),
+        None => (value, ""),
+    };
+    let head = head.trim_end();
+
+    let first = if head.ends_with(['"', '\'']) {
+        let quote = head.chars().last()?;
+        let open = head[..head.len() - 1].rfind(quote)?;
+        &head[open..]
+    } else {
+        head.rsplit(char::is_whitespace).next()?
+    };
+
+    if head.len() == first.len() && rest.is_empty() {
+        // A lone keyword such as `font: inherit` names no family.
+        return None;
+    }
+    Some(format!("{first}{rest}"))
+}
+
+fn family_stack(value: &str) -> impl Iterator<Item = String> + '_ {
+    value.split(',').map(normalize_family)
+}
+
+fn normalize_family(raw: &str) -> String {
+    raw.trim()
+        .trim_end_matches("!important")
+        .trim()
+        .trim_matches(['"', '\''])
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use super::{FontUsage, PageVocabulary};
+    use crate::model::FontUsageStatus;
+
+    #[test]
+    fn reports_selectors_using_each_family() {
+        let mut usage = FontUsage::default();
+        usage.add_stylesheet(
+            r#"
+            :root { --font-display: "Brand Display", Georgia, serif; }
+            /* h6 { font-family: Brand Text; } */
+            body, p { font-family: 'Brand Text', system-ui, sans-serif; }
+            h1 { font: italic 700 3rem/1.1 var(--font-display); }
+            @media (min-width: 40em) {
+                .nav a { font-family: "Brand Text" !important; }
+            }
+            @font-face { font-family: "Brand Text"; src: url(text.woff2); }
+            "#,
+        );
+        usage.add_stylesheet(".hero { font-family: var(--font-display); }");
+
+        assert_eq!(usage.selectors_for("Brand Text"), vec!["body, p", ".nav a"]);
+        assert_eq!(usage.selectors_for("brand display"), vec!["h1", ".hero"]);
+        assert!(usage.selectors_for("Unused Sans").is_empty());
+    }
+
+    #[test]
+    fn usage_status_checks_selectors_against_page_markup() {
+        let page = PageVocabulary::from_document(&Html::parse_document(
+            r#"<body><nav id="top" class="nav dark"><a>Home</a></nav></body>"#,
+        ));
+        let selectors = |values: &[&str]| {
+            values
+                .iter()
+                .map(|value| (*value).to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(page.could_match("h1"));
+        assert!(page.could_match(".nav.dark a"));
+        assert!(page.could_match(".missing, #top"));
+        assert!(!page.could_match(".legacy-banner h2"));
+
+        assert_eq!(page.usage_status(&[]), FontUsageStatus::Unused);
+        assert_eq!(
+            page.usage_status(&selectors(&[".legacy-banner", "#old-footer"])),
+            FontUsageStatus::UnmatchedSelectors
+        );
+        assert_eq!(
+            page.usage_status(&selectors(&[".legacy-banner", "body"])),
+            FontUsageStatus::Used
+        );
+    }
+}
@@ -1,22 +1,61 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
-use typopotamus_core::download::{self, DownloadReport};
-use typopotamus_core::extractor::{extract_fonts_from_url, normalize_target_url};
-use typopotamus_core::inspect::group_by_inferred_family;
-use typopotamus_core::model::{FontFamily, FontInfo};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use typopotamus_core::binary::{FontMetadata, probe_font_metadata};
+use typopotamus_core::cancel::CancellationToken;
+use typopotamus_core::download::{
+    DEFAULT_DOWNLOAD_TIMEOUT, DownloadReport, Downloader, existing_downloads,
+};
+use typopotamus_core::enrich::{EnrichOptions, enrich_fonts};
+use typopotamus_core::error::{ExtractError, error_chain};
+use typopotamus_core::extractor::{
+    DEFAULT_TIMEOUT, ExtractEvent, ExtractOptions, extract_fonts_with_fetcher, normalize_target_url,
+};
+use typopotamus_core::fetch::ReqwestFetcher;
+use typopotamus_core::inspect::{
+    GroupBy, Inference, InferenceOptions, group_indices_by_inferred_family, infer_family_groups,
+};
+use typopotamus_core::layout::DownloadLayout;
+use typopotamus_core::model::{FontFace, FontFamily, FontInfo};
+use typopotamus_core::output::{InspectFonts, InspectOutput, InspectView};
+use typopotamus_core::selection::{
+    filter_indices_by_format, filter_indices_by_weight, prefer_format_chain,
+};
+use typopotamus_core::session::Session;
+
+/// Share of the browser width the families pane starts with, in percent.
+const DEFAULT_FAMILIES_WIDTH: u16 = 35;
+const FAMILIES_WIDTH_STEP: u16 = 5;
+const MIN_FAMILIES_WIDTH: u16 = 15;
+const MAX_FAMILIES_WIDTH: u16 = 80;
+/// Rows covered by a visual-mode range.
+const VISUAL_RANGE_STYLE: Style = Style::new().bg(Color::DarkGray);
+/// Marks files already in the output directory, and faces with all of them.
+const PRESENT_ICON: &str = "✓";
+/// Marks faces with only some of their files in the output directory.
+const PARTLY_PRESENT_ICON: &str = "~";
+/// Written to the output directory by `x`.
+const SELECTION_FILE_NAME: &str = "typopotamus-selection.json";
+/// Width, in cells, of each file's bar while downloading.
+const PROGRESS_BAR_WIDTH: usize = 24;
+/// How long a file must have been arriving before its speed is shown.
+const MIN_SPEED_WINDOW: Duration = Duration::from_millis(250);
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum AppMode {
     Input,
     Scanning,
     Browsing,
+    Confirming,
     Downloading,
+    Results,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -25,11 +64,102 @@ enum FocusPane {
     Fonts,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum FontRow {
+    Face(usize),
+    Source { face: usize, font_index: usize },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum DownloadOutcome {
+    Saved,
+    Skipped,
+    Failed(String),
+}
+
+/// One row of the results screen shown after a download.
+#[derive(Clone, Debug)]
+struct DownloadResult {
+    name: String,
+    path: Option<PathBuf>,
+    size: Option<u64>,
+    outcome: DownloadOutcome,
+}
+
+/// One row of the progress list shown while downloading.
+#[derive(Clone, Debug)]
+struct FileProgress {
+    name: String,
+    received: u64,
+    content_length: Option<u64>,
+    started: Instant,
+    updated: Instant,
+    /// A later file has started, so this one is no longer in flight.
+    done: bool,
+}
+
+impl FileProgress {
+    fn fraction(&self) -> Option<f64> {
+        match self.content_length {
+            Some(length) if length > 0 => Some((self.received as f64 / length as f64).min(1.0)),
+            _ if self.done => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Bytes per second, averaged since the file started.
+    fn speed(&self) -> Option<u64> {
+        let elapsed = self.updated.duration_since(self.started);
+        (elapsed >= MIN_SPEED_WINDOW).then(|| (self.received as f64 / elapsed.as_secs_f64()) as u64)
+    }
+}
+
+/// Single-key filters that narrow the listed fonts and what `a` selects.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct QuickFilter {
+    weight: Option<u16>,
+    /// `Some(true)` lists only italic and oblique faces, `Some(false)` only
+    /// upright ones.
+    italic: Option<bool>,
+    woff2_only: bool,
+}
+
+impl QuickFilter {
+    fn is_active(self) -> bool {
+        self != Self::default()
+    }
+
+    fn label(self) -> String {
+        let mut parts = Vec::new();
+        if let Some(weight) = self.weight {
+            parts.push(weight.to_string());
+        }
+        match self.italic {
+            Some(true) => parts.push("italic".to_owned()),
+            Some(false) => parts.push("upright".to_owned()),
+            None => {}
+        }
+        if self.woff2_only {
+            parts.push("WOFF2".to_owned());
+        }
+        parts.join(", ")
+    }
+}
+
+enum ScanMessage {
+    Progress(ExtractEvent),
+    Enriching(usize),
+    Probing(usize),
+    Metadata(HashMap<String, FontMetadata>),
+    Finished(Result<Vec<FontInfo>, ExtractError>),
+}
+
 enum DownloadMessage {
     Progress {
         current: usize,
-        total: usize,
         name: String,
+        received: u64,
+        content_length: Option<u64>,
     },
     Finished(DownloadReport),
 }
@@ -38,34 +168,95 @@ pub struct App {
     pub should_quit: bool,
     url_input: String,
     output_dir: PathBuf,
+    inference: Inference,
+    binary_inference: bool,
+    layout: DownloadLayout,
+    extract_options: ExtractOptions,
+    /// Formats fonts are downloaded in when their `src` offers them, most
+    /// wanted first.
+    prefer_formats: Vec<String>,
     mode: AppMode,
     focus: FocusPane,
+    /// Width of the families pane in percent, kept across rescans.
+    families_width: u16,
+    families_hidden: bool,
+    /// The row of the focused pane where visual mode started; `Space`
+    /// toggles every row between it and the cursor.
+    visual_anchor: Option<usize>,
     status: String,
     fonts: Vec<FontInfo>,
     families: Vec<FontFamily>,
+    quick_filter: QuickFilter,
     selected_font_indices: HashSet<usize>,
+    /// Fonts already saved in `output_dir`, at the path a download would
+    /// use or wherever a sidecar records their URL.
+    present_font_indices: HashSet<usize>,
+    expanded_faces: HashSet<usize>,
     selected_family_index: usize,
     selected_font_row: usize,
-    scan_rx: Option<Receiver<Result<Vec<FontInfo>, String>>>,
+    download_results: Vec<DownloadResult>,
+    selected_result_row: usize,
+    /// The last download, with any retries merged in.
+    last_report: Option<DownloadReport>,
+    retrying: bool,
+    scan_rx: Option<Receiver<ScanMessage>>,
+    scan_stylesheets: usize,
+    scan_fonts_found: usize,
     download_rx: Option<Receiver<DownloadMessage>>,
+    /// Files of the running download, in the order they started.
+    download_progress: Vec<FileProgress>,
+    download_total: usize,
+    /// Connections, cookies and cached files from the last scan, reused by
+    /// its downloads.
+    session: Option<Session<ReqwestFetcher>>,
+    cancel: CancellationToken,
 }
 
 impl App {
-    pub fn new(output_dir: PathBuf, initial_url: Option<String>) -> Self {
+    pub fn new(
+        output_dir: PathBuf,
+        initial_url: Option<String>,
+        inference: InferenceOptions,
+        binary_inference: bool,
+        layout: DownloadLayout,
+        extract_options: ExtractOptions,
+        prefer_formats: Vec<String>,
+    ) -> Self {
         let mut app = Self {
             should_quit: false,
             url_input: initial_url.unwrap_or_default(),
             output_dir,
+            inference: inference.into(),
+            binary_inference,
+            layout,
+            extract_options,
+            prefer_formats,
             mode: AppMode::Input,
             focus: FocusPane::Families,
+            families_width: DEFAULT_FAMILIES_WIDTH,
+            families_hidden: false,
+            visual_anchor: None,
             status: "Enter a website URL to scan for fonts".to_owned(),
             fonts: Vec::new(),
             families: Vec::new(),
+            quick_filter: QuickFilter::default(),
             selected_font_indices: HashSet::new(),
+            present_font_indices: HashSet::new(),
+            expanded_faces: HashSet::new(),
             selected_family_index: 0,
             selected_font_row: 0,
+            download_results: Vec::new(),
+            selected_result_row: 0,
+            last_report: None,
+            retrying: false,
             scan_rx: None,
+            scan_stylesheets: 0,
+            scan_fonts_found: 0,
             download_rx: None,
+            download_progress: Vec::new(),
+            download_total: 0,
+            session: None,
+            cancel: CancellationToken::new(),
         };
 
         if !app.url_input.trim().is_empty() {
@@ -82,7 +273,7 @@ impl App {
 
     pub fn on_key_event(&mut self, key: KeyEvent) {
         if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-            self.should_quit = true;
+            self.request_quit();
             return;
         }
 
@@ -90,7 +281,9 @@ impl App {
             AppMode::Input => self.handle_input_mode_keys(key),
             AppMode::Scanning => self.handle_busy_mode_keys(key),
             AppMode::Browsing => self.handle_browsing_mode_keys(key),
+            AppMode::Confirming => self.handle_confirming_mode_keys(key),
             AppMode::Downloading => self.handle_downloading_mode_keys(key),
+            AppMode::Results => self.handle_results_mode_keys(key),
         }
     }
 
@@ -113,18 +306,26 @@ impl App {
 
         self.render_url_input(frame, main[0]);
 
-        if self.fonts.is_empty() {
+        if self.mode == AppMode::Results {
+            self.render_results(frame, main[1]);
+        } else if self.mode == AppMode::Downloading && !self.download_progress.is_empty() {
+            self.render_download_progress(frame, main[1]);
+        } else if self.fonts.is_empty() {
             self.render_empty_state(frame, main[1]);
         } else {
             self.render_browser(frame, main[1]);
         }
 
         self.render_footer(frame, vertical[2]);
+
+        if self.mode == AppMode::Confirming {
+            self.render_download_confirmation(frame, vertical[1]);
+        }
     }
 
     fn handle_input_mode_keys(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Char('q') => self.request_quit(),
             KeyCode::Enter => {
                 if !self.url_input.trim().is_empty() {
                     self.start_scan();
@@ -138,41 +339,119 @@ impl App {
             KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.url_input.clear();
             }
-            KeyCode::Char(character) => {
+            KeyCode::Char(character)
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    self.url_input.push(character);
-                }
+                    && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.url_input.push(character);
             }
             _ => {}
         }
     }
 
     fn handle_busy_mode_keys(&mut self, key: KeyEvent) {
-        if let KeyCode::Char('q') = key.code {
-            self.should_quit = true;
+        match key.code {
+            KeyCode::Char('q') => self.request_quit(),
+            KeyCode::Esc => {
+                self.cancel.cancel();
+                self.scan_rx = None;
+                self.mode = AppMode::Input;
+                self.status = "Scan cancelled".to_owned();
+            }
+            _ => {}
         }
     }
 
     fn handle_downloading_mode_keys(&mut self, key: KeyEvent) {
-        if let KeyCode::Char('q') = key.code {
-            self.should_quit = true;
+        match key.code {
+            KeyCode::Char('q') => self.request_quit(),
+            KeyCode::Esc => {
+                self.cancel.cancel();
+                self.status = "Cancelling download after the current file...".to_owned();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_confirming_mode_keys(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.request_quit(),
+            KeyCode::Enter | KeyCode::Char('y') => self.start_download(),
+            KeyCode::Esc | KeyCode::Char('n') => {
+                self.mode = AppMode::Browsing;
+                self.status = "Download cancelled".to_owned();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_results_mode_keys(&mut self, key: KeyEvent) {
+        let last = self.download_results.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('q') => self.request_quit(),
+            KeyCode::Esc | KeyCode::Enter => self.mode = AppMode::Browsing,
+            KeyCode::Char('r') => self.retry_failed_downloads(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_result_row = self.selected_result_row.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_result_row = (self.selected_result_row + 1).min(last);
+            }
+            KeyCode::Char('g') => self.selected_result_row = 0,
+            KeyCode::Char('G') => self.selected_result_row = last,
+            _ => {}
         }
     }
 
+    fn request_quit(&mut self) {
+        self.cancel.cancel();
+        self.should_quit = true;
+    }
+
     fn handle_browsing_mode_keys(&mut self, key: KeyEvent) {
         match key.code {
-            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Char('q') => self.request_quit(),
             KeyCode::Tab => self.toggle_focus(),
+            KeyCode::Char('<') => self.resize_families(false),
+            KeyCode::Char('>') => self.resize_families(true),
+            KeyCode::Char('z') => self.toggle_families_pane(),
+            KeyCode::Char('v') => self.toggle_visual_mode(),
+            KeyCode::Esc if self.visual_anchor.is_some() => {
+                self.visual_anchor = None;
+                self.status = "Visual mode cancelled".to_owned();
+            }
             KeyCode::Up | KeyCode::Char('k') => self.move_selection_up(),
             KeyCode::Down | KeyCode::Char('j') => self.move_selection_down(),
             KeyCode::Char('g') => self.jump_to_top(),
             KeyCode::Char('G') => self.jump_to_bottom(),
             KeyCode::Char(' ') => self.toggle_current_selection(),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                self.toggle_current_face_expansion()
+            }
             KeyCode::Char('f') => self.toggle_current_family_selection(),
             KeyCode::Char('a') => self.toggle_select_all(),
-            KeyCode::Char('d') => self.start_download(),
+            KeyCode::Char(digit @ '1'..='9') => {
+                let weight = digit.to_digit(10).unwrap_or_default() as u16 * 100;
+                self.set_quick_filter(QuickFilter {
+                    weight: (self.quick_filter.weight != Some(weight)).then_some(weight),
+                    ..self.quick_filter
+                });
+            }
+            KeyCode::Char('i') => self.set_quick_filter(QuickFilter {
+                italic: match self.quick_filter.italic {
+                    None => Some(true),
+                    Some(true) => Some(false),
+                    Some(false) => None,
+                },
+                ..self.quick_filter
+            }),
+            KeyCode::Char('w') => self.set_quick_filter(QuickFilter {
+                woff2_only: !self.quick_filter.woff2_only,
+                ..self.quick_filter
+            }),
+            KeyCode::Char('0') => self.set_quick_filter(QuickFilter::default()),
+            KeyCode::Char('d') => self.confirm_download(),
+            KeyCode::Char('x') => self.export_selection(),
             KeyCode::Char('e') => self.mode = AppMode::Input,
             KeyCode::Char('r') => self.start_scan(),
             _ => {}
@@ -181,33 +460,101 @@ impl App {
 
     fn poll_scan_channel(&mut self) {
         let mut clear_receiver = false;
+        let mut disconnected = false;
+        let mut messages = Vec::new();
 
         if let Some(receiver) = &self.scan_rx {
-            match receiver.try_recv() {
-                Ok(result) => {
+            loop {
+                match receiver.try_recv() {
+                    Ok(message) => messages.push(message),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        clear_receiver = true;
+                        disconnected = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        for message in messages {
+            match message {
+                ScanMessage::Progress(event) => self.record_scan_event(event),
+                ScanMessage::Enriching(count) => {
+                    self.status = format!("Requesting sizes of {count} font files ...");
+                }
+                ScanMessage::Probing(count) => {
+                    self.status = format!("Reading name/OS2 tables from {count} font files ...");
+                }
+                ScanMessage::Metadata(metadata) => self.inference.binary_metadata = metadata,
+                ScanMessage::Finished(result) => {
                     clear_receiver = true;
+                    disconnected = false;
                     match result {
                         Ok(fonts) => self.finish_scan(fonts),
                         Err(error) => {
                             self.mode = AppMode::Input;
-                            self.status = format!("Scan failed: {error}");
+                            self.status = format!("Scan failed: {}", error_chain(&error));
                         }
                     }
                 }
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    clear_receiver = true;
-                    self.mode = AppMode::Input;
-                    self.status = "Scan worker disconnected unexpectedly".to_owned();
-                }
             }
         }
 
+        if disconnected {
+            self.mode = AppMode::Input;
+            self.status = "Scan worker disconnected unexpectedly".to_owned();
+        }
+
         if clear_receiver {
             self.scan_rx = None;
         }
     }
 
+    fn record_scan_event(&mut self, event: ExtractEvent) {
+        match event {
+            ExtractEvent::PageFetched { url } => {
+                self.status = format!("Scanning {url}: page fetched, looking for stylesheets");
+            }
+            ExtractEvent::StylesheetDiscovered { url } => {
+                self.status = format!(
+                    "Scanning: {} stylesheets, {} fonts so far | fetching {url}",
+                    self.scan_stylesheets, self.scan_fonts_found
+                );
+            }
+            ExtractEvent::StylesheetFetched { .. } => self.scan_stylesheets += 1,
+            ExtractEvent::StylesheetFailed { url }
+            | ExtractEvent::ScriptFailed { url }
+            | ExtractEvent::PageFailed { url }
+            | ExtractEvent::SitemapFailed { url } => {
+                self.status = format!("Scanning: could not fetch {url}");
+            }
+            ExtractEvent::SitemapFetched { url, pages } => {
+                self.status = format!("Scanning: {url} lists {pages} pages");
+            }
+            ExtractEvent::ScriptFetched { url } => {
+                self.status = format!("Scanning: searching script {url} for font URLs");
+            }
+            ExtractEvent::FontsFound { source, count } => {
+                self.scan_fonts_found += count;
+                self.status = format!(
+                    "Scanning: {} stylesheets, {} fonts so far | {count} in {source}",
+                    self.scan_stylesheets, self.scan_fonts_found
+                );
+            }
+            ExtractEvent::LimitReached { limit } => {
+                self.status = format!("Scanning: stopped at the {limit} limit");
+            }
+            ExtractEvent::StylesheetTruncated { url, limit }
+            | ExtractEvent::SitemapTruncated { url, limit } => {
+                self.status = format!("Scanning: only the first {limit} bytes of {url} were read");
+            }
+            ExtractEvent::LossyDecoding { url, encoding } => {
+                self.status = format!("Scanning: {url} is not valid {encoding}");
+            }
+        }
+    }
+
     fn poll_download_channel(&mut self) {
         let mut clear_receiver = false;
         let mut disconnected = false;
@@ -231,13 +578,13 @@ impl App {
             match message {
                 DownloadMessage::Progress {
                     current,
-                    total,
                     name,
-                } => {
-                    self.status = format!("Downloading {current}/{total}: {name}");
-                }
+                    received,
+                    content_length,
+                } => self.record_download_progress(current, name, received, content_length),
                 DownloadMessage::Finished(report) => {
                     clear_receiver = true;
+                    disconnected = false;
                     self.finish_download(report);
                 }
             }
@@ -254,8 +601,53 @@ impl App {
         }
     }
 
+    fn record_download_progress(
+        &mut self,
+        current: usize,
+        name: String,
+        received: u64,
+        content_length: Option<u64>,
+    ) {
+        let now = Instant::now();
+        if current > self.download_progress.len() {
+            for file in &mut self.download_progress {
+                file.done = true;
+            }
+            self.download_progress.push(FileProgress {
+                name,
+                received,
+                content_length,
+                started: now,
+                updated: now,
+                done: false,
+            });
+        } else if let Some(file) = self.download_progress.get_mut(current - 1) {
+            file.received = received;
+            file.content_length = content_length;
+            file.updated = now;
+        }
+    }
+
     fn start_scan(&mut self) {
-        let normalized_url = normalize_target_url(&self.url_input);
+        let normalized_url = match normalize_target_url(&self.url_input) {
+            Ok(url) => url,
+            Err(error) => {
+                self.status = error_chain(&error);
+                return;
+            }
+        };
+        let session = match ReqwestFetcher::with_options(
+            DEFAULT_DOWNLOAD_TIMEOUT,
+            &self.extract_options.http,
+        ) {
+            Ok(client) => Session::new(client),
+            Err(error) => {
+                self.status = format!("Failed to create HTTP client: {}", error_chain(&error));
+                return;
+            }
+        };
+        let fetcher = session.share(session.inner().clone().with_timeout(DEFAULT_TIMEOUT));
+        self.session = Some(session);
         self.url_input = normalized_url.clone();
         self.mode = AppMode::Scanning;
         self.status = format!("Scanning {} ...", self.url_input);
@@ -263,37 +655,162 @@ impl App {
         self.fonts.clear();
         self.families.clear();
         self.selected_font_indices.clear();
+        self.expanded_faces.clear();
         self.selected_family_index = 0;
         self.selected_font_row = 0;
+        self.scan_stylesheets = 0;
+        self.scan_fonts_found = 0;
+
+        self.cancel.cancel();
+        self.cancel = CancellationToken::new();
+        let cancel = self.cancel.clone();
+        let binary_inference = self.binary_inference;
+        let extract_options = self.extract_options.clone();
+        let prefer_formats = self.prefer_formats.clone();
+        self.inference.binary_metadata.clear();
 
         let (sender, receiver) = mpsc::channel();
         self.scan_rx = Some(receiver);
 
         thread::spawn(move || {
-            let result = extract_fonts_from_url(&normalized_url).map_err(|error| error.to_string());
-            let _ = sender.send(result);
+            let mut result = extract_fonts_with_fetcher(
+                &normalized_url,
+                &fetcher,
+                &extract_options,
+                &cancel,
+                |event| {
+                    let _ = sender.send(ScanMessage::Progress(event.clone()));
+                },
+            );
+
+            if let Ok(fonts) = &mut result
+                && !fonts.is_empty()
+            {
+                prefer_format_chain(fonts, &prefer_formats);
+                let _ = sender.send(ScanMessage::Enriching(fonts.len()));
+                enrich_fonts(fonts, &fetcher, &EnrichOptions::default(), &cancel);
+            }
+
+            if binary_inference
+                && let Ok(fonts) = &result
+                && !fonts.is_empty()
+            {
+                let _ = sender.send(ScanMessage::Probing(fonts.len()));
+                let metadata = probe_font_metadata(fonts, &fetcher, &cancel);
+                let _ = sender.send(ScanMessage::Metadata(metadata));
+            }
+
+            let _ = sender.send(ScanMessage::Finished(result));
         });
     }
 
+    /// Browses the fonts of a saved inspect document without scanning;
+    /// `r` scans its first page again.
+    pub fn load_saved_scan(&mut self, mut saved: InspectFonts) {
+        prefer_format_chain(&mut saved.fonts, &self.prefer_formats);
+        if let Some(page) = saved.pages.first() {
+            self.url_input = page.clone();
+        }
+        self.inference.binary_metadata.clear();
+        self.finish_scan(saved.fonts);
+    }
+
     fn finish_scan(&mut self, fonts: Vec<FontInfo>) {
         self.fonts = fonts;
-        self.families = group_by_inferred_family(&self.fonts);
+        self.families =
+            group_indices_by_inferred_family(&self.fonts, &self.visible_indices(), &self.inference);
         self.mode = AppMode::Browsing;
-        self.focus = FocusPane::Families;
+        self.visual_anchor = None;
+        self.focus = if self.families_hidden {
+            FocusPane::Fonts
+        } else {
+            FocusPane::Families
+        };
         self.selected_family_index = 0;
         self.selected_font_row = 0;
+        self.refresh_present_fonts();
 
         if self.fonts.is_empty() {
             self.status = "No fonts were discovered on this website".to_owned();
         } else {
+            let face_count = self
+                .families
+                .iter()
+                .map(|family| family.faces.len())
+                .sum::<usize>();
             self.status = format!(
-                "Found {} fonts across {} families",
+                "Found {} fonts ({face_count} faces) across {} families",
                 self.fonts.len(),
                 self.families.len()
             );
         }
     }
 
+    /// Asks for confirmation before [`start_download`](Self::start_download).
+    fn confirm_download(&mut self) {
+        if self.selected_font_indices.is_empty() {
+            self.status = "Select at least one font before downloading".to_owned();
+            return;
+        }
+
+        self.refresh_present_fonts();
+        self.mode = AppMode::Confirming;
+    }
+
+    /// Writes the selected fonts to the output directory as an inspect
+    /// document in the font view, as `inspect --view font --format json`
+    /// would print them.
+    fn export_selection(&mut self) {
+        if self.selected_font_indices.is_empty() {
+            self.status = "Select at least one font before exporting".to_owned();
+            return;
+        }
+
+        let mut selected_indices: Vec<usize> = self.selected_font_indices.iter().copied().collect();
+        selected_indices.sort_unstable();
+        let groups = infer_family_groups(&self.fonts, &selected_indices, &self.inference);
+        let output = InspectOutput::new(
+            std::slice::from_ref(&self.url_input),
+            &self.fonts,
+            InspectView::Font,
+            GroupBy::InferredFamily,
+            groups,
+        );
+        let path = self.output_dir.join(SELECTION_FILE_NAME);
+        let written = serde_json::to_string_pretty(&output)
+            .map_err(|error| error.to_string())
+            .and_then(|json| {
+                fs::create_dir_all(&self.output_dir)
+                    .and_then(|()| fs::write(&path, json + "\n"))
+                    .map_err(|error| error.to_string())
+            });
+
+        self.status = match written {
+            Ok(()) => format!(
+                "Exported {} fonts to {}",
+                selected_indices.len(),
+                path.display()
+            ),
+            Err(error) => format!("Failed to export selection: {error}"),
+        };
+    }
+
+    fn download_layout(&self) -> DownloadLayout {
+        DownloadLayout {
+            inference: self.inference.clone(),
+            ..self.layout.clone()
+        }
+    }
+
+    fn refresh_present_fonts(&mut self) {
+        self.present_font_indices =
+            existing_downloads(&self.fonts, &self.download_layout(), &self.output_dir)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, path)| path.map(|_| index))
+                .collect();
+    }
+
     fn start_download(&mut self) {
         let mut selected_indices: Vec<usize> = self.selected_font_indices.iter().copied().collect();
         selected_indices.sort_unstable();
@@ -308,55 +825,182 @@ impl App {
             .filter_map(|index| self.fonts.get(index).cloned())
             .collect();
 
+        self.retrying = false;
+        self.spawn_download(fonts_to_download);
+    }
+
+    /// Downloads the files that failed last time again; files already saved
+    /// are left alone.
+    fn retry_failed_downloads(&mut self) {
+        let Some(report) = &self.last_report else {
+            return;
+        };
+        let urls = report.retryable_urls();
+        let fonts_to_download: Vec<FontInfo> = self
+            .fonts
+            .iter()
+            .filter(|font| urls.contains(&font.url.as_str()))
+            .cloned()
+            .collect();
+
+        if fonts_to_download.is_empty() {
+            self.status = "No failed downloads to retry".to_owned();
+            return;
+        }
+
+        self.retrying = true;
+        self.spawn_download(fonts_to_download);
+    }
+
+    fn spawn_download(&mut self, fonts_to_download: Vec<FontInfo>) {
         let output_dir = self.output_dir.clone();
+        let builder = Downloader::builder().layout(self.download_layout());
+        let downloader = match &self.session {
+            Some(session) => builder.fetcher(
+                session.share(
+                    session
+                        .inner()
+                        .clone()
+                        .with_timeout(DEFAULT_DOWNLOAD_TIMEOUT),
+                ),
+            ),
+            None => builder,
+        }
+        .build();
+        let downloader = match downloader {
+            Ok(downloader) => downloader,
+            Err(error) => {
+                self.status = format!("Failed to start download: {}", error_chain(&error));
+                return;
+            }
+        };
         let (sender, receiver) = mpsc::channel();
         self.download_rx = Some(receiver);
+        self.download_progress.clear();
+        self.download_total = fonts_to_download.len();
         self.mode = AppMode::Downloading;
         self.status = format!(
-            "Preparing download of {} fonts to {}",
+            "Downloading {} fonts to {}",
             fonts_to_download.len(),
             output_dir.display()
         );
 
+        self.cancel = CancellationToken::new();
+        let cancel = self.cancel.clone();
+
         thread::spawn(move || {
-            let report = download::download_fonts(
-                &fonts_to_download,
-                &output_dir,
-                |current, total, font| {
+            let report =
+                downloader.download(&fonts_to_download, &output_dir, &cancel, |progress| {
                     let _ = sender.send(DownloadMessage::Progress {
-                        current,
-                        total,
-                        name: font.name.clone(),
+                        current: progress.current,
+                        name: progress.font.name.clone(),
+                        received: progress.received,
+                        content_length: progress.content_length,
                     });
-                },
-            );
+                });
             let _ = sender.send(DownloadMessage::Finished(report));
         });
     }
 
     fn finish_download(&mut self, report: DownloadReport) {
-        self.mode = AppMode::Browsing;
+        let report = match self.last_report.take() {
+            Some(mut previous) if self.retrying => {
+                previous.merge_retry(report);
+                previous
+            }
+            _ => report,
+        };
+        self.retrying = false;
+        self.refresh_present_fonts();
+
+        self.download_results = self.download_results(&report);
+        self.selected_result_row = 0;
+        self.mode = if self.download_results.is_empty() {
+            AppMode::Browsing
+        } else {
+            AppMode::Results
+        };
 
-        if report.failures.is_empty() {
+        if report.cancelled {
+            self.status = format!(
+                "Download cancelled; saved {}/{} fonts to {}",
+                report.success_count(),
+                report.attempted,
+                self.output_dir.display()
+            );
+        } else if report.failures.is_empty() && report.skipped_files.is_empty() {
             self.status = format!(
                 "Downloaded {}/{} fonts to {}",
                 report.success_count(),
                 report.attempted,
                 self.output_dir.display()
             );
+        } else if report.failures.is_empty() {
+            self.status = format!(
+                "Downloaded {}/{} fonts to {} ({} already present, skipped)",
+                report.success_count(),
+                report.attempted,
+                self.output_dir.display(),
+                report.skipped_files.len()
+            );
         } else {
-            let first_failure = report.failures.first().cloned().unwrap_or_default();
+            let first_failure = report
+                .failures
+                .first()
+                .map(ToString::to_string)
+                .unwrap_or_default();
             self.status = format!(
-                "Downloaded {}/{} fonts ({} failed). First error: {}",
+                "Downloaded {}/{} fonts ({} failed, r to retry). First error: {}",
                 report.success_count(),
                 report.attempted,
                 report.failures.len(),
                 first_failure
             );
         }
+
+        self.last_report = Some(report);
+    }
+
+    /// Saved files first, then skipped ones, then failures.
+    fn download_results(&self, report: &DownloadReport) -> Vec<DownloadResult> {
+        let font_name = |url: &str| {
+            self.fonts
+                .iter()
+                .find(|font| font.url == url)
+                .map_or_else(|| url.to_owned(), |font| font.name.clone())
+        };
+        let file_name = |path: &Path| {
+            path.file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+        };
+
+        let saved = report.saved_files.iter().map(|saved| DownloadResult {
+            name: font_name(&saved.url),
+            path: Some(saved.path.clone()),
+            size: Some(saved.size),
+            outcome: DownloadOutcome::Saved,
+        });
+        let skipped = report.skipped_files.iter().map(|skipped| DownloadResult {
+            name: file_name(&skipped.path),
+            path: Some(skipped.path.clone()),
+            size: fs::metadata(&skipped.path)
+                .ok()
+                .map(|metadata| metadata.len()),
+            outcome: DownloadOutcome::Skipped,
+        });
+        let failed = report.failures.iter().map(|failure| DownloadResult {
+            name: failure.name.clone(),
+            path: None,
+            size: None,
+            outcome: DownloadOutcome::Failed(error_chain(&failure.error)),
+        });
+        saved.chain(skipped).chain(failed).collect()
     }
 
     fn toggle_focus(&mut self) {
+        // Switching to a hidden families pane brings it back.
+        self.families_hidden = false;
+        self.visual_anchor = None;
         self.focus = match self.focus {
             FocusPane::Families => FocusPane::Fonts,
             FocusPane::Fonts => FocusPane::Families,
@@ -365,6 +1009,32 @@ impl App {
         self.clamp_selection();
     }
 
+    fn resize_families(&mut self, wider: bool) {
+        self.families_hidden = false;
+        self.families_width = if wider {
+            (self.families_width + FAMILIES_WIDTH_STEP).min(MAX_FAMILIES_WIDTH)
+        } else {
+            self.families_width
+                .saturating_sub(FAMILIES_WIDTH_STEP)
+                .max(MIN_FAMILIES_WIDTH)
+        };
+        self.status = format!("Families pane: {}% of the width", self.families_width);
+    }
+
+    fn toggle_families_pane(&mut self) {
+        self.families_hidden = !self.families_hidden;
+        if self.families_hidden {
+            if self.focus == FocusPane::Families {
+                self.visual_anchor = None;
+            }
+            self.focus = FocusPane::Fonts;
+            self.status = "Families pane hidden; z or Tab shows it again".to_owned();
+        } else {
+            self.status = format!("Families pane: {}% of the width", self.families_width);
+        }
+        self.clamp_selection();
+    }
+
     fn move_selection_up(&mut self) {
         match self.focus {
             FocusPane::Families => {
@@ -391,10 +1061,7 @@ impl App {
                 }
             }
             FocusPane::Fonts => {
-                let current_family_len = self
-                    .current_family()
-                    .map_or(0, |family| family.font_indices.len());
-                let last = current_family_len.saturating_sub(1);
+                let last = self.font_rows().len().saturating_sub(1);
                 if self.selected_font_row < last {
                     self.selected_font_row += 1;
                 }
@@ -419,25 +1086,133 @@ impl App {
                 self.selected_font_row = 0;
             }
             FocusPane::Fonts => {
-                let last = self
-                    .current_family()
-                    .map_or(0, |family| family.font_indices.len().saturating_sub(1));
-                self.selected_font_row = last;
+                self.selected_font_row = self.font_rows().len().saturating_sub(1);
             }
         }
     }
 
-    fn toggle_current_selection(&mut self) {
+    fn toggle_visual_mode(&mut self) {
+        if self.visual_anchor.take().is_some() {
+            self.status = "Visual mode cancelled".to_owned();
+            return;
+        }
+
+        self.visual_anchor = Some(self.focused_row());
+        self.status =
+            "Visual mode: move to extend the range, Space toggles it, Esc cancels".to_owned();
+    }
+
+    fn focused_row(&self) -> usize {
         match self.focus {
-            FocusPane::Families => self.toggle_current_family_selection(),
+            FocusPane::Families => self.selected_family_index,
+            FocusPane::Fonts => self.selected_font_row,
+        }
+    }
+
+    /// Whether `row` of `pane` lies in the visual-mode range.
+    fn in_visual_range(&self, pane: FocusPane, row: usize) -> bool {
+        let Some(anchor) = self.visual_anchor.filter(|_| self.focus == pane) else {
+            return false;
+        };
+        let cursor = self.focused_row();
+        (anchor.min(cursor)..=anchor.max(cursor)).contains(&row)
+    }
+
+    /// Every font in the visual-mode range, in row order.
+    fn visual_range_indices(&self, anchor: usize) -> Vec<usize> {
+        let cursor = self.focused_row();
+        let rows = anchor.min(cursor)..=anchor.max(cursor);
+        let mut font_indices = Vec::new();
+        match self.focus {
+            FocusPane::Families => {
+                for family in self.families.iter().skip(*rows.start()).take(rows.count()) {
+                    font_indices.extend(&family.font_indices);
+                }
+            }
             FocusPane::Fonts => {
-                if let Some(index) = self.current_font_index()
-                    && !self.selected_font_indices.remove(&index)
+                let Some(family) = self.current_family() else {
+                    return Vec::new();
+                };
+                for row in self
+                    .font_rows()
+                    .iter()
+                    .skip(*rows.start())
+                    .take(rows.count())
                 {
-                    self.selected_font_indices.insert(index);
+                    match *row {
+                        FontRow::Face(face) => {
+                            if let Some(face) = family.faces.get(face) {
+                                font_indices.extend(&face.font_indices);
+                            }
+                        }
+                        FontRow::Source { font_index, .. } => font_indices.push(font_index),
+                    }
                 }
             }
         }
+
+        let mut seen = HashSet::new();
+        font_indices.retain(|font_index| seen.insert(*font_index));
+        font_indices
+    }
+
+    fn toggle_current_selection(&mut self) {
+        if let Some(anchor) = self.visual_anchor.take() {
+            let font_indices = self.visual_range_indices(anchor);
+            self.status = format!("Toggled {} fonts", font_indices.len());
+            self.toggle_indices(font_indices);
+            return;
+        }
+
+        match self.focus {
+            FocusPane::Families => self.toggle_current_family_selection(),
+            FocusPane::Fonts => match self.current_font_row() {
+                Some(FontRow::Face(face)) => {
+                    if let Some(font_indices) = self
+                        .current_family()
+                        .and_then(|family| family.faces.get(face))
+                        .map(|face| face.font_indices.clone())
+                    {
+                        self.toggle_indices(font_indices);
+                    }
+                }
+                Some(FontRow::Source { font_index, .. }) => self.toggle_indices(vec![font_index]),
+                None => {}
+            },
+        }
+    }
+
+    fn toggle_current_face_expansion(&mut self) {
+        self.visual_anchor = None;
+        if self.focus != FocusPane::Fonts {
+            self.focus = FocusPane::Fonts;
+            self.selected_font_row = 0;
+            return;
+        }
+
+        let face = match self.current_font_row() {
+            Some(FontRow::Face(face) | FontRow::Source { face, .. }) => face,
+            None => return,
+        };
+        let Some(key) = self
+            .current_family()
+            .and_then(|family| family.faces.get(face))
+            .and_then(FontFace::preferred_index)
+        else {
+            return;
+        };
+
+        if !self.expanded_faces.remove(&key) {
+            self.expanded_faces.insert(key);
+        }
+
+        if let Some(row) = self
+            .font_rows()
+            .iter()
+            .position(|row| *row == FontRow::Face(face))
+        {
+            self.selected_font_row = row;
+        }
     }
 
     fn toggle_current_family_selection(&mut self) {
@@ -448,6 +1223,10 @@ impl App {
             return;
         };
 
+        self.toggle_indices(font_indices);
+    }
+
+    fn toggle_indices(&mut self, font_indices: Vec<usize>) {
         let all_selected = font_indices
             .iter()
             .all(|font_index| self.selected_font_indices.contains(font_index));
@@ -463,25 +1242,83 @@ impl App {
         }
     }
 
+    /// Selects every listed font, or clears them when all are selected. Fonts
+    /// hidden by the quick filter keep their selection.
     fn toggle_select_all(&mut self) {
-        if self.fonts.is_empty() {
+        let visible = self.visible_indices();
+        if visible.is_empty() {
             return;
         }
 
-        if self.selected_font_indices.len() == self.fonts.len() {
-            self.selected_font_indices.clear();
+        self.toggle_indices(visible);
+    }
+
+    fn set_quick_filter(&mut self, filter: QuickFilter) {
+        self.quick_filter = filter;
+        self.visual_anchor = None;
+        let visible = self.visible_indices();
+        self.families = group_indices_by_inferred_family(&self.fonts, &visible, &self.inference);
+        self.selected_font_row = 0;
+        self.clamp_selection();
+
+        self.status = if filter.is_active() {
+            format!(
+                "Showing {} of {} fonts ({})",
+                visible.len(),
+                self.fonts.len(),
+                filter.label()
+            )
         } else {
-            self.selected_font_indices = (0..self.fonts.len()).collect();
+            format!("Showing all {} fonts", self.fonts.len())
+        };
+    }
+
+    /// Indices of the fonts that pass the quick filter.
+    fn visible_indices(&self) -> Vec<usize> {
+        let filter = self.quick_filter;
+        let mut indices = (0..self.fonts.len()).collect::<Vec<_>>();
+        if let Some(weight) = filter.weight {
+            indices = filter_indices_by_weight(&self.fonts, &indices, Some(weight), Some(weight));
+        }
+        if filter.woff2_only {
+            indices = filter_indices_by_format(&self.fonts, &indices, &["woff2".to_owned()]);
         }
+        if let Some(italic) = filter.italic {
+            indices.retain(|index| {
+                let style = self.fonts[*index].style.to_ascii_lowercase();
+                (style.contains("italic") || style.contains("oblique")) == italic
+            });
+        }
+        indices
     }
 
     fn current_family(&self) -> Option<&FontFamily> {
         self.families.get(self.selected_family_index)
     }
 
-    fn current_font_index(&self) -> Option<usize> {
-        let family = self.current_family()?;
-        family.font_indices.get(self.selected_font_row).copied()
+    fn font_rows(&self) -> Vec<FontRow> {
+        let Some(family) = self.current_family() else {
+            return Vec::new();
+        };
+
+        let mut rows = Vec::new();
+        for (face_index, face) in family.faces.iter().enumerate() {
+            rows.push(FontRow::Face(face_index));
+            if face
+                .preferred_index()
+                .is_some_and(|key| self.expanded_faces.contains(&key))
+            {
+                rows.extend(face.font_indices.iter().map(|font_index| FontRow::Source {
+                    face: face_index,
+                    font_index: *font_index,
+                }));
+            }
+        }
+        rows
+    }
+
+    fn current_font_row(&self) -> Option<FontRow> {
+        self.font_rows().get(self.selected_font_row).copied()
     }
 
     fn clamp_selection(&mut self) {
@@ -494,9 +1331,7 @@ impl App {
         let max_family = self.families.len().saturating_sub(1);
         self.selected_family_index = self.selected_family_index.min(max_family);
 
-        let max_font = self
-            .current_family()
-            .map_or(0, |family| family.font_indices.len().saturating_sub(1));
+        let max_font = self.font_rows().len().saturating_sub(1);
         self.selected_font_row = self.selected_font_row.min(max_font);
     }
 
@@ -505,14 +1340,19 @@ impl App {
             AppMode::Input => "Input",
             AppMode::Scanning => "Scanning",
             AppMode::Browsing => "Browsing",
+            AppMode::Confirming => "Confirm download",
             AppMode::Downloading => "Downloading",
+            AppMode::Results => "Results",
         };
 
-        let title = format!(
+        let mut title = format!(
             " Font Downloader TUI | mode: {mode_label} | selected: {}/{} ",
             self.selected_font_indices.len(),
             self.fonts.len()
         );
+        if self.quick_filter.is_active() {
+            title.push_str(&format!("| filter: {} ", self.quick_filter.label()));
+        }
 
         let paragraph = Paragraph::new(self.status.as_str())
             .block(Block::default().borders(Borders::ALL).title(title))
@@ -562,9 +1402,17 @@ impl App {
     }
 
     fn render_browser(&self, frame: &mut Frame, area: Rect) {
+        if self.families_hidden {
+            self.render_fonts(frame, area);
+            return;
+        }
+
         let columns = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .constraints([
+                Constraint::Percentage(self.families_width),
+                Constraint::Percentage(100 - self.families_width),
+            ])
             .split(area);
 
         self.render_families(frame, columns[0]);
@@ -575,7 +1423,8 @@ impl App {
         let items: Vec<ListItem> = self
             .families
             .iter()
-            .map(|family| {
+            .enumerate()
+            .map(|(row, family)| {
                 let selected_count = family
                     .font_indices
                     .iter()
@@ -590,11 +1439,27 @@ impl App {
                     "[-]"
                 };
 
-                ListItem::new(format!(
-                    "{marker} {} ({selected_count}/{})",
+                let present_count = family
+                    .font_indices
+                    .iter()
+                    .filter(|index| self.present_font_indices.contains(index))
+                    .count();
+                let present = if present_count == 0 {
+                    String::new()
+                } else {
+                    format!(" {PRESENT_ICON}{present_count}")
+                };
+
+                let item = ListItem::new(format!(
+                    "{marker} {} ({selected_count}/{}){present}",
                     family.name,
                     family.font_indices.len()
-                ))
+                ));
+                if self.in_visual_range(FocusPane::Families, row) {
+                    item.style(VISUAL_RANGE_STYLE)
+                } else {
+                    item
+                }
             })
             .collect();
 
@@ -630,30 +1495,49 @@ impl App {
             return;
         };
 
-        let items: Vec<ListItem> = family
-            .font_indices
+        let rows = self.font_rows();
+        let items: Vec<ListItem> = rows
             .iter()
-            .filter_map(|font_index| self.fonts.get(*font_index).map(|font| (font_index, font)))
-            .map(|(font_index, font)| {
-                let marker = if self.selected_font_indices.contains(font_index) {
-                    "[x]"
+            .enumerate()
+            .filter_map(|(row_index, row)| match *row {
+                FontRow::Face(face_index) => {
+                    let face = family.faces.get(face_index)?;
+                    Some((row_index, ListItem::new(self.face_line(face))))
+                }
+                FontRow::Source { font_index, .. } => {
+                    let font = self.fonts.get(font_index)?;
+                    let marker = if self.selected_font_indices.contains(&font_index) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    let present = if self.present_font_indices.contains(&font_index) {
+                        PRESENT_ICON
+                    } else {
+                        " "
+                    };
+                    Some((
+                        row_index,
+                        ListItem::new(format!(
+                            "    {marker} {present} {:<8} {:>9} {}",
+                            shrink_text(&font.format, 8),
+                            font.content_length.map(format_size).unwrap_or_default(),
+                            font.name
+                        )),
+                    ))
+                }
+            })
+            .map(|(row_index, item)| {
+                if self.in_visual_range(FocusPane::Fonts, row_index) {
+                    item.style(VISUAL_RANGE_STYLE)
                 } else {
-                    "[ ]"
-                };
-
-                let line = format!(
-                    "{marker} {:>4} {:<10} {:<8} {}",
-                    font.weight,
-                    shrink_text(&font.style, 10),
-                    shrink_text(&font.format, 8),
-                    font.name
-                );
-                ListItem::new(line)
+                    item
+                }
             })
             .collect();
 
         let mut state = ListState::default();
-        if !family.font_indices.is_empty() {
+        if !rows.is_empty() {
             state.select(Some(self.selected_font_row));
         }
 
@@ -676,14 +1560,233 @@ impl App {
         frame.render_stateful_widget(list, area, &mut state);
     }
 
+    fn face_line(&self, face: &FontFace) -> String {
+        let selected_count = face
+            .font_indices
+            .iter()
+            .filter(|index| self.selected_font_indices.contains(index))
+            .count();
+        let marker = if selected_count == 0 {
+            "[ ]"
+        } else if selected_count == face.font_indices.len() {
+            "[x]"
+        } else {
+            "[-]"
+        };
+
+        let present_count = face
+            .font_indices
+            .iter()
+            .filter(|index| self.present_font_indices.contains(index))
+            .count();
+        let present = if present_count == 0 {
+            " "
+        } else if present_count == face.font_indices.len() {
+            PRESENT_ICON
+        } else {
+            PARTLY_PRESENT_ICON
+        };
+
+        let expander = if face.font_indices.len() < 2 {
+            " "
+        } else if face
+            .preferred_index()
+            .is_some_and(|key| self.expanded_faces.contains(&key))
+        {
+            "▾"
+        } else {
+            "▸"
+        };
+
+        let formats = face
+            .font_indices
+            .iter()
+            .filter_map(|index| self.fonts.get(*index))
+            .map(|font| font.format.as_str())
+            .collect::<Vec<_>>()
+            .join("+");
+        let preferred = face
+            .preferred_index()
+            .and_then(|index| self.fonts.get(index));
+        let name = preferred.map_or("", |font| font.name.as_str());
+        let size = preferred
+            .and_then(|font| font.content_length)
+            .map(format_size)
+            .unwrap_or_default();
+
+        format!(
+            "{marker} {present} {expander} {:>4} {:<10} {:<16} {size:>9} {name}",
+            face.weight,
+            shrink_text(&face.style, 10),
+            shrink_text(&formats, 16)
+        )
+    }
+
+    fn render_download_confirmation(&self, frame: &mut Frame, area: Rect) {
+        let mut selected_indices: Vec<usize> = self.selected_font_indices.iter().copied().collect();
+        selected_indices.sort_unstable();
+        let families =
+            group_indices_by_inferred_family(&self.fonts, &selected_indices, &self.inference);
+
+        let sizes = selected_indices
+            .iter()
+            .filter_map(|index| self.fonts.get(*index))
+            .map(|font| font.content_length)
+            .collect::<Vec<_>>();
+        let known = sizes.iter().flatten().sum::<u64>();
+        let unknown = sizes.iter().filter(|size| size.is_none()).count();
+        let size = if unknown == sizes.len() {
+            "unknown".to_owned()
+        } else if unknown > 0 {
+            format!("at least {} ({unknown} unknown)", format_size(known))
+        } else {
+            format_size(known)
+        };
+
+        let present = selected_indices
+            .iter()
+            .filter(|index| self.present_font_indices.contains(index))
+            .count();
+
+        let text = vec![
+            Line::from(format!(
+                "{} files from {} families",
+                selected_indices.len(),
+                families.len()
+            )),
+            Line::from(format!(
+                "{} new, {present} already in the output directory",
+                selected_indices.len() - present
+            )),
+            Line::from(format!("Estimated size: {size}")),
+            Line::from(format!("Output: {}", self.output_dir.display())),
+            Line::from(""),
+            Line::from("Enter/y: download | Esc/n: cancel"),
+        ];
+
+        let width = area.width.min(60);
+        let height = area.height.min(text.len() as u16 + 2);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Download selected fonts?")
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_download_progress(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .download_progress
+            .iter()
+            .map(|file| {
+                let fraction = file.fraction();
+                let filled = (fraction.unwrap_or(0.0) * PROGRESS_BAR_WIDTH as f64).round() as usize;
+                let amount = match fraction {
+                    Some(fraction) => format!("{:>3.0}%", fraction * 100.0),
+                    None => format_size(file.received),
+                };
+                let speed = file
+                    .speed()
+                    .map(|speed| format!("{}/s", format_size(speed)))
+                    .unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<32} ", shrink_text(&file.name, 32))),
+                    Span::styled(
+                        "█".repeat(filled),
+                        Style::default().fg(if file.done { Color::Green } else { Color::Cyan }),
+                    ),
+                    Span::styled(
+                        "░".repeat(PROGRESS_BAR_WIDTH - filled),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(format!(" {amount:>9}  {speed:>12}")),
+                ]))
+            })
+            .collect();
+
+        // Keeps the file in flight in view on long downloads.
+        let mut state = ListState::default();
+        state.select(Some(self.download_progress.len() - 1));
+
+        let title = format!(
+            "Downloading ({}/{} files started)",
+            self.download_progress.len(),
+            self.download_total
+        );
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_results(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .download_results
+            .iter()
+            .map(|result| {
+                let (label, color) = match &result.outcome {
+                    DownloadOutcome::Saved => ("ok", Color::Green),
+                    DownloadOutcome::Skipped => ("skipped", Color::Yellow),
+                    DownloadOutcome::Failed(_) => ("failed", Color::Red),
+                };
+                let detail = match (&result.outcome, &result.path) {
+                    (DownloadOutcome::Failed(reason), _) => reason.clone(),
+                    (_, Some(path)) => path.display().to_string(),
+                    (_, None) => String::new(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{label:<8}"), Style::default().fg(color)),
+                    Span::raw(format!(
+                        "{:<32} {:>9}  {detail}",
+                        shrink_text(&result.name, 32),
+                        result.size.map(format_size).unwrap_or_default()
+                    )),
+                ]))
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected_result_row));
+
+        let failed = self
+            .download_results
+            .iter()
+            .filter(|result| matches!(result.outcome, DownloadOutcome::Failed(_)))
+            .count();
+        let title = format!(
+            "Download results ({} files, {failed} failed)",
+            self.download_results.len()
+        );
+
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
     fn render_footer(&self, frame: &mut Frame, area: Rect) {
         let help = match self.mode {
             AppMode::Input => "Type URL | Enter: scan | Ctrl+u: clear URL | q: quit",
-            AppMode::Scanning => "Scanning... please wait | q: quit",
+            AppMode::Scanning => "Scanning... please wait | Esc: cancel | q: quit",
             AppMode::Browsing => {
-                "Tab: switch pane | ↑/↓: move | Space: toggle | f: family toggle | a: toggle all | d: download | r: rescan | e: edit URL | q: quit"
+                "Tab: switch pane | ↑/↓: move | Space: toggle | Enter: expand formats | f: family toggle | a: toggle all listed | </>: resize panes | z: hide families | v: visual range | 1-9: weight | i: italic/upright | w: WOFF2 | 0: clear filters | d: download | x: export selection | r: rescan | e: edit URL | q: quit"
             }
-            AppMode::Downloading => "Downloading selected fonts... | q: quit",
+            AppMode::Confirming => "Enter/y: start download | Esc/n: cancel | q: quit",
+            AppMode::Downloading => "Downloading selected fonts... | Esc: cancel | q: quit",
+            AppMode::Results => "↑/↓: move | r: retry failed | Esc/Enter: back to fonts | q: quit",
         };
 
         let footer = Paragraph::new(format!(
@@ -698,6 +1801,16 @@ impl App {
     }
 }
 
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    }
+}
+
 fn shrink_text(input: &str, max_width: usize) -> String {
     if input.chars().count() <= max_width {
         return input.to_owned();
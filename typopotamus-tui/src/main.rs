@@ -4,8 +4,8 @@ use std::io;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
 use crossterm::event::{self, Event, KeyEventKind};
 use crossterm::execute;
 use crossterm::terminal::{
@@ -13,9 +13,69 @@ use crossterm::terminal::{
 };
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
+use typopotamus_core::binary::FontContainer;
+use typopotamus_core::config::Config;
+use typopotamus_core::download::ConflictPolicy;
+use typopotamus_core::extractor::{
+    DEFAULT_MAX_IMPORT_DEPTH, DEFAULT_MAX_STYLESHEET_BYTES, DEFAULT_MAX_STYLESHEETS, ExtractOptions,
+};
+use typopotamus_core::fetch::HttpOptions;
+use typopotamus_core::layout::{DEFAULT_DIRECTORY_TEMPLATE, DownloadLayout, PathTemplate};
+use typopotamus_core::output::InspectFonts;
 
 use crate::app::App;
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum OnConflict {
+    Rename,
+    Skip,
+    Overwrite,
+    Error,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(value: OnConflict) -> Self {
+        match value {
+            OnConflict::Rename => Self::Rename,
+            OnConflict::Skip => Self::Skip,
+            OnConflict::Overwrite => Self::Overwrite,
+            OnConflict::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum FormatArg {
+    Woff2,
+    Woff,
+    #[value(alias = "ttf")]
+    Truetype,
+    #[value(alias = "otf")]
+    Opentype,
+    #[value(alias = "embedded-opentype")]
+    Eot,
+    Svg,
+}
+
+impl From<FormatArg> for FontContainer {
+    fn from(value: FormatArg) -> Self {
+        match value {
+            FormatArg::Woff2 => Self::Woff2,
+            FormatArg::Woff => Self::Woff,
+            FormatArg::Truetype => Self::TrueType,
+            FormatArg::Opentype => Self::OpenType,
+            FormatArg::Eot => Self::Eot,
+            FormatArg::Svg => Self::Svg,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum InferFrom {
+    Name,
+    Binary,
+}
+
 #[derive(Debug, Parser)]
 #[command(
     name = "typopotamus-tui",
@@ -26,6 +86,14 @@ struct Args {
     #[arg(short, long, help = "Website URL to scan immediately")]
     url: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "url",
+        help = "Browse the fonts of a saved `inspect --view font --format json` document instead of scanning (- reads standard input)"
+    )]
+    from_json: Option<PathBuf>,
+
     #[arg(
         short,
         long,
@@ -33,10 +101,130 @@ struct Args {
         help = "Directory where selected fonts are saved"
     )]
     output: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Config file with inference rules (defaults to ~/.config/typopotamus/config.toml)"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "TOML file forcing fonts whose family, file name, or URL matches a pattern into a display family, e.g. \"brandfont-v7-*\" = \"Acme Sans\""
+    )]
+    aliases: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = InferFrom::Name,
+        value_enum,
+        help = "Group families from file names or from each font's name/OS2 tables"
+    )]
+    infer_from: InferFrom,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = DEFAULT_DIRECTORY_TEMPLATE,
+        value_parser = PathTemplate::parse,
+        help = "Directory layout below --output; placeholders: {family}, {inferred_family}, {weight}, {style}, {format}, {provider}, {host}"
+    )]
+    layout: PathTemplate,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = PathTemplate::parse,
+        help = "File name template, e.g. \"{family}-{weight}{style_suffix}.{ext}\"; also accepts {name} (source file name)"
+    )]
+    name_template: Option<PathTemplate>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMATS",
+        value_delimiter = ',',
+        help = "Download each font in the first format of this chain its src offers, e.g. ttf,woff2"
+    )]
+    prefer_format: Vec<FormatArg>,
+
+    #[arg(
+        long,
+        help = "Also search same-origin <script src> bundles for font URLs"
+    )]
+    scan_scripts: bool,
+
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        default_value_t = DEFAULT_MAX_IMPORT_DEPTH,
+        help = "Levels of @import followed below each linked stylesheet"
+    )]
+    max_import_depth: usize,
+
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = DEFAULT_MAX_STYLESHEETS,
+        help = "Stop fetching stylesheets after this many"
+    )]
+    max_stylesheets: usize,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop fetching stylesheets and scripts once this many bytes have been read"
+    )]
+    max_bytes: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_STYLESHEET_BYTES,
+        help = "Read at most this many bytes of any one stylesheet"
+    )]
+    max_stylesheet_bytes: u64,
+
+    #[arg(
+        long,
+        value_name = "PAGES",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        help = "Also scan up to PAGES pages (default 10) sampled from the site's sitemap.xml"
+    )]
+    sitemap: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Do not ask servers for gzip, brotli, or deflate compressed responses"
+    )]
+    no_compression: bool,
+
+    #[arg(
+        long,
+        default_value_t = OnConflict::Rename,
+        value_enum,
+        help = "What to do when a target file already exists"
+    )]
+    on_conflict: OnConflict,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let mut config = Config::load_or_default(args.config.as_deref())?;
+    if let Some(path) = &args.aliases {
+        config = config.with_family_aliases(path)?;
+    }
+    // Read before the terminal is taken over, so stdin can be a pipe.
+    let saved = args
+        .from_json
+        .as_deref()
+        .map(|path| {
+            InspectFonts::read(path).with_context(|| format!("could not load {}", path.display()))
+        })
+        .transpose()?;
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -46,7 +234,7 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let app_result = run_app(&mut terminal, args);
+    let app_result = run_app(&mut terminal, args, config, saved);
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
@@ -55,8 +243,45 @@ fn main() -> Result<()> {
     app_result
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, args: Args) -> Result<()> {
-    let mut app = App::new(args.output, args.url);
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    args: Args,
+    config: Config,
+    saved: Option<InspectFonts>,
+) -> Result<()> {
+    let http = HttpOptions {
+        compression: !args.no_compression,
+    };
+    let mut app = App::new(
+        args.output,
+        args.url,
+        config.inference,
+        args.infer_from == InferFrom::Binary,
+        DownloadLayout {
+            directory: args.layout,
+            file_name: args.name_template,
+            on_conflict: args.on_conflict.into(),
+            http,
+            ..DownloadLayout::default()
+        },
+        ExtractOptions {
+            scan_scripts: args.scan_scripts,
+            max_import_depth: args.max_import_depth,
+            max_stylesheets: args.max_stylesheets,
+            max_bytes: args.max_bytes,
+            max_stylesheet_bytes: Some(args.max_stylesheet_bytes),
+            sitemap_pages: args.sitemap,
+            http,
+            ..ExtractOptions::default()
+        },
+        args.prefer_format
+            .into_iter()
+            .map(|format| FontContainer::from(format).format().to_owned())
+            .collect(),
+    );
+    if let Some(saved) = saved {
+        app.load_saved_scan(saved);
+    }
 
     loop {
         app.tick();